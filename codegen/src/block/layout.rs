@@ -248,6 +248,8 @@ pub struct LayoutHardMacro {
     fmt: darling::util::SpannedValue<String>,
     pdk: syn::Type,
     name: String,
+    #[darling(default)]
+    lef: Option<syn::Expr>,
 }
 
 impl ToTokens for HasLayoutInputReceiver {
@@ -269,7 +271,7 @@ impl ToTokens for HasLayoutInputReceiver {
         };
 
         let has_layout_impls = layout.iter().map(|layout| {
-            let LayoutHardMacro { source, fmt, pdk, name } = layout;
+            let LayoutHardMacro { source, fmt, pdk, name, lef } = layout;
 
             // The raw_cell token stream must create an Arc<RawCell>.
             // The token stream has access to source.
@@ -277,6 +279,17 @@ impl ToTokens for HasLayoutInputReceiver {
                 "gds" => quote! {
                     cell.ctx.read_gds_cell(source, #name)?
                 },
+                "lef" => quote! {
+                    cell.ctx.read_lef_cell(source, #name)?
+                },
+                "def" => {
+                    let Some(lef) = lef else {
+                        proc_macro_error::abort!(fmt.span(), "DEF layout hard macros require a `lef` attribute");
+                    };
+                    quote! {
+                        cell.ctx.read_def_cell(source, { #lef }, #name)?
+                    }
+                }
                 fmtstr => proc_macro_error::abort!(fmt.span(), "unsupported layout hard macro format: `{}`", fmtstr),
             };
 
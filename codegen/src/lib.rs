@@ -358,11 +358,18 @@ pub fn derive_has_schematic_impl(input: TokenStream) -> TokenStream {
 /// * `fmt`: The layout source format.
 /// * `pdk`: The PDK to which source corresponds.
 ///
+/// The `def` format additionally requires a `lef` argument.
+///
 /// # Supported formats
 ///
 /// The following formats are supported:
 ///
 /// * `gds`: Source should be an expression that evaluates to the file path of a GDSII library.
+/// * `lef`: Source should be an expression that evaluates to a collection of LEF file paths,
+/// such as an array. Name should be the name of a macro defined in those files.
+/// * `def`: Source should be an expression that evaluates to the file path of a DEF design,
+/// and `lef` should evaluate to a collection of LEF file paths defining the macros, vias,
+/// and layers used by the design. Name should be the name of the design.
 ///
 /// Note that expressions can be arbitrary Rust expressions. Here are some examples:
 /// * `fmt = "\"/path/to/layout.gds\""` (note that you need the escaped quotes to make this a
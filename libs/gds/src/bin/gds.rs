@@ -0,0 +1,579 @@
+//! A command-line utility for inspecting and editing GDS files.
+#![warn(missing_docs)]
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use arcstr::ArcStr;
+use clap::{Parser, Subcommand};
+use gds::{GdsDepOrder, GdsElement, GdsError, GdsLibrary, GdsPoint, GdsResult, GdsUnits};
+
+/// The arguments to the GDS utility.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+pub struct Args {
+    /// The command to run.
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+/// A GDS utility command.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Prints the numbers of cells and elements of each type.
+    Stats {
+        /// The GDS file.
+        #[clap(value_hint = clap::ValueHint::FilePath)]
+        input: PathBuf,
+    },
+    /// Prints the cell hierarchy.
+    Tree {
+        /// The GDS file.
+        #[clap(value_hint = clap::ValueHint::FilePath)]
+        input: PathBuf,
+        /// The cell at the root of the tree.
+        ///
+        /// Defaults to all cells that are not instantiated by other cells.
+        #[clap(short, long)]
+        cell: Option<ArcStr>,
+    },
+    /// Writes the records of a GDS file as JSON.
+    Dump {
+        /// The GDS file.
+        #[clap(value_hint = clap::ValueHint::FilePath)]
+        input: PathBuf,
+        /// The JSON file to write. Defaults to standard output.
+        #[clap(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Prints the layers and datatypes used, with the number of elements on each.
+    Layers {
+        /// The GDS file.
+        #[clap(value_hint = clap::ValueHint::FilePath)]
+        input: PathBuf,
+    },
+    /// Writes a single cell and the cells it depends on to a new GDS file.
+    Extract {
+        /// The GDS file.
+        #[clap(value_hint = clap::ValueHint::FilePath)]
+        input: PathBuf,
+        /// The cell to extract.
+        cell: ArcStr,
+        /// The GDS file to write.
+        #[clap(short, long, value_name = "FILE")]
+        output: PathBuf,
+    },
+    /// Renames a cell and all references to it.
+    Rename {
+        /// The GDS file.
+        #[clap(value_hint = clap::ValueHint::FilePath)]
+        input: PathBuf,
+        /// The current name of the cell.
+        from: ArcStr,
+        /// The new name of the cell.
+        to: ArcStr,
+        /// The GDS file to write.
+        #[clap(short, long, value_name = "FILE")]
+        output: PathBuf,
+    },
+    /// Adds a prefix to the names of all cells.
+    Prefix {
+        /// The GDS file.
+        #[clap(value_hint = clap::ValueHint::FilePath)]
+        input: PathBuf,
+        /// The prefix to add.
+        prefix: String,
+        /// The GDS file to write.
+        #[clap(short, long, value_name = "FILE")]
+        output: PathBuf,
+    },
+    /// Prints the units of a GDS file, or rescales it to a new database unit.
+    Units {
+        /// The GDS file.
+        #[clap(value_hint = clap::ValueHint::FilePath)]
+        input: PathBuf,
+        /// The new database unit, in meters.
+        ///
+        /// All coordinates must be exactly representable in the new unit.
+        #[clap(long, value_name = "METERS", requires = "output")]
+        db_unit: Option<f64>,
+        /// The GDS file to write.
+        #[clap(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(args.command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> GdsResult<()> {
+    match command {
+        Command::Stats { input } => {
+            print!("{}", GdsLibrary::load(input)?.stats());
+        }
+        Command::Tree { input, cell } => {
+            let lib = GdsLibrary::load(input)?;
+            let mut out = io::stdout().lock();
+            for root in tree_roots(&lib, cell)? {
+                print_tree(&lib, &root, &mut HashSet::new(), &mut out)?;
+            }
+        }
+        Command::Dump { input, output } => match output {
+            Some(output) => GdsLibrary::dump_records(input, std::fs::File::create(output)?)?,
+            None => GdsLibrary::dump_records(input, io::stdout().lock())?,
+        },
+        Command::Layers { input } => {
+            let lib = GdsLibrary::load(input)?;
+            for ((layer, xtype), count) in layer_counts(&lib) {
+                println!("{layer}/{xtype}\t{count}");
+            }
+        }
+        Command::Extract {
+            input,
+            cell,
+            output,
+        } => {
+            let lib = GdsLibrary::load(input)?;
+            let structs = GdsDepOrder::new(&lib)
+                .try_cell_order(cell.clone())?
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>();
+            if structs.is_empty() {
+                return Err(GdsError::Str(format!("cell `{cell}` not found")));
+            }
+            let mut extracted = GdsLibrary::new(lib.name.clone());
+            extracted.units = lib.units.clone();
+            extracted.structs = structs;
+            extracted.save(output)?;
+        }
+        Command::Rename {
+            input,
+            from,
+            to,
+            output,
+        } => {
+            let mut lib = GdsLibrary::load(input)?;
+            if !lib.structs.iter().any(|s| s.name == from) {
+                return Err(GdsError::Str(format!("cell `{from}` not found")));
+            }
+            if lib.structs.iter().any(|s| s.name == to) {
+                return Err(GdsError::Str(format!("cell `{to}` already exists")));
+            }
+            rename_cells(&mut lib, |name| (*name == from).then(|| to.clone()));
+            lib.save(output)?;
+        }
+        Command::Prefix {
+            input,
+            prefix,
+            output,
+        } => {
+            let mut lib = GdsLibrary::load(input)?;
+            let names = lib
+                .structs
+                .iter()
+                .map(|s| s.name.clone())
+                .collect::<HashSet<_>>();
+            rename_cells(&mut lib, |name| {
+                names
+                    .contains(name)
+                    .then(|| arcstr::format!("{prefix}{name}"))
+            });
+            lib.save(output)?;
+        }
+        Command::Units {
+            input,
+            db_unit,
+            output,
+        } => {
+            let mut lib = GdsLibrary::load(input)?;
+            match (db_unit, output) {
+                (Some(db_unit), Some(output)) => {
+                    rescale(&mut lib, db_unit)?;
+                    lib.save(output)?;
+                }
+                _ => {
+                    println!("database unit: {} m", lib.units.db_unit());
+                    println!("user unit:     {} m", lib.units.user_unit());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the cells that should be printed at the root of the hierarchy.
+fn tree_roots(lib: &GdsLibrary, cell: Option<ArcStr>) -> GdsResult<Vec<ArcStr>> {
+    if let Some(cell) = cell {
+        if !lib.structs.iter().any(|s| s.name == cell) {
+            return Err(GdsError::Str(format!("cell `{cell}` not found")));
+        }
+        return Ok(vec![cell]);
+    }
+    let instantiated = lib
+        .structs
+        .iter()
+        .flat_map(|s| s.elems.iter().filter_map(ref_name))
+        .collect::<HashSet<_>>();
+    Ok(lib
+        .structs
+        .iter()
+        .filter(|s| !instantiated.contains(&s.name))
+        .map(|s| s.name.clone())
+        .collect())
+}
+
+/// Prints `cell` and the cells it instantiates, indented by the number of `ancestors`.
+///
+/// Cells that instantiate one of their ancestors are marked as cyclic rather than expanded.
+fn print_tree(
+    lib: &GdsLibrary,
+    cell: &ArcStr,
+    ancestors: &mut HashSet<ArcStr>,
+    out: &mut impl Write,
+) -> GdsResult<()> {
+    let depth = ancestors.len();
+    if ancestors.contains(cell) {
+        writeln!(out, "{:indent$}{cell} (cycle)", "", indent = 2 * depth)?;
+        return Ok(());
+    }
+    writeln!(out, "{:indent$}{cell}", "", indent = 2 * depth)?;
+    let Some(strukt) = lib.structs.iter().find(|s| s.name == *cell) else {
+        return Ok(());
+    };
+    // Preserve the order of first instantiation, counting repeated instances.
+    let mut children: Vec<(ArcStr, usize)> = Vec::new();
+    for elem in strukt.elems.iter() {
+        let count = match elem {
+            GdsElement::GdsStructRef(_) => 1,
+            GdsElement::GdsArrayRef(aref) => aref.cols.max(0) as usize * aref.rows.max(0) as usize,
+            _ => continue,
+        };
+        let name = ref_name(elem).unwrap();
+        match children.iter_mut().find(|(child, _)| *child == name) {
+            Some((_, n)) => *n += count,
+            None => children.push((name, count)),
+        }
+    }
+    ancestors.insert(cell.clone());
+    for (child, count) in children {
+        if count > 1 {
+            writeln!(out, "{:indent$}{count} x", "", indent = 2 * (depth + 1))?;
+        }
+        print_tree(lib, &child, ancestors, out)?;
+    }
+    ancestors.remove(cell);
+    Ok(())
+}
+
+/// Counts the elements on each layer and datatype, text type, node type, or box type.
+fn layer_counts(lib: &GdsLibrary) -> BTreeMap<(i16, i16), usize> {
+    let mut counts = BTreeMap::new();
+    for elem in lib.structs.iter().flat_map(|s| s.elems.iter()) {
+        let spec = match elem {
+            GdsElement::GdsBoundary(x) => (x.layer, x.datatype),
+            GdsElement::GdsPath(x) => (x.layer, x.datatype),
+            GdsElement::GdsTextElem(x) => (x.layer, x.texttype),
+            GdsElement::GdsNode(x) => (x.layer, x.nodetype),
+            GdsElement::GdsBox(x) => (x.layer, x.boxtype),
+            GdsElement::GdsStructRef(_) | GdsElement::GdsArrayRef(_) => continue,
+        };
+        *counts.entry(spec).or_default() += 1;
+    }
+    counts
+}
+
+/// Returns the name of the cell referenced by `elem`, if it is a reference.
+fn ref_name(elem: &GdsElement) -> Option<ArcStr> {
+    match elem {
+        GdsElement::GdsStructRef(x) => Some(x.name.clone()),
+        GdsElement::GdsArrayRef(x) => Some(x.name.clone()),
+        _ => None,
+    }
+}
+
+/// Renames cells and references for which `rename` returns a new name.
+fn rename_cells(lib: &mut GdsLibrary, rename: impl Fn(&ArcStr) -> Option<ArcStr>) {
+    let mut renamed = HashMap::new();
+    let mut new_name = |name: &mut ArcStr| {
+        if let Some(to) = renamed
+            .entry(name.clone())
+            .or_insert_with(|| rename(name))
+            .clone()
+        {
+            *name = to;
+        }
+    };
+    for strukt in lib.structs.iter_mut() {
+        new_name(&mut strukt.name);
+        for elem in strukt.elems.iter_mut() {
+            match elem {
+                GdsElement::GdsStructRef(x) => new_name(&mut x.name),
+                GdsElement::GdsArrayRef(x) => new_name(&mut x.name),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Converts all coordinates and lengths in `lib` to a database unit of `db_unit` meters.
+///
+/// The size of the user unit is unchanged.
+fn rescale(lib: &mut GdsLibrary, db_unit: f64) -> GdsResult<()> {
+    if db_unit <= 0. || !db_unit.is_finite() {
+        return Err(GdsError::Str(format!("invalid database unit: {db_unit}")));
+    }
+    let factor = lib.units.db_unit() / db_unit;
+    let scale = |x: i32| -> GdsResult<i32> {
+        let scaled = x as f64 * factor;
+        if (scaled - scaled.round()).abs() > 1e-6 || scaled.abs() > i32::MAX as f64 {
+            return Err(GdsError::Str(format!(
+                "coordinate {x} cannot be represented exactly with a database unit of {db_unit} m"
+            )));
+        }
+        Ok(scaled.round() as i32)
+    };
+    let scale_point = |p: &mut GdsPoint| -> GdsResult<()> {
+        p.x = scale(p.x)?;
+        p.y = scale(p.y)?;
+        Ok(())
+    };
+    let scale_opt = |x: &mut Option<i32>| -> GdsResult<()> {
+        if let Some(x) = x {
+            *x = scale(*x)?;
+        }
+        Ok(())
+    };
+
+    for elem in lib.structs.iter_mut().flat_map(|s| s.elems.iter_mut()) {
+        match elem {
+            GdsElement::GdsBoundary(x) => x.xy.iter_mut().try_for_each(scale_point)?,
+            GdsElement::GdsPath(x) => {
+                x.xy.iter_mut().try_for_each(scale_point)?;
+                scale_opt(&mut x.width)?;
+                scale_opt(&mut x.begin_extn)?;
+                scale_opt(&mut x.end_extn)?;
+            }
+            GdsElement::GdsStructRef(x) => scale_point(&mut x.xy)?,
+            GdsElement::GdsArrayRef(x) => x.xy.iter_mut().try_for_each(scale_point)?,
+            GdsElement::GdsTextElem(x) => {
+                scale_point(&mut x.xy)?;
+                scale_opt(&mut x.width)?;
+            }
+            GdsElement::GdsNode(x) => x.xy.iter_mut().try_for_each(scale_point)?,
+            GdsElement::GdsBox(x) => x.xy.iter_mut().try_for_each(scale_point)?,
+        }
+    }
+
+    lib.units = GdsUnits::new(db_unit / lib.units.user_unit(), db_unit);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use gds::{GdsBoundary, GdsStruct, GdsStructRef};
+
+    use super::*;
+
+    /// Creates a library in which `top` instantiates `mid` twice, and `mid` instantiates `leaf`.
+    fn sample_lib() -> GdsLibrary {
+        let sref = |name: &str, x| -> GdsElement {
+            GdsStructRef {
+                name: name.into(),
+                xy: GdsPoint::new(x, 0),
+                ..Default::default()
+            }
+            .into()
+        };
+        let mut leaf = GdsStruct::new("leaf");
+        leaf.elems.push(
+            GdsBoundary {
+                layer: 1,
+                datatype: 0,
+                xy: GdsPoint::vec(&[(0, 0), (10, 0), (10, 20), (0, 20), (0, 0)]),
+                ..Default::default()
+            }
+            .into(),
+        );
+        let mut mid = GdsStruct::new("mid");
+        mid.elems.push(sref("leaf", 0));
+        let mut top = GdsStruct::new("top");
+        top.elems.push(sref("mid", 0));
+        top.elems.push(sref("mid", 100));
+
+        let mut lib = GdsLibrary::new("sample");
+        lib.structs.extend([leaf, mid, top]);
+        lib
+    }
+
+    /// Saves `lib` to a temporary directory, returning the directory and the file path.
+    fn save(lib: &GdsLibrary) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("input.gds");
+        lib.save(&path).unwrap();
+        (dir, path)
+    }
+
+    fn names(lib: &GdsLibrary) -> Vec<&str> {
+        lib.structs.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    fn refs(lib: &GdsLibrary) -> Vec<ArcStr> {
+        lib.structs
+            .iter()
+            .flat_map(|s| s.elems.iter().filter_map(ref_name))
+            .collect()
+    }
+
+    #[test]
+    fn renames_cells() {
+        let (dir, input) = save(&sample_lib());
+        let output = dir.path().join("output.gds");
+        run(Command::Rename {
+            input: input.clone(),
+            from: "mid".into(),
+            to: "middle".into(),
+            output: output.clone(),
+        })
+        .unwrap();
+        let lib = GdsLibrary::load(&output).unwrap();
+        assert_eq!(names(&lib), ["leaf", "middle", "top"]);
+        assert_eq!(refs(&lib), ["leaf", "middle", "middle"]);
+
+        // Renaming to an existing cell fails.
+        assert!(run(Command::Rename {
+            input,
+            from: "mid".into(),
+            to: "leaf".into(),
+            output,
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn prefixes_cells() {
+        let (dir, input) = save(&sample_lib());
+        let output = dir.path().join("output.gds");
+        run(Command::Prefix {
+            input,
+            prefix: "x_".into(),
+            output: output.clone(),
+        })
+        .unwrap();
+        let lib = GdsLibrary::load(&output).unwrap();
+        assert_eq!(names(&lib), ["x_leaf", "x_mid", "x_top"]);
+        assert_eq!(refs(&lib), ["x_leaf", "x_mid", "x_mid"]);
+    }
+
+    #[test]
+    fn extracts_cells() {
+        let (dir, input) = save(&sample_lib());
+        let output = dir.path().join("output.gds");
+        run(Command::Extract {
+            input: input.clone(),
+            cell: "mid".into(),
+            output: output.clone(),
+        })
+        .unwrap();
+        let lib = GdsLibrary::load(&output).unwrap();
+        assert_eq!(names(&lib), ["leaf", "mid"]);
+
+        assert!(run(Command::Extract {
+            input,
+            cell: "missing".into(),
+            output,
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_extracting_cyclic_cells() {
+        let mut lib = sample_lib();
+        lib.structs[0].elems.push(
+            GdsStructRef {
+                name: "mid".into(),
+                ..Default::default()
+            }
+            .into(),
+        );
+        let (dir, input) = save(&lib);
+        assert!(run(Command::Extract {
+            input,
+            cell: "top".into(),
+            output: dir.path().join("output.gds"),
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn rescales_units() {
+        let (dir, input) = save(&sample_lib());
+        let output = dir.path().join("output.gds");
+        run(Command::Units {
+            input: input.clone(),
+            db_unit: Some(5e-10),
+            output: Some(output.clone()),
+        })
+        .unwrap();
+        let lib = GdsLibrary::load(&output).unwrap();
+        assert_eq!(lib.units.db_unit(), 5e-10);
+        assert!((lib.units.user_unit() - 1e-6).abs() < 1e-18);
+        let GdsElement::GdsBoundary(ref b) = lib.structs[0].elems[0] else {
+            panic!("expected a boundary");
+        };
+        assert_eq!(b.xy[2], GdsPoint::new(20, 40));
+        let GdsElement::GdsStructRef(ref r) = lib.structs[2].elems[1] else {
+            panic!("expected a reference");
+        };
+        assert_eq!(r.xy, GdsPoint::new(200, 0));
+
+        // A 3nm grid cannot represent a 10nm coordinate.
+        assert!(run(Command::Units {
+            input,
+            db_unit: Some(3e-9),
+            output: Some(output),
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn prints_trees() {
+        let lib = sample_lib();
+        let mut out = Vec::new();
+        for root in tree_roots(&lib, None).unwrap() {
+            print_tree(&lib, &root, &mut HashSet::new(), &mut out).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "top\n  2 x\n  mid\n    leaf\n"
+        );
+    }
+
+    #[test]
+    fn prints_cyclic_trees() {
+        let mut lib = sample_lib();
+        lib.structs[0].elems.push(
+            GdsStructRef {
+                name: "mid".into(),
+                ..Default::default()
+            }
+            .into(),
+        );
+        let mut out = Vec::new();
+        print_tree(&lib, &"top".into(), &mut HashSet::new(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "top\n  2 x\n  mid\n    leaf\n      mid (cycle)\n"
+        );
+    }
+}
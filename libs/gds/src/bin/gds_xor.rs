@@ -0,0 +1,67 @@
+//! Compares the geometry of two GDS files.
+//!
+//! Exits with status 0 if the files are geometrically identical, 1 if they differ,
+//! and 2 if either file could not be read or compared.
+#![warn(missing_docs)]
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+use gds::compare::GdsComparison;
+use gds::{GdsLibrary, GdsResult};
+
+/// The arguments to the GDS comparison binary.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+pub struct Args {
+    /// The reference GDS file.
+    #[clap(value_parser, value_hint = clap::ValueHint::FilePath)]
+    pub left: PathBuf,
+    /// The GDS file to compare against the reference.
+    #[clap(value_parser, value_hint = clap::ValueHint::FilePath)]
+    pub right: PathBuf,
+    /// A GDS file to which the XOR of each differing cell should be written.
+    #[clap(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+    /// Only print a summary, omitting the coordinates of differing regions.
+    #[clap(short, long)]
+    pub quiet: bool,
+}
+
+fn run(args: &Args) -> GdsResult<bool> {
+    let left = GdsLibrary::load(&args.left)?;
+    let right = GdsLibrary::load(&args.right)?;
+    let comparison = GdsComparison::new(&left, &right)?;
+
+    if args.quiet {
+        println!(
+            "{} missing cell(s), {} extra cell(s), {} differing cell(s)",
+            comparison.missing_cells.len(),
+            comparison.extra_cells.len(),
+            comparison.cells.len()
+        );
+    } else {
+        print!("{comparison}");
+    }
+
+    if let Some(ref output) = args.output {
+        comparison
+            .to_gds(format!("{}_xor", left.name), left.units.clone())?
+            .save(output)?;
+    }
+
+    Ok(comparison.is_identical())
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,548 @@
+//! Geometric comparison of GDS libraries.
+//!
+//! Compares the cells of two [`GdsLibrary`]s by flattening each cell and computing the
+//! exclusive-or (XOR) of its geometry on every layer. Unlike a record-level comparison,
+//! this ignores differences in how geometry is represented, such as the order of elements,
+//! the starting vertex of polygons, or paths versus equivalent boundaries.
+//!
+//! Text, node, and property records are not compared.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+use arcstr::ArcStr;
+use geometry::bbox::Bbox;
+use geometry::boolean::Region;
+use geometry::point::Point;
+use geometry::polygon::Polygon;
+use geometry::rect::Rect;
+
+use crate::{
+    GdsArrayRef, GdsBoundary, GdsElement, GdsError, GdsLayerSpec, GdsLibrary, GdsPoint, GdsResult,
+    GdsStrans, GdsStruct, GdsStructRef, GdsUnits,
+};
+
+/// The geometric differences between the two versions of a layer in a cell.
+#[derive(Debug, Clone)]
+pub struct LayerDiff {
+    /// The layer.
+    pub layer: GdsLayerSpec,
+    /// The region covered by exactly one of the two versions of the layer.
+    pub xor: Region,
+    /// The bounding boxes of the connected components of [`LayerDiff::xor`].
+    pub regions: Vec<Rect>,
+}
+
+impl LayerDiff {
+    /// The total area of the differences, in database units squared.
+    pub fn area(&self) -> i64 {
+        self.xor.area()
+    }
+}
+
+/// A difference in the number of instances of a cell within another cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstanceDiff {
+    /// The name of the instantiated cell.
+    pub cell: ArcStr,
+    /// The number of instances in the first library.
+    pub left: usize,
+    /// The number of instances in the second library.
+    pub right: usize,
+}
+
+/// The differences between the two versions of a cell.
+#[derive(Debug, Clone)]
+pub struct CellDiff {
+    /// The name of the cell.
+    pub name: ArcStr,
+    /// Differences in the cells instantiated directly by the cell.
+    pub instances: Vec<InstanceDiff>,
+    /// Differences in the flattened geometry of the cell, ordered by layer.
+    pub layers: Vec<LayerDiff>,
+}
+
+impl CellDiff {
+    /// Returns `true` if the hierarchy and geometry of the cell are the same in both libraries.
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty() && self.layers.is_empty()
+    }
+}
+
+/// The result of comparing two [`GdsLibrary`]s.
+#[derive(Debug, Clone, Default)]
+pub struct GdsComparison {
+    /// Cells that are only in the first library.
+    pub missing_cells: Vec<ArcStr>,
+    /// Cells that are only in the second library.
+    pub extra_cells: Vec<ArcStr>,
+    /// Differences in cells present in both libraries.
+    ///
+    /// Cells without differences are omitted.
+    pub cells: Vec<CellDiff>,
+}
+
+impl GdsComparison {
+    /// Compares two libraries cell by cell.
+    ///
+    /// Returns an error if the libraries have different database units, or if either
+    /// library instantiates a cell that it does not define.
+    pub fn new(left: &GdsLibrary, right: &GdsLibrary) -> GdsResult<Self> {
+        if left.units.db_unit() != right.units.db_unit() {
+            return Err(GdsError::Str(format!(
+                "database units differ: {} m and {} m",
+                left.units.db_unit(),
+                right.units.db_unit()
+            )));
+        }
+
+        let right_names = right
+            .structs
+            .iter()
+            .map(|strukt| &strukt.name)
+            .collect::<HashSet<_>>();
+        let common = left
+            .structs
+            .iter()
+            .map(|strukt| &strukt.name)
+            .filter(|name| right_names.contains(name))
+            .collect::<Vec<_>>();
+        let mut left_flat = Flattener::new(left, common.iter().copied());
+        let mut right_flat = Flattener::new(right, common.iter().copied());
+        let mut comparison = GdsComparison::default();
+
+        for strukt in left.structs.iter() {
+            let Some(other) = right_flat.structs.get(&strukt.name).copied() else {
+                comparison.missing_cells.push(strukt.name.clone());
+                continue;
+            };
+
+            let instances = instance_diffs(strukt, other);
+
+            let left_layers = left_flat.flatten(&strukt.name)?;
+            let right_layers = right_flat.flatten(&strukt.name)?;
+            let mut specs = left_layers
+                .keys()
+                .chain(right_layers.keys())
+                .copied()
+                .collect::<Vec<_>>();
+            specs.sort();
+            specs.dedup();
+
+            let mut layers = Vec::new();
+            let empty = Vec::new();
+            for spec in specs {
+                let a = Region::from_polygons(left_layers.get(&spec).unwrap_or(&empty));
+                let b = Region::from_polygons(right_layers.get(&spec).unwrap_or(&empty));
+                let xor = a.xor(&b);
+                if xor.is_empty() {
+                    continue;
+                }
+                let regions = xor
+                    .components()
+                    .iter()
+                    .filter_map(|component| component.bbox())
+                    .collect();
+                layers.push(LayerDiff {
+                    layer: GdsLayerSpec::new(spec.0, spec.1),
+                    xor,
+                    regions,
+                });
+            }
+
+            let diff = CellDiff {
+                name: strukt.name.clone(),
+                instances,
+                layers,
+            };
+            if !diff.is_empty() {
+                comparison.cells.push(diff);
+            }
+        }
+
+        comparison.extra_cells = right
+            .structs
+            .iter()
+            .filter(|strukt| !left_flat.structs.contains_key(&strukt.name))
+            .map(|strukt| strukt.name.clone())
+            .collect();
+
+        Ok(comparison)
+    }
+
+    /// Returns `true` if the libraries contain the same cells with the same hierarchy and geometry.
+    pub fn is_identical(&self) -> bool {
+        self.missing_cells.is_empty() && self.extra_cells.is_empty() && self.cells.is_empty()
+    }
+
+    /// Creates a library containing the geometric differences of each differing cell.
+    ///
+    /// Each cell of the returned library contains the XOR of the flattened geometry of the
+    /// corresponding cell on each layer, drawn on that layer.
+    ///
+    /// Returns an error if a difference lies outside the range of GDS coordinates.
+    pub fn to_gds(&self, name: impl Into<ArcStr>, units: GdsUnits) -> GdsResult<GdsLibrary> {
+        let mut lib = GdsLibrary::new(name);
+        lib.units = units;
+        for cell in self.cells.iter().filter(|cell| !cell.layers.is_empty()) {
+            let mut strukt = GdsStruct::new(cell.name.clone());
+            let point = |p: &Point| {
+                let convert = |v: i64| {
+                    i32::try_from(v).map_err(|_| {
+                        GdsError::Str(format!(
+                            "difference in cell `{}` at ({}, {}) is out of the range of GDS coordinates",
+                            cell.name, p.x, p.y
+                        ))
+                    })
+                };
+                Ok(GdsPoint::new(convert(p.x)?, convert(p.y)?))
+            };
+            for diff in cell.layers.iter() {
+                for polygon in region_polygons(&diff.xor) {
+                    let mut xy = polygon
+                        .points()
+                        .iter()
+                        .map(point)
+                        .collect::<GdsResult<Vec<_>>>()?;
+                    xy.push(xy[0].clone());
+                    strukt.elems.push(
+                        GdsBoundary {
+                            layer: diff.layer.layer,
+                            datatype: diff.layer.xtype,
+                            xy,
+                            ..Default::default()
+                        }
+                        .into(),
+                    );
+                }
+            }
+            lib.structs.push(strukt);
+        }
+        Ok(lib)
+    }
+}
+
+impl fmt::Display for GdsComparison {
+    /// Writes a human-readable report of the differences.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_identical() {
+            return writeln!(f, "libraries are identical");
+        }
+        for name in self.missing_cells.iter() {
+            writeln!(f, "missing cell: {name}")?;
+        }
+        for name in self.extra_cells.iter() {
+            writeln!(f, "extra cell: {name}")?;
+        }
+        for cell in self.cells.iter() {
+            writeln!(f, "cell {}:", cell.name)?;
+            for inst in cell.instances.iter() {
+                writeln!(
+                    f,
+                    "  instances of {}: {} -> {}",
+                    inst.cell, inst.left, inst.right
+                )?;
+            }
+            for layer in cell.layers.iter() {
+                writeln!(
+                    f,
+                    "  layer {}/{}: XOR area {} in {} region(s)",
+                    layer.layer.layer,
+                    layer.layer.xtype,
+                    layer.area(),
+                    layer.regions.len()
+                )?;
+                for r in layer.regions.iter() {
+                    writeln!(
+                        f,
+                        "    ({}, {}) to ({}, {})",
+                        r.left(),
+                        r.bot(),
+                        r.right(),
+                        r.top()
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Counts the instances of each cell directly within `left` and `right`, returning the
+/// cells whose counts differ.
+fn instance_diffs(left: &GdsStruct, right: &GdsStruct) -> Vec<InstanceDiff> {
+    let mut counts: BTreeMap<ArcStr, (usize, usize)> = BTreeMap::new();
+    for (strukt, right) in [(left, false), (right, true)] {
+        for elem in strukt.elems.iter() {
+            let (name, n) = match elem {
+                GdsElement::GdsStructRef(sref) => (&sref.name, 1),
+                GdsElement::GdsArrayRef(aref) => (
+                    &aref.name,
+                    aref.rows.max(0) as usize * aref.cols.max(0) as usize,
+                ),
+                _ => continue,
+            };
+            let entry = counts.entry(name.clone()).or_default();
+            if right {
+                entry.1 += n;
+            } else {
+                entry.0 += n;
+            }
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|(_, (left, right))| left != right)
+        .map(|(cell, (left, right))| InstanceDiff { cell, left, right })
+        .collect()
+}
+
+/// Flattened polygons, grouped by layer and datatype.
+pub(crate) type FlatLayers = HashMap<(i16, i16), Vec<Polygon>>;
+
+/// Flattens the cells of a library, caching the result for each cell.
+///
+/// A cell is evicted from the cache once each of its uses has flattened it: every cell
+/// that instantiates it, and each call to [`Flattener::flatten`] named in [`Flattener::new`].
+/// The cache therefore only holds cells that are still needed, rather than every cell
+/// of the library.
+pub(crate) struct Flattener<'a> {
+    structs: HashMap<ArcStr, &'a GdsStruct>,
+    pub(crate) cache: HashMap<ArcStr, Arc<FlatLayers>>,
+    /// The number of remaining uses of each cell.
+    uses: HashMap<ArcStr, usize>,
+}
+
+impl<'a> Flattener<'a> {
+    /// Creates a flattener for the cells of `lib`, each of the cells in `tops` of which
+    /// will be flattened once through [`Flattener::flatten`].
+    pub(crate) fn new<'b>(lib: &'a GdsLibrary, tops: impl IntoIterator<Item = &'b ArcStr>) -> Self {
+        let mut uses: HashMap<ArcStr, usize> = HashMap::new();
+        for strukt in lib.structs.iter() {
+            for child in children(strukt) {
+                *uses.entry(child.clone()).or_default() += 1;
+            }
+        }
+        for name in tops {
+            *uses.entry(name.clone()).or_default() += 1;
+        }
+        Self {
+            structs: lib
+                .structs
+                .iter()
+                .map(|strukt| (strukt.name.clone(), strukt))
+                .collect(),
+            cache: HashMap::new(),
+            uses,
+        }
+    }
+
+    pub(crate) fn flatten(&mut self, name: &ArcStr) -> GdsResult<Arc<FlatLayers>> {
+        let flat = self.flatten_inner(name, &mut Vec::new())?;
+        self.release(name);
+        Ok(flat)
+    }
+
+    /// Records a use of the named cell, evicting it from the cache if it has no uses left.
+    fn release(&mut self, name: &ArcStr) {
+        if let Some(uses) = self.uses.get_mut(name) {
+            *uses = uses.saturating_sub(1);
+            if *uses == 0 {
+                self.cache.remove(name);
+            }
+        }
+    }
+
+    fn flatten_inner(
+        &mut self,
+        name: &ArcStr,
+        stack: &mut Vec<ArcStr>,
+    ) -> GdsResult<Arc<FlatLayers>> {
+        if let Some(flat) = self.cache.get(name) {
+            return Ok(flat.clone());
+        }
+        if stack.contains(name) {
+            return Err(GdsError::Str(format!("cell `{name}` instantiates itself")));
+        }
+        let strukt = *self
+            .structs
+            .get(name)
+            .ok_or_else(|| GdsError::Str(format!("instantiated cell `{name}` is not defined")))?;
+
+        stack.push(name.clone());
+        let mut flat = FlatLayers::new();
+        for elem in strukt.elems.iter() {
+            match elem {
+                GdsElement::GdsBoundary(b) => {
+                    flat.entry((b.layer, b.datatype))
+                        .or_default()
+                        .push(polygon(&b.xy));
+                }
+                GdsElement::GdsBox(b) => {
+                    flat.entry((b.layer, b.boxtype))
+                        .or_default()
+                        .push(polygon(&b.xy));
+                }
+                GdsElement::GdsPath(path) => {
+                    flat.entry((path.layer, path.datatype)).or_default().extend(
+                        path.to_polygons().into_iter().map(|xy| {
+                            Polygon::from_verts(
+                                xy.iter()
+                                    .map(|p| Point::new(p.x as i64, p.y as i64))
+                                    .collect(),
+                            )
+                        }),
+                    );
+                }
+                GdsElement::GdsStructRef(GdsStructRef {
+                    name, xy, strans, ..
+                }) => {
+                    let child = self.flatten_inner(name, stack)?;
+                    let offset = (xy.x as i64, xy.y as i64);
+                    add_transformed(&mut flat, &child, &Placement::new(offset, strans.as_ref()));
+                }
+                GdsElement::GdsArrayRef(aref) => {
+                    let child = self.flatten_inner(&aref.name, stack)?;
+                    for placement in array_placements(aref) {
+                        add_transformed(&mut flat, &child, &placement);
+                    }
+                }
+                GdsElement::GdsTextElem(_) | GdsElement::GdsNode(_) => {}
+            }
+        }
+        stack.pop();
+        for child in children(strukt) {
+            self.release(child);
+        }
+
+        let flat = Arc::new(flat);
+        self.cache.insert(name.clone(), flat.clone());
+        Ok(flat)
+    }
+}
+
+/// Returns the distinct cells instantiated directly by `strukt`.
+fn children(strukt: &GdsStruct) -> HashSet<&ArcStr> {
+    strukt
+        .elems
+        .iter()
+        .filter_map(|elem| match elem {
+            GdsElement::GdsStructRef(sref) => Some(&sref.name),
+            GdsElement::GdsArrayRef(aref) => Some(&aref.name),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The placement of an instance: reflection about the x-axis, magnification, and
+/// counter-clockwise rotation, followed by a translation.
+struct Placement {
+    offset: (f64, f64),
+    reflected: bool,
+    mag: f64,
+    angle: f64,
+}
+
+impl Placement {
+    fn new(offset: (i64, i64), strans: Option<&GdsStrans>) -> Self {
+        Self {
+            offset: (offset.0 as f64, offset.1 as f64),
+            reflected: strans.map(|s| s.reflected).unwrap_or_default(),
+            mag: strans.and_then(|s| s.mag).unwrap_or(1.),
+            angle: strans.and_then(|s| s.angle).unwrap_or_default(),
+        }
+    }
+
+    fn apply(&self, p: Point) -> Point {
+        let (x, mut y) = (p.x as f64 * self.mag, p.y as f64 * self.mag);
+        if self.reflected {
+            y = -y;
+        }
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        Point::new(
+            (x * cos - y * sin + self.offset.0).round() as i64,
+            (x * sin + y * cos + self.offset.1).round() as i64,
+        )
+    }
+}
+
+fn add_transformed(flat: &mut FlatLayers, child: &FlatLayers, placement: &Placement) {
+    for (layer, polygons) in child.iter() {
+        flat.entry(*layer)
+            .or_default()
+            .extend(polygons.iter().map(|polygon| {
+                Polygon::from_verts(
+                    polygon
+                        .points()
+                        .iter()
+                        .map(|p| placement.apply(*p))
+                        .collect(),
+                )
+            }));
+    }
+}
+
+/// Returns the placements of the instances of an array reference.
+fn array_placements(aref: &GdsArrayRef) -> Vec<Placement> {
+    let (cols, rows) = (aref.cols.max(0) as i64, aref.rows.max(0) as i64);
+    let [origin, col_end, row_end] = &aref.xy;
+    let step = |end: &GdsPoint, n: i64| {
+        if n == 0 {
+            (0, 0)
+        } else {
+            (
+                (end.x as i64 - origin.x as i64) / n,
+                (end.y as i64 - origin.y as i64) / n,
+            )
+        }
+    };
+    let (col_step, row_step) = (step(col_end, cols), step(row_end, rows));
+
+    let mut placements = Vec::new();
+    for row in 0..rows {
+        for col in 0..cols {
+            // Offsets are kept in 64 bits, since they need not fit in a GDS point.
+            let offset = (
+                origin.x as i64 + col * col_step.0 + row * row_step.0,
+                origin.y as i64 + col * col_step.1 + row * row_step.1,
+            );
+            placements.push(Placement::new(offset, aref.strans.as_ref()));
+        }
+    }
+    placements
+}
+
+/// Converts the vertices of a boundary or box, in which the first point is repeated at the end.
+fn polygon(xy: &[GdsPoint]) -> Polygon {
+    let mut points = xy
+        .iter()
+        .map(|p| Point::new(p.x as i64, p.y as i64))
+        .collect::<Vec<_>>();
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    Polygon::from_verts(points)
+}
+
+/// Converts a region to polygons covering the same area.
+fn region_polygons(region: &Region) -> Vec<Polygon> {
+    match region.rects() {
+        Some(rects) => rects
+            .into_iter()
+            .map(|r| {
+                Polygon::from_verts(vec![
+                    Point::new(r.left(), r.bot()),
+                    Point::new(r.right(), r.bot()),
+                    Point::new(r.right(), r.top()),
+                    Point::new(r.left(), r.top()),
+                ])
+            })
+            .collect(),
+        None => region
+            .trapezoids()
+            .iter()
+            .map(|t| Polygon::from_verts(t.vertices()))
+            .collect(),
+    }
+}
@@ -74,6 +74,9 @@
 //! Note these text-based representations will generally be substantially larger than binary GDSII data.
 #![warn(missing_docs)]
 
+#[cfg(feature = "compare")]
+pub mod compare;
+mod path;
 #[doc(hidden)]
 mod read;
 mod ser;
@@ -82,6 +85,7 @@ mod tests;
 #[doc(hidden)]
 mod write;
 
+use std::collections::{HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::error::Error;
 use std::fs::File;
@@ -104,6 +108,7 @@ extern crate derive_builder;
 // Internal Modules
 use read::{GdsParser, GdsScanner, GdsStructScan};
 pub use ser::{SerdeFile, SerializationFormat};
+pub use write::GdsStreamWriter;
 use write::GdsWriter;
 
 /// An enumeration of GDS record types.
@@ -364,6 +369,32 @@ pub struct GdsStrans {
 #[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct GdsPresentation(u8, u8);
 
+impl GdsPresentation {
+    /// Creates presentation flags from a font number and justifications.
+    ///
+    /// The font number ranges from 0 to 3.
+    /// Vertical justification is 0 for top, 1 for middle, and 2 for bottom;
+    /// horizontal justification is 0 for left, 1 for center, and 2 for right.
+    pub fn new(font: u8, vertical: u8, horizontal: u8) -> Self {
+        Self(
+            0,
+            (font & 0b11) << 4 | (vertical & 0b11) << 2 | (horizontal & 0b11),
+        )
+    }
+    /// Gets the font number.
+    pub fn font(&self) -> u8 {
+        (self.1 >> 4) & 0b11
+    }
+    /// Gets the vertical justification: 0 for top, 1 for middle, or 2 for bottom.
+    pub fn vertical(&self) -> u8 {
+        (self.1 >> 2) & 0b11
+    }
+    /// Gets the horizontal justification: 0 for left, 1 for center, or 2 for right.
+    pub fn horizontal(&self) -> u8 {
+        self.1 & 0b11
+    }
+}
+
 /// GDS element flags.
 ///
 /// As configured by `ELFLAGS` records.
@@ -408,7 +439,7 @@ impl GdsUnits {
     }
     /// Gets the user-unit size in meters. Largely for display/debug.
     pub fn user_unit(&self) -> f64 {
-        self.0 / self.1
+        self.1 / self.0
     }
 }
 
@@ -817,6 +848,20 @@ pub struct GdsStats {
     boxes: usize,
 }
 
+impl fmt::Display for GdsStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "libraries:   {}", self.libraries)?;
+        writeln!(f, "structs:     {}", self.structs)?;
+        writeln!(f, "boundaries:  {}", self.boundaries)?;
+        writeln!(f, "paths:       {}", self.paths)?;
+        writeln!(f, "struct refs: {}", self.struct_refs)?;
+        writeln!(f, "array refs:  {}", self.array_refs)?;
+        writeln!(f, "text elems:  {}", self.text_elems)?;
+        writeln!(f, "nodes:       {}", self.nodes)?;
+        writeln!(f, "boxes:       {}", self.boxes)
+    }
+}
+
 /// GDS modification dates and times.
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct GdsDateTimes {
@@ -971,6 +1016,13 @@ impl GdsLibrary {
         GdsParser::from_bytes(bytes)?.parse_lib()
     }
 
+    /// Writes each [GdsRecord] of the GDS file at path `fname` to `writer` as JSON.
+    ///
+    /// Records are read and written one at a time, rather than loading the whole library into memory.
+    pub fn dump_records(fname: impl AsRef<Path>, writer: impl Write) -> GdsResult<()> {
+        GdsParser::open(fname)?.dump(writer)
+    }
+
     /// Runs a first-pass scan of GDSII data in `fname`.
     ///
     /// Returns a vector of [GdsStructScan]s including summary info per struct.
@@ -1006,6 +1058,102 @@ impl GdsLibrary {
     }
 }
 
+/// A helper for retrieving GDS dependencies in reverse topological order.
+///
+/// Creates a vector of references Gds structs, ordered by their instance dependencies.
+/// Each item in the ordered return value is guaranteed *not* to instantiate any item which comes later.
+/// References to structs that are not defined in the library are ignored.
+/// References that would complete a cycle of instances are also ignored;
+/// [`GdsDepOrder::try_total_order`] and [`GdsDepOrder::try_cell_order`] report them as errors.
+#[derive(Debug)]
+pub struct GdsDepOrder<'a> {
+    strukts: HashMap<ArcStr, &'a GdsStruct>,
+    stack: Vec<&'a GdsStruct>,
+    seen: HashSet<ArcStr>,
+    /// The structs whose dependencies are currently being added.
+    visiting: HashSet<ArcStr>,
+    /// The first struct found to instantiate itself, directly or indirectly.
+    cycle: Option<ArcStr>,
+}
+impl<'a> GdsDepOrder<'a> {
+    /// Creates a new [`GdsDepOrder`] for a [`GdsLibrary`].
+    pub fn new(gdslib: &'a GdsLibrary) -> Self {
+        // First create a map from names to structs
+        let mut strukts = HashMap::new();
+        for s in &gdslib.structs {
+            strukts.insert(s.name.clone(), s);
+        }
+        Self {
+            strukts,
+            stack: Vec::new(),
+            seen: HashSet::new(),
+            visiting: HashSet::new(),
+            cycle: None,
+        }
+    }
+    /// Returns a reverse topological sort of all structs in `gdslib`.
+    pub fn total_order(mut self) -> Vec<&'a GdsStruct> {
+        let strukts = self.strukts.values().copied().collect::<Vec<&GdsStruct>>();
+        for s in strukts {
+            self.push(s)
+        }
+        self.stack
+    }
+    /// Returns all dependencies of a given cell in reverse topological order.
+    pub fn cell_order(mut self, cell: impl Into<ArcStr>) -> Vec<&'a GdsStruct> {
+        if let Some(strukt) = self.strukts.get(&cell.into()) {
+            self.push(strukt);
+        }
+        self.stack
+    }
+    /// Returns a reverse topological sort of all structs in `gdslib`,
+    /// or an error if any struct instantiates itself.
+    pub fn try_total_order(mut self) -> GdsResult<Vec<&'a GdsStruct>> {
+        let strukts = self.strukts.values().copied().collect::<Vec<&GdsStruct>>();
+        for s in strukts {
+            self.push(s)
+        }
+        self.finish()
+    }
+    /// Returns all dependencies of a given cell in reverse topological order,
+    /// or an error if any of them instantiates itself.
+    pub fn try_cell_order(mut self, cell: impl Into<ArcStr>) -> GdsResult<Vec<&'a GdsStruct>> {
+        if let Some(strukt) = self.strukts.get(&cell.into()) {
+            self.push(strukt);
+        }
+        self.finish()
+    }
+    fn finish(self) -> GdsResult<Vec<&'a GdsStruct>> {
+        match self.cycle {
+            Some(name) => Err(GdsError::Str(format!("cell `{name}` instantiates itself"))),
+            None => Ok(self.stack),
+        }
+    }
+    /// Adds all of `strukt`'s dependencies, and then `strukt` itself, to the stack.
+    fn push(&mut self, strukt: &'a GdsStruct) {
+        if self.visiting.contains(&strukt.name) {
+            self.cycle.get_or_insert_with(|| strukt.name.clone());
+            return;
+        }
+        if !self.seen.contains(&strukt.name) {
+            self.visiting.insert(strukt.name.clone());
+            for elem in &strukt.elems {
+                let name = match elem {
+                    GdsElement::GdsStructRef(ref x) => &x.name,
+                    GdsElement::GdsArrayRef(ref x) => &x.name,
+                    _ => continue,
+                };
+                if let Some(dep) = self.strukts.get(name) {
+                    self.push(dep);
+                }
+            }
+            self.visiting.remove(&strukt.name);
+            self.seen.insert(strukt.name.clone());
+            self.stack.push(strukt);
+        }
+    }
+}
+
 // Enable [GdsLibrary] and [GdsStruct] serialization to file, in each of `utils` supported formats.
 impl SerdeFile for GdsLibrary {}
 impl SerdeFile for GdsStruct {}
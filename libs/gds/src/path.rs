@@ -0,0 +1,175 @@
+//! Conversion of paths to polygons.
+
+use std::f64::consts::PI;
+
+use crate::{GdsPath, GdsPoint};
+
+/// The number of vertices used to approximate the circle at a round path end.
+const ROUND_END_VERTICES: usize = 32;
+
+/// A point or direction in floating point coordinates.
+#[derive(Clone, Copy)]
+struct Vec2 {
+    x: f64,
+    y: f64,
+}
+
+impl Vec2 {
+    fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    fn add(self, other: Self, scale: f64) -> Self {
+        Self::new(self.x + other.x * scale, self.y + other.y * scale)
+    }
+
+    fn dot(self, other: Self) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    fn cross(self, other: Self) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// The unit normal to the left of this direction.
+    fn left(self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    fn point(self) -> GdsPoint {
+        GdsPoint::new(self.x.round() as i32, self.y.round() as i32)
+    }
+}
+
+impl GdsPath {
+    /// Converts this path to polygons that together cover the same area.
+    ///
+    /// Each segment becomes a rectangle, and each bend a miter joint. Round ends
+    /// (path type 1) are approximated by polygons with 32 vertices. Vertices are rounded to
+    /// the nearest database unit, so paths of odd width may grow or shrink by up to one unit.
+    ///
+    /// Each polygon is given by its vertices, without repeating the first vertex.
+    /// Returns no polygons for zero-width paths.
+    pub fn to_polygons(&self) -> Vec<Vec<GdsPoint>> {
+        let half_width = self.width.unwrap_or_default().unsigned_abs() as f64 / 2.;
+        let mut points = self
+            .xy
+            .iter()
+            .map(|p| Vec2::new(p.x as f64, p.y as f64))
+            .collect::<Vec<_>>();
+        points.dedup_by(|a, b| a.x == b.x && a.y == b.y);
+        if half_width == 0. || points.is_empty() {
+            return Vec::new();
+        }
+
+        let path_type = self.path_type.unwrap_or_default();
+        let (begin_extn, end_extn) = match path_type {
+            2 => (half_width, half_width),
+            4 => (
+                self.begin_extn.unwrap_or_default() as f64,
+                self.end_extn.unwrap_or_default() as f64,
+            ),
+            _ => (0., 0.),
+        };
+
+        let mut polygons = Vec::new();
+        if path_type == 1 {
+            for &end in [points[0], points[points.len() - 1]]
+                .iter()
+                .take(points.len())
+            {
+                polygons.push(circle(end, half_width));
+            }
+        }
+        if points.len() == 1 {
+            // A single point only has area if its ends are extended in some direction.
+            if path_type == 2 {
+                let dir = Vec2::new(1., 0.);
+                polygons.push(rectangle(points[0], points[0], dir, half_width, half_width));
+            }
+            return polygons;
+        }
+
+        let dirs = points
+            .windows(2)
+            .map(|pair| {
+                let (dx, dy) = (pair[1].x - pair[0].x, pair[1].y - pair[0].y);
+                let len = dx.hypot(dy);
+                Vec2::new(dx / len, dy / len)
+            })
+            .collect::<Vec<_>>();
+
+        let last = dirs.len() - 1;
+        for (i, (pair, &dir)) in points.windows(2).zip(dirs.iter()).enumerate() {
+            let start = if i == 0 { begin_extn } else { 0. };
+            let end = if i == last { end_extn } else { 0. };
+            polygons.push(rectangle(
+                pair[0].add(dir, -start),
+                pair[1].add(dir, end),
+                dir,
+                half_width,
+                half_width,
+            ));
+        }
+
+        for (vertex, pair) in points[1..].iter().zip(dirs.windows(2)) {
+            let (d0, d1) = (pair[0], pair[1]);
+            let cross = d0.cross(d1);
+            if cross == 0. && d0.dot(d1) > 0. {
+                continue;
+            }
+            // The joint fills the outside of the bend.
+            let side = if cross > 0. { -1. } else { 1. };
+            let (n0, n1) = (d0.left(), d1.left());
+            let denom = 1. + n0.dot(n1);
+            if denom < 1e-9 {
+                // The path reverses direction, so the miter is unbounded.
+                // Square off the bend instead.
+                polygons.push(rectangle(*vertex, *vertex, d0, half_width, half_width));
+                continue;
+            }
+            let miter = vertex
+                .add(n0, side * half_width / denom)
+                .add(n1, side * half_width / denom);
+            polygons.push(vec![
+                vertex.point(),
+                vertex.add(n0, side * half_width).point(),
+                miter.point(),
+                vertex.add(n1, side * half_width).point(),
+            ]);
+        }
+
+        polygons
+    }
+}
+
+/// Returns the rectangle of half width `half_width` around the segment from `p0` to `p1`
+/// in direction `dir`, extended by `extn` past each end if the segment is a single point.
+fn rectangle(p0: Vec2, p1: Vec2, dir: Vec2, half_width: f64, extn: f64) -> Vec<GdsPoint> {
+    let (p0, p1) = if p0.x == p1.x && p0.y == p1.y {
+        (p0.add(dir, -extn), p1.add(dir, extn))
+    } else {
+        (p0, p1)
+    };
+    let normal = dir.left();
+    vec![
+        p0.add(normal, -half_width).point(),
+        p1.add(normal, -half_width).point(),
+        p1.add(normal, half_width).point(),
+        p0.add(normal, half_width).point(),
+    ]
+}
+
+/// Approximates the circle of the given radius around `center`.
+fn circle(center: Vec2, radius: f64) -> Vec<GdsPoint> {
+    (0..ROUND_END_VERTICES)
+        .map(|i| {
+            let angle = 2. * PI * i as f64 / ROUND_END_VERTICES as f64;
+            Vec2::new(
+                center.x + radius * angle.cos(),
+                center.y + radius * angle.sin(),
+            )
+            .point()
+        })
+        .collect()
+}
@@ -870,7 +870,6 @@ impl GdsParser {
     }
 
     /// JSON-serializes and writes all contents of the iterator to `writer`
-    #[cfg(test)]
     pub fn write_records(&mut self, writer: &mut impl Write) -> GdsResult<()> {
         loop {
             let r = self.next()?;
@@ -880,25 +879,27 @@ impl GdsParser {
             let entry: (usize, GdsRecord) = (self.numread, r);
             let s = serde_json::to_string(&entry).unwrap();
             write!(writer, "\t")?;
-            writer.write_all(s.as_bytes()).unwrap();
+            writer.write_all(s.as_bytes())?;
             writeln!(writer, ",")?;
         }
     }
 
-    /// Opens a GDS file `gds` and writes all [GdsRecord]s to JSON file `json`.
-    #[cfg(test)]
-    pub fn dump(gds: &str, json: &str) -> GdsResult<()> {
+    /// Writes all remaining [GdsRecord]s to `writer` as a JSON list.
+    pub fn dump(&mut self, writer: impl Write) -> GdsResult<()> {
         // This streams one record at a time, rather than loading all into memory.
-        // Create a ReaderIter from `gds`
-        let mut me = Self::open(gds)?;
-        // Create the JSON file
-        let mut w = BufWriter::new(File::create(json)?);
+        let mut w = BufWriter::new(writer);
         // Write it as a JSON list/sequence; add the opening bracket
         writeln!(w, "[")?;
         // Write all the records
-        me.write_records(&mut w)?;
+        self.write_records(&mut w)?;
         // And close the list
         writeln!(w, "]")?;
         Ok(())
     }
+
+    /// Opens a GDS file `gds` and writes all [GdsRecord]s to JSON file `json`.
+    #[cfg(test)]
+    pub fn dump_file(gds: &str, json: &str) -> GdsResult<()> {
+        Self::open(gds)?.dump(File::create(json)?)
+    }
 }
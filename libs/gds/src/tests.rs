@@ -1,3 +1,6 @@
+#[cfg(feature = "compare")]
+use geometry::rect::Rect;
+
 use super::*;
 
 /// Specified creation date for test cases.
@@ -32,6 +35,18 @@ fn floats() -> GdsResult<()> {
     Ok(())
 }
 
+#[test]
+fn units() {
+    // The default 1nm database unit is 1e-3 of a 1µm user unit.
+    let units = GdsUnits::default();
+    assert_eq!(units.db_unit(), 1e-9);
+    assert!((units.user_unit() - 1e-6).abs() < 1e-18);
+
+    let units = GdsUnits::new(1e-2, 1e-8);
+    assert_eq!(units.db_unit(), 1e-8);
+    assert!((units.user_unit() - 1e-6).abs() < 1e-18);
+}
+
 #[test]
 fn stats() -> GdsResult<()> {
     // Test collecting statistics
@@ -74,7 +89,7 @@ fn it_reads() -> GdsResult<()> {
 
 #[test]
 fn it_dumps_records() -> GdsResult<()> {
-    GdsParser::dump(&resource("sample1.gds"), &resource("sample1.records.json"))?;
+    GdsParser::dump_file(&resource("sample1.gds"), &resource("sample1.records.json"))?;
     Ok(())
 }
 
@@ -179,6 +194,334 @@ fn record_too_long() -> GdsResult<()> {
     }
 }
 
+#[test]
+fn dep_order() -> GdsResult<()> {
+    let sref = |name: &str| -> GdsElement {
+        GdsStructRef {
+            name: name.into(),
+            ..Default::default()
+        }
+        .into()
+    };
+    let mut lib = GdsLibrary::new("deps");
+    let mut top = GdsStruct::new("top");
+    top.elems.push(sref("mid"));
+    top.elems.push(sref("leaf"));
+    let mut mid = GdsStruct::new("mid");
+    mid.elems.push(sref("leaf"));
+    // References to undefined cells are ignored
+    mid.elems.push(sref("undefined"));
+    lib.structs.push(top);
+    lib.structs.push(mid);
+    lib.structs.push(GdsStruct::new("leaf"));
+    lib.structs.push(GdsStruct::new("unused"));
+
+    let names = |order: Vec<&GdsStruct>| order.iter().map(|s| s.name.clone()).collect::<Vec<_>>();
+    assert_eq!(
+        names(GdsDepOrder::new(&lib).cell_order("top")),
+        vec!["leaf", "mid", "top"]
+    );
+    assert_eq!(
+        names(GdsDepOrder::new(&lib).cell_order("mid")),
+        vec!["leaf", "mid"]
+    );
+    assert!(GdsDepOrder::new(&lib).cell_order("undefined").is_empty());
+    assert_eq!(GdsDepOrder::new(&lib).total_order().len(), 4);
+    assert_eq!(GdsDepOrder::new(&lib).try_total_order()?.len(), 4);
+
+    // References that complete a cycle are ignored, or reported by the fallible orders.
+    lib.structs[2].elems.push(sref("top"));
+    assert_eq!(
+        names(GdsDepOrder::new(&lib).cell_order("top")),
+        vec!["leaf", "mid", "top"]
+    );
+    assert!(GdsDepOrder::new(&lib).try_cell_order("top").is_err());
+    assert!(GdsDepOrder::new(&lib).try_total_order().is_err());
+    assert!(GdsDepOrder::new(&lib).try_cell_order("unused").is_ok());
+    Ok(())
+}
+
+#[test]
+fn stream_writer() -> GdsResult<()> {
+    let lib = GdsLibrary::load(resource("sample1.gds"))?;
+    let mut expected = Vec::new();
+    lib.write(&mut expected)?;
+
+    // Write the header alone, then each struct separately
+    let mut header = lib.clone();
+    header.structs.clear();
+    let mut streamed = Vec::new();
+    let mut writer = GdsStreamWriter::new(&mut streamed, &header)?;
+    for strukt in lib.structs.iter() {
+        writer.write_struct(strukt)?;
+    }
+    writer.finish()?;
+
+    assert_eq!(streamed, expected);
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "compare")]
+fn compare_identical() -> GdsResult<()> {
+    let lib = GdsLibrary::load(resource("sample1.gds"))?;
+    let comparison = compare::GdsComparison::new(&lib, &reload(&lib)?)?;
+    assert!(comparison.is_identical());
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "compare")]
+fn compare_ignores_representation() -> GdsResult<()> {
+    // A path and the equivalent boundary, drawn with different starting vertices
+    let mut left = GdsLibrary::new("left");
+    let mut cell = GdsStruct::new("cell");
+    cell.elems.push(
+        GdsPath {
+            layer: 1,
+            datatype: 0,
+            xy: GdsPoint::vec(&[(0, 0), (100, 0)]),
+            width: Some(20),
+            path_type: Some(2),
+            ..Default::default()
+        }
+        .into(),
+    );
+    left.structs.push(cell);
+
+    let mut right = GdsLibrary::new("right");
+    let mut cell = GdsStruct::new("cell");
+    cell.elems.push(
+        GdsBoundary {
+            layer: 1,
+            datatype: 0,
+            xy: GdsPoint::vec(&[(110, 10), (-10, 10), (-10, -10), (110, -10), (110, 10)]),
+            ..Default::default()
+        }
+        .into(),
+    );
+    right.structs.push(cell);
+
+    assert!(compare::GdsComparison::new(&left, &right)?.is_identical());
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "compare")]
+fn compare_reports_differences() -> GdsResult<()> {
+    let rect = |layer, x0, y0, x1, y1| -> GdsElement {
+        GdsBoundary {
+            layer,
+            datatype: 0,
+            xy: GdsPoint::vec(&[(x0, y0), (x1, y0), (x1, y1), (x0, y1), (x0, y0)]),
+            ..Default::default()
+        }
+        .into()
+    };
+    let sref = |name: &str, x, y, angle: Option<f64>| -> GdsElement {
+        GdsStructRef {
+            name: name.into(),
+            xy: GdsPoint::new(x, y),
+            strans: angle.map(|angle| GdsStrans {
+                angle: Some(angle),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+        .into()
+    };
+
+    let mut leaf = GdsStruct::new("leaf");
+    leaf.elems.push(rect(1, 0, 0, 100, 50));
+
+    let mut left = GdsLibrary::new("left");
+    let mut top = GdsStruct::new("top");
+    top.elems.push(sref("leaf", 0, 0, None));
+    top.elems.push(rect(2, 0, 0, 10, 10));
+    left.structs.push(leaf.clone());
+    left.structs.push(top);
+    left.structs.push(GdsStruct::new("unused"));
+
+    // Rotate the instance by 90 degrees, and add a second instance
+    let mut right = GdsLibrary::new("right");
+    let mut top = GdsStruct::new("top");
+    top.elems.push(sref("leaf", 50, 0, Some(90.)));
+    top.elems.push(sref("leaf", 1000, 0, None));
+    top.elems.push(rect(2, 0, 0, 10, 10));
+    right.structs.push(leaf);
+    right.structs.push(top);
+    right.structs.push(GdsStruct::new("new"));
+
+    let comparison = compare::GdsComparison::new(&left, &right)?;
+    assert!(!comparison.is_identical());
+    assert_eq!(comparison.missing_cells, vec![ArcStr::from("unused")]);
+    assert_eq!(comparison.extra_cells, vec![ArcStr::from("new")]);
+    assert_eq!(comparison.cells.len(), 1);
+
+    let top = &comparison.cells[0];
+    assert_eq!(top.name, "top");
+    assert_eq!(
+        top.instances,
+        vec![compare::InstanceDiff {
+            cell: "leaf".into(),
+            left: 1,
+            right: 2,
+        }]
+    );
+    assert_eq!(top.layers.len(), 1);
+    let diff = &top.layers[0];
+    assert_eq!(diff.layer, GdsLayerSpec::new(1, 0));
+    // The rotated instance covers (0, 0) to (50, 100), overlapping the original in a
+    // 50 by 50 square; the second instance is entirely new.
+    assert_eq!(diff.area(), 2 * 2500 + 5000);
+    assert!(diff.regions.contains(&Rect::from_sides(1000, 0, 1100, 50)));
+
+    // The difference library contains the XOR on the original layer
+    let xor = reload(&comparison.to_gds("xor", left.units.clone())?)?;
+    let mut empty = GdsLibrary::new("empty");
+    empty.structs.push(GdsStruct::new("top"));
+    let xor = compare::GdsComparison::new(&empty, &xor)?;
+    assert_eq!(xor.cells[0].layers[0].layer, GdsLayerSpec::new(1, 0));
+    assert_eq!(xor.cells[0].layers[0].area(), diff.area());
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "compare")]
+fn compare_round_path_ends() -> GdsResult<()> {
+    let lib = |path_type| {
+        let mut lib = GdsLibrary::new("lib");
+        let mut cell = GdsStruct::new("cell");
+        cell.elems.push(
+            GdsPath {
+                layer: 1,
+                datatype: 0,
+                xy: GdsPoint::vec(&[(0, 0), (100, 0)]),
+                width: Some(20),
+                path_type: Some(path_type),
+                ..Default::default()
+            }
+            .into(),
+        );
+        lib.structs.push(cell);
+        lib
+    };
+
+    let comparison = compare::GdsComparison::new(&lib(0), &lib(1))?;
+    let diff = &comparison.cells[0].layers[0];
+    // The round ends add two half disks of radius 10 beyond the ends of the path.
+    assert!((diff.area() - 314).abs() < 10);
+    assert_eq!(diff.regions.len(), 2);
+    assert!(diff
+        .regions
+        .iter()
+        .all(|r| r.right() <= 0 || r.left() >= 100));
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "compare")]
+fn compare_reports_differences_out_of_gds_range() -> GdsResult<()> {
+    let mut leaf = GdsStruct::new("leaf");
+    leaf.elems.push(
+        GdsBoundary {
+            layer: 1,
+            datatype: 0,
+            xy: GdsPoint::vec(&[(0, 0), (2000, 0), (2000, 10), (0, 10), (0, 0)]),
+            ..Default::default()
+        }
+        .into(),
+    );
+    // The second instance of the array extends past the largest GDS coordinate.
+    let x0 = i32::MAX - 2000;
+    let mut top = GdsStruct::new("top");
+    top.elems.push(
+        GdsArrayRef {
+            name: "leaf".into(),
+            xy: [
+                GdsPoint::new(x0, 0),
+                GdsPoint::new(i32::MAX, 0),
+                GdsPoint::new(x0, 100),
+            ],
+            cols: 2,
+            rows: 1,
+            ..Default::default()
+        }
+        .into(),
+    );
+    let mut left = GdsLibrary::new("left");
+    left.structs.push(leaf.clone());
+    left.structs.push(top);
+    let mut right = GdsLibrary::new("right");
+    right.structs.push(leaf);
+    right.structs.push(GdsStruct::new("top"));
+
+    let comparison = compare::GdsComparison::new(&left, &right)?;
+    let diff = &comparison.cells[0].layers[0];
+    // The overlapping instances cover 3000 by 10 units.
+    assert_eq!(diff.area(), 3000 * 10);
+    assert!(comparison.to_gds("xor", left.units.clone()).is_err());
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "compare")]
+fn compare_evicts_flattened_cells() -> GdsResult<()> {
+    let lib = GdsLibrary::load(resource("sample1.gds"))?;
+    let names = lib
+        .structs
+        .iter()
+        .map(|s| s.name.clone())
+        .collect::<Vec<_>>();
+    let mut flattener = compare::Flattener::new(&lib, names.iter());
+    for name in names.iter() {
+        flattener.flatten(name)?;
+    }
+    assert!(flattener.cache.is_empty());
+    Ok(())
+}
+
+#[test]
+fn path_to_polygons() {
+    let path = |path_type| GdsPath {
+        layer: 1,
+        datatype: 0,
+        xy: GdsPoint::vec(&[(0, 0), (100, 0), (100, 100)]),
+        width: Some(20),
+        path_type: Some(path_type),
+        ..Default::default()
+    };
+
+    // Two segments and a miter joint filling the outside of the bend
+    let polygons = path(0).to_polygons();
+    assert_eq!(polygons.len(), 3);
+    assert_eq!(
+        polygons[0],
+        GdsPoint::vec(&[(0, -10), (100, -10), (100, 10), (0, 10)])
+    );
+    assert_eq!(
+        polygons[2],
+        GdsPoint::vec(&[(100, 0), (100, -10), (110, -10), (110, 0)])
+    );
+
+    // Round ends add a circle at each end
+    let polygons = path(1).to_polygons();
+    assert_eq!(polygons.len(), 5);
+    assert!(polygons[0]
+        .iter()
+        .all(|p| p.x.abs() <= 10 && p.y.abs() <= 10));
+    assert!(polygons[0].contains(&GdsPoint::new(-10, 0)));
+    assert!(polygons[1].contains(&GdsPoint::new(100, 110)));
+}
+
+/// Write `lib` to bytes and read it back.
+#[cfg(feature = "compare")]
+fn reload(lib: &GdsLibrary) -> GdsResult<GdsLibrary> {
+    let mut bytes = Vec::new();
+    lib.write(&mut bytes)?;
+    GdsLibrary::from_bytes(bytes)
+}
+
 /// Compare `lib` to "golden" data loaded from JSON at path `golden`.
 fn check(lib: &GdsLibrary, fname: impl AsRef<Path>) {
     use crate::ser::SerializationFormat::Json;
@@ -218,6 +218,54 @@ impl<'wr> GdsWriter<'wr> {
     }
 }
 
+/// A writer that encodes a [GdsLibrary] one [GdsStruct] at a time.
+///
+/// Unlike [GdsLibrary::save], which requires the entire library to be in memory,
+/// each struct can be dropped as soon as it has been written.
+/// The library is not complete until [GdsStreamWriter::finish] is called.
+pub struct GdsStreamWriter<'wr> {
+    writer: GdsWriter<'wr>,
+}
+
+impl<'wr> GdsStreamWriter<'wr> {
+    /// Creates a new [GdsStreamWriter] with destination file `fname`.
+    ///
+    /// Writes the header of `lib`, including its name, units, and dates.
+    /// The structs of `lib` are written immediately.
+    pub fn open(fname: impl AsRef<Path>, lib: &GdsLibrary) -> GdsResult<Self> {
+        if let Some(prefix) = fname.as_ref().parent() {
+            std::fs::create_dir_all(prefix)?;
+        }
+        let file = BufWriter::new(File::create(fname)?);
+        Self::new(file, lib)
+    }
+
+    /// Creates a new [GdsStreamWriter] to destination `dest`.
+    ///
+    /// Writes the header of `lib`, including its name, units, and dates.
+    /// The structs of `lib` are written immediately.
+    pub fn new(dest: impl Write + 'wr, lib: &GdsLibrary) -> GdsResult<Self> {
+        let mut writer = GdsWriter::new(dest);
+        writer.encode_lib_header(lib)?;
+        for strukt in lib.structs.iter() {
+            writer.encode_struct(strukt)?;
+        }
+        Ok(Self { writer })
+    }
+
+    /// Writes [GdsStruct] `strukt`.
+    pub fn write_struct(&mut self, strukt: &GdsStruct) -> GdsResult<()> {
+        self.writer.encode_struct(strukt)
+    }
+
+    /// Writes the library terminator and flushes the destination.
+    pub fn finish(mut self) -> GdsResult<()> {
+        self.writer.encode_record(GdsRecord::EndLib)?;
+        self.writer.dest.flush()?;
+        Ok(())
+    }
+}
+
 /// [Encode] implementation for [GdsWriter].
 ///
 /// Dispatches record-level calls back to the `write_record(s)` methods.
@@ -249,7 +297,18 @@ trait Encode {
     // Default Methods
     /// Encodes a [GdsLibrary].
     fn encode_lib(&mut self, lib: &GdsLibrary) -> GdsResult<()> {
-        // Write our header content
+        self.encode_lib_header(lib)?;
+        // Write all of our Structs/Cells
+        for strukt in lib.structs.iter() {
+            self.encode_struct(strukt)?;
+        }
+        // And finally, the library terminator
+        self.encode_record(GdsRecord::EndLib)?;
+        Ok(())
+    }
+
+    /// Encodes the records preceding the structs of a [GdsLibrary].
+    fn encode_lib_header(&mut self, lib: &GdsLibrary) -> GdsResult<()> {
         self.encode_records(&[
             GdsRecord::Header {
                 version: lib.version,
@@ -259,14 +318,7 @@ trait Encode {
             },
             GdsRecord::LibName(lib.name.clone()),
             GdsRecord::Units(lib.units.0, lib.units.1),
-        ])?;
-        // Write all of our Structs/Cells
-        for strukt in lib.structs.iter() {
-            self.encode_struct(strukt)?;
-        }
-        // And finally, the library terminator
-        self.encode_record(GdsRecord::EndLib)?;
-        Ok(())
+        ])
     }
 
     /// Encodes a [GdsStruct].
@@ -0,0 +1,762 @@
+//! Boolean operations on sets of polygons.
+//!
+//! Operands are decomposed into vertical slabs, within which the region covered
+//! by each operand is a set of disjoint trapezoids. The result of an operation is
+//! stored in the same form, and can be converted back into boundary polygons or,
+//! for Manhattan regions, into rectangles.
+//!
+//! Inputs with only horizontal and vertical edges are processed exactly.
+//! Vertices of non-Manhattan results (e.g. edge intersections) are rounded to the
+//! nearest integer coordinate.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::bbox::Bbox;
+use crate::intersect::Intersect;
+use crate::point::Point;
+use crate::polygon::Polygon;
+use crate::rect::Rect;
+use crate::union::Union;
+
+/// The tolerance used when comparing computed coordinates of non-Manhattan edges.
+const EPSILON: f64 = 1e-9;
+
+/// A boolean operation on two regions.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum BooleanOp {
+    /// Points covered by either region.
+    Union,
+    /// Points covered by both regions.
+    Intersection,
+    /// Points covered by the first region but not the second.
+    Difference,
+    /// Points covered by exactly one of the regions.
+    Xor,
+}
+
+impl BooleanOp {
+    fn apply(&self, a: bool, b: bool) -> bool {
+        match self {
+            Self::Union => a || b,
+            Self::Intersection => a && b,
+            Self::Difference => a && !b,
+            Self::Xor => a != b,
+        }
+    }
+}
+
+/// A trapezoid with vertical left and right sides.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Trapezoid {
+    /// The x-coordinate of the left side.
+    pub x0: i64,
+    /// The x-coordinate of the right side.
+    pub x1: i64,
+    /// The y-coordinates of the bottom edge at `x0` and `x1`.
+    pub bot: (i64, i64),
+    /// The y-coordinates of the top edge at `x0` and `x1`.
+    pub top: (i64, i64),
+}
+
+impl Trapezoid {
+    /// Returns `true` if this trapezoid is an axis-aligned rectangle.
+    #[inline]
+    pub fn is_rect(&self) -> bool {
+        self.bot.0 == self.bot.1 && self.top.0 == self.top.1
+    }
+
+    /// Returns the vertices of this trapezoid in counterclockwise order,
+    /// omitting repeated vertices.
+    pub fn vertices(&self) -> Vec<Point> {
+        let mut pts = vec![
+            Point::new(self.x0, self.bot.0),
+            Point::new(self.x1, self.bot.1),
+            Point::new(self.x1, self.top.1),
+            Point::new(self.x0, self.top.0),
+        ];
+        pts.dedup();
+        if pts.len() > 1 && pts.first() == pts.last() {
+            pts.pop();
+        }
+        pts
+    }
+
+    /// Returns twice the area of this trapezoid.
+    fn double_area(&self) -> i128 {
+        let h0 = (self.top.0 - self.bot.0) as i128;
+        let h1 = (self.top.1 - self.bot.1) as i128;
+        (self.x1 - self.x0) as i128 * (h0 + h1)
+    }
+}
+
+/// A region of the plane, stored as a set of disjoint trapezoids.
+///
+/// # Examples
+///
+/// ```
+/// # use geometry::prelude::*;
+/// # use geometry::boolean::Region;
+/// let a = Region::from(Rect::from_sides(0, 0, 100, 100));
+/// let b = Region::from(Rect::from_sides(50, 50, 150, 150));
+///
+/// let union = a.union(&b);
+/// assert_eq!(union.area(), 17_500);
+/// assert_eq!(union.boundaries().len(), 1);
+///
+/// let diff = a.difference(&b);
+/// assert_eq!(diff.area(), 7_500);
+/// assert_eq!(diff.rects().unwrap().len(), 2);
+/// ```
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Region {
+    trapezoids: Vec<Trapezoid>,
+}
+
+/// A non-vertical edge of a polygon.
+#[derive(Debug, Copy, Clone)]
+struct Edge {
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+    /// `+1` if the original edge pointed in the positive x direction, `-1` otherwise.
+    dir: i32,
+    /// `false` for edges of the first operand, `true` for edges of the second.
+    second: bool,
+}
+
+impl Edge {
+    fn new(p0: Point, p1: Point, second: bool) -> Option<Self> {
+        let (x0, y0, x1, y1) = (p0.x as f64, p0.y as f64, p1.x as f64, p1.y as f64);
+        match p0.x.cmp(&p1.x) {
+            Ordering::Equal => None,
+            Ordering::Less => Some(Self {
+                x0,
+                y0,
+                x1,
+                y1,
+                dir: 1,
+                second,
+            }),
+            Ordering::Greater => Some(Self {
+                x0: x1,
+                y0: y1,
+                x1: x0,
+                y1: y0,
+                dir: -1,
+                second,
+            }),
+        }
+    }
+
+    #[inline]
+    fn is_horizontal(&self) -> bool {
+        self.y0 == self.y1
+    }
+
+    fn y_at(&self, x: f64) -> f64 {
+        if x == self.x0 || self.is_horizontal() {
+            self.y0
+        } else if x == self.x1 {
+            self.y1
+        } else {
+            self.y0 + (self.y1 - self.y0) * (x - self.x0) / (self.x1 - self.x0)
+        }
+    }
+
+    /// Returns the x-coordinate at which this edge properly crosses `other`, if any.
+    fn crossing(&self, other: &Edge) -> Option<f64> {
+        let (dx1, dy1) = (self.x1 - self.x0, self.y1 - self.y0);
+        let (dx2, dy2) = (other.x1 - other.x0, other.y1 - other.y0);
+        let denom = dx1 * dy2 - dy1 * dx2;
+        if denom == 0. {
+            return None;
+        }
+        let t = ((other.x0 - self.x0) * dy2 - (other.y0 - self.y0) * dx2) / denom;
+        let u = ((other.x0 - self.x0) * dy1 - (other.y0 - self.y0) * dx1) / denom;
+        if t > 0. && t < 1. && u > 0. && u < 1. {
+            Some(self.x0 + t * dx1)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the non-vertical edges of the polygon with the given vertices.
+///
+/// Edges of clockwise polygons are reversed, so that every polygon has a positive
+/// winding number around its interior regardless of its orientation.
+fn polygon_edges(points: &[Point], second: bool) -> impl Iterator<Item = Edge> + '_ {
+    let n = points.len();
+    let double_area = (0..n)
+        .map(|i| {
+            let (p0, p1) = (points[i], points[(i + 1) % n]);
+            p0.x as i128 * p1.y as i128 - p1.x as i128 * p0.y as i128
+        })
+        .sum::<i128>();
+    let flip = double_area < 0;
+    (0..n).filter_map(move |i| {
+        let mut edge = Edge::new(points[i], points[(i + 1) % n], second)?;
+        if flip {
+            edge.dir = -edge.dir;
+        }
+        Some(edge)
+    })
+}
+
+impl Region {
+    /// Creates an empty region.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a region covering the union of the given polygons.
+    ///
+    /// Overlapping polygons are merged, regardless of their orientation. A point is
+    /// considered to lie within a polygon if the polygon's winding number around it is nonzero.
+    pub fn from_polygons<'a>(polygons: impl IntoIterator<Item = &'a Polygon>) -> Self {
+        let edges = polygons
+            .into_iter()
+            .flat_map(|p| polygon_edges(p.points(), false))
+            .collect();
+        Self::sweep(edges, BooleanOp::Union)
+    }
+
+    /// Creates a region covering the union of the given rectangles.
+    pub fn from_rects(rects: impl IntoIterator<Item = Rect>) -> Self {
+        let edges = rects
+            .into_iter()
+            .flat_map(|r| {
+                let (l, b, r, t) = (
+                    r.left() as f64,
+                    r.bot() as f64,
+                    r.right() as f64,
+                    r.top() as f64,
+                );
+                [
+                    Edge {
+                        x0: l,
+                        y0: b,
+                        x1: r,
+                        y1: b,
+                        dir: 1,
+                        second: false,
+                    },
+                    Edge {
+                        x0: l,
+                        y0: t,
+                        x1: r,
+                        y1: t,
+                        dir: -1,
+                        second: false,
+                    },
+                ]
+            })
+            .collect();
+        Self::sweep(edges, BooleanOp::Union)
+    }
+
+    /// Returns the trapezoids that make up this region.
+    ///
+    /// The trapezoids are disjoint and ordered by their left side, then by their bottom edge.
+    #[inline]
+    pub fn trapezoids(&self) -> &[Trapezoid] {
+        &self.trapezoids
+    }
+
+    /// Returns `true` if this region covers no area.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.trapezoids.is_empty()
+    }
+
+    /// Returns `true` if this region only has horizontal and vertical edges.
+    pub fn is_manhattan(&self) -> bool {
+        self.trapezoids.iter().all(Trapezoid::is_rect)
+    }
+
+    /// Returns the area covered by this region.
+    ///
+    /// Non-Manhattan regions may have half-integer areas, which are rounded down.
+    pub fn area(&self) -> i64 {
+        (self
+            .trapezoids
+            .iter()
+            .map(Trapezoid::double_area)
+            .sum::<i128>()
+            / 2) as i64
+    }
+
+    /// Applies the given boolean operation to this region and `other`.
+    pub fn boolean(&self, other: &Region, op: BooleanOp) -> Region {
+        let edges = self
+            .edges(false)
+            .chain(other.edges(true))
+            .collect::<Vec<_>>();
+        Self::sweep(edges, op)
+    }
+
+    /// Returns the region covered by either this region or `other`.
+    #[inline]
+    pub fn union(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::Union)
+    }
+
+    /// Returns the region covered by both this region and `other`.
+    #[inline]
+    pub fn intersection(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::Intersection)
+    }
+
+    /// Returns the region covered by this region but not by `other`.
+    #[inline]
+    pub fn difference(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::Difference)
+    }
+
+    /// Returns the region covered by exactly one of this region and `other`.
+    #[inline]
+    pub fn xor(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::Xor)
+    }
+
+    /// Decomposes this region into disjoint rectangles.
+    ///
+    /// Horizontally adjacent rectangles with the same vertical extent are merged.
+    /// Returns [`None`] if the region is not Manhattan.
+    pub fn rects(&self) -> Option<Vec<Rect>> {
+        if !self.is_manhattan() {
+            return None;
+        }
+        let mut traps = self.trapezoids.clone();
+        traps.sort_by_key(|t| (t.bot.0, t.top.0, t.x0));
+        let mut rects: Vec<Rect> = Vec::with_capacity(traps.len());
+        for t in traps {
+            match rects.last_mut() {
+                Some(r) if r.bot() == t.bot.0 && r.top() == t.top.0 && r.right() == t.x0 => {
+                    *r = Rect::from_sides(r.left(), r.bot(), t.x1, r.top());
+                }
+                _ => rects.push(Rect::from_sides(t.x0, t.bot.0, t.x1, t.top.0)),
+            }
+        }
+        Some(rects)
+    }
+
+    /// Returns the boundaries of this region as polygons.
+    ///
+    /// Outer boundaries are oriented counterclockwise, while the boundaries
+    /// of holes are oriented clockwise. Collinear vertices are removed.
+    pub fn boundaries(&self) -> Vec<Polygon> {
+        // Split vertical sides of trapezoids at every vertex lying on the same vertical line,
+        // so that shared sides of adjacent trapezoids cancel exactly.
+        let mut breaks: HashMap<i64, BTreeSet<i64>> = HashMap::new();
+        for t in self.trapezoids.iter() {
+            let b0 = breaks.entry(t.x0).or_default();
+            b0.insert(t.bot.0);
+            b0.insert(t.top.0);
+            let b1 = breaks.entry(t.x1).or_default();
+            b1.insert(t.bot.1);
+            b1.insert(t.top.1);
+        }
+
+        let mut segments: HashMap<(Point, Point), usize> = HashMap::new();
+        let mut add = |p0: Point, p1: Point| {
+            if p0 == p1 {
+                return;
+            }
+            if let Some(count) = segments.get_mut(&(p1, p0)) {
+                *count -= 1;
+                if *count == 0 {
+                    segments.remove(&(p1, p0));
+                }
+            } else {
+                *segments.entry((p0, p1)).or_default() += 1;
+            }
+        };
+        for t in self.trapezoids.iter() {
+            add(Point::new(t.x0, t.bot.0), Point::new(t.x1, t.bot.1));
+            add(Point::new(t.x1, t.top.1), Point::new(t.x0, t.top.0));
+            let right = breaks[&t.x1].range(t.bot.1..=t.top.1).collect::<Vec<_>>();
+            for w in right.windows(2) {
+                add(Point::new(t.x1, *w[0]), Point::new(t.x1, *w[1]));
+            }
+            let left = breaks[&t.x0].range(t.bot.0..=t.top.0).collect::<Vec<_>>();
+            for w in left.windows(2) {
+                add(Point::new(t.x0, *w[1]), Point::new(t.x0, *w[0]));
+            }
+        }
+
+        let mut outgoing: HashMap<Point, Vec<Point>> = HashMap::new();
+        let mut starts = segments
+            .into_iter()
+            .flat_map(|((p0, p1), count)| std::iter::repeat((p0, p1)).take(count))
+            .collect::<Vec<_>>();
+        starts.sort();
+        for &(p0, p1) in starts.iter() {
+            outgoing.entry(p0).or_default().push(p1);
+        }
+
+        let mut boundaries = Vec::new();
+        for (start, _) in starts {
+            let Some(first) = outgoing.get_mut(&start).and_then(|next| next.pop()) else {
+                continue;
+            };
+            let mut pts = vec![start];
+            let (mut prev, mut cur) = (start, first);
+            while cur != start {
+                pts.push(cur);
+                let candidates = outgoing.get_mut(&cur).expect("boundary must be closed");
+                // Take the leftmost turn, which keeps loops that touch at a vertex separate.
+                let idx = (0..candidates.len())
+                    .max_by(|&i, &j| {
+                        turn_angle(prev, cur, candidates[i]).total_cmp(&turn_angle(
+                            prev,
+                            cur,
+                            candidates[j],
+                        ))
+                    })
+                    .expect("boundary must be closed");
+                let next = candidates.swap_remove(idx);
+                (prev, cur) = (cur, next);
+            }
+            let mut polygon = Polygon::from_verts(pts);
+            polygon.remove_collinear_vertices();
+            if polygon.points().len() >= 3 {
+                boundaries.push(polygon);
+            }
+        }
+        boundaries
+    }
+
+    /// Splits this region into its connected components.
+    ///
+    /// Parts of the region that only touch at a corner are separate components.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use geometry::prelude::*;
+    /// # use geometry::boolean::Region;
+    /// let region = Region::from_rects([
+    ///     Rect::from_sides(0, 0, 10, 10),
+    ///     Rect::from_sides(10, 0, 20, 30),
+    ///     Rect::from_sides(20, 30, 30, 40),
+    /// ]);
+    /// let components = region.components();
+    /// assert_eq!(components.len(), 2);
+    /// assert_eq!(components[0].area(), 400);
+    /// ```
+    pub fn components(&self) -> Vec<Region> {
+        let n = self.trapezoids.len();
+        let mut parent = (0..n).collect::<Vec<_>>();
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        let mut by_left: HashMap<i64, Vec<usize>> = HashMap::new();
+        for (i, t) in self.trapezoids.iter().enumerate() {
+            by_left.entry(t.x0).or_default().push(i);
+        }
+        for (i, a) in self.trapezoids.iter().enumerate() {
+            // Trapezoids to the right of `a`, and trapezoids in the same slab as `a`.
+            let neighbors = [a.x1, a.x0]
+                .into_iter()
+                .flat_map(|x| by_left.get(&x).into_iter().flatten().copied())
+                .collect::<Vec<_>>();
+            for j in neighbors {
+                let b = &self.trapezoids[j];
+                let touching = if a.x1 == b.x0 {
+                    a.bot.1.max(b.bot.0) < a.top.1.min(b.top.0)
+                } else {
+                    a.x0 == b.x0 && a.x1 == b.x1 && (a.top == b.bot || b.top == a.bot)
+                };
+                if touching {
+                    let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+                    parent[ri] = rj;
+                }
+            }
+        }
+
+        let mut components: Vec<Region> = Vec::new();
+        let mut index = HashMap::new();
+        for (i, t) in self.trapezoids.iter().enumerate() {
+            let root = find(&mut parent, i);
+            let k = *index.entry(root).or_insert_with(|| {
+                components.push(Region::new());
+                components.len() - 1
+            });
+            components[k].trapezoids.push(*t);
+        }
+        components
+    }
+
+    /// Returns the edges of the trapezoids making up this region.
+    fn edges(&self, second: bool) -> impl Iterator<Item = Edge> + '_ {
+        self.trapezoids.iter().flat_map(move |t| {
+            [
+                Edge::new(Point::new(t.x0, t.bot.0), Point::new(t.x1, t.bot.1), second),
+                Edge::new(Point::new(t.x1, t.top.1), Point::new(t.x0, t.top.0), second),
+            ]
+            .into_iter()
+            .flatten()
+        })
+    }
+
+    /// Computes the region resulting from applying `op` to the regions bounded by `edges`.
+    fn sweep(mut edges: Vec<Edge>, op: BooleanOp) -> Region {
+        edges.sort_by(|a, b| a.x0.total_cmp(&b.x0));
+
+        let mut xs = edges.iter().flat_map(|e| [e.x0, e.x1]).collect::<Vec<_>>();
+        if !edges.iter().all(Edge::is_horizontal) {
+            for (i, a) in edges.iter().enumerate() {
+                for b in edges[i + 1..].iter() {
+                    if b.x0 >= a.x1 {
+                        break;
+                    }
+                    xs.extend(a.crossing(b));
+                }
+            }
+        }
+        xs.sort_by(f64::total_cmp);
+        xs.dedup();
+
+        let mut trapezoids = Vec::new();
+        let mut active: Vec<Edge> = Vec::new();
+        let mut next = 0;
+        for w in xs.windows(2) {
+            let (xa, xb) = (w[0], w[1]);
+            active.retain(|e| e.x1 > xa);
+            while next < edges.len() && edges[next].x0 <= xa {
+                active.push(edges[next]);
+                next += 1;
+            }
+
+            let mut spans = active
+                .iter()
+                .map(|e| (e.y_at(xa), e.y_at(xb), e))
+                .collect::<Vec<_>>();
+            spans.sort_by(|a, b| (a.0 + a.1).total_cmp(&(b.0 + b.1)));
+
+            let (mut wa, mut wb) = (0, 0);
+            let mut bottom = None;
+            let mut i = 0;
+            while i < spans.len() {
+                let (ya, yb, _) = spans[i];
+                // Process coincident edges together, so that regions touching
+                // along an edge are merged.
+                while i < spans.len()
+                    && (spans[i].0 - ya).abs() < EPSILON
+                    && (spans[i].1 - yb).abs() < EPSILON
+                {
+                    let e = spans[i].2;
+                    if e.second {
+                        wb += e.dir;
+                    } else {
+                        wa += e.dir;
+                    }
+                    i += 1;
+                }
+                let inside = op.apply(wa != 0, wb != 0);
+                match (bottom, inside) {
+                    (None, true) => bottom = Some((ya, yb)),
+                    (Some(bot), false) => {
+                        let trap = Trapezoid {
+                            x0: xa.round() as i64,
+                            x1: xb.round() as i64,
+                            bot: (bot.0.round() as i64, bot.1.round() as i64),
+                            top: (ya.round() as i64, yb.round() as i64),
+                        };
+                        if trap.x0 < trap.x1 && trap.double_area() > 0 {
+                            trapezoids.push(trap);
+                        }
+                        bottom = None;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Region { trapezoids }
+    }
+}
+
+/// Returns the signed angle of the turn from direction `p0 -> p1` to direction `p1 -> p2`.
+fn turn_angle(p0: Point, p1: Point, p2: Point) -> f64 {
+    let (dx1, dy1) = ((p1.x - p0.x) as f64, (p1.y - p0.y) as f64);
+    let (dx2, dy2) = ((p2.x - p1.x) as f64, (p2.y - p1.y) as f64);
+    (dx1 * dy2 - dy1 * dx2).atan2(dx1 * dx2 + dy1 * dy2)
+}
+
+impl From<Rect> for Region {
+    #[inline]
+    fn from(value: Rect) -> Self {
+        Self::from_rects([value])
+    }
+}
+
+impl From<&Polygon> for Region {
+    #[inline]
+    fn from(value: &Polygon) -> Self {
+        Self::from_polygons([value])
+    }
+}
+
+impl From<Polygon> for Region {
+    #[inline]
+    fn from(value: Polygon) -> Self {
+        Self::from(&value)
+    }
+}
+
+impl Bbox for Region {
+    fn bbox(&self) -> Option<Rect> {
+        self.trapezoids
+            .iter()
+            .map(|t| Rect::from_sides(t.x0, t.bot.0.min(t.bot.1), t.x1, t.top.0.max(t.top.1)))
+            .reduce(|a, b| a.union(b))
+    }
+}
+
+impl Union<Region> for Region {
+    type Output = Region;
+
+    fn union(&self, other: &Region) -> Self::Output {
+        Region::union(self, other)
+    }
+}
+
+impl Intersect<Region> for Region {
+    type Output = Region;
+
+    fn intersect(&self, other: &Region) -> Option<Self::Output> {
+        Some(self.intersection(other)).filter(|r| !r.is_empty())
+    }
+}
+
+impl Union<Polygon> for Polygon {
+    type Output = Region;
+
+    fn union(&self, other: &Polygon) -> Self::Output {
+        Region::from(self).union(&Region::from(other))
+    }
+}
+
+impl Intersect<Polygon> for Polygon {
+    type Output = Region;
+
+    fn intersect(&self, other: &Polygon) -> Option<Self::Output> {
+        Region::from(self).intersect(&Region::from(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: i64, y: i64, size: i64) -> Polygon {
+        Polygon::from_verts(vec![
+            Point::new(x, y),
+            Point::new(x + size, y),
+            Point::new(x + size, y + size),
+            Point::new(x, y + size),
+        ])
+    }
+
+    #[test]
+    fn union_of_touching_rects_has_one_boundary() {
+        let region = Region::from_rects([
+            Rect::from_sides(0, 0, 10, 10),
+            Rect::from_sides(10, 0, 20, 10),
+            Rect::from_sides(5, 10, 15, 20),
+        ]);
+        assert_eq!(region.area(), 300);
+        let boundaries = region.boundaries();
+        assert_eq!(boundaries.len(), 1);
+        assert_eq!(boundaries[0].points().len(), 8);
+    }
+
+    #[test]
+    fn difference_can_create_holes() {
+        let outer = Region::from(Rect::from_sides(0, 0, 30, 30));
+        let inner = Region::from(Rect::from_sides(10, 10, 20, 20));
+        let ring = outer.difference(&inner);
+        assert_eq!(ring.area(), 800);
+
+        let mut boundaries = ring.boundaries();
+        boundaries.sort_by_key(|p| p.left());
+        assert_eq!(boundaries.len(), 2);
+        assert_eq!(
+            boundaries[1].points(),
+            &vec![
+                Point::new(10, 10),
+                Point::new(10, 20),
+                Point::new(20, 20),
+                Point::new(20, 10),
+            ]
+        );
+        assert_eq!(ring.rects().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn xor_of_identical_regions_is_empty() {
+        let a = Region::from(square(0, 0, 10));
+        assert!(a.xor(&a).is_empty());
+        assert!(a.intersect(&Region::from(square(20, 20, 10))).is_none());
+    }
+
+    #[test]
+    fn corner_touching_squares_stay_separate() {
+        let region = Region::from_polygons([&square(0, 0, 10), &square(10, 10, 10)]);
+        assert_eq!(region.boundaries().len(), 2);
+    }
+
+    #[test]
+    fn components_split_disjoint_parts() {
+        let region = Region::from_polygons([
+            &square(0, 0, 10),
+            &square(5, 5, 10),
+            &square(30, 0, 10),
+            &square(40, 10, 10),
+        ]);
+        let components = region.components();
+        assert_eq!(components.len(), 3);
+        assert_eq!(components[0].area(), 175);
+        assert_eq!(
+            components.iter().map(Region::area).sum::<i64>(),
+            region.area()
+        );
+    }
+
+    #[test]
+    fn non_manhattan_intersection() {
+        let triangle =
+            Polygon::from_verts(vec![Point::new(0, 0), Point::new(15, 0), Point::new(0, 15)]);
+        let region = triangle.intersect(&square(0, 0, 10)).unwrap();
+        assert!(!region.is_manhattan());
+        assert_eq!(region.area(), 87);
+        assert_eq!(region.rects(), None);
+        assert_eq!(region.bbox(), Some(Rect::from_sides(0, 0, 10, 10)));
+
+        let union = triangle.union(&square(0, 0, 10));
+        assert_eq!(union.area(), 125);
+        assert_eq!(union.boundaries().len(), 1);
+    }
+
+    #[test]
+    fn clockwise_polygons_are_merged() {
+        let mut pts = square(0, 0, 10).points().clone();
+        pts.reverse();
+        let region = Region::from_polygons([&Polygon::from_verts(pts), &square(5, 0, 10)]);
+        assert_eq!(
+            region.rects().unwrap(),
+            vec![Rect::from_sides(0, 0, 15, 10)]
+        );
+    }
+}
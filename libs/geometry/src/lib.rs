@@ -14,6 +14,7 @@ extern crate self as geometry;
 
 pub mod align;
 pub mod bbox;
+pub mod boolean;
 pub mod contains;
 pub mod corner;
 pub mod dims;
@@ -21,6 +22,7 @@ pub mod dir;
 pub mod edge;
 pub mod intersect;
 pub mod orientation;
+pub mod path;
 pub mod place;
 pub mod point;
 pub mod polygon;
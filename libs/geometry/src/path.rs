@@ -0,0 +1,308 @@
+//! Paths (wires) with a centerline and a constant width.
+
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bbox::Bbox;
+use crate::point::Point;
+use crate::polygon::Polygon;
+use crate::rect::Rect;
+use crate::transform::{TransformMut, Transformation, TranslateMut};
+
+/// The number of segments used to approximate each rounded end of a path.
+const ROUND_END_SEGMENTS: usize = 8;
+
+/// The shape of the ends of a [`Path`].
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum PathEnd {
+    /// The path ends flush with its first and last points.
+    #[default]
+    Flush,
+    /// The path is extended by half its width past its first and last points.
+    HalfWidth,
+    /// The path ends in semicircles centered at its first and last points.
+    Round,
+    /// The path is extended past its first and last points by the given amounts.
+    Custom {
+        /// The extension past the first point.
+        begin: i64,
+        /// The extension past the last point.
+        end: i64,
+    },
+}
+
+/// A path, given by a centerline and a constant width.
+///
+/// # Examples
+///
+/// ```
+/// # use geometry::prelude::*;
+/// let path = Path::new(
+///     vec![Point::new(0, 0), Point::new(100, 0), Point::new(100, 100)],
+///     20,
+///     PathEnd::Flush,
+/// );
+/// assert_eq!(path.bbox(), Some(Rect::from_sides(0, -10, 110, 100)));
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Path {
+    /// The points that make up the centerline of the path.
+    points: Vec<Point>,
+    /// The width of the path.
+    width: i64,
+    /// The shape of the ends of the path.
+    end: PathEnd,
+}
+
+impl Path {
+    /// Creates a path with the given centerline, width, and end style.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` is empty or `width` is negative.
+    pub fn new(points: Vec<Point>, width: i64, end: PathEnd) -> Self {
+        assert!(!points.is_empty(), "a path must have at least one point");
+        assert!(width >= 0, "path width must be non-negative");
+        Self { points, width, end }
+    }
+
+    /// Returns the points that make up the centerline of the path.
+    #[inline]
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    /// Returns the width of the path.
+    #[inline]
+    pub fn width(&self) -> i64 {
+        self.width
+    }
+
+    /// Returns the shape of the ends of the path.
+    #[inline]
+    pub fn end(&self) -> PathEnd {
+        self.end
+    }
+
+    /// Returns the distances that the path extends past its first and last points.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use geometry::prelude::*;
+    /// let path = Path::new(vec![Point::new(0, 0), Point::new(100, 0)], 20, PathEnd::HalfWidth);
+    /// assert_eq!(path.extensions(), (10, 10));
+    /// ```
+    pub fn extensions(&self) -> (i64, i64) {
+        match self.end {
+            PathEnd::Flush => (0, 0),
+            PathEnd::HalfWidth | PathEnd::Round => (self.width / 2, self.width / 2),
+            PathEnd::Custom { begin, end } => (begin, end),
+        }
+    }
+
+    /// Converts the path to a polygon covering the same area.
+    ///
+    /// Joints between segments are mitered. Rounded ends are approximated
+    /// by line segments.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use geometry::prelude::*;
+    /// let path = Path::new(vec![Point::new(0, 0), Point::new(100, 0)], 20, PathEnd::Flush);
+    /// assert_eq!(
+    ///     path.to_polygon().points(),
+    ///     &vec![
+    ///         Point::new(0, 10),
+    ///         Point::new(100, 10),
+    ///         Point::new(100, -10),
+    ///         Point::new(0, -10),
+    ///     ],
+    /// );
+    /// ```
+    pub fn to_polygon(&self) -> Polygon {
+        let mut pts: Vec<(f64, f64)> = Vec::with_capacity(self.points.len());
+        for p in self.points.iter() {
+            let p = (p.x as f64, p.y as f64);
+            if pts.last() != Some(&p) {
+                pts.push(p);
+            }
+        }
+
+        let hw = self.width as f64 / 2.;
+        if pts.len() < 2 {
+            // A path with no length only covers area if its ends are extended.
+            let (x, y) = pts[0];
+            let (begin, end) = match self.end {
+                PathEnd::Flush => (0., 0.),
+                PathEnd::Custom { begin, end } => (begin as f64, end as f64),
+                PathEnd::HalfWidth | PathEnd::Round => (hw, hw),
+            };
+            return to_polygon(vec![
+                (x - begin, y + hw),
+                (x + end, y + hw),
+                (x + end, y - hw),
+                (x - begin, y - hw),
+            ]);
+        }
+
+        let n = pts.len();
+        let dirs: Vec<(f64, f64)> = pts
+            .windows(2)
+            .map(|w| {
+                let (dx, dy) = (w[1].0 - w[0].0, w[1].1 - w[0].1);
+                let len = dx.hypot(dy);
+                (dx / len, dy / len)
+            })
+            .collect();
+
+        if !matches!(self.end, PathEnd::Round) {
+            let (begin, end) = self.extensions();
+            let (d0, dn) = (dirs[0], dirs[n - 2]);
+            pts[0] = (
+                pts[0].0 - d0.0 * begin as f64,
+                pts[0].1 - d0.1 * begin as f64,
+            );
+            pts[n - 1] = (
+                pts[n - 1].0 + dn.0 * end as f64,
+                pts[n - 1].1 + dn.1 * end as f64,
+            );
+        }
+
+        let left = offset_line(&pts, &dirs, hw);
+        let right = offset_line(&pts, &dirs, -hw);
+
+        let mut out = Vec::with_capacity(2 * n + 2 * ROUND_END_SEGMENTS);
+        out.extend(left);
+        if matches!(self.end, PathEnd::Round) {
+            out.extend(round_end(pts[n - 1], dirs[n - 2], hw));
+        }
+        out.extend(right.into_iter().rev());
+        if matches!(self.end, PathEnd::Round) {
+            out.extend(round_end(pts[0], (-dirs[0].0, -dirs[0].1), hw));
+        }
+        to_polygon(out)
+    }
+}
+
+/// Offsets the given centerline by `dist` to the left, mitering joints between segments.
+fn offset_line(pts: &[(f64, f64)], dirs: &[(f64, f64)], dist: f64) -> Vec<(f64, f64)> {
+    let n = pts.len();
+    let normal = |d: (f64, f64)| (-d.1 * dist, d.0 * dist);
+    let mut out = Vec::with_capacity(n);
+
+    let n0 = normal(dirs[0]);
+    out.push((pts[0].0 + n0.0, pts[0].1 + n0.1));
+    for i in 1..n - 1 {
+        let (d1, d2) = (dirs[i - 1], dirs[i]);
+        let (n1, n2) = (normal(d1), normal(d2));
+        let cross = d1.0 * d2.1 - d1.1 * d2.0;
+        if cross.abs() < 1e-9 {
+            out.push((pts[i].0 + n1.0, pts[i].1 + n1.1));
+            continue;
+        }
+        // Intersect the offset lines of the two segments meeting at this point.
+        let a = (pts[i].0 + n1.0, pts[i].1 + n1.1);
+        let b = (pts[i].0 + n2.0, pts[i].1 + n2.1);
+        let t = ((b.0 - a.0) * d2.1 - (b.1 - a.1) * d2.0) / cross;
+        out.push((a.0 + d1.0 * t, a.1 + d1.1 * t));
+    }
+    let nn = normal(dirs[n - 2]);
+    out.push((pts[n - 1].0 + nn.0, pts[n - 1].1 + nn.1));
+    out
+}
+
+/// Returns the interior points of a semicircular cap of radius `r` centered at `center`,
+/// facing in direction `dir`, ordered from the left side of the path to the right side.
+fn round_end(center: (f64, f64), dir: (f64, f64), r: f64) -> Vec<(f64, f64)> {
+    let start = dir.1.atan2(dir.0) + PI / 2.;
+    (1..ROUND_END_SEGMENTS)
+        .map(|i| {
+            let theta = start - PI * i as f64 / ROUND_END_SEGMENTS as f64;
+            (center.0 + r * theta.cos(), center.1 + r * theta.sin())
+        })
+        .collect()
+}
+
+fn to_polygon(pts: Vec<(f64, f64)>) -> Polygon {
+    Polygon::from_verts(
+        pts.into_iter()
+            .map(|(x, y)| Point::new(x.round() as i64, y.round() as i64))
+            .collect(),
+    )
+}
+
+impl Bbox for Path {
+    fn bbox(&self) -> Option<Rect> {
+        self.to_polygon().bbox()
+    }
+}
+
+impl TranslateMut for Path {
+    fn translate_mut(&mut self, p: Point) {
+        self.points.translate_mut(p);
+    }
+}
+
+impl TransformMut for Path {
+    fn transform_mut(&mut self, trans: Transformation) {
+        self.points.transform_mut(trans);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn path_to_polygon_miters_corners() {
+        let path = Path::new(
+            vec![Point::new(0, 0), Point::new(100, 0), Point::new(100, 100)],
+            20,
+            PathEnd::Flush,
+        );
+        assert_eq!(
+            path.to_polygon().points(),
+            &vec![
+                Point::new(0, 10),
+                Point::new(90, 10),
+                Point::new(90, 100),
+                Point::new(110, 100),
+                Point::new(110, -10),
+                Point::new(0, -10),
+            ]
+        );
+    }
+
+    #[test]
+    fn path_extensions_extend_bbox() {
+        let points = vec![Point::new(0, 0), Point::new(0, 100)];
+        for (end, bbox) in [
+            (PathEnd::Flush, Rect::from_sides(-5, 0, 5, 100)),
+            (PathEnd::HalfWidth, Rect::from_sides(-5, -5, 5, 105)),
+            (PathEnd::Round, Rect::from_sides(-5, -5, 5, 105)),
+            (
+                PathEnd::Custom { begin: 20, end: 3 },
+                Rect::from_sides(-5, -20, 5, 103),
+            ),
+        ] {
+            let path = Path::new(points.clone(), 10, end);
+            assert_eq!(path.bbox(), Some(bbox), "unexpected bbox for {end:?}");
+        }
+    }
+
+    #[test]
+    fn transformed_path_preserves_width() {
+        let path = Path::new(
+            vec![Point::new(0, 0), Point::new(100, 0)],
+            20,
+            PathEnd::HalfWidth,
+        )
+        .transform(Transformation::builder().angle(90.).build());
+        assert_eq!(path.points(), &[Point::new(0, 0), Point::new(0, 100)]);
+        assert_eq!(path.width(), 20);
+        assert_eq!(path.bbox(), Some(Rect::from_sides(-10, -10, 10, 110)));
+    }
+}
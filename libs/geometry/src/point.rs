@@ -9,7 +9,9 @@ use crate::snap::snap_to_grid;
 use crate::transform::{HasTransformedView, TransformMut, Transformation, TranslateMut};
 
 /// A point in two-dimensional space.
-#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(
+    Debug, Copy, Clone, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
 pub struct Point {
     /// The x-coordinate of the point.
     pub x: i64,
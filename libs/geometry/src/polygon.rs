@@ -3,10 +3,32 @@
 use serde::{Deserialize, Serialize};
 
 use crate::bbox::Bbox;
+use crate::boolean::Region;
+use crate::contains::{Containment, Contains};
 use crate::point::Point;
 use crate::rect::Rect;
 use crate::transform::{TransformMut, Transformation, TranslateMut};
 
+/// The direction in which the vertices of a polygon wind around its interior.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Winding {
+    /// Vertices are ordered counterclockwise.
+    CounterClockwise,
+    /// Vertices are ordered clockwise.
+    Clockwise,
+}
+
+/// The most restrictive class of edge angles that a polygon's edges fall into.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EdgeClass {
+    /// All edges are horizontal or vertical.
+    Manhattan,
+    /// All edges are horizontal, vertical, or at 45 degrees.
+    Octilinear,
+    /// Edges may be at any angle.
+    AllAngle,
+}
+
 /// A polygon, with vertex coordinates given
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Polygon {
@@ -97,6 +119,237 @@ impl Polygon {
         &self.points
     }
 
+    /// Returns an iterator over the edges of the polygon, including the closing edge
+    /// from the last vertex back to the first.
+    pub fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        let n = self.points.len();
+        (0..n).map(move |i| (self.points[i], self.points[(i + 1) % n]))
+    }
+
+    /// Returns twice the signed area of the polygon.
+    ///
+    /// The area is positive if the vertices are ordered counterclockwise.
+    fn double_signed_area(&self) -> i128 {
+        self.edges()
+            .map(|(p0, p1)| p0.x as i128 * p1.y as i128 - p1.x as i128 * p0.y as i128)
+            .sum()
+    }
+
+    /// Returns the signed area of the polygon.
+    ///
+    /// The area is positive if the vertices are ordered counterclockwise,
+    /// and negative if they are ordered clockwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use geometry::prelude::*;
+    /// let points = vec![
+    ///     Point { x: 0, y: 0 },
+    ///     Point { x: 0, y: 5 },
+    ///     Point { x: 3, y: 0 },
+    /// ];
+    /// let polygon = Polygon::from_verts(points);
+    /// assert_eq!(polygon.signed_area(), -7.5);
+    /// ```
+    pub fn signed_area(&self) -> f64 {
+        self.double_signed_area() as f64 / 2.
+    }
+
+    /// Returns the area of the polygon.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use geometry::prelude::*;
+    /// let points = vec![
+    ///     Point { x: 0, y: 0 },
+    ///     Point { x: 0, y: 5 },
+    ///     Point { x: 3, y: 0 },
+    /// ];
+    /// let polygon = Polygon::from_verts(points);
+    /// assert_eq!(polygon.area(), 7.5);
+    /// ```
+    pub fn area(&self) -> f64 {
+        self.signed_area().abs()
+    }
+
+    /// Returns the perimeter of the polygon.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use geometry::prelude::*;
+    /// let points = vec![
+    ///     Point { x: 0, y: 0 },
+    ///     Point { x: 0, y: 4 },
+    ///     Point { x: 3, y: 0 },
+    /// ];
+    /// let polygon = Polygon::from_verts(points);
+    /// assert_eq!(polygon.perimeter(), 12.);
+    /// ```
+    pub fn perimeter(&self) -> f64 {
+        self.edges()
+            .map(|(p0, p1)| ((p1.x - p0.x) as f64).hypot((p1.y - p0.y) as f64))
+            .sum()
+    }
+
+    /// Returns the direction in which the vertices of the polygon wind around its interior.
+    ///
+    /// Returns [`None`] if the polygon has zero area.
+    pub fn winding(&self) -> Option<Winding> {
+        match self.double_signed_area().signum() {
+            1 => Some(Winding::CounterClockwise),
+            -1 => Some(Winding::Clockwise),
+            _ => None,
+        }
+    }
+
+    /// Reverses the order of the vertices of the polygon, reversing its winding.
+    pub fn reverse(&mut self) {
+        self.points.reverse();
+    }
+
+    /// Reorders the vertices of the polygon so that they wind counterclockwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use geometry::prelude::*;
+    /// # use geometry::polygon::Winding;
+    /// let points = vec![
+    ///     Point { x: 0, y: 0 },
+    ///     Point { x: 0, y: 5 },
+    ///     Point { x: 3, y: 0 },
+    /// ];
+    /// let mut polygon = Polygon::from_verts(points);
+    /// assert_eq!(polygon.winding(), Some(Winding::Clockwise));
+    /// polygon.normalize_winding();
+    /// assert_eq!(polygon.winding(), Some(Winding::CounterClockwise));
+    /// ```
+    pub fn normalize_winding(&mut self) {
+        if self.winding() == Some(Winding::Clockwise) {
+            self.reverse();
+        }
+    }
+
+    /// Returns the most restrictive class of edge angles that the polygon's edges fall into.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use geometry::prelude::*;
+    /// # use geometry::polygon::EdgeClass;
+    /// let points = vec![
+    ///     Point { x: 0, y: 0 },
+    ///     Point { x: 10, y: 0 },
+    ///     Point { x: 0, y: 10 },
+    /// ];
+    /// let polygon = Polygon::from_verts(points);
+    /// assert_eq!(polygon.edge_class(), EdgeClass::Octilinear);
+    /// ```
+    pub fn edge_class(&self) -> EdgeClass {
+        self.edges()
+            .map(|(p0, p1)| {
+                let (dx, dy) = (p1.x - p0.x, p1.y - p0.y);
+                if dx == 0 || dy == 0 {
+                    EdgeClass::Manhattan
+                } else if dx.abs() == dy.abs() {
+                    EdgeClass::Octilinear
+                } else {
+                    EdgeClass::AllAngle
+                }
+            })
+            .max()
+            .unwrap_or(EdgeClass::Manhattan)
+    }
+
+    /// Returns `true` if all edges of the polygon are horizontal or vertical.
+    #[inline]
+    pub fn is_manhattan(&self) -> bool {
+        self.edge_class() == EdgeClass::Manhattan
+    }
+
+    /// Removes repeated vertices and vertices that lie on the line between their neighbors.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use geometry::prelude::*;
+    /// let points = vec![
+    ///     Point { x: 0, y: 0 },
+    ///     Point { x: 5, y: 0 },
+    ///     Point { x: 10, y: 0 },
+    ///     Point { x: 10, y: 10 },
+    ///     Point { x: 10, y: 10 },
+    ///     Point { x: 0, y: 10 },
+    /// ];
+    /// let mut polygon = Polygon::from_verts(points);
+    /// polygon.remove_collinear_vertices();
+    /// assert_eq!(polygon.points().len(), 4);
+    /// ```
+    pub fn remove_collinear_vertices(&mut self) {
+        loop {
+            let n = self.points.len();
+            if n < 3 {
+                return;
+            }
+            let mut kept = Vec::with_capacity(n);
+            for i in 0..n {
+                let prev = kept.last().copied().unwrap_or(self.points[(i + n - 1) % n]);
+                let (cur, next) = (self.points[i], self.points[(i + 1) % n]);
+                if orientation(prev, cur, next) != 0 {
+                    kept.push(cur);
+                }
+            }
+            if kept.len() == n {
+                return;
+            }
+            self.points = kept;
+        }
+    }
+
+    /// Returns `true` if any two edges of the polygon touch or cross,
+    /// other than consecutive edges meeting at their shared vertex.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use geometry::prelude::*;
+    /// let points = vec![
+    ///     Point { x: 0, y: 0 },
+    ///     Point { x: 10, y: 10 },
+    ///     Point { x: 10, y: 0 },
+    ///     Point { x: 0, y: 10 },
+    /// ];
+    /// let polygon = Polygon::from_verts(points);
+    /// assert!(polygon.is_self_intersecting());
+    /// ```
+    pub fn is_self_intersecting(&self) -> bool {
+        let edges = self.edges().collect::<Vec<_>>();
+        let n = edges.len();
+        for i in 0..n {
+            for j in i + 1..n {
+                let (a, b) = (edges[i], edges[j]);
+                if j == i + 1 || (i == 0 && j == n - 1) {
+                    // Consecutive edges share a vertex; they only intersect
+                    // elsewhere if they fold back onto each other.
+                    let (shared, p, q) = if j == i + 1 {
+                        (a.1, a.0, b.1)
+                    } else {
+                        (a.0, a.1, b.0)
+                    };
+                    if n > 2 && orientation(p, shared, q) == 0 && dot(p, shared, q) > 0 {
+                        return true;
+                    }
+                } else if segments_intersect(a, b) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
     /// Returns the center point of the polygon.
     ///
     /// Returns a point with x-coordinate equal to the average of all x-coordinates
@@ -134,6 +387,93 @@ impl Bbox for Polygon {
     }
 }
 
+/// Returns the sign of the cross product of `b - a` and `c - b`.
+///
+/// Positive if `a`, `b`, `c` make a left turn, negative for a right turn, and zero if collinear.
+fn orientation(a: Point, b: Point, c: Point) -> i32 {
+    let cross =
+        (b.x - a.x) as i128 * (c.y - b.y) as i128 - (b.y - a.y) as i128 * (c.x - b.x) as i128;
+    cross.signum() as i32
+}
+
+/// Returns the dot product of `a - b` and `c - b`.
+fn dot(a: Point, b: Point, c: Point) -> i128 {
+    (a.x as i128 - b.x as i128) * (c.x as i128 - b.x as i128)
+        + (a.y as i128 - b.y as i128) * (c.y as i128 - b.y as i128)
+}
+
+/// Returns `true` if `p` lies on the segment from `a` to `b`.
+fn on_segment(p: Point, (a, b): (Point, Point)) -> bool {
+    orientation(a, b, p) == 0
+        && p.x >= a.x.min(b.x)
+        && p.x <= a.x.max(b.x)
+        && p.y >= a.y.min(b.y)
+        && p.y <= a.y.max(b.y)
+}
+
+/// Returns `true` if the two closed segments share at least one point.
+fn segments_intersect(s1: (Point, Point), s2: (Point, Point)) -> bool {
+    let d1 = orientation(s2.0, s2.1, s1.0);
+    let d2 = orientation(s2.0, s2.1, s1.1);
+    let d3 = orientation(s1.0, s1.1, s2.0);
+    let d4 = orientation(s1.0, s1.1, s2.1);
+    (d1 * d2 < 0 && d3 * d4 < 0)
+        || on_segment(s1.0, s2)
+        || on_segment(s1.1, s2)
+        || on_segment(s2.0, s1)
+        || on_segment(s2.1, s1)
+}
+
+impl Contains<Point> for Polygon {
+    /// Returns [`Containment::Full`] if the point lies within the polygon or on its boundary,
+    /// using the nonzero winding rule.
+    fn contains(&self, other: &Point) -> Containment {
+        let p = *other;
+        let mut winding = 0;
+        for (a, b) in self.edges() {
+            if on_segment(p, (a, b)) {
+                return Containment::Full;
+            }
+            if a.y <= p.y {
+                if b.y > p.y && orientation(a, b, p) > 0 {
+                    winding += 1;
+                }
+            } else if b.y <= p.y && orientation(a, b, p) < 0 {
+                winding -= 1;
+            }
+        }
+        if winding != 0 {
+            Containment::Full
+        } else {
+            Containment::None
+        }
+    }
+}
+
+impl Contains<Rect> for Polygon {
+    fn contains(&self, other: &Rect) -> Containment {
+        region_containment(&Region::from(self), &Region::from(*other))
+    }
+}
+
+impl Contains<Polygon> for Polygon {
+    fn contains(&self, other: &Polygon) -> Containment {
+        region_containment(&Region::from(self), &Region::from(other))
+    }
+}
+
+/// Computes how `inner` is contained within `outer`, based on the area of their intersection.
+fn region_containment(outer: &Region, inner: &Region) -> Containment {
+    let overlap = outer.intersection(inner).area();
+    if overlap == 0 {
+        Containment::None
+    } else if overlap == inner.area() {
+        Containment::Full
+    } else {
+        Containment::Partial
+    }
+}
+
 impl TranslateMut for Polygon {
     fn translate_mut(&mut self, p: Point) {
         self.points.translate_mut(p);
@@ -145,3 +485,100 @@ impl TransformMut for Polygon {
         self.points.transform_mut(trans);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::contains::{Containment, Contains};
+    use crate::prelude::*;
+
+    fn l_shape() -> Polygon {
+        Polygon::from_verts(vec![
+            Point::new(0, 0),
+            Point::new(20, 0),
+            Point::new(20, 10),
+            Point::new(10, 10),
+            Point::new(10, 20),
+            Point::new(0, 20),
+        ])
+    }
+
+    #[test]
+    fn polygon_contains_points() {
+        let polygon = l_shape();
+        assert!(polygon.contains(&Point::new(5, 15)).is_full());
+        assert!(polygon.contains(&Point::new(15, 5)).is_full());
+        assert!(polygon.contains(&Point::new(20, 5)).is_full());
+        assert!(polygon.contains(&Point::new(15, 15)).is_none());
+        assert!(polygon.contains(&Point::new(-1, 0)).is_none());
+    }
+
+    #[test]
+    fn polygon_contains_rects() {
+        let polygon = l_shape();
+        assert_eq!(
+            polygon.contains(&Rect::from_sides(0, 0, 20, 10)),
+            Containment::Full
+        );
+        assert_eq!(
+            polygon.contains(&Rect::from_sides(5, 5, 15, 15)),
+            Containment::Partial
+        );
+        assert_eq!(
+            polygon.contains(&Rect::from_sides(11, 11, 20, 20)),
+            Containment::None
+        );
+    }
+
+    #[test]
+    fn self_intersection_detects_touching_edges() {
+        assert!(!l_shape().is_self_intersecting());
+
+        // Two squares touching at a single vertex.
+        let bowtie = Polygon::from_verts(vec![
+            Point::new(0, 0),
+            Point::new(10, 0),
+            Point::new(10, 10),
+            Point::new(20, 10),
+            Point::new(20, 20),
+            Point::new(10, 20),
+            Point::new(10, 10),
+            Point::new(0, 10),
+        ]);
+        assert!(bowtie.is_self_intersecting());
+
+        // An edge that doubles back on the previous edge.
+        let spike = Polygon::from_verts(vec![
+            Point::new(0, 0),
+            Point::new(10, 0),
+            Point::new(5, 0),
+            Point::new(5, 10),
+        ]);
+        assert!(spike.is_self_intersecting());
+
+        // The same spike, with coordinates whose products overflow `i64`.
+        let scale = 10_000_000_000;
+        let spike = Polygon::from_verts(vec![
+            Point::new(0, 0),
+            Point::new(10 * scale, 0),
+            Point::new(5 * scale, 0),
+            Point::new(5 * scale, 10 * scale),
+        ]);
+        assert!(spike.is_self_intersecting());
+    }
+
+    #[test]
+    fn remove_collinear_vertices_removes_spikes() {
+        let mut polygon = Polygon::from_verts(vec![
+            Point::new(0, 0),
+            Point::new(10, 0),
+            Point::new(10, 10),
+            Point::new(10, 15),
+            Point::new(10, 10),
+            Point::new(0, 10),
+        ]);
+        polygon.remove_collinear_vertices();
+        assert_eq!(polygon.area(), 100.);
+        assert_eq!(polygon.points().len(), 4);
+        assert!(!polygon.is_self_intersecting());
+    }
+}
@@ -7,6 +7,7 @@ pub use crate::dims::Dims;
 pub use crate::dir::Dir;
 pub use crate::edge::Edge;
 pub use crate::orientation::{NamedOrientation, Orientation};
+pub use crate::path::{Path, PathEnd};
 pub use crate::place::{PlaceBbox, PlaceMode, PlaceRect};
 pub use crate::point::Point;
 pub use crate::polygon::Polygon;
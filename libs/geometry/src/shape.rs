@@ -2,6 +2,8 @@
 
 use crate::{
     bbox::Bbox,
+    corner::Corner,
+    path::Path,
     polygon::Polygon,
     prelude::Transform,
     rect::Rect,
@@ -16,6 +18,8 @@ pub enum Shape {
     Rect(Rect),
     /// A polygon.
     Polygon(Polygon),
+    /// A path.
+    Path(Path),
 }
 
 impl Shape {
@@ -28,7 +32,7 @@ impl Shape {
         }
     }
 
-    /// If this shape is a rectangle, returns the contained polygon.
+    /// If this shape is a polygon, returns the contained polygon.
     /// Otherwise, returns [`None`].
     pub fn polygon(&self) -> Option<&Polygon> {
         match self {
@@ -36,6 +40,29 @@ impl Shape {
             _ => None,
         }
     }
+
+    /// If this shape is a path, returns the contained path.
+    /// Otherwise, returns [`None`].
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Path(p) => Some(p),
+            _ => None,
+        }
+    }
+
+    /// Converts this shape to a polygon covering the same area.
+    pub fn to_polygon(&self) -> Polygon {
+        match self {
+            Self::Rect(r) => Polygon::from_verts(vec![
+                r.corner(Corner::LowerLeft),
+                r.corner(Corner::LowerRight),
+                r.corner(Corner::UpperRight),
+                r.corner(Corner::UpperLeft),
+            ]),
+            Self::Polygon(p) => p.clone(),
+            Self::Path(p) => p.to_polygon(),
+        }
+    }
 }
 
 impl TranslateMut for Shape {
@@ -43,6 +70,7 @@ impl TranslateMut for Shape {
         match self {
             Shape::Rect(rect) => rect.translate_mut(p),
             Shape::Polygon(polygon) => polygon.translate_mut(p),
+            Shape::Path(path) => path.translate_mut(p),
         };
     }
 }
@@ -52,6 +80,7 @@ impl TransformMut for Shape {
         match self {
             Shape::Rect(rect) => rect.transform_mut(trans),
             Shape::Polygon(polygon) => polygon.transform_mut(trans),
+            Shape::Path(path) => path.transform_mut(trans),
         }
     }
 }
@@ -69,6 +98,7 @@ impl Bbox for Shape {
         match self {
             Shape::Rect(rect) => rect.bbox(),
             Shape::Polygon(polygon) => polygon.bbox(),
+            Shape::Path(path) => path.bbox(),
         }
     }
 }
@@ -80,6 +110,20 @@ impl From<Rect> for Shape {
     }
 }
 
+impl From<Polygon> for Shape {
+    #[inline]
+    fn from(value: Polygon) -> Self {
+        Self::Polygon(value)
+    }
+}
+
+impl From<Path> for Shape {
+    #[inline]
+    fn from(value: Path) -> Self {
+        Self::Path(value)
+    }
+}
+
 impl<T: Bbox> BoundingUnion<T> for Shape {
     type Output = Option<Rect>;
 
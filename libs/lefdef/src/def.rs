@@ -0,0 +1,460 @@
+//! DEF parsing.
+
+use arcstr::ArcStr;
+
+use crate::lex::Tokens;
+use crate::{
+    DefComponent, DefDesign, DefNet, DefOrient, DefPin, DefPinPort, DefPlacement, DefPoint,
+    DefRoute, DefRouteItem, DefShape, DefShapeKind, DefVia, LefDefResult, PinDirection,
+};
+
+/// Sections that are skipped entirely.
+const SKIPPED_SECTIONS: &[&str] = &[
+    "PROPERTYDEFINITIONS",
+    "STYLES",
+    "NONDEFAULTRULES",
+    "REGIONS",
+    "PINPROPERTIES",
+    "BLOCKAGES",
+    "SLOTS",
+    "FILLS",
+    "SCANCHAINS",
+    "GROUPS",
+    "BEGINEXT",
+];
+
+pub(crate) struct DefParser {
+    tokens: Tokens,
+}
+
+impl DefParser {
+    pub(crate) fn new(src: &str) -> Self {
+        Self {
+            tokens: Tokens::new(src),
+        }
+    }
+
+    pub(crate) fn parse(mut self) -> LefDefResult<DefDesign> {
+        let mut design = DefDesign::default();
+        while let Some(keyword) = self.tokens.try_next() {
+            match keyword.as_str() {
+                "DESIGN" => {
+                    design.name = self.tokens.next()?;
+                    self.tokens.expect(";")?;
+                }
+                "UNITS" => {
+                    self.tokens.expect("DISTANCE")?;
+                    self.tokens.expect("MICRONS")?;
+                    design.units = self.tokens.number()?;
+                    self.tokens.expect(";")?;
+                }
+                "DIEAREA" => {
+                    while !self.tokens.eat(";") {
+                        design.die_area.push(self.point()?);
+                    }
+                }
+                "VIAS" => design.vias = self.section("VIAS", Self::via)?,
+                "COMPONENTS" => design.components = self.section("COMPONENTS", Self::component)?,
+                "PINS" => design.pins = self.section("PINS", Self::pin)?,
+                "NETS" => design.nets = self.section("NETS", Self::net)?,
+                "SPECIALNETS" => design.special_nets = self.section("SPECIALNETS", Self::net)?,
+                "END" => {
+                    self.tokens.expect("DESIGN")?;
+                    break;
+                }
+                kw if SKIPPED_SECTIONS.contains(&kw) => self.tokens.skip_block(kw)?,
+                _ => self.tokens.skip_statement()?,
+            }
+        }
+        Ok(design)
+    }
+
+    /// Parses the items of a section up to and including `END name`.
+    ///
+    /// Each item starts with `-` and is parsed by `item`.
+    fn section<T>(
+        &mut self,
+        name: &str,
+        mut item: impl FnMut(&mut Self) -> LefDefResult<T>,
+    ) -> LefDefResult<Vec<T>> {
+        // Skip the item count.
+        self.tokens.skip_statement()?;
+        let mut items = Vec::new();
+        loop {
+            if self.tokens.eat("END") {
+                self.tokens.expect(name)?;
+                return Ok(items);
+            }
+            self.tokens.expect("-")?;
+            items.push(item(self)?);
+        }
+    }
+
+    /// Skips the remainder of a `+` option.
+    fn skip_option(&mut self) -> LefDefResult<()> {
+        while !self.tokens.at("+") && !self.tokens.at(";") {
+            self.tokens.next()?;
+        }
+        Ok(())
+    }
+
+    fn point(&mut self) -> LefDefResult<DefPoint> {
+        self.tokens.expect("(")?;
+        let p = DefPoint::new(self.tokens.number()?, self.tokens.number()?);
+        self.tokens.expect(")")?;
+        Ok(p)
+    }
+
+    fn orient(&mut self) -> LefDefResult<DefOrient> {
+        let orient = self.tokens.next()?;
+        DefOrient::parse(&orient).ok_or_else(|| {
+            self.tokens
+                .error_before(format!("invalid orientation `{orient}`"))
+        })
+    }
+
+    /// Parses a placement after its `PLACED`, `FIXED`, or `COVER` keyword.
+    fn placement(&mut self, status: &str) -> LefDefResult<DefPlacement> {
+        Ok(DefPlacement {
+            fixed: status != "PLACED",
+            at: self.point()?,
+            orient: self.orient()?,
+        })
+    }
+
+    /// Skips optional `MASK`, `SPACING`, and `DESIGNRULEWIDTH` specifications of a pin shape.
+    fn skip_shape_options(&mut self) -> LefDefResult<()> {
+        while self.tokens.eat("MASK")
+            || self.tokens.eat("SPACING")
+            || self.tokens.eat("DESIGNRULEWIDTH")
+        {
+            self.tokens.next()?;
+        }
+        Ok(())
+    }
+
+    fn rect(&mut self, layer: ArcStr) -> LefDefResult<DefShape> {
+        self.skip_shape_options()?;
+        Ok(DefShape {
+            layer,
+            kind: DefShapeKind::Rect(self.point()?, self.point()?),
+        })
+    }
+
+    fn polygon(&mut self, layer: ArcStr) -> LefDefResult<DefShape> {
+        self.skip_shape_options()?;
+        let mut points = Vec::new();
+        while self.tokens.at("(") {
+            points.push(self.point()?);
+        }
+        if points.len() < 3 {
+            return Err(self
+                .tokens
+                .error_before("POLYGON requires at least three points"));
+        }
+        Ok(DefShape {
+            layer,
+            kind: DefShapeKind::Polygon(points),
+        })
+    }
+
+    fn via(&mut self) -> LefDefResult<DefVia> {
+        let name = self.tokens.next()?;
+        let mut shapes = Vec::new();
+        let mut rule = GeneratedVia::default();
+        let mut generated = false;
+        while !self.tokens.eat(";") {
+            self.tokens.expect("+")?;
+            match self.tokens.next()?.as_str() {
+                "RECT" => {
+                    let layer = self.tokens.next()?;
+                    shapes.push(self.rect(layer)?);
+                }
+                "POLYGON" => {
+                    let layer = self.tokens.next()?;
+                    shapes.push(self.polygon(layer)?);
+                }
+                "VIARULE" => {
+                    generated = true;
+                    self.tokens.next()?;
+                }
+                "CUTSIZE" => rule.cut_size = (self.tokens.number()?, self.tokens.number()?),
+                "LAYERS" => {
+                    rule.layers = [
+                        self.tokens.next()?,
+                        self.tokens.next()?,
+                        self.tokens.next()?,
+                    ]
+                }
+                "CUTSPACING" => rule.cut_spacing = (self.tokens.number()?, self.tokens.number()?),
+                "ENCLOSURE" => {
+                    rule.enclosure = [
+                        self.tokens.number()?,
+                        self.tokens.number()?,
+                        self.tokens.number()?,
+                        self.tokens.number()?,
+                    ]
+                }
+                "ROWCOL" => rule.rowcol = (self.tokens.number()?, self.tokens.number()?),
+                "ORIGIN" => rule.origin = (self.tokens.number()?, self.tokens.number()?),
+                "OFFSET" => {
+                    rule.offset = [
+                        self.tokens.number()?,
+                        self.tokens.number()?,
+                        self.tokens.number()?,
+                        self.tokens.number()?,
+                    ]
+                }
+                _ => self.skip_option()?,
+            }
+        }
+        if generated {
+            shapes.extend(rule.shapes());
+        }
+        Ok(DefVia { name, shapes })
+    }
+
+    fn component(&mut self) -> LefDefResult<DefComponent> {
+        let name = self.tokens.next()?;
+        let macro_name = self.tokens.next()?;
+        let mut placement = None;
+        while !self.tokens.eat(";") {
+            self.tokens.expect("+")?;
+            match self.tokens.next()?.as_str() {
+                status @ ("PLACED" | "FIXED" | "COVER") => {
+                    placement = Some(self.placement(status)?)
+                }
+                _ => self.skip_option()?,
+            }
+        }
+        Ok(DefComponent {
+            name,
+            macro_name,
+            placement,
+        })
+    }
+
+    fn pin(&mut self) -> LefDefResult<DefPin> {
+        let name = self.tokens.next()?;
+        let mut pin = DefPin {
+            net: name.clone(),
+            name,
+            direction: None,
+            usage: None,
+            ports: Vec::new(),
+        };
+        let mut port = DefPinPort::default();
+        while !self.tokens.eat(";") {
+            self.tokens.expect("+")?;
+            match self.tokens.next()?.as_str() {
+                "NET" => pin.net = self.tokens.next()?,
+                "DIRECTION" => {
+                    let direction = self.tokens.next()?;
+                    pin.direction = Some(PinDirection::parse(&direction).ok_or_else(|| {
+                        self.tokens
+                            .error_before(format!("invalid pin direction `{direction}`"))
+                    })?);
+                }
+                "USE" => pin.usage = Some(self.tokens.next()?),
+                "PORT" => {
+                    if port != DefPinPort::default() {
+                        pin.ports.push(std::mem::take(&mut port));
+                    }
+                }
+                "LAYER" => {
+                    let layer = self.tokens.next()?;
+                    port.shapes.push(self.rect(layer)?);
+                }
+                "POLYGON" => {
+                    let layer = self.tokens.next()?;
+                    port.shapes.push(self.polygon(layer)?);
+                }
+                status @ ("PLACED" | "FIXED" | "COVER") => {
+                    port.placement = Some(self.placement(status)?)
+                }
+                _ => self.skip_option()?,
+            }
+        }
+        if port != DefPinPort::default() {
+            pin.ports.push(port);
+        }
+        Ok(pin)
+    }
+
+    fn net(&mut self) -> LefDefResult<DefNet> {
+        let name = self.tokens.next()?;
+        let mut net = DefNet {
+            name,
+            connections: Vec::new(),
+            routes: Vec::new(),
+        };
+        while self.tokens.eat("(") {
+            let component = self.tokens.next()?;
+            let pin = self.tokens.next()?;
+            // Skip the optional `+ SYNTHESIZED` flag.
+            while self.tokens.next()? != ")" {}
+            net.connections.push((component, pin));
+        }
+        while !self.tokens.eat(";") {
+            self.tokens.expect("+")?;
+            match self.tokens.next()?.as_str() {
+                "ROUTED" | "FIXED" | "COVER" | "NOSHIELD" => self.wiring(&mut net.routes)?,
+                "SHIELD" => {
+                    self.tokens.next()?;
+                    self.wiring(&mut net.routes)?;
+                }
+                _ => self.skip_option()?,
+            }
+        }
+        Ok(net)
+    }
+
+    /// Parses a coordinate of a route point, where `*` repeats the previous coordinate.
+    fn coord(&mut self, prev: Option<i64>) -> LefDefResult<i64> {
+        if self.tokens.eat("*") {
+            prev.ok_or_else(|| self.tokens.error_before("`*` without a previous point"))
+        } else {
+            self.tokens.number()
+        }
+    }
+
+    /// Parses routes separated by `NEW` until the next option or the end of the statement.
+    fn wiring(&mut self, routes: &mut Vec<DefRoute>) -> LefDefResult<()> {
+        loop {
+            let mut route = DefRoute {
+                layer: self.tokens.next()?,
+                width: None,
+                items: Vec::new(),
+            };
+            if let Some(width) = self.tokens.peek().and_then(|t| t.parse().ok()) {
+                self.tokens.next()?;
+                route.width = Some(width);
+            }
+            loop {
+                if self.tokens.at("+")
+                    && matches!(self.tokens.peek2(), Some("SHAPE" | "STYLE" | "MASK"))
+                {
+                    self.tokens.next()?;
+                    self.tokens.next()?;
+                    self.tokens.next()?;
+                } else if self.tokens.eat("TAPER") {
+                } else if self.tokens.eat("TAPERRULE") || self.tokens.eat("STYLE") {
+                    self.tokens.next()?;
+                } else {
+                    break;
+                }
+            }
+
+            let mut prev: Option<DefPoint> = None;
+            loop {
+                match self.tokens.peek() {
+                    Some("(") => {
+                        self.tokens.next()?;
+                        let x = self.coord(prev.map(|p| p.x))?;
+                        let y = self.coord(prev.map(|p| p.y))?;
+                        // Skip the optional extension value.
+                        while self.tokens.next()? != ")" {}
+                        let p = DefPoint::new(x, y);
+                        prev = Some(p);
+                        route.items.push(DefRouteItem::Point(p));
+                    }
+                    Some("MASK") => {
+                        self.tokens.next()?;
+                        self.tokens.next()?;
+                    }
+                    Some("RECT") => {
+                        self.tokens.next()?;
+                        self.tokens.expect("(")?;
+                        let p0 = DefPoint::new(self.tokens.number()?, self.tokens.number()?);
+                        let p1 = DefPoint::new(self.tokens.number()?, self.tokens.number()?);
+                        self.tokens.expect(")")?;
+                        route.items.push(DefRouteItem::Rect(p0, p1));
+                    }
+                    Some("VIRTUAL") => {
+                        // A virtual point connects to the previous point without a wire,
+                        // so the route is split.
+                        self.tokens.next()?;
+                        let p = self.point()?;
+                        prev = Some(p);
+                        let next = DefRoute {
+                            layer: route.layer.clone(),
+                            width: route.width,
+                            items: vec![DefRouteItem::Point(p)],
+                        };
+                        routes.push(std::mem::replace(&mut route, next));
+                    }
+                    Some("NEW" | "+" | ";") | None => break,
+                    Some(_) => {
+                        route.items.push(DefRouteItem::Via(self.tokens.next()?));
+                        if self.tokens.peek().and_then(DefOrient::parse).is_some() {
+                            return Err(crate::LefDefError::Unsupported(arcstr::literal!(
+                                "rotated vias"
+                            )));
+                        }
+                    }
+                }
+            }
+            routes.push(route);
+            if !self.tokens.eat("NEW") {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// The parameters of a via generated from a via rule.
+#[derive(Default)]
+struct GeneratedVia {
+    cut_size: (i64, i64),
+    layers: [ArcStr; 3],
+    cut_spacing: (i64, i64),
+    enclosure: [i64; 4],
+    rowcol: (i64, i64),
+    origin: (i64, i64),
+    offset: [i64; 4],
+}
+
+impl GeneratedVia {
+    /// Expands the via into its cut and enclosure shapes.
+    ///
+    /// The cut array is centered on the via origin, shifted by `ORIGIN`.
+    /// Each metal enclosure is shifted by its `OFFSET`.
+    fn shapes(&self) -> Vec<DefShape> {
+        let (rows, cols) = (self.rowcol.0.max(1), self.rowcol.1.max(1));
+        let width = cols * self.cut_size.0 + (cols - 1) * self.cut_spacing.0;
+        let height = rows * self.cut_size.1 + (rows - 1) * self.cut_spacing.1;
+        let (x0, y0) = (self.origin.0 - width / 2, self.origin.1 - height / 2);
+        let rect = |layer: &ArcStr, x0: i64, y0: i64, x1: i64, y1: i64| DefShape {
+            layer: layer.clone(),
+            kind: DefShapeKind::Rect(DefPoint::new(x0, y0), DefPoint::new(x1, y1)),
+        };
+
+        let [bot, cut, top] = &self.layers;
+        let [bx, by, tx, ty] = self.enclosure;
+        let [box_, boy, tox, toy] = self.offset;
+        let mut shapes = vec![
+            rect(
+                bot,
+                x0 - bx + box_,
+                y0 - by + boy,
+                x0 + width + bx + box_,
+                y0 + height + by + boy,
+            ),
+            rect(
+                top,
+                x0 - tx + tox,
+                y0 - ty + toy,
+                x0 + width + tx + tox,
+                y0 + height + ty + toy,
+            ),
+        ];
+        for row in 0..rows {
+            for col in 0..cols {
+                let x = x0 + col * (self.cut_size.0 + self.cut_spacing.0);
+                let y = y0 + row * (self.cut_size.1 + self.cut_spacing.1);
+                shapes.push(rect(cut, x, y, x + self.cut_size.0, y + self.cut_size.1));
+            }
+        }
+        shapes
+    }
+}
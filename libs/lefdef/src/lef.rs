@@ -0,0 +1,314 @@
+//! LEF parsing.
+
+use arcstr::ArcStr;
+
+use crate::lex::Tokens;
+use crate::{
+    LefDefError, LefDefResult, LefGeometry, LefLayer, LefLibrary, LefMacro, LefPin, LefPoint,
+    LefShape, LefShapeKind, LefVia, LefViaRef, PinDirection,
+};
+
+/// Top-level statements whose contents end with `END <keyword>`.
+const KEYWORD_BLOCKS: &[&str] = &[
+    "PROPERTYDEFINITIONS",
+    "SPACING",
+    "NOISETABLE",
+    "CORRECTIONTABLE",
+    "IRDROP",
+    "BEGINEXT",
+];
+
+/// Top-level statements whose contents end with `END <name>`.
+const NAMED_BLOCKS: &[&str] = &["SITE", "VIARULE", "NONDEFAULTRULE", "ARRAY"];
+
+pub(crate) struct LefParser {
+    tokens: Tokens,
+}
+
+impl LefParser {
+    pub(crate) fn new(src: &str) -> Self {
+        Self {
+            tokens: Tokens::new(src),
+        }
+    }
+
+    pub(crate) fn parse(mut self) -> LefDefResult<LefLibrary> {
+        let mut lib = LefLibrary::new();
+        while let Some(keyword) = self.tokens.try_next() {
+            match keyword.as_str() {
+                "VERSION" => {
+                    lib.version = Some(self.tokens.next()?);
+                    self.tokens.expect(";")?;
+                }
+                "UNITS" => lib.database_microns = self.units()?,
+                "LAYER" => lib.layers.push(self.layer()?),
+                "VIA" => lib.vias.push(self.via()?),
+                "MACRO" => lib.macros.push(self.macro_()?),
+                "END" => {
+                    self.tokens.expect("LIBRARY")?;
+                    break;
+                }
+                kw if KEYWORD_BLOCKS.contains(&kw) => self.tokens.skip_block(kw)?,
+                kw if NAMED_BLOCKS.contains(&kw) => {
+                    let name = self.tokens.next()?;
+                    self.tokens.skip_block(&name)?;
+                }
+                _ => self.tokens.skip_statement()?,
+            }
+        }
+        Ok(lib)
+    }
+
+    fn point(&mut self) -> LefDefResult<LefPoint> {
+        // Points may optionally be parenthesized.
+        let paren = self.tokens.eat("(");
+        let p = LefPoint::new(self.tokens.number()?, self.tokens.number()?);
+        if paren {
+            self.tokens.expect(")")?;
+        }
+        Ok(p)
+    }
+
+    /// Parses points up to and including the terminating semicolon.
+    fn points(&mut self) -> LefDefResult<Vec<LefPoint>> {
+        let mut points = Vec::new();
+        while !self.tokens.eat(";") {
+            points.push(self.point()?);
+        }
+        Ok(points)
+    }
+
+    fn units(&mut self) -> LefDefResult<Option<u32>> {
+        let mut microns = None;
+        loop {
+            match self.tokens.next()?.as_str() {
+                "END" => {
+                    self.tokens.expect("UNITS")?;
+                    return Ok(microns);
+                }
+                "DATABASE" => {
+                    self.tokens.expect("MICRONS")?;
+                    microns = Some(self.tokens.number()?);
+                    self.tokens.expect(";")?;
+                }
+                _ => self.tokens.skip_statement()?,
+            }
+        }
+    }
+
+    fn layer(&mut self) -> LefDefResult<LefLayer> {
+        let name = self.tokens.next()?;
+        let mut layer = LefLayer {
+            name,
+            kind: None,
+            width: None,
+        };
+        loop {
+            match self.tokens.next()?.as_str() {
+                "END" if self.tokens.eat(&layer.name) => return Ok(layer),
+                "TYPE" => {
+                    layer.kind = Some(self.tokens.next()?);
+                    self.tokens.skip_statement()?;
+                }
+                "WIDTH" => {
+                    layer.width = Some(self.tokens.number()?);
+                    self.tokens.expect(";")?;
+                }
+                _ => self.tokens.skip_statement()?,
+            }
+        }
+    }
+
+    fn via(&mut self) -> LefDefResult<LefVia> {
+        let name = self.tokens.next()?;
+        // Skip the optional `DEFAULT` or `GENERATED` keyword.
+        while !self.tokens.at("LAYER") && !self.tokens.at("END") && !self.tokens.at("VIARULE") {
+            self.tokens.next()?;
+        }
+        let mut shapes = Vec::new();
+        let mut layer = None;
+        loop {
+            match self.tokens.next()?.as_str() {
+                "END" if self.tokens.eat(&name) => return Ok(LefVia { name, shapes }),
+                "LAYER" => {
+                    layer = Some(self.tokens.next()?);
+                    self.tokens.expect(";")?;
+                }
+                kw @ ("RECT" | "POLYGON") => {
+                    let kind = self.shape_kind(kw)?;
+                    let layer = layer
+                        .clone()
+                        .ok_or_else(|| self.tokens.error_before("via shape before LAYER"))?;
+                    shapes.push(LefShape { layer, kind });
+                }
+                "VIARULE" => {
+                    return Err(LefDefError::Unsupported(arcstr::format!(
+                        "generated via `{name}`"
+                    )))
+                }
+                _ => self.tokens.skip_statement()?,
+            }
+        }
+    }
+
+    /// Parses the geometry of a `RECT` or `POLYGON` statement whose keyword was just consumed.
+    fn shape_kind(&mut self, keyword: &str) -> LefDefResult<LefShapeKind> {
+        self.mask()?;
+        let points = self.points()?;
+        if keyword == "RECT" {
+            match points.as_slice() {
+                [p0, p1] => Ok(LefShapeKind::Rect(*p0, *p1)),
+                _ => Err(self.tokens.error_before("RECT requires two points")),
+            }
+        } else if points.len() < 3 {
+            Err(self
+                .tokens
+                .error_before("POLYGON requires at least three points"))
+        } else {
+            Ok(LefShapeKind::Polygon(points))
+        }
+    }
+
+    /// Skips an optional `MASK n` specification.
+    ///
+    /// Also rejects `ITERATE` arrays of shapes, which are not supported.
+    fn mask(&mut self) -> LefDefResult<()> {
+        if self.tokens.eat("MASK") {
+            self.tokens.next()?;
+        }
+        if self.tokens.at("ITERATE") {
+            return Err(LefDefError::Unsupported(arcstr::literal!(
+                "ITERATE geometry"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Parses `LAYER`, `RECT`, `POLYGON`, `PATH`, and `VIA` statements up to and including `END`.
+    fn geometry(&mut self) -> LefDefResult<LefGeometry> {
+        let mut geometry = LefGeometry::default();
+        let mut layer: Option<ArcStr> = None;
+        let mut width = 0.;
+        loop {
+            match self.tokens.next()?.as_str() {
+                "END" => return Ok(geometry),
+                "LAYER" => {
+                    layer = Some(self.tokens.next()?);
+                    width = 0.;
+                    self.tokens.skip_statement()?;
+                }
+                "WIDTH" => {
+                    width = self.tokens.number()?;
+                    self.tokens.expect(";")?;
+                }
+                kw @ ("RECT" | "POLYGON" | "PATH") => {
+                    let layer = layer
+                        .clone()
+                        .ok_or_else(|| self.tokens.error_before(format!("{kw} before LAYER")))?;
+                    let kind = if kw == "PATH" {
+                        self.mask()?;
+                        LefShapeKind::Path {
+                            width,
+                            points: self.points()?,
+                        }
+                    } else {
+                        self.shape_kind(kw)?
+                    };
+                    geometry.shapes.push(LefShape { layer, kind });
+                }
+                "VIA" => {
+                    self.mask()?;
+                    let at = self.point()?;
+                    let name = self.tokens.next()?;
+                    self.tokens.expect(";")?;
+                    geometry.vias.push(LefViaRef { name, at });
+                }
+                _ => self.tokens.skip_statement()?,
+            }
+        }
+    }
+
+    fn pin(&mut self) -> LefDefResult<LefPin> {
+        let name = self.tokens.next()?;
+        let mut pin = LefPin {
+            name,
+            direction: None,
+            usage: None,
+            ports: Vec::new(),
+        };
+        loop {
+            match self.tokens.next()?.as_str() {
+                "END" => {
+                    self.tokens.expect(&pin.name)?;
+                    return Ok(pin);
+                }
+                "DIRECTION" => {
+                    let direction = self.tokens.next()?;
+                    pin.direction = Some(PinDirection::parse(&direction).ok_or_else(|| {
+                        self.tokens
+                            .error_before(format!("invalid pin direction `{direction}`"))
+                    })?);
+                    self.tokens.skip_statement()?;
+                }
+                "USE" => {
+                    pin.usage = Some(self.tokens.next()?);
+                    self.tokens.expect(";")?;
+                }
+                "PORT" => pin.ports.push(self.geometry()?),
+                _ => self.tokens.skip_statement()?,
+            }
+        }
+    }
+
+    fn macro_(&mut self) -> LefDefResult<LefMacro> {
+        let name = self.tokens.next()?;
+        let mut m = LefMacro {
+            name,
+            class: None,
+            foreign: None,
+            origin: LefPoint::default(),
+            size: None,
+            pins: Vec::new(),
+            obs: LefGeometry::default(),
+        };
+        loop {
+            match self.tokens.next()?.as_str() {
+                "END" => {
+                    self.tokens.expect(&m.name)?;
+                    return Ok(m);
+                }
+                "CLASS" => {
+                    m.class = Some(self.tokens.next()?);
+                    self.tokens.skip_statement()?;
+                }
+                "FOREIGN" => {
+                    m.foreign = Some(self.tokens.next()?);
+                    self.tokens.skip_statement()?;
+                }
+                "ORIGIN" => {
+                    m.origin = self.point()?;
+                    self.tokens.expect(";")?;
+                }
+                "SIZE" => {
+                    let w = self.tokens.number()?;
+                    self.tokens.expect("BY")?;
+                    let h = self.tokens.number()?;
+                    self.tokens.expect(";")?;
+                    m.size = Some((w, h));
+                }
+                "PIN" => m.pins.push(self.pin()?),
+                "OBS" => {
+                    let obs = self.geometry()?;
+                    m.obs.shapes.extend(obs.shapes);
+                    m.obs.vias.extend(obs.vias);
+                }
+                "DENSITY" => {
+                    while !self.tokens.eat("END") {
+                        self.tokens.skip_statement()?;
+                    }
+                }
+                _ => self.tokens.skip_statement()?,
+            }
+        }
+    }
+}
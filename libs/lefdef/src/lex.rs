@@ -0,0 +1,187 @@
+//! Tokenization of LEF and DEF files.
+
+use std::str::FromStr;
+
+use arcstr::ArcStr;
+
+use crate::{LefDefError, LefDefResult};
+
+/// A token and the line on which it appears.
+#[derive(Debug, Clone)]
+struct Token {
+    text: ArcStr,
+    line: usize,
+}
+
+/// A stream of tokens.
+///
+/// LEF and DEF statements are sequences of whitespace-separated words. Semicolons
+/// and parentheses are always tokens of their own, `#` starts a comment that runs to
+/// the end of the line, and double-quoted strings are single tokens.
+pub(crate) struct Tokens {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Tokens {
+    /// Splits `src` into tokens.
+    pub(crate) fn new(src: &str) -> Self {
+        let mut tokens = Vec::new();
+        let mut word = String::new();
+        let mut line = 1;
+        let mut chars = src.chars().peekable();
+
+        fn flush(word: &mut String, line: usize, tokens: &mut Vec<Token>) {
+            if !word.is_empty() {
+                tokens.push(Token {
+                    text: ArcStr::from(word.as_str()),
+                    line,
+                });
+                word.clear();
+            }
+        }
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\n' => {
+                    flush(&mut word, line, &mut tokens);
+                    line += 1;
+                }
+                c if c.is_whitespace() => flush(&mut word, line, &mut tokens),
+                '#' if word.is_empty() => {
+                    for c in chars.by_ref() {
+                        if c == '\n' {
+                            line += 1;
+                            break;
+                        }
+                    }
+                }
+                '"' if word.is_empty() => {
+                    let start = line;
+                    for c in chars.by_ref() {
+                        match c {
+                            '"' => break,
+                            '\n' => {
+                                line += 1;
+                                word.push(c);
+                            }
+                            c => word.push(c),
+                        }
+                    }
+                    tokens.push(Token {
+                        text: ArcStr::from(word.as_str()),
+                        line: start,
+                    });
+                    word.clear();
+                }
+                ';' | '(' | ')' => {
+                    flush(&mut word, line, &mut tokens);
+                    tokens.push(Token {
+                        text: ArcStr::from(c.to_string()),
+                        line,
+                    });
+                }
+                c => word.push(c),
+            }
+        }
+        flush(&mut word, line, &mut tokens);
+
+        Self { tokens, pos: 0 }
+    }
+
+    /// Returns the next token without consuming it.
+    pub(crate) fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|t| t.text.as_str())
+    }
+
+    /// Returns the token after the next token without consuming anything.
+    pub(crate) fn peek2(&self) -> Option<&str> {
+        self.tokens.get(self.pos + 1).map(|t| t.text.as_str())
+    }
+
+    /// Returns true if the next token is `keyword`.
+    pub(crate) fn at(&self, keyword: &str) -> bool {
+        self.peek() == Some(keyword)
+    }
+
+    /// Consumes and returns the next token, if any.
+    pub(crate) fn try_next(&mut self) -> Option<ArcStr> {
+        let token = self.tokens.get(self.pos)?.text.clone();
+        self.pos += 1;
+        Some(token)
+    }
+
+    /// Consumes and returns the next token.
+    pub(crate) fn next(&mut self) -> LefDefResult<ArcStr> {
+        self.try_next()
+            .ok_or_else(|| self.error("unexpected end of file"))
+    }
+
+    /// Consumes the next token if it is `keyword`.
+    pub(crate) fn eat(&mut self, keyword: &str) -> bool {
+        if self.at(keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes the next token, returning an error if it is not `keyword`.
+    pub(crate) fn expect(&mut self, keyword: &str) -> LefDefResult<()> {
+        let token = self.next()?;
+        if token == keyword {
+            Ok(())
+        } else {
+            Err(self.error_before(format!("expected `{keyword}`, found `{token}`")))
+        }
+    }
+
+    /// Consumes and parses the next token as a number.
+    pub(crate) fn number<T: FromStr>(&mut self) -> LefDefResult<T> {
+        let token = self.next()?;
+        token
+            .parse()
+            .map_err(|_| self.error_before(format!("expected a number, found `{token}`")))
+    }
+
+    /// Skips tokens up to and including the next semicolon.
+    pub(crate) fn skip_statement(&mut self) -> LefDefResult<()> {
+        while self.next()? != ";" {}
+        Ok(())
+    }
+
+    /// Skips tokens up to and including `END name`.
+    pub(crate) fn skip_block(&mut self, name: &str) -> LefDefResult<()> {
+        loop {
+            if self.next()? == "END" && self.eat(name) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Returns a parse error at the most recently consumed token.
+    pub(crate) fn error_before(&self, msg: impl Into<String>) -> LefDefError {
+        let line = self
+            .pos
+            .checked_sub(1)
+            .map_or(1, |pos| self.tokens[pos].line);
+        LefDefError::Parse {
+            msg: msg.into(),
+            line,
+        }
+    }
+
+    /// Returns a parse error at the next token.
+    pub(crate) fn error(&self, msg: impl Into<String>) -> LefDefError {
+        let line = self
+            .tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |t| t.line);
+        LefDefError::Parse {
+            msg: msg.into(),
+            line,
+        }
+    }
+}
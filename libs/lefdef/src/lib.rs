@@ -0,0 +1,487 @@
+//! A library for parsing LEF and DEF files.
+//!
+//! LEF (Library Exchange Format) describes the technology layers and vias of a process,
+//! and the abstract views of cells: their size, pins, and obstructions.
+//! DEF (Design Exchange Format) describes a placed and routed design: the cells it
+//! instantiates, its I/O pins, and the wires that connect them.
+//!
+//! Both formats are parsed into plain data structures. Units are kept as they appear
+//! in each file: LEF coordinates are in microns, while DEF coordinates are integers in
+//! the database units given by [`DefDesign::units`].
+//!
+//! Only the constructs needed to reconstruct layout geometry are kept. Other statements,
+//! such as timing, antenna, and spacing rules, are skipped.
+//!
+//! ### Usage
+//!
+//! ```skip
+//! let lef = LefLibrary::load("cells.lef")?;
+//! let def = DefDesign::load("top.def")?;
+//! ```
+
+#![warn(missing_docs)]
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arcstr::ArcStr;
+use serde::{Deserialize, Serialize};
+
+mod def;
+mod lef;
+mod lex;
+#[cfg(test)]
+mod tests;
+
+/// A point in a LEF file, in microns.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LefPoint {
+    /// The x-coordinate.
+    pub x: f64,
+    /// The y-coordinate.
+    pub y: f64,
+}
+
+impl LefPoint {
+    /// Creates a new [`LefPoint`].
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+}
+
+/// The direction of a LEF or DEF pin.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PinDirection {
+    /// An input pin.
+    Input,
+    /// An output pin.
+    Output,
+    /// A bidirectional pin.
+    InOut,
+    /// A pin that passes through a cell without connecting to its contents.
+    Feedthru,
+}
+
+impl PinDirection {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "INPUT" => Self::Input,
+            "OUTPUT" => Self::Output,
+            "INOUT" => Self::InOut,
+            "FEEDTHRU" => Self::Feedthru,
+            _ => return None,
+        })
+    }
+}
+
+/// A LEF library.
+///
+/// May contain technology information, cell abstracts, or both.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LefLibrary {
+    /// The LEF version.
+    pub version: Option<ArcStr>,
+    /// The number of database units per micron.
+    pub database_microns: Option<u32>,
+    /// Layer definitions.
+    pub layers: Vec<LefLayer>,
+    /// Fixed via definitions.
+    pub vias: Vec<LefVia>,
+    /// Cell abstracts.
+    pub macros: Vec<LefMacro>,
+}
+
+impl LefLibrary {
+    /// Creates an empty [`LefLibrary`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a [`LefLibrary`] from the file at `fname`.
+    pub fn load(fname: impl AsRef<Path>) -> LefDefResult<Self> {
+        Self::parse(&std::fs::read_to_string(fname)?)
+    }
+
+    /// Parses the contents of a LEF file.
+    pub fn parse(src: &str) -> LefDefResult<Self> {
+        lef::LefParser::new(src).parse()
+    }
+
+    /// Adds the contents of `other` to this library.
+    ///
+    /// Useful for combining a technology LEF with cell LEFs.
+    pub fn merge(&mut self, other: LefLibrary) {
+        self.version = self.version.take().or(other.version);
+        self.database_microns = self.database_microns.or(other.database_microns);
+        self.layers.extend(other.layers);
+        self.vias.extend(other.vias);
+        self.macros.extend(other.macros);
+    }
+
+    /// Returns the layer with the given name.
+    pub fn layer(&self, name: &str) -> Option<&LefLayer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    /// Returns the via with the given name.
+    pub fn via(&self, name: &str) -> Option<&LefVia> {
+        self.vias.iter().find(|via| via.name == name)
+    }
+
+    /// Returns the macro with the given name.
+    pub fn macro_named(&self, name: &str) -> Option<&LefMacro> {
+        self.macros.iter().find(|m| m.name == name)
+    }
+}
+
+/// A LEF layer definition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LefLayer {
+    /// The name of the layer.
+    pub name: ArcStr,
+    /// The type of the layer, such as `ROUTING` or `CUT`.
+    pub kind: Option<ArcStr>,
+    /// The default width of wires on the layer, in microns.
+    pub width: Option<f64>,
+}
+
+/// A fixed LEF via.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LefVia {
+    /// The name of the via.
+    pub name: ArcStr,
+    /// The shapes of the via, relative to its origin.
+    pub shapes: Vec<LefShape>,
+}
+
+/// A cell abstract.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LefMacro {
+    /// The name of the macro.
+    pub name: ArcStr,
+    /// The class of the macro, such as `CORE` or `BLOCK`.
+    pub class: Option<ArcStr>,
+    /// The name of the layout cell implementing the macro.
+    pub foreign: Option<ArcStr>,
+    /// The offset added to all geometry of the macro when it is placed.
+    pub origin: LefPoint,
+    /// The width and height of the macro.
+    pub size: Option<(f64, f64)>,
+    /// The pins of the macro.
+    pub pins: Vec<LefPin>,
+    /// Geometry that blocks routing over the macro.
+    pub obs: LefGeometry,
+}
+
+/// A pin of a LEF macro.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LefPin {
+    /// The name of the pin.
+    pub name: ArcStr,
+    /// The direction of the pin.
+    pub direction: Option<PinDirection>,
+    /// The use of the pin, such as `SIGNAL` or `POWER`.
+    pub usage: Option<ArcStr>,
+    /// The geometry of the pin.
+    ///
+    /// Each entry is a separate `PORT` of the pin.
+    pub ports: Vec<LefGeometry>,
+}
+
+/// Shapes and vias in a LEF port or obstruction.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LefGeometry {
+    /// Shapes.
+    pub shapes: Vec<LefShape>,
+    /// Placed vias.
+    pub vias: Vec<LefViaRef>,
+}
+
+/// A shape on a LEF layer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LefShape {
+    /// The layer of the shape.
+    pub layer: ArcStr,
+    /// The geometry of the shape.
+    pub kind: LefShapeKind,
+}
+
+/// The geometry of a [`LefShape`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LefShapeKind {
+    /// A rectangle given by two opposite corners.
+    Rect(LefPoint, LefPoint),
+    /// A polygon.
+    Polygon(Vec<LefPoint>),
+    /// A path of the given width with square ends extending half the width past its endpoints.
+    Path {
+        /// The width of the path.
+        width: f64,
+        /// The points along the center of the path.
+        points: Vec<LefPoint>,
+    },
+}
+
+/// A via placed in a LEF port or obstruction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LefViaRef {
+    /// The name of the via.
+    pub name: ArcStr,
+    /// The location of the via origin.
+    pub at: LefPoint,
+}
+
+/// A point in a DEF file, in database units.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DefPoint {
+    /// The x-coordinate.
+    pub x: i64,
+    /// The y-coordinate.
+    pub y: i64,
+}
+
+impl DefPoint {
+    /// Creates a new [`DefPoint`].
+    pub fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+}
+
+/// The orientation of a placed DEF component or pin.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DefOrient {
+    /// No rotation.
+    #[default]
+    N,
+    /// Rotated 180 degrees.
+    S,
+    /// Rotated 90 degrees counter-clockwise.
+    W,
+    /// Rotated 90 degrees clockwise.
+    E,
+    /// Mirrored about the y-axis.
+    FN,
+    /// Mirrored about the x-axis.
+    FS,
+    /// Mirrored about the x-axis, then rotated 90 degrees counter-clockwise.
+    FW,
+    /// Mirrored about the y-axis, then rotated 90 degrees counter-clockwise.
+    FE,
+}
+
+impl DefOrient {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "N" => Self::N,
+            "S" => Self::S,
+            "W" => Self::W,
+            "E" => Self::E,
+            "FN" => Self::FN,
+            "FS" => Self::FS,
+            "FW" => Self::FW,
+            "FE" => Self::FE,
+            _ => return None,
+        })
+    }
+}
+
+/// The placement of a DEF component or pin.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DefPlacement {
+    /// Whether the placement may not be changed by tools.
+    pub fixed: bool,
+    /// The location of the placed object.
+    ///
+    /// For components, this is the lower-left corner of the oriented macro.
+    pub at: DefPoint,
+    /// The orientation of the placed object.
+    pub orient: DefOrient,
+}
+
+/// A placed and routed design.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefDesign {
+    /// The name of the design.
+    pub name: ArcStr,
+    /// The number of database units per micron.
+    pub units: u32,
+    /// The outline of the design.
+    pub die_area: Vec<DefPoint>,
+    /// Via definitions.
+    pub vias: Vec<DefVia>,
+    /// Instances of LEF macros.
+    pub components: Vec<DefComponent>,
+    /// The I/O pins of the design.
+    pub pins: Vec<DefPin>,
+    /// Signal nets.
+    pub nets: Vec<DefNet>,
+    /// Power, ground, and other specially routed nets.
+    pub special_nets: Vec<DefNet>,
+}
+
+impl Default for DefDesign {
+    fn default() -> Self {
+        Self {
+            name: ArcStr::new(),
+            units: 100,
+            die_area: Vec::new(),
+            vias: Vec::new(),
+            components: Vec::new(),
+            pins: Vec::new(),
+            nets: Vec::new(),
+            special_nets: Vec::new(),
+        }
+    }
+}
+
+impl DefDesign {
+    /// Reads a [`DefDesign`] from the file at `fname`.
+    pub fn load(fname: impl AsRef<Path>) -> LefDefResult<Self> {
+        Self::parse(&std::fs::read_to_string(fname)?)
+    }
+
+    /// Parses the contents of a DEF file.
+    pub fn parse(src: &str) -> LefDefResult<Self> {
+        def::DefParser::new(src).parse()
+    }
+
+    /// Returns the via with the given name.
+    pub fn via(&self, name: &str) -> Option<&DefVia> {
+        self.vias.iter().find(|via| via.name == name)
+    }
+}
+
+/// A via defined in a DEF file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefVia {
+    /// The name of the via.
+    pub name: ArcStr,
+    /// The shapes of the via, relative to its origin.
+    ///
+    /// Vias generated from via rules are expanded into their cut and enclosure shapes.
+    pub shapes: Vec<DefShape>,
+}
+
+/// A shape on a DEF layer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefShape {
+    /// The layer of the shape.
+    pub layer: ArcStr,
+    /// The vertices of the shape.
+    pub kind: DefShapeKind,
+}
+
+/// The geometry of a [`DefShape`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DefShapeKind {
+    /// A rectangle given by two opposite corners.
+    Rect(DefPoint, DefPoint),
+    /// A polygon.
+    Polygon(Vec<DefPoint>),
+}
+
+/// An instance of a LEF macro.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefComponent {
+    /// The name of the instance.
+    pub name: ArcStr,
+    /// The name of the instantiated macro.
+    pub macro_name: ArcStr,
+    /// The placement of the instance, if it has been placed.
+    pub placement: Option<DefPlacement>,
+}
+
+/// An I/O pin of a design.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefPin {
+    /// The name of the pin.
+    pub name: ArcStr,
+    /// The net connected to the pin.
+    pub net: ArcStr,
+    /// The direction of the pin.
+    pub direction: Option<PinDirection>,
+    /// The use of the pin, such as `SIGNAL` or `POWER`.
+    pub usage: Option<ArcStr>,
+    /// The physical ports of the pin.
+    pub ports: Vec<DefPinPort>,
+}
+
+/// A physical port of a [`DefPin`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefPinPort {
+    /// The shapes of the port, relative to its placement.
+    pub shapes: Vec<DefShape>,
+    /// The placement of the port, if it has been placed.
+    pub placement: Option<DefPlacement>,
+}
+
+/// A net.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefNet {
+    /// The name of the net.
+    pub name: ArcStr,
+    /// The connected component pins, as pairs of component name and pin name.
+    ///
+    /// Connections to I/O pins of the design use the component name `PIN`.
+    pub connections: Vec<(ArcStr, ArcStr)>,
+    /// The routed wires of the net.
+    pub routes: Vec<DefRoute>,
+}
+
+/// A sequence of connected wire segments and vias.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefRoute {
+    /// The layer on which the route starts.
+    pub layer: ArcStr,
+    /// The width of wire segments.
+    ///
+    /// Given explicitly for special nets. Regular nets use the default width of the
+    /// routing layer.
+    pub width: Option<i64>,
+    /// The points, vias, and rectangles along the route.
+    pub items: Vec<DefRouteItem>,
+}
+
+/// An element of a [`DefRoute`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DefRouteItem {
+    /// A point along the center of the route.
+    ///
+    /// Consecutive points are connected by wire segments with square ends that
+    /// extend half the wire width past the points.
+    Point(DefPoint),
+    /// A via placed at the previous point.
+    ///
+    /// Subsequent segments continue on the other routing layer of the via.
+    Via(ArcStr),
+    /// A rectangle relative to the previous point.
+    Rect(DefPoint, DefPoint),
+}
+
+/// The [`LefDefError`] result type.
+pub type LefDefResult<T> = Result<T, LefDefError>;
+
+/// A LEF or DEF parsing error.
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum LefDefError {
+    /// An I/O error.
+    #[error("I/O error: {0}")]
+    Io(Arc<std::io::Error>),
+    /// A syntax error.
+    #[error("error parsing line {line}: {msg}")]
+    Parse {
+        /// A description of the error.
+        msg: String,
+        /// The line on which the error occurred.
+        line: usize,
+    },
+    /// An unsupported feature.
+    #[error("unsupported LEF/DEF feature: {0}")]
+    Unsupported(ArcStr),
+}
+
+impl From<std::io::Error> for LefDefError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(Arc::new(e))
+    }
+}
@@ -0,0 +1,268 @@
+use super::*;
+
+const LEF: &str = r#"
+VERSION 5.8 ;
+BUSBITCHARS "[]" ;
+DIVIDERCHAR "/" ;
+
+UNITS
+  DATABASE MICRONS 1000 ;
+END UNITS
+
+PROPERTYDEFINITIONS
+  LAYER LEF58_TYPE STRING ;
+END PROPERTYDEFINITIONS
+
+LAYER met1
+  TYPE ROUTING ;
+  DIRECTION HORIZONTAL ;
+  PITCH 0.34 ;
+  WIDTH 0.14 ;
+  PROPERTY LEF58_TYPE "TYPE ROUTING ; # not a comment" ;
+END met1
+
+LAYER via
+  TYPE CUT ;
+END via
+
+SITE unithd
+  SIZE 0.46 BY 2.72 ;
+END unithd
+
+VIA M1M2_PR DEFAULT
+  LAYER met1 ;
+    RECT -0.16 -0.13 0.16 0.13 ;
+  LAYER via ;
+    RECT -0.075 -0.075 0.075 0.075 ;
+END M1M2_PR
+
+MACRO inv
+  CLASS CORE ;
+  FOREIGN inv ;
+  ORIGIN 0 0 ;
+  SIZE 1.38 BY 2.72 ;
+  SYMMETRY X Y R90 ;
+  SITE unithd ;
+  PIN a[0]
+    DIRECTION INPUT ;
+    USE SIGNAL ;
+    PORT
+      LAYER li1 ;
+        RECT 0.1 1.0 0.4 1.3 ;
+        POLYGON 0.1 0.1 0.3 0.1 0.3 0.3 ;
+    END
+  END a[0]
+  PIN y
+    DIRECTION OUTPUT TRISTATE ;
+    PORT
+      LAYER met1 ;
+        WIDTH 0.2 ;
+        PATH 0.5 0.5 0.5 1.5 ;
+      VIA 0.5 1.5 M1M2_PR ;
+    END
+  END y
+  OBS
+    LAYER li1 ;
+      RECT 0 0 1.38 0.2 ;
+  END
+END inv
+
+END LIBRARY
+"#;
+
+#[test]
+fn parse_lef() {
+    let lib = LefLibrary::parse(LEF).unwrap();
+    assert_eq!(lib.version.as_deref(), Some("5.8"));
+    assert_eq!(lib.database_microns, Some(1000));
+
+    assert_eq!(lib.layers.len(), 2);
+    let met1 = lib.layer("met1").unwrap();
+    assert_eq!(met1.kind.as_deref(), Some("ROUTING"));
+    assert_eq!(met1.width, Some(0.14));
+    assert_eq!(lib.layer("via").unwrap().width, None);
+
+    let via = lib.via("M1M2_PR").unwrap();
+    assert_eq!(via.shapes.len(), 2);
+    assert_eq!(via.shapes[1].layer, "via");
+
+    let inv = lib.macro_named("inv").unwrap();
+    assert_eq!(inv.class.as_deref(), Some("CORE"));
+    assert_eq!(inv.size, Some((1.38, 2.72)));
+    assert_eq!(inv.pins.len(), 2);
+
+    let a = &inv.pins[0];
+    assert_eq!(a.name, "a[0]");
+    assert_eq!(a.direction, Some(PinDirection::Input));
+    assert_eq!(a.usage.as_deref(), Some("SIGNAL"));
+    assert_eq!(
+        a.ports[0].shapes,
+        vec![
+            LefShape {
+                layer: arcstr::literal!("li1"),
+                kind: LefShapeKind::Rect(LefPoint::new(0.1, 1.0), LefPoint::new(0.4, 1.3)),
+            },
+            LefShape {
+                layer: arcstr::literal!("li1"),
+                kind: LefShapeKind::Polygon(vec![
+                    LefPoint::new(0.1, 0.1),
+                    LefPoint::new(0.3, 0.1),
+                    LefPoint::new(0.3, 0.3),
+                ]),
+            },
+        ]
+    );
+
+    let y = &inv.pins[1];
+    assert_eq!(y.direction, Some(PinDirection::Output));
+    assert_eq!(
+        y.ports[0].shapes[0].kind,
+        LefShapeKind::Path {
+            width: 0.2,
+            points: vec![LefPoint::new(0.5, 0.5), LefPoint::new(0.5, 1.5)],
+        }
+    );
+    assert_eq!(
+        y.ports[0].vias,
+        vec![LefViaRef {
+            name: arcstr::literal!("M1M2_PR"),
+            at: LefPoint::new(0.5, 1.5),
+        }]
+    );
+
+    assert_eq!(inv.obs.shapes.len(), 1);
+}
+
+#[test]
+fn parse_lef_reports_line_of_error() {
+    let err = LefLibrary::parse("VERSION 5.8 ;\nMACRO a\n  SIZE 1 X 2 ;\nEND a\n").unwrap_err();
+    assert!(matches!(err, LefDefError::Parse { line: 3, .. }), "{err}");
+}
+
+const DEF: &str = r#"
+VERSION 5.8 ;
+DIVIDERCHAR "/" ;
+BUSBITCHARS "[]" ;
+DESIGN top ;
+UNITS DISTANCE MICRONS 1000 ;
+DIEAREA ( 0 0 ) ( 10000 20000 ) ;
+ROW row_0 unithd 0 0 N DO 10 BY 1 STEP 460 0 ;
+TRACKS X 230 DO 20 STEP 460 LAYER met1 ;
+
+VIAS 2 ;
+  - via_fixed + RECT met1 ( -100 -100 ) ( 100 100 ) + RECT via ( -50 -50 ) ( 50 50 ) ;
+  - via_gen + VIARULE M1M2 + CUTSIZE 150 150 + LAYERS met1 via met2
+    + CUTSPACING 170 170 + ENCLOSURE 50 10 10 50 + ROWCOL 1 2 ;
+END VIAS
+
+COMPONENTS 2 ;
+  - u0 inv + PLACED ( 1000 2000 ) FS ;
+  - u1 inv + SOURCE NETLIST + UNPLACED ;
+END COMPONENTS
+
+PINS 2 ;
+  - din + NET din + DIRECTION INPUT + USE SIGNAL
+    + LAYER met2 ( -70 0 ) ( 70 140 ) + PLACED ( 5000 0 ) N ;
+  - vdd + NET vdd + SPECIAL + DIRECTION INOUT + USE POWER
+    + PORT + LAYER met1 ( 0 0 ) ( 100 100 ) + FIXED ( 0 0 ) N
+    + PORT + LAYER met1 ( 0 0 ) ( 100 100 ) + FIXED ( 9900 0 ) N ;
+END PINS
+
+SPECIALNETS 1 ;
+  - vdd ( * vpwr ) + USE POWER
+    + ROUTED met1 480 + SHAPE STRIPE ( 0 2720 ) ( 10000 * ) ;
+END SPECIALNETS
+
+NETS 1 ;
+  - din ( PIN din ) ( u0 a[0] + SYNTHESIZED ) + USE SIGNAL
+    + ROUTED met2 ( 5000 70 ) ( * 2500 ) via_gen ( 1200 * 0 )
+    NEW met1 ( 1200 2500 ) VIRTUAL ( 1500 2500 ) ( 1500 3000 ) ;
+END NETS
+
+END DESIGN
+"#;
+
+#[test]
+fn parse_def() {
+    let def = DefDesign::parse(DEF).unwrap();
+    assert_eq!(def.name, "top");
+    assert_eq!(def.units, 1000);
+    assert_eq!(
+        def.die_area,
+        vec![DefPoint::new(0, 0), DefPoint::new(10000, 20000)]
+    );
+
+    assert_eq!(def.via("via_fixed").unwrap().shapes.len(), 2);
+    let rect = |layer: &str, x0, y0, x1, y1| DefShape {
+        layer: layer.into(),
+        kind: DefShapeKind::Rect(DefPoint::new(x0, y0), DefPoint::new(x1, y1)),
+    };
+    assert_eq!(
+        def.via("via_gen").unwrap().shapes,
+        vec![
+            rect("met1", -285, -85, 285, 85),
+            rect("met2", -245, -125, 245, 125),
+            rect("via", -235, -75, -85, 75),
+            rect("via", 85, -75, 235, 75),
+        ]
+    );
+
+    assert_eq!(def.components.len(), 2);
+    assert_eq!(def.components[0].macro_name, "inv");
+    assert_eq!(
+        def.components[0].placement,
+        Some(DefPlacement {
+            fixed: false,
+            at: DefPoint::new(1000, 2000),
+            orient: DefOrient::FS,
+        })
+    );
+    assert_eq!(def.components[1].placement, None);
+
+    let din = &def.pins[0];
+    assert_eq!(din.direction, Some(PinDirection::Input));
+    assert_eq!(din.ports.len(), 1);
+    assert_eq!(din.ports[0].shapes, vec![rect("met2", -70, 0, 70, 140)]);
+    assert_eq!(din.ports[0].placement.unwrap().at, DefPoint::new(5000, 0));
+    let vdd = &def.pins[1];
+    assert_eq!(vdd.usage.as_deref(), Some("POWER"));
+    assert_eq!(vdd.ports.len(), 2);
+    assert!(vdd.ports[1].placement.unwrap().fixed);
+
+    let vdd = &def.special_nets[0];
+    assert_eq!(
+        vdd.routes,
+        vec![DefRoute {
+            layer: "met1".into(),
+            width: Some(480),
+            items: vec![
+                DefRouteItem::Point(DefPoint::new(0, 2720)),
+                DefRouteItem::Point(DefPoint::new(10000, 2720)),
+            ],
+        }]
+    );
+
+    let din = &def.nets[0];
+    assert_eq!(
+        din.connections,
+        vec![("PIN".into(), "din".into()), ("u0".into(), "a[0]".into())]
+    );
+    assert_eq!(din.routes.len(), 3);
+    assert_eq!(
+        din.routes[0].items,
+        vec![
+            DefRouteItem::Point(DefPoint::new(5000, 70)),
+            DefRouteItem::Point(DefPoint::new(5000, 2500)),
+            DefRouteItem::Via("via_gen".into()),
+            DefRouteItem::Point(DefPoint::new(1200, 2500)),
+        ]
+    );
+    assert_eq!(din.routes[1].layer, "met1");
+    assert_eq!(
+        din.routes[2].items,
+        vec![
+            DefRouteItem::Point(DefPoint::new(1500, 2500)),
+            DefRouteItem::Point(DefPoint::new(1500, 3000)),
+        ]
+    );
+}
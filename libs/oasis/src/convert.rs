@@ -0,0 +1,490 @@
+//! Conversions to and from GDSII.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use arcstr::ArcStr;
+use gds::{
+    GdsArrayRef, GdsBoundary, GdsElement, GdsLibrary, GdsPath, GdsPoint, GdsStrans, GdsStruct,
+    GdsStructRef, GdsTextElem, GdsUnits,
+};
+
+use super::*;
+
+/// The maximum number of GDS elements produced by expanding repetitions
+/// when converting a library to GDS.
+const MAX_EXPANDED_ELEMENTS: usize = 1 << 24;
+
+/// Groups of identical elements, keyed by everything but their position.
+///
+/// Preserves the order in which keys are first seen.
+struct Groups<K> {
+    keys: HashMap<K, usize>,
+    groups: Vec<(K, Vec<OasisPoint>)>,
+}
+
+impl<K: Hash + Eq + Clone> Groups<K> {
+    fn new() -> Self {
+        Self {
+            keys: HashMap::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, key: K, offsets: impl IntoIterator<Item = OasisPoint>) {
+        let idx = *self.keys.entry(key.clone()).or_insert_with(|| {
+            self.groups.push((key, Vec::new()));
+            self.groups.len() - 1
+        });
+        self.groups[idx].1.extend(offsets);
+    }
+
+    /// Returns each group's key, position, and repetition.
+    fn into_repetitions(self) -> impl Iterator<Item = (K, OasisPoint, Option<OasisRepetition>)> {
+        self.groups.into_iter().map(|(key, mut points)| {
+            // Use the lower-left point as the origin so that lattices are detected.
+            points.sort_by_key(|p| (p.y, p.x));
+            points.dedup();
+            let repetition = OasisRepetition::from_offsets(&points);
+            (key, points[0], repetition)
+        })
+    }
+}
+
+/// The grouping key of a placement.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct PlacementKey {
+    cell: ArcStr,
+    flip: bool,
+    angle: u64,
+    mag: u64,
+}
+
+fn point(p: &GdsPoint) -> OasisPoint {
+    OasisPoint::new(p.x as i64, p.y as i64)
+}
+
+fn gds_point(p: OasisPoint) -> OasisResult<GdsPoint> {
+    Ok(GdsPoint::new(p.x.try_into()?, p.y.try_into()?))
+}
+
+fn coordinate_overflow() -> OasisError {
+    OasisError::InvalidArgument(arcstr::literal!("coordinate overflow"))
+}
+
+/// Returns the GDS point at `p` displaced by `offset`.
+fn displaced(p: OasisPoint, offset: OasisPoint) -> OasisResult<GdsPoint> {
+    gds_point(p.checked_add(offset).ok_or_else(coordinate_overflow)?)
+}
+
+fn number(n: i16) -> OasisResult<u64> {
+    Ok(u64::try_from(n)?)
+}
+
+/// Returns the lower-left corner, width, and height of `xy`
+/// if it describes a closed axis-aligned rectangle.
+fn rectangle(xy: &[GdsPoint]) -> Option<(OasisPoint, u64, u64)> {
+    if xy.len() != 5 || xy[0] != xy[4] {
+        return None;
+    }
+    let pts = xy[..4].iter().map(point).collect::<Vec<_>>();
+    let horiz = |a: OasisPoint, b: OasisPoint| a.y == b.y && a.x != b.x;
+    let vert = |a: OasisPoint, b: OasisPoint| a.x == b.x && a.y != b.y;
+    let edges = (0..4).map(|i| (pts[i], pts[(i + 1) % 4]));
+    let alternating = edges.clone().step_by(2).all(|(a, b)| horiz(a, b))
+        && edges.clone().skip(1).step_by(2).all(|(a, b)| vert(a, b));
+    let alternating_rev = edges.clone().step_by(2).all(|(a, b)| vert(a, b))
+        && edges.skip(1).step_by(2).all(|(a, b)| horiz(a, b));
+    if !(alternating || alternating_rev) {
+        return None;
+    }
+    let x0 = pts.iter().map(|p| p.x).min()?;
+    let y0 = pts.iter().map(|p| p.y).min()?;
+    let x1 = pts.iter().map(|p| p.x).max()?;
+    let y1 = pts.iter().map(|p| p.y).max()?;
+    Some((OasisPoint::new(x0, y0), (x1 - x0) as u64, (y1 - y0) as u64))
+}
+
+/// Returns the OASIS extension of a path end, or [`None`] if OASIS cannot represent it.
+fn path_extension(path: &GdsPath, extn: Option<i32>) -> Option<OasisPathExtension> {
+    Some(match path.path_type.unwrap_or_default() {
+        0 => OasisPathExtension::Flush,
+        2 => OasisPathExtension::HalfWidth,
+        4 => OasisPathExtension::Explicit(extn.unwrap_or_default() as i64),
+        _ => return None,
+    })
+}
+
+fn strans(strans: &Option<GdsStrans>) -> (bool, f64, f64) {
+    match strans {
+        Some(strans) => (
+            strans.reflected,
+            strans.angle.unwrap_or_default(),
+            strans.mag.unwrap_or(1.),
+        ),
+        None => (false, 0., 1.),
+    }
+}
+
+fn gds_strans(flip: bool, angle: f64, mag: f64) -> Option<GdsStrans> {
+    if !flip && angle == 0. && mag == 1. {
+        return None;
+    }
+    Some(GdsStrans {
+        reflected: flip,
+        mag: Some(mag).filter(|&mag| mag != 1.),
+        angle: Some(angle).filter(|&angle| angle != 0.),
+        ..Default::default()
+    })
+}
+
+impl OasisLibrary {
+    /// Converts a [`gds::GdsLibrary`] to an [`OasisLibrary`].
+    ///
+    /// Identical rectangles and cell placements are merged into repetitions.
+    /// Array references are converted to placements with grid repetitions.
+    /// Text presentation, node elements, and properties are not retained.
+    ///
+    /// Round-ended paths and paths with odd widths, which OASIS cannot represent,
+    /// are converted to polygons (see [`GdsPath::to_polygons`]).
+    ///
+    /// Returns an error for negative layer or datatype numbers.
+    pub fn from_gds(lib: &GdsLibrary) -> OasisResult<Self> {
+        let mut unit = 1e-6 / lib.units.db_unit();
+        if (unit - unit.round()).abs() < 1e-6 {
+            unit = unit.round();
+        }
+        let cells = lib
+            .structs
+            .iter()
+            .map(Self::cell_from_gds)
+            .collect::<OasisResult<_>>()?;
+        Ok(Self { unit, cells })
+    }
+
+    fn cell_from_gds(strukt: &GdsStruct) -> OasisResult<OasisCell> {
+        let mut cell = OasisCell::new(strukt.name.clone());
+        let mut rects = Groups::new();
+        let mut placements = Groups::new();
+
+        for elem in strukt.elems.iter() {
+            match elem {
+                GdsElement::GdsBoundary(b) => {
+                    let (layer, datatype) = (number(b.layer)?, number(b.datatype)?);
+                    if let Some((p0, w, h)) = rectangle(&b.xy) {
+                        rects.push((layer, datatype, w, h), [p0]);
+                    } else {
+                        let mut points = b.xy.iter().map(point).collect::<Vec<_>>();
+                        if points.len() > 1 && points.first() == points.last() {
+                            points.pop();
+                        }
+                        cell.elements.push(OasisElement::Polygon(OasisPolygon {
+                            layer,
+                            datatype,
+                            points,
+                            repetition: None,
+                        }));
+                    }
+                }
+                GdsElement::GdsBox(b) => {
+                    let (layer, datatype) = (number(b.layer)?, number(b.boxtype)?);
+                    let Some((p0, w, h)) = rectangle(&b.xy) else {
+                        return Err(OasisError::Unsupported(arcstr::literal!(
+                            "non-rectangular GDS box"
+                        )));
+                    };
+                    rects.push((layer, datatype, w, h), [p0]);
+                }
+                GdsElement::GdsPath(p) => {
+                    let (layer, datatype) = (number(p.layer)?, number(p.datatype)?);
+                    let width = p.width.unwrap_or_default().unsigned_abs() as u64;
+                    let extensions = path_extension(p, p.begin_extn)
+                        .zip(path_extension(p, p.end_extn))
+                        .filter(|_| width % 2 == 0);
+                    if let Some((start_extension, end_extension)) = extensions {
+                        cell.elements.push(OasisElement::Path(OasisPath {
+                            layer,
+                            datatype,
+                            half_width: width / 2,
+                            start_extension,
+                            end_extension,
+                            points: p.xy.iter().map(point).collect(),
+                            repetition: None,
+                        }));
+                    } else {
+                        // OASIS paths have neither round ends nor odd widths.
+                        cell.elements
+                            .extend(p.to_polygons().into_iter().map(|points| {
+                                OasisElement::Polygon(OasisPolygon {
+                                    layer,
+                                    datatype,
+                                    points: points.iter().map(point).collect(),
+                                    repetition: None,
+                                })
+                            }));
+                    }
+                }
+                GdsElement::GdsTextElem(t) => {
+                    cell.elements.push(OasisElement::Text(OasisText {
+                        string: t.string.clone(),
+                        layer: number(t.layer)?,
+                        texttype: number(t.texttype)?,
+                        x: t.xy.x as i64,
+                        y: t.xy.y as i64,
+                        repetition: None,
+                    }));
+                }
+                GdsElement::GdsStructRef(r) => {
+                    let (flip, angle, mag) = strans(&r.strans);
+                    let key = PlacementKey {
+                        cell: r.name.clone(),
+                        flip,
+                        angle: angle.to_bits(),
+                        mag: mag.to_bits(),
+                    };
+                    placements.push(key, [point(&r.xy)]);
+                }
+                GdsElement::GdsArrayRef(a) => {
+                    let (flip, angle, mag) = strans(&a.strans);
+                    let (cols, rows) = (u64::try_from(a.cols)?, u64::try_from(a.rows)?);
+                    if cols == 0 || rows == 0 {
+                        continue;
+                    }
+                    let p0 = point(&a.xy[0]);
+                    let repetition = if cols * rows > 1 {
+                        Some(OasisRepetition::Grid {
+                            cols,
+                            rows,
+                            col_step: OasisPoint::new(
+                                (a.xy[1].x as i64 - p0.x) / cols as i64,
+                                (a.xy[1].y as i64 - p0.y) / cols as i64,
+                            ),
+                            row_step: OasisPoint::new(
+                                (a.xy[2].x as i64 - p0.x) / rows as i64,
+                                (a.xy[2].y as i64 - p0.y) / rows as i64,
+                            ),
+                        })
+                    } else {
+                        None
+                    };
+                    cell.elements.push(OasisElement::Placement(OasisPlacement {
+                        cell: a.name.clone(),
+                        x: p0.x,
+                        y: p0.y,
+                        flip,
+                        angle,
+                        mag,
+                        repetition,
+                    }));
+                }
+                // Nodes carry no geometry.
+                GdsElement::GdsNode(_) => {}
+            }
+        }
+
+        for ((layer, datatype, width, height), p0, repetition) in rects.into_repetitions() {
+            cell.elements.push(OasisElement::Rectangle(OasisRectangle {
+                layer,
+                datatype,
+                x: p0.x,
+                y: p0.y,
+                width,
+                height,
+                repetition,
+            }));
+        }
+        for (key, p0, repetition) in placements.into_repetitions() {
+            cell.elements.push(OasisElement::Placement(OasisPlacement {
+                cell: key.cell,
+                x: p0.x,
+                y: p0.y,
+                flip: key.flip,
+                angle: f64::from_bits(key.angle),
+                mag: f64::from_bits(key.mag),
+                repetition,
+            }));
+        }
+        Ok(cell)
+    }
+
+    /// Converts to a [`gds::GdsLibrary`] named `name`.
+    ///
+    /// Placements with grid repetitions are converted to array references.
+    /// All other repetitions are expanded into individual elements.
+    ///
+    /// Returns an error if repetitions expand to more than 2^24 elements in total,
+    /// or if a repeated element's coordinates overflow.
+    pub fn to_gds(&self, name: impl Into<ArcStr>) -> OasisResult<GdsLibrary> {
+        let mut lib = GdsLibrary::new(name);
+        lib.units = GdsUnits::new(1. / self.unit, 1e-6 / self.unit);
+        let mut budget = MAX_EXPANDED_ELEMENTS;
+        lib.structs = self
+            .cells
+            .iter()
+            .map(|cell| Self::cell_to_gds(cell, &mut budget))
+            .collect::<OasisResult<_>>()?;
+        Ok(lib)
+    }
+
+    /// Converts `cell` to GDS, expanding at most `budget` repeated elements.
+    fn cell_to_gds(cell: &OasisCell, budget: &mut usize) -> OasisResult<GdsStruct> {
+        let mut strukt = GdsStruct::new(cell.name.clone());
+        let mut offsets = |repetition: &Option<OasisRepetition>| match repetition {
+            Some(repetition) => {
+                *budget = budget.checked_sub(repetition.len()).ok_or_else(|| {
+                    OasisError::Unsupported(arcstr::format!(
+                        "repetitions expanding to more than {MAX_EXPANDED_ELEMENTS} elements"
+                    ))
+                })?;
+                repetition.offsets()
+            }
+            None => Ok(vec![OasisPoint::default()]),
+        };
+        let layer = |n: u64| -> OasisResult<i16> { Ok(i16::try_from(n)?) };
+
+        for elem in cell.elements.iter() {
+            match elem {
+                OasisElement::Placement(p) => {
+                    let p0 = OasisPoint::new(p.x, p.y);
+                    let strans = gds_strans(p.flip, p.angle, p.mag);
+                    if let Some(OasisRepetition::Grid {
+                        cols,
+                        rows,
+                        col_step,
+                        row_step,
+                    }) = p.repetition
+                    {
+                        let (gds_cols, gds_rows) = (i16::try_from(cols)?, i16::try_from(rows)?);
+                        let corner = |step: OasisPoint, n: i16| {
+                            let step = step.checked_scale(n.into());
+                            displaced(p0, step.ok_or_else(coordinate_overflow)?)
+                        };
+                        strukt.elems.push(
+                            GdsArrayRef {
+                                name: p.cell.clone(),
+                                xy: [
+                                    gds_point(p0)?,
+                                    corner(col_step, gds_cols)?,
+                                    corner(row_step, gds_rows)?,
+                                ],
+                                cols: gds_cols,
+                                rows: gds_rows,
+                                strans,
+                                ..Default::default()
+                            }
+                            .into(),
+                        );
+                        continue;
+                    }
+                    for offset in offsets(&p.repetition)? {
+                        strukt.elems.push(
+                            GdsStructRef {
+                                name: p.cell.clone(),
+                                xy: displaced(p0, offset)?,
+                                strans: strans.clone(),
+                                ..Default::default()
+                            }
+                            .into(),
+                        );
+                    }
+                }
+                OasisElement::Text(t) => {
+                    for offset in offsets(&t.repetition)? {
+                        strukt.elems.push(
+                            GdsTextElem {
+                                string: t.string.clone(),
+                                layer: layer(t.layer)?,
+                                texttype: layer(t.texttype)?,
+                                xy: displaced(OasisPoint::new(t.x, t.y), offset)?,
+                                ..Default::default()
+                            }
+                            .into(),
+                        );
+                    }
+                }
+                OasisElement::Rectangle(r) => {
+                    let (w, h) = (r.width as i64, r.height as i64);
+                    for offset in offsets(&r.repetition)? {
+                        let p0 = displaced(OasisPoint::new(r.x, r.y), offset)?;
+                        let p0 = OasisPoint::new(p0.x.into(), p0.y.into());
+                        let xy = [(0, 0), (w, 0), (w, h), (0, h), (0, 0)]
+                            .into_iter()
+                            .map(|(dx, dy)| displaced(p0, OasisPoint::new(dx, dy)))
+                            .collect::<OasisResult<_>>()?;
+                        strukt.elems.push(
+                            GdsBoundary {
+                                layer: layer(r.layer)?,
+                                datatype: layer(r.datatype)?,
+                                xy,
+                                ..Default::default()
+                            }
+                            .into(),
+                        );
+                    }
+                }
+                OasisElement::Polygon(p) => {
+                    for offset in offsets(&p.repetition)? {
+                        let mut xy = p
+                            .points
+                            .iter()
+                            .map(|&pt| displaced(pt, offset))
+                            .collect::<OasisResult<Vec<_>>>()?;
+                        if let Some(first) = xy.first().cloned() {
+                            xy.push(first);
+                        }
+                        strukt.elems.push(
+                            GdsBoundary {
+                                layer: layer(p.layer)?,
+                                datatype: layer(p.datatype)?,
+                                xy,
+                                ..Default::default()
+                            }
+                            .into(),
+                        );
+                    }
+                }
+                OasisElement::Path(p) => {
+                    let extn = |ext: OasisPathExtension| match ext {
+                        OasisPathExtension::Flush => 0,
+                        OasisPathExtension::HalfWidth => p.half_width as i64,
+                        OasisPathExtension::Explicit(d) => d,
+                    };
+                    let (path_type, begin_extn, end_extn) =
+                        match (p.start_extension, p.end_extension) {
+                            (OasisPathExtension::Flush, OasisPathExtension::Flush) => {
+                                (0, None, None)
+                            }
+                            (OasisPathExtension::HalfWidth, OasisPathExtension::HalfWidth) => {
+                                (2, None, None)
+                            }
+                            (start, end) => (
+                                4,
+                                Some(i32::try_from(extn(start))?),
+                                Some(i32::try_from(extn(end))?),
+                            ),
+                        };
+                    for offset in offsets(&p.repetition)? {
+                        strukt.elems.push(
+                            GdsPath {
+                                layer: layer(p.layer)?,
+                                datatype: layer(p.datatype)?,
+                                xy: p
+                                    .points
+                                    .iter()
+                                    .map(|&pt| displaced(pt, offset))
+                                    .collect::<OasisResult<_>>()?,
+                                width: Some(i32::try_from(2 * p.half_width)?),
+                                path_type: Some(path_type),
+                                begin_extn,
+                                end_extn,
+                                ..Default::default()
+                            }
+                            .into(),
+                        );
+                    }
+                }
+            }
+        }
+        Ok(strukt)
+    }
+}
@@ -0,0 +1,667 @@
+//! A library for parsing and writing OASIS files.
+//!
+//! OASIS (SEMI P39) is the successor to GDSII for storing and exchanging IC layout data.
+//! It encodes the same hierarchy of cells, placements, and geometry as GDSII,
+//! but uses variable-length integers, modal variables, repetitions, and optional
+//! compression to produce substantially smaller files.
+//!
+//! Layout data is represented in two forms:
+//!
+//! * An [`OasisLibrary`] consists of a set of cells ([`OasisCell`]s),
+//!   each of which contains a list of [`OasisElement`]s:
+//!   placements of other cells, text, rectangles, polygons, and paths.
+//!   All modal variables and name references are resolved in this form.
+//! * For storage on disk, a library is flattened to a series of [`OasisRecord`]s.
+//!   Records can be read and written directly using an [`OasisReader`] and an [`OasisWriter`].
+//!
+//! Conversions to and from the [`gds`] crate's [`gds::GdsLibrary`] are provided
+//! by [`OasisLibrary::from_gds`] and [`OasisLibrary::to_gds`].
+//!
+//! ### Usage
+//!
+//! Loading an [`OasisLibrary`] from disk:
+//!
+//! ```skip
+//! let lib = OasisLibrary::load("sample.oas")?;
+//! ```
+//!
+//! Creating a new library with a single cell, and writing it to bytes:
+//!
+//! ```rust
+//! use oasis::{OasisCell, OasisElement, OasisLibrary, OasisRectangle};
+//! let mut lib = OasisLibrary::new(1000.);
+//! let mut cell = OasisCell::new("mycell");
+//! cell.elements.push(OasisElement::Rectangle(OasisRectangle {
+//!     layer: 1,
+//!     datatype: 0,
+//!     x: 0,
+//!     y: 0,
+//!     width: 100,
+//!     height: 200,
+//!     repetition: None,
+//! }));
+//! lib.cells.push(cell);
+//!
+//! let mut bytes = Vec::new();
+//! lib.write(&mut bytes).unwrap();
+//! assert_eq!(OasisLibrary::from_bytes(&bytes).unwrap(), lib);
+//! ```
+//!
+//! ### Unsupported features
+//!
+//! Properties, extension records, and layer names are parsed but not retained in an [`OasisLibrary`].
+//! Trapezoids are converted to polygons when read. Circles and compact trapezoids are not supported.
+#![warn(missing_docs)]
+
+mod convert;
+mod read;
+#[cfg(test)]
+mod tests;
+mod write;
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use arcstr::ArcStr;
+use serde::{Deserialize, Serialize};
+
+pub use read::OasisReader;
+pub use write::OasisWriter;
+
+/// The magic bytes at the start of every OASIS file.
+pub const MAGIC: &[u8] = b"%SEMI-OASIS\r\n";
+
+/// The OASIS version written by this crate.
+pub const VERSION: &str = "1.0";
+
+/// An OASIS spatial point or displacement.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub struct OasisPoint {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl OasisPoint {
+    /// Creates a new [`OasisPoint`].
+    pub const fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+
+    fn checked_scale(self, n: i64) -> Option<Self> {
+        Some(Self::new(self.x.checked_mul(n)?, self.y.checked_mul(n)?))
+    }
+
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        Some(Self::new(
+            self.x.checked_add(rhs.x)?,
+            self.y.checked_add(rhs.y)?,
+        ))
+    }
+}
+
+impl std::ops::Add for OasisPoint {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl std::ops::Sub for OasisPoint {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+/// A repetition of an OASIS element.
+///
+/// Repeated elements are displaced relative to the position of the element itself.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub enum OasisRepetition {
+    /// A lattice of `cols` by `rows` elements.
+    ///
+    /// The element in row `i` and column `j` is displaced by `i * row_step + j * col_step`.
+    Grid {
+        /// The number of columns.
+        cols: u64,
+        /// The number of rows.
+        rows: u64,
+        /// The displacement between adjacent columns.
+        col_step: OasisPoint,
+        /// The displacement between adjacent rows.
+        row_step: OasisPoint,
+    },
+    /// An arbitrary list of displacements, starting with the origin.
+    Arbitrary(Vec<OasisPoint>),
+}
+
+impl OasisRepetition {
+    /// Returns the displacement of each repeated element, starting with the origin.
+    ///
+    /// Returns an error if the number of elements does not fit in a [`usize`]
+    /// or a displacement does not fit in an [`OasisPoint`].
+    pub fn offsets(&self) -> OasisResult<Vec<OasisPoint>> {
+        match self {
+            Self::Grid {
+                cols,
+                rows,
+                col_step,
+                row_step,
+            } => {
+                let overflow =
+                    || OasisError::InvalidArgument(arcstr::literal!("grid repetition overflow"));
+                let len = cols.checked_mul(*rows).ok_or_else(overflow)?;
+                let mut offsets = Vec::with_capacity(usize::try_from(len)?);
+                for i in 0..i64::try_from(*rows)? {
+                    let row = row_step.checked_scale(i).ok_or_else(overflow)?;
+                    for j in 0..i64::try_from(*cols)? {
+                        let offset = col_step
+                            .checked_scale(j)
+                            .and_then(|col| row.checked_add(col))
+                            .ok_or_else(overflow)?;
+                        offsets.push(offset);
+                    }
+                }
+                Ok(offsets)
+            }
+            Self::Arbitrary(offsets) => Ok(offsets.clone()),
+        }
+    }
+
+    /// The number of repeated elements, saturating at [`usize::MAX`].
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Grid { cols, rows, .. } => {
+                usize::try_from(cols.saturating_mul(*rows)).unwrap_or(usize::MAX)
+            }
+            Self::Arbitrary(offsets) => offsets.len(),
+        }
+    }
+
+    /// Returns `true` if the repetition contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Creates the most compact repetition with the given displacements.
+    ///
+    /// Displacements are relative to the first one. Returns [`None`] if fewer than
+    /// two displacements are given.
+    pub fn from_offsets(offsets: &[OasisPoint]) -> Option<Self> {
+        if offsets.len() < 2 {
+            return None;
+        }
+        let origin = offsets[0];
+        let mut sorted = offsets.iter().map(|&p| p - origin).collect::<Vec<_>>();
+        sorted.sort_by_key(|p| (p.y, p.x));
+
+        // Check whether the displacements form a complete lattice.
+        if sorted[0] == OasisPoint::default() {
+            let cols = sorted.iter().take_while(|p| p.y == 0).count();
+            let rows = sorted.len() / cols;
+            if rows * cols == sorted.len() {
+                let col_step = sorted.get(1).copied().filter(|_| cols > 1);
+                let row_step = sorted.get(cols).copied().filter(|_| rows > 1);
+                let grid = Self::Grid {
+                    cols: cols as u64,
+                    rows: rows as u64,
+                    col_step: col_step.unwrap_or_default(),
+                    row_step: row_step.unwrap_or_default(),
+                };
+                if let Ok(mut lattice) = grid.offsets() {
+                    lattice.sort_by_key(|p| (p.y, p.x));
+                    if lattice == sorted {
+                        return Some(grid);
+                    }
+                }
+            }
+        }
+        Some(Self::Arbitrary(
+            offsets.iter().map(|&p| p - origin).collect(),
+        ))
+    }
+}
+
+/// A repetition as it appears in an [`OasisRecord`].
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub enum OasisRepetitionSpec {
+    /// Reuses the previous repetition.
+    Reuse,
+    /// An explicit repetition.
+    Explicit(OasisRepetition),
+}
+
+/// A reference to a named object, either by name or by reference number.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub enum OasisNameRef {
+    /// An explicit name.
+    Name(ArcStr),
+    /// A reference number into the corresponding name table.
+    Ref(u64),
+}
+
+/// The extension of one end of an OASIS path.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub enum OasisPathExtension {
+    /// The path ends flush with its endpoint.
+    #[default]
+    Flush,
+    /// The path extends beyond its endpoint by its half-width.
+    HalfWidth,
+    /// The path extends beyond its endpoint by the given distance.
+    Explicit(i64),
+}
+
+/// An interval of layer or datatype numbers.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub struct OasisInterval {
+    /// The inclusive lower bound.
+    pub lo: u64,
+    /// The inclusive upper bound, or [`None`] if the interval is unbounded.
+    pub hi: Option<u64>,
+}
+
+/// An OASIS property value.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[allow(missing_docs)]
+pub enum OasisPropValue {
+    Real(f64),
+    Unsigned(u64),
+    Signed(i64),
+    AString(ArcStr),
+    BString(Vec<u8>),
+    NString(ArcStr),
+    AStringRef(u64),
+    BStringRef(u64),
+    NStringRef(u64),
+}
+
+/// Byte offsets of the name tables in an OASIS file.
+///
+/// Each table is described by a `(strict, offset)` pair, where an offset of zero
+/// indicates that the table is not present.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub struct OasisTableOffsets {
+    pub cellname: (bool, u64),
+    pub textstring: (bool, u64),
+    pub propname: (bool, u64),
+    pub propstring: (bool, u64),
+    pub layername: (bool, u64),
+    pub xname: (bool, u64),
+}
+
+/// The validation scheme of an OASIS file.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum OasisValidation {
+    /// No validation.
+    None,
+    /// A CRC-32 checksum of the file contents.
+    #[default]
+    Crc32,
+    /// A simple sum of the bytes of the file contents.
+    Checksum32,
+}
+
+/// An OASIS record.
+///
+/// Fields wrapped in [`Option`]s are omitted from the record when [`None`],
+/// in which case their values are given by the corresponding modal variables.
+/// Point lists are given as vertex displacements relative to the record's position,
+/// excluding the position itself.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[allow(missing_docs)]
+pub enum OasisRecord {
+    Pad,
+    Start {
+        version: ArcStr,
+        unit: f64,
+        /// Table offsets, if stored in the `START` record rather than the `END` record.
+        offsets: Option<OasisTableOffsets>,
+    },
+    End {
+        /// Table offsets, if stored in the `END` record rather than the `START` record.
+        offsets: Option<OasisTableOffsets>,
+        validation: OasisValidation,
+    },
+    CellName {
+        name: ArcStr,
+        refnum: Option<u64>,
+    },
+    TextString {
+        string: ArcStr,
+        refnum: Option<u64>,
+    },
+    PropName {
+        name: ArcStr,
+        refnum: Option<u64>,
+    },
+    PropString {
+        string: Vec<u8>,
+        refnum: Option<u64>,
+    },
+    LayerName {
+        name: ArcStr,
+        layers: OasisInterval,
+        datatypes: OasisInterval,
+        /// Whether the name applies to text layers rather than geometry layers.
+        text: bool,
+    },
+    Cell(OasisNameRef),
+    XyAbsolute,
+    XyRelative,
+    Placement {
+        cell: Option<OasisNameRef>,
+        x: Option<i64>,
+        y: Option<i64>,
+        /// The magnification, if not 1.
+        mag: Option<f64>,
+        /// The counterclockwise rotation in degrees.
+        angle: f64,
+        /// Whether the cell is reflected about the x-axis before rotation.
+        flip: bool,
+        repetition: Option<OasisRepetitionSpec>,
+    },
+    Text {
+        string: Option<OasisNameRef>,
+        layer: Option<u64>,
+        texttype: Option<u64>,
+        x: Option<i64>,
+        y: Option<i64>,
+        repetition: Option<OasisRepetitionSpec>,
+    },
+    Rectangle {
+        layer: Option<u64>,
+        datatype: Option<u64>,
+        width: Option<u64>,
+        height: Option<u64>,
+        /// Whether the height is equal to the width.
+        square: bool,
+        x: Option<i64>,
+        y: Option<i64>,
+        repetition: Option<OasisRepetitionSpec>,
+    },
+    Polygon {
+        layer: Option<u64>,
+        datatype: Option<u64>,
+        points: Option<Vec<OasisPoint>>,
+        x: Option<i64>,
+        y: Option<i64>,
+        repetition: Option<OasisRepetitionSpec>,
+    },
+    Path {
+        layer: Option<u64>,
+        datatype: Option<u64>,
+        half_width: Option<u64>,
+        /// The start and end extensions. [`None`] for either end reuses the previous extension.
+        extensions: Option<(Option<OasisPathExtension>, Option<OasisPathExtension>)>,
+        points: Option<Vec<OasisPoint>>,
+        x: Option<i64>,
+        y: Option<i64>,
+        repetition: Option<OasisRepetitionSpec>,
+    },
+    Trapezoid {
+        layer: Option<u64>,
+        datatype: Option<u64>,
+        /// Whether the parallel sides of the trapezoid are vertical.
+        vertical: bool,
+        width: Option<u64>,
+        height: Option<u64>,
+        delta_a: i64,
+        delta_b: i64,
+        x: Option<i64>,
+        y: Option<i64>,
+        repetition: Option<OasisRepetitionSpec>,
+    },
+    CTrapezoid {
+        layer: Option<u64>,
+        datatype: Option<u64>,
+        ctype: Option<u64>,
+        width: Option<u64>,
+        height: Option<u64>,
+        x: Option<i64>,
+        y: Option<i64>,
+        repetition: Option<OasisRepetitionSpec>,
+    },
+    Circle {
+        layer: Option<u64>,
+        datatype: Option<u64>,
+        radius: Option<u64>,
+        x: Option<i64>,
+        y: Option<i64>,
+        repetition: Option<OasisRepetitionSpec>,
+    },
+    Property {
+        name: Option<OasisNameRef>,
+        /// The property values, or [`None`] to reuse the previous values.
+        values: Option<Vec<OasisPropValue>>,
+        standard: bool,
+    },
+    PropertyRepeat,
+    XName {
+        attribute: u64,
+        string: Vec<u8>,
+        refnum: Option<u64>,
+    },
+    XElement {
+        attribute: u64,
+        data: Vec<u8>,
+    },
+    XGeometry {
+        attribute: u64,
+        layer: Option<u64>,
+        datatype: Option<u64>,
+        data: Vec<u8>,
+        x: Option<i64>,
+        y: Option<i64>,
+        repetition: Option<OasisRepetitionSpec>,
+    },
+}
+
+/// An OASIS library.
+///
+/// The root of the OASIS layout tree. On disk, each library corresponds to a single `.oas` file.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+pub struct OasisLibrary {
+    /// The number of database units per micron.
+    pub unit: f64,
+    /// The cells of the library.
+    pub cells: Vec<OasisCell>,
+}
+
+impl OasisLibrary {
+    /// Creates a new and empty [`OasisLibrary`] with `unit` database units per micron.
+    pub fn new(unit: f64) -> Self {
+        Self {
+            unit,
+            cells: Vec::new(),
+        }
+    }
+
+    /// Reads an [`OasisLibrary`] from the file at path `fname`.
+    pub fn load(fname: impl AsRef<Path>) -> OasisResult<Self> {
+        let bytes = std::fs::read(fname)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Reads an [`OasisLibrary`] from `bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> OasisResult<Self> {
+        read::OasisParser::new(OasisReader::new(bytes)?).parse_lib()
+    }
+
+    /// Saves to the file at path `fname`.
+    pub fn save(&self, fname: impl AsRef<Path>) -> OasisResult<()> {
+        if let Some(prefix) = fname.as_ref().parent() {
+            std::fs::create_dir_all(prefix)?;
+        }
+        let mut file = BufWriter::new(File::create(fname)?);
+        self.write(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Writes to `dest`.
+    ///
+    /// Name tables are written for cell names and text strings,
+    /// and the contents of each cell are compressed in a `CBLOCK`.
+    pub fn write(&self, dest: impl Write) -> OasisResult<()> {
+        write::OasisEncoder::new(OasisWriter::new(dest)?).encode_lib(self)
+    }
+}
+
+/// An OASIS cell.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+pub struct OasisCell {
+    /// The cell name.
+    pub name: ArcStr,
+    /// The elements of the cell.
+    pub elements: Vec<OasisElement>,
+}
+
+impl OasisCell {
+    /// Creates a new and empty [`OasisCell`].
+    pub fn new(name: impl Into<ArcStr>) -> Self {
+        Self {
+            name: name.into(),
+            elements: Vec::new(),
+        }
+    }
+}
+
+/// An element of an [`OasisCell`].
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[allow(missing_docs)]
+pub enum OasisElement {
+    Placement(OasisPlacement),
+    Text(OasisText),
+    Rectangle(OasisRectangle),
+    Polygon(OasisPolygon),
+    Path(OasisPath),
+}
+
+/// A placement of a cell.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct OasisPlacement {
+    /// The name of the placed cell.
+    pub cell: ArcStr,
+    /// The x-coordinate of the placement.
+    pub x: i64,
+    /// The y-coordinate of the placement.
+    pub y: i64,
+    /// Whether the cell is reflected about the x-axis before rotation.
+    pub flip: bool,
+    /// The counterclockwise rotation in degrees.
+    pub angle: f64,
+    /// The magnification.
+    pub mag: f64,
+    /// The repetition of the placement.
+    pub repetition: Option<OasisRepetition>,
+}
+
+/// A text label.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub struct OasisText {
+    pub string: ArcStr,
+    pub layer: u64,
+    pub texttype: u64,
+    pub x: i64,
+    pub y: i64,
+    pub repetition: Option<OasisRepetition>,
+}
+
+/// An axis-aligned rectangle.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct OasisRectangle {
+    /// The layer number.
+    pub layer: u64,
+    /// The datatype number.
+    pub datatype: u64,
+    /// The x-coordinate of the lower-left corner.
+    pub x: i64,
+    /// The y-coordinate of the lower-left corner.
+    pub y: i64,
+    /// The width.
+    pub width: u64,
+    /// The height.
+    pub height: u64,
+    /// The repetition of the rectangle.
+    pub repetition: Option<OasisRepetition>,
+}
+
+/// A polygon.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct OasisPolygon {
+    /// The layer number.
+    pub layer: u64,
+    /// The datatype number.
+    pub datatype: u64,
+    /// The vertices of the polygon, without a repeated closing vertex.
+    pub points: Vec<OasisPoint>,
+    /// The repetition of the polygon.
+    pub repetition: Option<OasisRepetition>,
+}
+
+/// A path.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct OasisPath {
+    /// The layer number.
+    pub layer: u64,
+    /// The datatype number.
+    pub datatype: u64,
+    /// Half of the width of the path.
+    pub half_width: u64,
+    /// The extension of the start of the path.
+    pub start_extension: OasisPathExtension,
+    /// The extension of the end of the path.
+    pub end_extension: OasisPathExtension,
+    /// The vertices of the path.
+    pub points: Vec<OasisPoint>,
+    /// The repetition of the path.
+    pub repetition: Option<OasisRepetition>,
+}
+
+/// The [`OasisError`] result type.
+pub type OasisResult<T> = Result<T, OasisError>;
+
+/// An enumeration of OASIS errors.
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum OasisError {
+    /// An I/O error.
+    #[error("I/O error: {0}")]
+    Io(Arc<std::io::Error>),
+    /// An error decoding OASIS data.
+    #[error("error decoding OASIS data at byte {pos}: {msg}")]
+    Decode {
+        /// A description of the error.
+        msg: String,
+        /// The byte offset at which the error occurred.
+        pos: usize,
+    },
+    /// A mismatch between a file's validation signature and its contents.
+    #[error("validation signature mismatch: expected {expected:#010x}, found {found:#010x}")]
+    Validation {
+        /// The signature stored in the file.
+        expected: u32,
+        /// The signature computed from the file contents.
+        found: u32,
+    },
+    /// An unsupported feature.
+    #[error("unsupported OASIS feature: {0}")]
+    Unsupported(ArcStr),
+    /// An error converting an integer to a narrower type.
+    #[error("error converting an integer to the necessary type: {0}")]
+    TryFromInt(#[from] std::num::TryFromIntError),
+    /// An invalid argument.
+    #[error("invalid argument: {0}")]
+    InvalidArgument(ArcStr),
+}
+
+impl From<std::io::Error> for OasisError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(Arc::new(e))
+    }
+}
@@ -0,0 +1,1200 @@
+//! Utilities for decoding and reading.
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use arcstr::ArcStr;
+use flate2::read::DeflateDecoder;
+
+use super::*;
+
+/// The maximum number of bytes preallocated for a decompressed `CBLOCK`.
+const MAX_CBLOCK_PREALLOC: usize = 1 << 20;
+
+/// A record-level OASIS reader.
+///
+/// Reads [`OasisRecord`]s from an in-memory OASIS file.
+/// The contents of `CBLOCK` records are decompressed transparently.
+pub struct OasisReader<'a> {
+    /// The file contents.
+    data: &'a [u8],
+    /// The current position in `data`.
+    pos: usize,
+    /// The decompressed contents of the current `CBLOCK`, and the position within them.
+    cblock: Option<(Vec<u8>, usize)>,
+    /// Whether the table offsets are stored in the `END` record.
+    offsets_in_end: bool,
+    /// Whether the `END` record has been read.
+    done: bool,
+}
+
+impl<'a> OasisReader<'a> {
+    /// Creates a new [`OasisReader`] for the OASIS file contents `data`.
+    ///
+    /// Returns an error if `data` does not begin with the OASIS magic bytes.
+    pub fn new(data: &'a [u8]) -> OasisResult<Self> {
+        if !data.starts_with(MAGIC) {
+            return Err(OasisError::Decode {
+                msg: "missing OASIS magic bytes".into(),
+                pos: 0,
+            });
+        }
+        Ok(Self {
+            data,
+            pos: MAGIC.len(),
+            cblock: None,
+            offsets_in_end: false,
+            done: false,
+        })
+    }
+
+    /// Reads the next record, or returns [`None`] after the `END` record has been read.
+    pub fn read_record(&mut self) -> OasisResult<Option<OasisRecord>> {
+        loop {
+            if self.done {
+                return Ok(None);
+            }
+            let id = self.read_uint()?;
+            if id == 34 {
+                self.read_cblock()?;
+                continue;
+            }
+            return self.read_record_content(id).map(Some);
+        }
+    }
+
+    fn err<T>(&self, msg: impl Into<String>) -> OasisResult<T> {
+        Err(OasisError::Decode {
+            msg: msg.into(),
+            pos: self.pos,
+        })
+    }
+
+    fn read_u8(&mut self) -> OasisResult<u8> {
+        if let Some((data, pos)) = &mut self.cblock {
+            let b = data[*pos];
+            *pos += 1;
+            if *pos == data.len() {
+                self.cblock = None;
+            }
+            return Ok(b);
+        }
+        match self.data.get(self.pos) {
+            Some(&b) => {
+                self.pos += 1;
+                Ok(b)
+            }
+            None => self.err("unexpected end of file"),
+        }
+    }
+
+    /// Returns the number of bytes left to read, including the rest of the current `CBLOCK`.
+    fn remaining(&self) -> usize {
+        let cblock = self
+            .cblock
+            .as_ref()
+            .map_or(0, |(data, pos)| data.len() - pos);
+        cblock + self.data.len() - self.pos
+    }
+
+    fn read_bytes(&mut self, n: usize) -> OasisResult<Vec<u8>> {
+        (0..n).map(|_| self.read_u8()).collect()
+    }
+
+    fn read_uint(&mut self) -> OasisResult<u64> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let b = self.read_u8()?;
+            if shift >= 64 || (shift > 0 && u64::from(b & 0x7f) >> (64 - shift) != 0) {
+                return self.err("unsigned integer overflow");
+            }
+            value |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn read_sint(&mut self) -> OasisResult<i64> {
+        let value = self.read_uint()?;
+        let magnitude = (value >> 1) as i64;
+        Ok(if value & 1 == 1 {
+            -magnitude
+        } else {
+            magnitude
+        })
+    }
+
+    fn read_real_payload(&mut self, kind: u64) -> OasisResult<f64> {
+        Ok(match kind {
+            0 => self.read_uint()? as f64,
+            1 => -(self.read_uint()? as f64),
+            2 => 1. / self.read_uint()? as f64,
+            3 => -1. / self.read_uint()? as f64,
+            4 => self.read_uint()? as f64 / self.read_uint()? as f64,
+            5 => -(self.read_uint()? as f64) / self.read_uint()? as f64,
+            6 => f32::from_le_bytes(self.read_bytes(4)?.try_into().unwrap()) as f64,
+            7 => f64::from_le_bytes(self.read_bytes(8)?.try_into().unwrap()),
+            _ => return self.err(format!("invalid real type {kind}")),
+        })
+    }
+
+    fn read_real(&mut self) -> OasisResult<f64> {
+        let kind = self.read_uint()?;
+        self.read_real_payload(kind)
+    }
+
+    fn read_bstring(&mut self) -> OasisResult<Vec<u8>> {
+        let len = self.read_uint()? as usize;
+        self.read_bytes(len)
+    }
+
+    fn read_string(&mut self) -> OasisResult<ArcStr> {
+        let bytes = self.read_bstring()?;
+        match String::from_utf8(bytes) {
+            Ok(s) => Ok(s.into()),
+            Err(_) => self.err("invalid string"),
+        }
+    }
+
+    fn read_interval(&mut self) -> OasisResult<OasisInterval> {
+        Ok(match self.read_uint()? {
+            0 => OasisInterval { lo: 0, hi: None },
+            1 => OasisInterval {
+                lo: 0,
+                hi: Some(self.read_uint()?),
+            },
+            2 => {
+                let bound = self.read_uint()?;
+                OasisInterval {
+                    lo: bound,
+                    hi: Some(bound),
+                }
+            }
+            3 => OasisInterval {
+                lo: self.read_uint()?,
+                hi: None,
+            },
+            4 => OasisInterval {
+                lo: self.read_uint()?,
+                hi: Some(self.read_uint()?),
+            },
+            kind => return self.err(format!("invalid interval type {kind}")),
+        })
+    }
+
+    /// Reads a 3-delta or the first form of a g-delta, given its direction and magnitude.
+    fn octangular(&self, dir: u64, mag: i64) -> OasisResult<OasisPoint> {
+        Ok(match dir {
+            0 => OasisPoint::new(mag, 0),
+            1 => OasisPoint::new(0, mag),
+            2 => OasisPoint::new(-mag, 0),
+            3 => OasisPoint::new(0, -mag),
+            4 => OasisPoint::new(mag, mag),
+            5 => OasisPoint::new(-mag, mag),
+            6 => OasisPoint::new(-mag, -mag),
+            7 => OasisPoint::new(mag, -mag),
+            _ => return self.err(format!("invalid direction {dir}")),
+        })
+    }
+
+    fn read_gdelta(&mut self) -> OasisResult<OasisPoint> {
+        let value = self.read_uint()?;
+        if value & 1 == 0 {
+            self.octangular((value >> 1) & 0x7, (value >> 4) as i64)
+        } else {
+            let mag = (value >> 2) as i64;
+            let x = if value & 2 != 0 { -mag } else { mag };
+            Ok(OasisPoint::new(x, self.read_sint()?))
+        }
+    }
+
+    /// Reads a point list, returning cumulative vertex displacements.
+    ///
+    /// For polygons, implicit vertices of Manhattan point lists are included.
+    fn read_point_list(&mut self, polygon: bool) -> OasisResult<Vec<OasisPoint>> {
+        let kind = self.read_uint()?;
+        let count = self.read_uint()? as usize;
+        // Each point takes at least one byte, so longer lists cannot be valid.
+        if count > self.remaining() {
+            return self.err(format!("point list of {count} points exceeds the input"));
+        }
+        let mut deltas = Vec::with_capacity(count);
+        match kind {
+            0 | 1 => {
+                for i in 0..count {
+                    let d = self.read_sint()?;
+                    let horiz = (i % 2 == 0) == (kind == 0);
+                    deltas.push(if horiz {
+                        OasisPoint::new(d, 0)
+                    } else {
+                        OasisPoint::new(0, d)
+                    });
+                }
+            }
+            2 => {
+                for _ in 0..count {
+                    let value = self.read_uint()?;
+                    deltas.push(self.octangular(value & 0x3, (value >> 2) as i64)?);
+                }
+            }
+            3 => {
+                for _ in 0..count {
+                    let value = self.read_uint()?;
+                    deltas.push(self.octangular(value & 0x7, (value >> 3) as i64)?);
+                }
+            }
+            4 => {
+                for _ in 0..count {
+                    deltas.push(self.read_gdelta()?);
+                }
+            }
+            5 => {
+                let mut prev = OasisPoint::default();
+                for _ in 0..count {
+                    prev = prev + self.read_gdelta()?;
+                    deltas.push(prev);
+                }
+            }
+            _ => return self.err(format!("invalid point list type {kind}")),
+        }
+
+        let mut points = Vec::with_capacity(count + 1);
+        let mut current = OasisPoint::default();
+        for d in deltas {
+            current = current + d;
+            points.push(current);
+        }
+        if polygon && (kind == 0 || kind == 1) {
+            // Manhattan polygon point lists omit the vertex before the closing edge.
+            let horiz = (count % 2 == 0) == (kind == 0);
+            points.push(if horiz {
+                OasisPoint::new(0, current.y)
+            } else {
+                OasisPoint::new(current.x, 0)
+            });
+        }
+        Ok(points)
+    }
+
+    /// Reads the number of elements in a repetition, which is stored minus two.
+    fn read_count(&mut self) -> OasisResult<u64> {
+        match self.read_uint()?.checked_add(2) {
+            Some(n) => Ok(n),
+            None => self.err("repetition count overflow"),
+        }
+    }
+
+    /// Reads the dimensions of a grid repetition, which must have a representable size.
+    fn read_grid_size(&mut self) -> OasisResult<(u64, u64)> {
+        let cols = self.read_count()?;
+        let rows = self.read_count()?;
+        if cols.checked_mul(rows).is_none() {
+            return self.err("repetition count overflow");
+        }
+        Ok((cols, rows))
+    }
+
+    fn read_repetition(&mut self) -> OasisResult<OasisRepetitionSpec> {
+        let kind = self.read_uint()?;
+        let axis = |x: bool, d: i64| {
+            if x {
+                OasisPoint::new(d, 0)
+            } else {
+                OasisPoint::new(0, d)
+            }
+        };
+        let repetition = match kind {
+            0 => return Ok(OasisRepetitionSpec::Reuse),
+            1 => {
+                let (cols, rows) = self.read_grid_size()?;
+                OasisRepetition::Grid {
+                    cols,
+                    rows,
+                    col_step: OasisPoint::new(self.read_uint()? as i64, 0),
+                    row_step: OasisPoint::new(0, self.read_uint()? as i64),
+                }
+            }
+            2 | 3 => {
+                let n = self.read_count()?;
+                let step = axis(kind == 2, self.read_uint()? as i64);
+                if kind == 2 {
+                    OasisRepetition::Grid {
+                        cols: n,
+                        rows: 1,
+                        col_step: step,
+                        row_step: OasisPoint::default(),
+                    }
+                } else {
+                    OasisRepetition::Grid {
+                        cols: 1,
+                        rows: n,
+                        col_step: OasisPoint::default(),
+                        row_step: step,
+                    }
+                }
+            }
+            4..=7 => {
+                let n = self.read_count()?;
+                let grid = if kind == 5 || kind == 7 {
+                    self.read_uint()? as i64
+                } else {
+                    1
+                };
+                let mut offsets = vec![OasisPoint::default()];
+                let mut current = 0i64;
+                for _ in 1..n {
+                    let delta = self.read_uint()? as i64;
+                    current = match delta.checked_mul(grid).and_then(|d| current.checked_add(d)) {
+                        Some(current) => current,
+                        None => return self.err("repetition displacement overflow"),
+                    };
+                    offsets.push(axis(kind <= 5, current));
+                }
+                OasisRepetition::Arbitrary(offsets)
+            }
+            8 => {
+                let (cols, rows) = self.read_grid_size()?;
+                OasisRepetition::Grid {
+                    cols,
+                    rows,
+                    col_step: self.read_gdelta()?,
+                    row_step: self.read_gdelta()?,
+                }
+            }
+            9 => OasisRepetition::Grid {
+                cols: self.read_count()?,
+                rows: 1,
+                col_step: self.read_gdelta()?,
+                row_step: OasisPoint::default(),
+            },
+            10 | 11 => {
+                let n = self.read_count()?;
+                let grid = if kind == 11 {
+                    self.read_uint()? as i64
+                } else {
+                    1
+                };
+                let mut offsets = vec![OasisPoint::default()];
+                let mut current = OasisPoint::default();
+                for _ in 1..n {
+                    let delta = self.read_gdelta()?;
+                    current = match delta
+                        .checked_scale(grid)
+                        .and_then(|d| current.checked_add(d))
+                    {
+                        Some(current) => current,
+                        None => return self.err("repetition displacement overflow"),
+                    };
+                    offsets.push(current);
+                }
+                OasisRepetition::Arbitrary(offsets)
+            }
+            _ => return self.err(format!("invalid repetition type {kind}")),
+        };
+        Ok(OasisRepetitionSpec::Explicit(repetition))
+    }
+
+    fn read_table_offsets(&mut self) -> OasisResult<OasisTableOffsets> {
+        let mut pair =
+            || -> OasisResult<(bool, u64)> { Ok((self.read_uint()? != 0, self.read_uint()?)) };
+        Ok(OasisTableOffsets {
+            cellname: pair()?,
+            textstring: pair()?,
+            propname: pair()?,
+            propstring: pair()?,
+            layername: pair()?,
+            xname: pair()?,
+        })
+    }
+
+    fn read_prop_value(&mut self) -> OasisResult<OasisPropValue> {
+        let kind = self.read_uint()?;
+        Ok(match kind {
+            0..=7 => OasisPropValue::Real(self.read_real_payload(kind)?),
+            8 => OasisPropValue::Unsigned(self.read_uint()?),
+            9 => OasisPropValue::Signed(self.read_sint()?),
+            10 => OasisPropValue::AString(self.read_string()?),
+            11 => OasisPropValue::BString(self.read_bstring()?),
+            12 => OasisPropValue::NString(self.read_string()?),
+            13 => OasisPropValue::AStringRef(self.read_uint()?),
+            14 => OasisPropValue::BStringRef(self.read_uint()?),
+            15 => OasisPropValue::NStringRef(self.read_uint()?),
+            _ => return self.err(format!("invalid property value type {kind}")),
+        })
+    }
+
+    fn read_cblock(&mut self) -> OasisResult<()> {
+        if self.cblock.is_some() {
+            return self.err("nested CBLOCK");
+        }
+        let comp_type = self.read_uint()?;
+        if comp_type != 0 {
+            return self.err(format!("invalid CBLOCK compression type {comp_type}"));
+        }
+        let uncomp_len = self.read_uint()? as usize;
+        let comp_len = self.read_uint()? as usize;
+        let comp = self.read_bytes(comp_len)?;
+        // The declared length is untrusted, so neither allocate nor decompress beyond it.
+        let mut data = Vec::with_capacity(uncomp_len.min(MAX_CBLOCK_PREALLOC));
+        DeflateDecoder::new(comp.as_slice())
+            .take(uncomp_len as u64 + 1)
+            .read_to_end(&mut data)?;
+        if data.len() != uncomp_len {
+            return self.err("CBLOCK uncompressed length mismatch");
+        }
+        if !data.is_empty() {
+            self.cblock = Some((data, 0));
+        }
+        Ok(())
+    }
+
+    fn read_end(&mut self, offsets_in_end: bool) -> OasisResult<OasisRecord> {
+        if self.cblock.is_some() {
+            return self.err("END record inside CBLOCK");
+        }
+        let offsets = if offsets_in_end {
+            Some(self.read_table_offsets()?)
+        } else {
+            None
+        };
+        self.read_bstring()?;
+        let validation = match self.read_uint()? {
+            0 => OasisValidation::None,
+            1 => OasisValidation::Crc32,
+            2 => OasisValidation::Checksum32,
+            kind => return self.err(format!("invalid validation scheme {kind}")),
+        };
+        let signed = &self.data[..self.pos];
+        if validation != OasisValidation::None {
+            let expected = u32::from_le_bytes(self.read_bytes(4)?.try_into().unwrap());
+            let found = signature(validation, signed);
+            if expected != found {
+                return Err(OasisError::Validation { expected, found });
+            }
+        }
+        self.done = true;
+        Ok(OasisRecord::End {
+            offsets,
+            validation,
+        })
+    }
+
+    fn read_record_content(&mut self, id: u64) -> OasisResult<OasisRecord> {
+        Ok(match id {
+            0 => OasisRecord::Pad,
+            1 => {
+                let version = self.read_string()?;
+                let unit = self.read_real()?;
+                self.offsets_in_end = self.read_uint()? != 0;
+                let offsets = if self.offsets_in_end {
+                    None
+                } else {
+                    Some(self.read_table_offsets()?)
+                };
+                OasisRecord::Start {
+                    version,
+                    unit,
+                    offsets,
+                }
+            }
+            2 => self.read_end(self.offsets_in_end)?,
+            3 | 4 => OasisRecord::CellName {
+                name: self.read_string()?,
+                refnum: if id == 4 {
+                    Some(self.read_uint()?)
+                } else {
+                    None
+                },
+            },
+            5 | 6 => OasisRecord::TextString {
+                string: self.read_string()?,
+                refnum: if id == 6 {
+                    Some(self.read_uint()?)
+                } else {
+                    None
+                },
+            },
+            7 | 8 => OasisRecord::PropName {
+                name: self.read_string()?,
+                refnum: if id == 8 {
+                    Some(self.read_uint()?)
+                } else {
+                    None
+                },
+            },
+            9 | 10 => OasisRecord::PropString {
+                string: self.read_bstring()?,
+                refnum: if id == 10 {
+                    Some(self.read_uint()?)
+                } else {
+                    None
+                },
+            },
+            11 | 12 => OasisRecord::LayerName {
+                name: self.read_string()?,
+                layers: self.read_interval()?,
+                datatypes: self.read_interval()?,
+                text: id == 12,
+            },
+            13 => OasisRecord::Cell(OasisNameRef::Ref(self.read_uint()?)),
+            14 => OasisRecord::Cell(OasisNameRef::Name(self.read_string()?)),
+            15 => OasisRecord::XyAbsolute,
+            16 => OasisRecord::XyRelative,
+            17 | 18 => {
+                let info = self.read_u8()?;
+                let cell = self.read_name_ref(info & 0x80 != 0, info & 0x40 != 0)?;
+                let (mag, angle) = if id == 17 {
+                    (None, f64::from((info >> 1) & 0x3) * 90.)
+                } else {
+                    let mag = self.read_if(info & 0x04 != 0, Self::read_real)?;
+                    let angle = self.read_if(info & 0x02 != 0, Self::read_real)?;
+                    (mag, angle.unwrap_or_default())
+                };
+                OasisRecord::Placement {
+                    cell,
+                    mag,
+                    angle,
+                    x: self.read_if(info & 0x20 != 0, Self::read_sint)?,
+                    y: self.read_if(info & 0x10 != 0, Self::read_sint)?,
+                    flip: info & 0x01 != 0,
+                    repetition: self.read_if(info & 0x08 != 0, Self::read_repetition)?,
+                }
+            }
+            19 => {
+                let info = self.read_u8()?;
+                OasisRecord::Text {
+                    string: self.read_name_ref(info & 0x40 != 0, info & 0x20 != 0)?,
+                    layer: self.read_if(info & 0x01 != 0, Self::read_uint)?,
+                    texttype: self.read_if(info & 0x02 != 0, Self::read_uint)?,
+                    x: self.read_if(info & 0x10 != 0, Self::read_sint)?,
+                    y: self.read_if(info & 0x08 != 0, Self::read_sint)?,
+                    repetition: self.read_if(info & 0x04 != 0, Self::read_repetition)?,
+                }
+            }
+            20 => {
+                let info = self.read_u8()?;
+                let square = info & 0x80 != 0;
+                OasisRecord::Rectangle {
+                    layer: self.read_if(info & 0x01 != 0, Self::read_uint)?,
+                    datatype: self.read_if(info & 0x02 != 0, Self::read_uint)?,
+                    width: self.read_if(info & 0x40 != 0, Self::read_uint)?,
+                    height: self.read_if(info & 0x20 != 0 && !square, Self::read_uint)?,
+                    square,
+                    x: self.read_if(info & 0x10 != 0, Self::read_sint)?,
+                    y: self.read_if(info & 0x08 != 0, Self::read_sint)?,
+                    repetition: self.read_if(info & 0x04 != 0, Self::read_repetition)?,
+                }
+            }
+            21 => {
+                let info = self.read_u8()?;
+                OasisRecord::Polygon {
+                    layer: self.read_if(info & 0x01 != 0, Self::read_uint)?,
+                    datatype: self.read_if(info & 0x02 != 0, Self::read_uint)?,
+                    points: self.read_if(info & 0x20 != 0, |r| r.read_point_list(true))?,
+                    x: self.read_if(info & 0x10 != 0, Self::read_sint)?,
+                    y: self.read_if(info & 0x08 != 0, Self::read_sint)?,
+                    repetition: self.read_if(info & 0x04 != 0, Self::read_repetition)?,
+                }
+            }
+            22 => {
+                let info = self.read_u8()?;
+                let layer = self.read_if(info & 0x01 != 0, Self::read_uint)?;
+                let datatype = self.read_if(info & 0x02 != 0, Self::read_uint)?;
+                let half_width = self.read_if(info & 0x40 != 0, Self::read_uint)?;
+                let extensions = self.read_if(info & 0x80 != 0, |r| {
+                    let scheme = r.read_uint()?;
+                    let mut ext = |bits: u64| -> OasisResult<Option<OasisPathExtension>> {
+                        Ok(match bits {
+                            0 => None,
+                            1 => Some(OasisPathExtension::Flush),
+                            2 => Some(OasisPathExtension::HalfWidth),
+                            _ => Some(OasisPathExtension::Explicit(r.read_sint()?)),
+                        })
+                    };
+                    let start = ext((scheme >> 2) & 0x3)?;
+                    let end = ext(scheme & 0x3)?;
+                    Ok((start, end))
+                })?;
+                OasisRecord::Path {
+                    layer,
+                    datatype,
+                    half_width,
+                    extensions,
+                    points: self.read_if(info & 0x20 != 0, |r| r.read_point_list(false))?,
+                    x: self.read_if(info & 0x10 != 0, Self::read_sint)?,
+                    y: self.read_if(info & 0x08 != 0, Self::read_sint)?,
+                    repetition: self.read_if(info & 0x04 != 0, Self::read_repetition)?,
+                }
+            }
+            23..=25 => {
+                let info = self.read_u8()?;
+                let layer = self.read_if(info & 0x01 != 0, Self::read_uint)?;
+                let datatype = self.read_if(info & 0x02 != 0, Self::read_uint)?;
+                let width = self.read_if(info & 0x40 != 0, Self::read_uint)?;
+                let height = self.read_if(info & 0x20 != 0, Self::read_uint)?;
+                let delta_a = if id != 25 { self.read_sint()? } else { 0 };
+                let delta_b = if id != 24 { self.read_sint()? } else { 0 };
+                OasisRecord::Trapezoid {
+                    layer,
+                    datatype,
+                    vertical: info & 0x80 != 0,
+                    width,
+                    height,
+                    delta_a,
+                    delta_b,
+                    x: self.read_if(info & 0x10 != 0, Self::read_sint)?,
+                    y: self.read_if(info & 0x08 != 0, Self::read_sint)?,
+                    repetition: self.read_if(info & 0x04 != 0, Self::read_repetition)?,
+                }
+            }
+            26 => {
+                let info = self.read_u8()?;
+                OasisRecord::CTrapezoid {
+                    layer: self.read_if(info & 0x01 != 0, Self::read_uint)?,
+                    datatype: self.read_if(info & 0x02 != 0, Self::read_uint)?,
+                    ctype: self.read_if(info & 0x80 != 0, Self::read_uint)?,
+                    width: self.read_if(info & 0x40 != 0, Self::read_uint)?,
+                    height: self.read_if(info & 0x20 != 0, Self::read_uint)?,
+                    x: self.read_if(info & 0x10 != 0, Self::read_sint)?,
+                    y: self.read_if(info & 0x08 != 0, Self::read_sint)?,
+                    repetition: self.read_if(info & 0x04 != 0, Self::read_repetition)?,
+                }
+            }
+            27 => {
+                let info = self.read_u8()?;
+                OasisRecord::Circle {
+                    layer: self.read_if(info & 0x01 != 0, Self::read_uint)?,
+                    datatype: self.read_if(info & 0x02 != 0, Self::read_uint)?,
+                    radius: self.read_if(info & 0x20 != 0, Self::read_uint)?,
+                    x: self.read_if(info & 0x10 != 0, Self::read_sint)?,
+                    y: self.read_if(info & 0x08 != 0, Self::read_sint)?,
+                    repetition: self.read_if(info & 0x04 != 0, Self::read_repetition)?,
+                }
+            }
+            28 => {
+                let info = self.read_u8()?;
+                let name = self.read_name_ref(info & 0x04 != 0, info & 0x02 != 0)?;
+                let values = if info & 0x08 != 0 {
+                    None
+                } else {
+                    let count = match info >> 4 {
+                        15 => self.read_uint()?,
+                        n => u64::from(n),
+                    };
+                    Some(
+                        (0..count)
+                            .map(|_| self.read_prop_value())
+                            .collect::<OasisResult<_>>()?,
+                    )
+                };
+                OasisRecord::Property {
+                    name,
+                    values,
+                    standard: info & 0x01 != 0,
+                }
+            }
+            29 => OasisRecord::PropertyRepeat,
+            30 | 31 => OasisRecord::XName {
+                attribute: self.read_uint()?,
+                string: self.read_bstring()?,
+                refnum: if id == 31 {
+                    Some(self.read_uint()?)
+                } else {
+                    None
+                },
+            },
+            32 => OasisRecord::XElement {
+                attribute: self.read_uint()?,
+                data: self.read_bstring()?,
+            },
+            33 => {
+                let info = self.read_u8()?;
+                OasisRecord::XGeometry {
+                    attribute: self.read_uint()?,
+                    layer: self.read_if(info & 0x01 != 0, Self::read_uint)?,
+                    datatype: self.read_if(info & 0x02 != 0, Self::read_uint)?,
+                    data: self.read_bstring()?,
+                    x: self.read_if(info & 0x10 != 0, Self::read_sint)?,
+                    y: self.read_if(info & 0x08 != 0, Self::read_sint)?,
+                    repetition: self.read_if(info & 0x04 != 0, Self::read_repetition)?,
+                }
+            }
+            _ => return self.err(format!("invalid record type {id}")),
+        })
+    }
+
+    fn read_if<T>(
+        &mut self,
+        present: bool,
+        f: impl FnOnce(&mut Self) -> OasisResult<T>,
+    ) -> OasisResult<Option<T>> {
+        if present {
+            f(self).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn read_name_ref(&mut self, present: bool, by_ref: bool) -> OasisResult<Option<OasisNameRef>> {
+        self.read_if(present, |r| {
+            Ok(if by_ref {
+                OasisNameRef::Ref(r.read_uint()?)
+            } else {
+                OasisNameRef::Name(r.read_string()?)
+            })
+        })
+    }
+}
+
+/// Computes the validation signature of `data`.
+fn signature(validation: OasisValidation, data: &[u8]) -> u32 {
+    match validation {
+        OasisValidation::None => 0,
+        OasisValidation::Crc32 => {
+            let mut crc = flate2::Crc::new();
+            crc.update(data);
+            crc.sum()
+        }
+        OasisValidation::Checksum32 => data
+            .iter()
+            .fold(0u32, |sum, &b| sum.wrapping_add(u32::from(b))),
+    }
+}
+
+/// The modal variables of an OASIS file.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Modal {
+    pub(crate) relative: bool,
+    pub(crate) repetition: Option<OasisRepetition>,
+    pub(crate) placement_x: i64,
+    pub(crate) placement_y: i64,
+    pub(crate) placement_cell: Option<OasisNameRef>,
+    pub(crate) layer: Option<u64>,
+    pub(crate) datatype: Option<u64>,
+    pub(crate) textlayer: Option<u64>,
+    pub(crate) texttype: Option<u64>,
+    pub(crate) text_x: i64,
+    pub(crate) text_y: i64,
+    pub(crate) text_string: Option<OasisNameRef>,
+    pub(crate) geometry_x: i64,
+    pub(crate) geometry_y: i64,
+    pub(crate) geometry_w: Option<u64>,
+    pub(crate) geometry_h: Option<u64>,
+    pub(crate) polygon_points: Option<Vec<OasisPoint>>,
+    pub(crate) path_half_width: Option<u64>,
+    pub(crate) path_points: Option<Vec<OasisPoint>>,
+    pub(crate) path_start_extension: Option<OasisPathExtension>,
+    pub(crate) path_end_extension: Option<OasisPathExtension>,
+}
+
+impl Modal {
+    /// Updates a modal coordinate with an optional record value.
+    pub(crate) fn coord(relative: bool, modal: &mut i64, value: Option<i64>) -> i64 {
+        if let Some(value) = value {
+            *modal = if relative { *modal + value } else { value };
+        }
+        *modal
+    }
+
+    /// Updates a modal variable with an optional record value.
+    fn update<T: Clone>(modal: &mut Option<T>, value: Option<T>, name: &str) -> OasisResult<T> {
+        if let Some(value) = value {
+            *modal = Some(value);
+        }
+        modal.clone().ok_or_else(|| OasisError::Decode {
+            msg: format!("undefined modal variable {name}"),
+            pos: 0,
+        })
+    }
+
+    fn repetition(
+        &mut self,
+        value: Option<OasisRepetitionSpec>,
+    ) -> OasisResult<Option<OasisRepetition>> {
+        Ok(match value {
+            None => None,
+            Some(OasisRepetitionSpec::Reuse) => {
+                Some(Self::update(&mut self.repetition, None, "repetition")?)
+            }
+            Some(OasisRepetitionSpec::Explicit(rep)) => {
+                self.repetition = Some(rep.clone());
+                Some(rep)
+            }
+        })
+    }
+}
+
+/// A library-level OASIS parser.
+///
+/// Resolves modal variables and name references while reading records.
+pub(crate) struct OasisParser<'a> {
+    reader: OasisReader<'a>,
+    modal: Modal,
+    cellnames: HashMap<u64, ArcStr>,
+    textstrings: HashMap<u64, ArcStr>,
+    implicit_cellnames: u64,
+    implicit_textstrings: u64,
+}
+
+/// A cell whose name references have not yet been resolved.
+struct UnresolvedCell {
+    name: OasisNameRef,
+    elements: Vec<UnresolvedElement>,
+}
+
+enum UnresolvedElement {
+    Placement(OasisNameRef, OasisPlacement),
+    Text(OasisNameRef, OasisText),
+    Resolved(OasisElement),
+}
+
+impl<'a> OasisParser<'a> {
+    pub(crate) fn new(reader: OasisReader<'a>) -> Self {
+        Self {
+            reader,
+            modal: Modal::default(),
+            cellnames: HashMap::new(),
+            textstrings: HashMap::new(),
+            implicit_cellnames: 0,
+            implicit_textstrings: 0,
+        }
+    }
+
+    pub(crate) fn parse_lib(mut self) -> OasisResult<OasisLibrary> {
+        let unit = match self.reader.read_record()? {
+            Some(OasisRecord::Start { version, unit, .. }) => {
+                if version != VERSION {
+                    return Err(OasisError::Unsupported(arcstr::format!(
+                        "OASIS version {version}"
+                    )));
+                }
+                unit
+            }
+            _ => return self.reader.err("expected START record"),
+        };
+
+        let mut cells: Vec<UnresolvedCell> = Vec::new();
+        loop {
+            let record = match self.reader.read_record()? {
+                Some(record) => record,
+                None => return self.reader.err("missing END record"),
+            };
+            match record {
+                OasisRecord::End { .. } => break,
+                OasisRecord::CellName { name, refnum } => {
+                    let refnum = refnum.unwrap_or_else(|| {
+                        self.implicit_cellnames += 1;
+                        self.implicit_cellnames - 1
+                    });
+                    self.cellnames.insert(refnum, name);
+                }
+                OasisRecord::TextString { string, refnum } => {
+                    let refnum = refnum.unwrap_or_else(|| {
+                        self.implicit_textstrings += 1;
+                        self.implicit_textstrings - 1
+                    });
+                    self.textstrings.insert(refnum, string);
+                }
+                OasisRecord::Cell(name) => {
+                    self.modal = Modal::default();
+                    cells.push(UnresolvedCell {
+                        name,
+                        elements: Vec::new(),
+                    });
+                }
+                OasisRecord::XyAbsolute => self.modal.relative = false,
+                OasisRecord::XyRelative => self.modal.relative = true,
+                OasisRecord::Start { .. } => return self.reader.err("unexpected START record"),
+                record => {
+                    if let Some(elem) = self.parse_element(record)? {
+                        let Some(cell) = cells.last_mut() else {
+                            return self.reader.err("element outside of a cell");
+                        };
+                        cell.elements.push(elem);
+                    }
+                }
+            }
+        }
+
+        let cells = cells
+            .into_iter()
+            .map(|cell| self.resolve_cell(cell))
+            .collect::<OasisResult<_>>()?;
+        Ok(OasisLibrary { unit, cells })
+    }
+
+    fn parse_element(&mut self, record: OasisRecord) -> OasisResult<Option<UnresolvedElement>> {
+        let m = &mut self.modal;
+        let rel = m.relative;
+        Ok(Some(match record {
+            OasisRecord::Placement {
+                cell,
+                x,
+                y,
+                mag,
+                angle,
+                flip,
+                repetition,
+            } => {
+                let cell = Modal::update(&mut m.placement_cell, cell, "placement-cell")?;
+                let x = Modal::coord(rel, &mut m.placement_x, x);
+                let y = Modal::coord(rel, &mut m.placement_y, y);
+                let repetition = m.repetition(repetition)?;
+                UnresolvedElement::Placement(
+                    cell,
+                    OasisPlacement {
+                        cell: ArcStr::default(),
+                        x,
+                        y,
+                        flip,
+                        angle,
+                        mag: mag.unwrap_or(1.),
+                        repetition,
+                    },
+                )
+            }
+            OasisRecord::Text {
+                string,
+                layer,
+                texttype,
+                x,
+                y,
+                repetition,
+            } => {
+                let string = Modal::update(&mut m.text_string, string, "text-string")?;
+                let layer = Modal::update(&mut m.textlayer, layer, "textlayer")?;
+                let texttype = Modal::update(&mut m.texttype, texttype, "texttype")?;
+                let x = Modal::coord(rel, &mut m.text_x, x);
+                let y = Modal::coord(rel, &mut m.text_y, y);
+                let repetition = m.repetition(repetition)?;
+                UnresolvedElement::Text(
+                    string,
+                    OasisText {
+                        string: ArcStr::default(),
+                        layer,
+                        texttype,
+                        x,
+                        y,
+                        repetition,
+                    },
+                )
+            }
+            OasisRecord::Rectangle {
+                layer,
+                datatype,
+                width,
+                height,
+                square,
+                x,
+                y,
+                repetition,
+            } => {
+                let layer = Modal::update(&mut m.layer, layer, "layer")?;
+                let datatype = Modal::update(&mut m.datatype, datatype, "datatype")?;
+                let width = Modal::update(&mut m.geometry_w, width, "geometry-w")?;
+                let height = if square {
+                    m.geometry_h = Some(width);
+                    width
+                } else {
+                    Modal::update(&mut m.geometry_h, height, "geometry-h")?
+                };
+                let x = Modal::coord(rel, &mut m.geometry_x, x);
+                let y = Modal::coord(rel, &mut m.geometry_y, y);
+                let repetition = m.repetition(repetition)?;
+                UnresolvedElement::Resolved(OasisElement::Rectangle(OasisRectangle {
+                    layer,
+                    datatype,
+                    x,
+                    y,
+                    width,
+                    height,
+                    repetition,
+                }))
+            }
+            OasisRecord::Polygon {
+                layer,
+                datatype,
+                points,
+                x,
+                y,
+                repetition,
+            } => {
+                let layer = Modal::update(&mut m.layer, layer, "layer")?;
+                let datatype = Modal::update(&mut m.datatype, datatype, "datatype")?;
+                let points = Modal::update(&mut m.polygon_points, points, "polygon-point-list")?;
+                let x = Modal::coord(rel, &mut m.geometry_x, x);
+                let y = Modal::coord(rel, &mut m.geometry_y, y);
+                let repetition = m.repetition(repetition)?;
+                let origin = OasisPoint::new(x, y);
+                UnresolvedElement::Resolved(OasisElement::Polygon(OasisPolygon {
+                    layer,
+                    datatype,
+                    points: std::iter::once(origin)
+                        .chain(points.into_iter().map(|p| origin + p))
+                        .collect(),
+                    repetition,
+                }))
+            }
+            OasisRecord::Path {
+                layer,
+                datatype,
+                half_width,
+                extensions,
+                points,
+                x,
+                y,
+                repetition,
+            } => {
+                let layer = Modal::update(&mut m.layer, layer, "layer")?;
+                let datatype = Modal::update(&mut m.datatype, datatype, "datatype")?;
+                let half_width =
+                    Modal::update(&mut m.path_half_width, half_width, "path-halfwidth")?;
+                let (start, end) = extensions.unwrap_or_default();
+                let start_extension =
+                    Modal::update(&mut m.path_start_extension, start, "path-start-extension")?;
+                let end_extension =
+                    Modal::update(&mut m.path_end_extension, end, "path-end-extension")?;
+                let points = Modal::update(&mut m.path_points, points, "path-point-list")?;
+                let x = Modal::coord(rel, &mut m.geometry_x, x);
+                let y = Modal::coord(rel, &mut m.geometry_y, y);
+                let repetition = m.repetition(repetition)?;
+                let origin = OasisPoint::new(x, y);
+                UnresolvedElement::Resolved(OasisElement::Path(OasisPath {
+                    layer,
+                    datatype,
+                    half_width,
+                    start_extension,
+                    end_extension,
+                    points: std::iter::once(origin)
+                        .chain(points.into_iter().map(|p| origin + p))
+                        .collect(),
+                    repetition,
+                }))
+            }
+            OasisRecord::Trapezoid {
+                layer,
+                datatype,
+                vertical,
+                width,
+                height,
+                delta_a,
+                delta_b,
+                x,
+                y,
+                repetition,
+            } => {
+                let layer = Modal::update(&mut m.layer, layer, "layer")?;
+                let datatype = Modal::update(&mut m.datatype, datatype, "datatype")?;
+                let w = Modal::update(&mut m.geometry_w, width, "geometry-w")? as i64;
+                let h = Modal::update(&mut m.geometry_h, height, "geometry-h")? as i64;
+                let x = Modal::coord(rel, &mut m.geometry_x, x);
+                let y = Modal::coord(rel, &mut m.geometry_y, y);
+                let repetition = m.repetition(repetition)?;
+                let (a, b) = (delta_a, delta_b);
+                let points = if vertical {
+                    [
+                        OasisPoint::new(0, a.max(0)),
+                        OasisPoint::new(0, h + b.min(0)),
+                        OasisPoint::new(w, h - b.max(0)),
+                        OasisPoint::new(w, -a.min(0)),
+                    ]
+                } else {
+                    [
+                        OasisPoint::new(a.max(0), h),
+                        OasisPoint::new(w + b.min(0), h),
+                        OasisPoint::new(w - b.max(0), 0),
+                        OasisPoint::new(-a.min(0), 0),
+                    ]
+                };
+                let origin = OasisPoint::new(x, y);
+                UnresolvedElement::Resolved(OasisElement::Polygon(OasisPolygon {
+                    layer,
+                    datatype,
+                    points: points.into_iter().map(|p| origin + p).collect(),
+                    repetition,
+                }))
+            }
+            OasisRecord::CTrapezoid { .. } => {
+                return Err(OasisError::Unsupported(arcstr::literal!(
+                    "CTRAPEZOID records"
+                )))
+            }
+            OasisRecord::Circle { .. } => {
+                return Err(OasisError::Unsupported(arcstr::literal!("CIRCLE records")))
+            }
+            OasisRecord::XGeometry {
+                layer,
+                datatype,
+                x,
+                y,
+                repetition,
+                ..
+            } => {
+                // Extension geometry is skipped, but still updates modal variables.
+                Modal::update(&mut m.layer, layer, "layer").ok();
+                Modal::update(&mut m.datatype, datatype, "datatype").ok();
+                Modal::coord(rel, &mut m.geometry_x, x);
+                Modal::coord(rel, &mut m.geometry_y, y);
+                m.repetition(repetition)?;
+                return Ok(None);
+            }
+            // Properties and other name tables are not retained.
+            OasisRecord::Pad
+            | OasisRecord::PropName { .. }
+            | OasisRecord::PropString { .. }
+            | OasisRecord::LayerName { .. }
+            | OasisRecord::Property { .. }
+            | OasisRecord::PropertyRepeat
+            | OasisRecord::XName { .. }
+            | OasisRecord::XElement { .. } => return Ok(None),
+            record => return self.reader.err(format!("unexpected record {record:?}")),
+        }))
+    }
+
+    fn resolve(
+        names: &HashMap<u64, ArcStr>,
+        name: OasisNameRef,
+        kind: &str,
+    ) -> OasisResult<ArcStr> {
+        match name {
+            OasisNameRef::Name(name) => Ok(name),
+            OasisNameRef::Ref(refnum) => {
+                names
+                    .get(&refnum)
+                    .cloned()
+                    .ok_or_else(|| OasisError::Decode {
+                        msg: format!("undefined {kind} reference number {refnum}"),
+                        pos: 0,
+                    })
+            }
+        }
+    }
+
+    fn resolve_cell(&self, cell: UnresolvedCell) -> OasisResult<OasisCell> {
+        Ok(OasisCell {
+            name: Self::resolve(&self.cellnames, cell.name, "cell name")?,
+            elements: cell
+                .elements
+                .into_iter()
+                .map(|elem| {
+                    Ok(match elem {
+                        UnresolvedElement::Placement(name, placement) => {
+                            OasisElement::Placement(OasisPlacement {
+                                cell: Self::resolve(&self.cellnames, name, "cell name")?,
+                                ..placement
+                            })
+                        }
+                        UnresolvedElement::Text(string, text) => OasisElement::Text(OasisText {
+                            string: Self::resolve(&self.textstrings, string, "text string")?,
+                            ..text
+                        }),
+                        UnresolvedElement::Resolved(elem) => elem,
+                    })
+                })
+                .collect::<OasisResult<_>>()?,
+        })
+    }
+}
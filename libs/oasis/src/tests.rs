@@ -0,0 +1,551 @@
+use super::read::OasisParser;
+use super::write::{write_gdelta, write_real, write_sint, write_uint};
+use super::*;
+
+/// Creates a library exercising each element type and repetition form.
+fn sample_lib() -> OasisLibrary {
+    let mut lib = OasisLibrary::new(1000.);
+    let mut leaf = OasisCell::new("leaf");
+    leaf.elements.push(OasisElement::Rectangle(OasisRectangle {
+        layer: 1,
+        datatype: 0,
+        x: 0,
+        y: 0,
+        width: 100,
+        height: 100,
+        repetition: None,
+    }));
+    leaf.elements.push(OasisElement::Rectangle(OasisRectangle {
+        layer: 1,
+        datatype: 0,
+        x: -50,
+        y: 200,
+        width: 100,
+        height: 40,
+        repetition: Some(OasisRepetition::Grid {
+            cols: 3,
+            rows: 2,
+            col_step: OasisPoint::new(200, 0),
+            row_step: OasisPoint::new(0, 300),
+        }),
+    }));
+    leaf.elements.push(OasisElement::Polygon(OasisPolygon {
+        layer: 2,
+        datatype: 5,
+        points: vec![
+            OasisPoint::new(0, 0),
+            OasisPoint::new(100, 0),
+            OasisPoint::new(100, 100),
+            OasisPoint::new(50, 150),
+            OasisPoint::new(-13, 7),
+        ],
+        repetition: Some(OasisRepetition::Arbitrary(vec![
+            OasisPoint::new(0, 0),
+            OasisPoint::new(1000, 17),
+            OasisPoint::new(-5, 2000),
+        ])),
+    }));
+    leaf.elements.push(OasisElement::Path(OasisPath {
+        layer: 3,
+        datatype: 0,
+        half_width: 10,
+        start_extension: OasisPathExtension::HalfWidth,
+        end_extension: OasisPathExtension::Explicit(-3),
+        points: vec![
+            OasisPoint::new(0, 0),
+            OasisPoint::new(0, 500),
+            OasisPoint::new(300, 800),
+        ],
+        repetition: None,
+    }));
+    leaf.elements.push(OasisElement::Text(OasisText {
+        string: "vdd".into(),
+        layer: 4,
+        texttype: 1,
+        x: 5,
+        y: -5,
+        repetition: None,
+    }));
+    lib.cells.push(leaf);
+
+    let mut top = OasisCell::new("top");
+    top.elements.push(OasisElement::Placement(OasisPlacement {
+        cell: "leaf".into(),
+        x: 0,
+        y: 0,
+        flip: false,
+        angle: 0.,
+        mag: 1.,
+        repetition: None,
+    }));
+    top.elements.push(OasisElement::Placement(OasisPlacement {
+        cell: "leaf".into(),
+        x: 10_000,
+        y: -10_000,
+        flip: true,
+        angle: 90.,
+        mag: 1.,
+        repetition: Some(OasisRepetition::Grid {
+            cols: 4,
+            rows: 1,
+            col_step: OasisPoint::new(-2000, 0),
+            row_step: OasisPoint::default(),
+        }),
+    }));
+    top.elements.push(OasisElement::Placement(OasisPlacement {
+        cell: "leaf".into(),
+        x: 10_000,
+        y: -10_000,
+        flip: false,
+        angle: 45.,
+        mag: 2.5,
+        repetition: None,
+    }));
+    top.elements.push(OasisElement::Text(OasisText {
+        string: "vdd".into(),
+        layer: 4,
+        texttype: 1,
+        x: 5,
+        y: -5,
+        repetition: Some(OasisRepetition::Grid {
+            cols: 2,
+            rows: 2,
+            col_step: OasisPoint::new(10, 10),
+            row_step: OasisPoint::new(-10, 10),
+        }),
+    }));
+    lib.cells.push(top);
+    lib
+}
+
+fn encode(lib: &OasisLibrary) -> OasisResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    lib.write(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Decodes `bytes`, prefixed by the magic bytes, as a sequence of records.
+fn read_records(bytes: &[u8]) -> OasisResult<Vec<OasisRecord>> {
+    let data = [MAGIC, bytes].concat();
+    let mut reader = OasisReader::new(&data)?;
+    let mut records = Vec::new();
+    while let Ok(Some(record)) = reader.read_record() {
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[test]
+fn integers() -> OasisResult<()> {
+    let mut buf = Vec::new();
+    write_uint(&mut buf, 0);
+    write_uint(&mut buf, 127);
+    write_uint(&mut buf, 128);
+    write_uint(&mut buf, 16_383);
+    assert_eq!(buf, [0x00, 0x7f, 0x80, 0x01, 0xff, 0x7f]);
+
+    let mut buf = Vec::new();
+    write_sint(&mut buf, 0);
+    write_sint(&mut buf, 1);
+    write_sint(&mut buf, -1);
+    write_sint(&mut buf, -64);
+    assert_eq!(buf, [0x00, 0x02, 0x03, 0x81, 0x01]);
+
+    let mut buf = Vec::new();
+    write_real(&mut buf, 1000.);
+    write_real(&mut buf, -2.);
+    write_real(&mut buf, 0.5);
+    assert_eq!(&buf[..5], [0x00, 0xe8, 0x07, 0x01, 0x02]);
+    assert_eq!(buf[5], 7);
+    assert_eq!(f64::from_le_bytes(buf[6..].try_into().unwrap()), 0.5);
+    Ok(())
+}
+
+#[test]
+fn gdeltas() -> OasisResult<()> {
+    let mut buf = Vec::new();
+    write_gdelta(&mut buf, OasisPoint::new(3, 0));
+    write_gdelta(&mut buf, OasisPoint::new(-2, -2));
+    write_gdelta(&mut buf, OasisPoint::new(3, -4));
+    assert_eq!(buf, [0x30, 0x2c, 0x0d, 0x09]);
+    Ok(())
+}
+
+#[test]
+fn it_round_trips_records() -> OasisResult<()> {
+    let records = vec![
+        OasisRecord::CellName {
+            name: "a".into(),
+            refnum: Some(3),
+        },
+        OasisRecord::Cell(OasisNameRef::Name("a".into())),
+        OasisRecord::XyRelative,
+        OasisRecord::Placement {
+            cell: Some(OasisNameRef::Ref(3)),
+            x: Some(-7),
+            y: None,
+            mag: Some(0.25),
+            angle: 30.,
+            flip: true,
+            repetition: Some(OasisRepetitionSpec::Explicit(OasisRepetition::Grid {
+                cols: 2,
+                rows: 3,
+                col_step: OasisPoint::new(5, 0),
+                row_step: OasisPoint::new(0, 8),
+            })),
+        },
+        OasisRecord::Rectangle {
+            layer: Some(1),
+            datatype: None,
+            width: Some(4),
+            height: None,
+            square: true,
+            x: None,
+            y: Some(9),
+            repetition: Some(OasisRepetitionSpec::Reuse),
+        },
+        OasisRecord::Trapezoid {
+            layer: None,
+            datatype: Some(2),
+            vertical: true,
+            width: Some(10),
+            height: Some(20),
+            delta_a: -3,
+            delta_b: 4,
+            x: Some(1),
+            y: Some(2),
+            repetition: None,
+        },
+        OasisRecord::Property {
+            name: Some(OasisNameRef::Name("S_GDS_PROPERTY".into())),
+            values: Some(vec![
+                OasisPropValue::Unsigned(1),
+                OasisPropValue::BString(b"net".to_vec()),
+            ]),
+            standard: true,
+        },
+        OasisRecord::LayerName {
+            name: "met1".into(),
+            layers: OasisInterval {
+                lo: 68,
+                hi: Some(68),
+            },
+            datatypes: OasisInterval { lo: 0, hi: None },
+            text: false,
+        },
+    ];
+
+    let mut bytes = Vec::new();
+    let mut writer = OasisWriter::new(&mut bytes)?;
+    for record in records.iter() {
+        writer.write_record(record)?;
+    }
+    assert_eq!(read_records(&bytes[MAGIC.len()..])?, records);
+    Ok(())
+}
+
+#[test]
+fn it_round_trips() -> OasisResult<()> {
+    let lib = sample_lib();
+    let bytes = encode(&lib)?;
+    assert_eq!(OasisLibrary::from_bytes(&bytes)?, lib);
+    Ok(())
+}
+
+#[test]
+fn it_saves_and_loads() -> OasisResult<()> {
+    let lib = sample_lib();
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("nested/sample.oas");
+    lib.save(&path)?;
+    assert_eq!(OasisLibrary::load(&path)?, lib);
+    Ok(())
+}
+
+#[test]
+fn it_compresses_cells() -> OasisResult<()> {
+    let mut lib = OasisLibrary::new(1000.);
+    let mut cell = OasisCell::new("many");
+    for i in 0..1000 {
+        cell.elements.push(OasisElement::Rectangle(OasisRectangle {
+            layer: 1,
+            datatype: 0,
+            x: i * 7,
+            y: (i % 13) * 11,
+            width: 5,
+            height: 5,
+            repetition: None,
+        }));
+    }
+    lib.cells.push(cell);
+    let bytes = encode(&lib)?;
+    assert!(bytes.len() < 1000 * 4);
+    assert_eq!(OasisLibrary::from_bytes(&bytes)?, lib);
+    Ok(())
+}
+
+#[test]
+fn it_validates() -> OasisResult<()> {
+    let mut bytes = encode(&sample_lib())?;
+    // Corrupt a byte of padding in the `END` record.
+    let idx = bytes.len() - 20;
+    bytes[idx] ^= 0xff;
+    assert!(matches!(
+        OasisLibrary::from_bytes(&bytes),
+        Err(OasisError::Validation { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn it_rejects_lengths_exceeding_the_input() -> OasisResult<()> {
+    // A POLYGON whose point list claims 2^40 points.
+    let mut body = Vec::new();
+    write_uint(&mut body, 21);
+    body.push(0x20);
+    write_uint(&mut body, 4);
+    write_uint(&mut body, 1 << 40);
+    let data = [MAGIC, &body].concat();
+    assert!(matches!(
+        OasisReader::new(&data)?.read_record(),
+        Err(OasisError::Decode { .. })
+    ));
+
+    // A CBLOCK whose declared uncompressed length is far larger than its contents.
+    let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), Default::default());
+    std::io::Write::write_all(&mut encoder, &[0; 16])?;
+    let comp = encoder.finish()?;
+    let mut body = Vec::new();
+    write_uint(&mut body, 34);
+    write_uint(&mut body, 0);
+    write_uint(&mut body, 1 << 40);
+    write_uint(&mut body, comp.len() as u64);
+    body.extend(comp);
+    let data = [MAGIC, &body].concat();
+    assert!(matches!(
+        OasisReader::new(&data)?.read_record(),
+        Err(OasisError::Decode { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn it_rejects_oversized_repetitions() -> OasisResult<()> {
+    let rectangle = |repetition: &[u64]| {
+        let mut body = Vec::new();
+        write_uint(&mut body, 20);
+        body.push(0x04);
+        for &v in repetition {
+            write_uint(&mut body, v);
+        }
+        [MAGIC, &body].concat()
+    };
+    // A row whose element count overflows when the implicit two is added.
+    let data = rectangle(&[2, u64::MAX, 10]);
+    assert!(matches!(
+        OasisReader::new(&data)?.read_record(),
+        Err(OasisError::Decode { .. })
+    ));
+    // A grid whose element count overflows.
+    let data = rectangle(&[1, 1 << 33, 1 << 33, 10, 10]);
+    assert!(matches!(
+        OasisReader::new(&data)?.read_record(),
+        Err(OasisError::Decode { .. })
+    ));
+
+    let grid = OasisRepetition::Grid {
+        cols: 1 << 33,
+        rows: 1 << 33,
+        col_step: OasisPoint::new(10, 0),
+        row_step: OasisPoint::new(0, 10),
+    };
+    assert_eq!(grid.len(), usize::MAX);
+    assert!(grid.offsets().is_err());
+
+    // A grid that parses but is too large to expand into GDS elements.
+    let mut lib = OasisLibrary::new(1000.);
+    let mut cell = OasisCell::new("top");
+    cell.elements.push(OasisElement::Rectangle(OasisRectangle {
+        layer: 1,
+        datatype: 0,
+        x: 0,
+        y: 0,
+        width: 1,
+        height: 1,
+        repetition: Some(OasisRepetition::Grid {
+            cols: 1 << 20,
+            rows: 1 << 20,
+            col_step: OasisPoint::new(2, 0),
+            row_step: OasisPoint::new(0, 2),
+        }),
+    }));
+    lib.cells.push(cell);
+    let lib = OasisLibrary::from_bytes(&encode(&lib)?)?;
+    assert!(matches!(lib.to_gds("top"), Err(OasisError::Unsupported(_))));
+    Ok(())
+}
+
+#[test]
+fn it_reads_modal_and_implicit_records() -> OasisResult<()> {
+    // Exercises implicit reference numbers, relative coordinates,
+    // Manhattan point lists with implicit vertices, and modal reuse.
+    let mut body = Vec::new();
+    write_uint(&mut body, 1);
+    write_uint(&mut body, 3);
+    body.extend_from_slice(b"1.0");
+    write_real(&mut body, 1000.);
+    write_uint(&mut body, 1);
+    write_uint(&mut body, 3);
+    write_uint(&mut body, 3);
+    body.extend_from_slice(b"abc");
+    write_uint(&mut body, 13);
+    write_uint(&mut body, 0);
+    write_uint(&mut body, 16);
+    // POLYGON with layer, datatype, a type 0 point list, and x/y.
+    write_uint(&mut body, 21);
+    body.push(0x3b);
+    write_uint(&mut body, 6);
+    write_uint(&mut body, 0);
+    write_uint(&mut body, 0);
+    write_uint(&mut body, 2);
+    write_sint(&mut body, 10);
+    write_sint(&mut body, 20);
+    write_sint(&mut body, 100);
+    write_sint(&mut body, 100);
+    // Repeat the polygon, displaced by (5, 0) relative to the previous one.
+    write_uint(&mut body, 21);
+    body.push(0x10);
+    write_sint(&mut body, 5);
+
+    let mut data = [MAGIC, &body].concat();
+    data.push(2);
+    for _ in 0..6 {
+        data.extend([0, 0]);
+    }
+    let pad = 256 - 1 - 12 - 2 - 1 - 4;
+    write_uint(&mut data, pad as u64);
+    data.extend(std::iter::repeat(0).take(pad));
+    write_uint(&mut data, 1);
+    let mut crc = flate2::Crc::new();
+    crc.update(&data);
+    data.extend(crc.sum().to_le_bytes());
+
+    let lib = OasisParser::new(OasisReader::new(&data)?).parse_lib()?;
+    let square = |x0| {
+        vec![
+            OasisPoint::new(x0, 100),
+            OasisPoint::new(x0 + 10, 100),
+            OasisPoint::new(x0 + 10, 120),
+            OasisPoint::new(x0, 120),
+        ]
+    };
+    let polygons = lib.cells[0]
+        .elements
+        .iter()
+        .map(|elem| match elem {
+            OasisElement::Polygon(p) => p.points.clone(),
+            _ => panic!("expected a polygon"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(lib.cells[0].name, "abc");
+    assert_eq!(polygons, vec![square(100), square(105)]);
+    Ok(())
+}
+
+#[test]
+fn it_converts_gds() -> OasisResult<()> {
+    let lib = sample_lib();
+    let gds = lib.to_gds("sample")?;
+    assert!((gds.units.db_unit() - 1e-9).abs() < 1e-18);
+    let converted = OasisLibrary::from_gds(&gds)?;
+    assert_eq!(converted.unit, 1000.);
+    // Converting back to GDS produces the same geometry, up to element order.
+    let mut expected = gds
+        .structs
+        .iter()
+        .map(|s| s.elems.len())
+        .collect::<Vec<_>>();
+    let regenerated = converted.to_gds("sample")?;
+    let mut found = regenerated
+        .structs
+        .iter()
+        .map(|s| s.elems.len())
+        .collect::<Vec<_>>();
+    expected.sort();
+    found.sort();
+    assert_eq!(expected, found);
+
+    // Rectangles are merged into repetitions.
+    let leaf = &converted.cells[0];
+    let rects = leaf
+        .elements
+        .iter()
+        .filter(|elem| matches!(elem, OasisElement::Rectangle(_)))
+        .count();
+    assert_eq!(rects, 2);
+    assert!(leaf
+        .elements
+        .contains(&OasisElement::Rectangle(OasisRectangle {
+            layer: 1,
+            datatype: 0,
+            x: -50,
+            y: 200,
+            width: 100,
+            height: 40,
+            repetition: Some(OasisRepetition::Grid {
+                cols: 3,
+                rows: 2,
+                col_step: OasisPoint::new(200, 0),
+                row_step: OasisPoint::new(0, 300),
+            }),
+        })));
+    Ok(())
+}
+
+#[test]
+fn it_converts_round_and_odd_width_gds_paths_to_polygons() -> OasisResult<()> {
+    let path = |width, path_type| {
+        gds::GdsElement::GdsPath(gds::GdsPath {
+            layer: 1,
+            datatype: 0,
+            xy: vec![gds::GdsPoint::new(0, 0), gds::GdsPoint::new(1000, 0)],
+            width: Some(width),
+            path_type: Some(path_type),
+            ..Default::default()
+        })
+    };
+    let mut strukt = gds::GdsStruct::new("paths");
+    strukt.elems.push(path(100, 1));
+    strukt.elems.push(path(101, 0));
+    strukt.elems.push(path(100, 2));
+    let mut gds = gds::GdsLibrary::new("paths");
+    gds.structs.push(strukt);
+
+    let converted = OasisLibrary::from_gds(&gds)?;
+    let elements = &converted.cells[0].elements;
+    let paths = elements
+        .iter()
+        .filter(|elem| matches!(elem, OasisElement::Path(_)))
+        .count();
+    assert_eq!(paths, 1);
+    let polygons = elements
+        .iter()
+        .filter_map(|elem| match elem {
+            OasisElement::Polygon(p) => Some(p),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    // The round-ended path becomes a body and two end caps, and the odd-width path a body.
+    assert_eq!(polygons.len(), 4);
+    let x_range = |p: &OasisPolygon| {
+        let xs = p.points.iter().map(|p| p.x);
+        (xs.clone().min().unwrap(), xs.max().unwrap())
+    };
+    assert!(polygons.iter().any(|p| x_range(p) == (-50, 50)));
+    assert!(polygons.iter().any(|p| x_range(p) == (950, 1050)));
+
+    // The result can be written as OASIS.
+    let mut bytes = Vec::new();
+    converted.write(&mut bytes)?;
+    Ok(())
+}
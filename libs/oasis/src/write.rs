@@ -0,0 +1,1024 @@
+//! Utilities for encoding and writing.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use arcstr::ArcStr;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+
+use super::read::Modal;
+use super::*;
+
+/// The size in bytes of an `END` record.
+const END_RECORD_LEN: usize = 256;
+
+/// A record-level OASIS writer.
+///
+/// Writes [`OasisRecord`]s to a destination implementing [`Write`],
+/// optionally compressing groups of records into `CBLOCK`s.
+pub struct OasisWriter<W: Write> {
+    /// The write destination.
+    dest: W,
+    /// The number of bytes written to `dest`.
+    pos: u64,
+    /// The running CRC-32 of all bytes written to `dest`.
+    crc: flate2::Crc,
+    /// The running byte sum of all bytes written to `dest`.
+    checksum: u32,
+    /// The uncompressed contents of the current `CBLOCK`, if any.
+    cblock: Option<Vec<u8>>,
+}
+
+impl<W: Write> OasisWriter<W> {
+    /// Creates a new [`OasisWriter`] and writes the OASIS magic bytes to `dest`.
+    pub fn new(dest: W) -> OasisResult<Self> {
+        let mut writer = Self {
+            dest,
+            pos: 0,
+            crc: flate2::Crc::new(),
+            checksum: 0,
+            cblock: None,
+        };
+        writer.write_bytes(MAGIC)?;
+        Ok(writer)
+    }
+
+    /// The current byte offset in the file.
+    ///
+    /// Records buffered in an open `CBLOCK` are not included.
+    pub fn pos(&self) -> u64 {
+        self.pos
+    }
+
+    /// Writes `record`.
+    ///
+    /// Writing an [`OasisRecord::End`] record pads it to the required length
+    /// and appends the validation signature.
+    pub fn write_record(&mut self, record: &OasisRecord) -> OasisResult<()> {
+        let mut buf = Vec::new();
+        if let OasisRecord::End {
+            offsets,
+            validation,
+        } = record
+        {
+            if self.cblock.is_some() {
+                return Err(OasisError::InvalidArgument(arcstr::literal!(
+                    "END record inside CBLOCK"
+                )));
+            }
+            write_uint(&mut buf, 2);
+            if let Some(offsets) = offsets {
+                write_table_offsets(&mut buf, offsets);
+            }
+            let sig_len = if *validation == OasisValidation::None {
+                0
+            } else {
+                4
+            };
+            // The padding string and its length prefix fill the remainder of the record.
+            let base = buf.len() + 1 + sig_len;
+            let mut pad_len = END_RECORD_LEN - base - 1;
+            if pad_len >= 0x80 {
+                pad_len -= 1;
+            }
+            write_bstring(&mut buf, &vec![0; pad_len]);
+            write_uint(
+                &mut buf,
+                match validation {
+                    OasisValidation::None => 0,
+                    OasisValidation::Crc32 => 1,
+                    OasisValidation::Checksum32 => 2,
+                },
+            );
+            self.write_bytes(&buf)?;
+            if *validation != OasisValidation::None {
+                let sig = match validation {
+                    OasisValidation::Crc32 => self.crc.sum(),
+                    _ => self.checksum,
+                };
+                self.write_bytes(&sig.to_le_bytes())?;
+            }
+            self.dest.flush()?;
+            return Ok(());
+        }
+        encode_record(&mut buf, record)?;
+        self.write_bytes(&buf)
+    }
+
+    /// Begins a `CBLOCK`.
+    ///
+    /// Subsequent records are buffered and compressed when [`OasisWriter::end_cblock`] is called.
+    pub fn begin_cblock(&mut self) -> OasisResult<()> {
+        if self.cblock.is_some() {
+            return Err(OasisError::InvalidArgument(arcstr::literal!(
+                "nested CBLOCK"
+            )));
+        }
+        self.cblock = Some(Vec::new());
+        Ok(())
+    }
+
+    /// Compresses and writes the current `CBLOCK`.
+    pub fn end_cblock(&mut self) -> OasisResult<()> {
+        let Some(data) = self.cblock.take() else {
+            return Err(OasisError::InvalidArgument(arcstr::literal!(
+                "no CBLOCK to end"
+            )));
+        };
+        if data.is_empty() {
+            return Ok(());
+        }
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data)?;
+        let comp = encoder.finish()?;
+        let mut buf = Vec::with_capacity(comp.len() + 16);
+        write_uint(&mut buf, 34);
+        write_uint(&mut buf, 0);
+        write_uint(&mut buf, data.len() as u64);
+        write_uint(&mut buf, comp.len() as u64);
+        buf.extend_from_slice(&comp);
+        self.write_bytes(&buf)
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> OasisResult<()> {
+        if let Some(cblock) = &mut self.cblock {
+            cblock.extend_from_slice(bytes);
+            return Ok(());
+        }
+        self.dest.write_all(bytes)?;
+        self.crc.update(bytes);
+        self.checksum = bytes
+            .iter()
+            .fold(self.checksum, |sum, &b| sum.wrapping_add(u32::from(b)));
+        self.pos += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+pub(crate) fn write_uint(buf: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let b = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(b);
+            return;
+        }
+        buf.push(b | 0x80);
+    }
+}
+
+pub(crate) fn write_sint(buf: &mut Vec<u8>, value: i64) {
+    write_uint(buf, (value.unsigned_abs() << 1) | u64::from(value < 0));
+}
+
+pub(crate) fn write_real(buf: &mut Vec<u8>, value: f64) {
+    if value.fract() == 0. && value.abs() < (1u64 << 53) as f64 {
+        write_uint(buf, u64::from(value < 0.));
+        write_uint(buf, value.abs() as u64);
+    } else {
+        write_uint(buf, 7);
+        buf.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn write_bstring(buf: &mut Vec<u8>, value: &[u8]) {
+    write_uint(buf, value.len() as u64);
+    buf.extend_from_slice(value);
+}
+
+fn write_interval(buf: &mut Vec<u8>, interval: &OasisInterval) {
+    match (interval.lo, interval.hi) {
+        (0, None) => write_uint(buf, 0),
+        (0, Some(hi)) => {
+            write_uint(buf, 1);
+            write_uint(buf, hi);
+        }
+        (lo, Some(hi)) if lo == hi => {
+            write_uint(buf, 2);
+            write_uint(buf, lo);
+        }
+        (lo, None) => {
+            write_uint(buf, 3);
+            write_uint(buf, lo);
+        }
+        (lo, Some(hi)) => {
+            write_uint(buf, 4);
+            write_uint(buf, lo);
+            write_uint(buf, hi);
+        }
+    }
+}
+
+fn write_table_offsets(buf: &mut Vec<u8>, offsets: &OasisTableOffsets) {
+    for (strict, offset) in [
+        offsets.cellname,
+        offsets.textstring,
+        offsets.propname,
+        offsets.propstring,
+        offsets.layername,
+        offsets.xname,
+    ] {
+        write_uint(buf, u64::from(strict));
+        write_uint(buf, offset);
+    }
+}
+
+/// Returns the direction and magnitude of an octangular displacement.
+///
+/// Directions are numbered E, N, W, S, NE, NW, SW, SE.
+fn octangular(p: OasisPoint) -> Option<(u64, u64)> {
+    let (x, y) = (p.x, p.y);
+    let dir = match (x.signum(), y.signum()) {
+        (1, 0) | (0, 0) => 0,
+        (0, 1) => 1,
+        (-1, 0) => 2,
+        (0, -1) => 3,
+        _ if x.abs() != y.abs() => return None,
+        (1, 1) => 4,
+        (-1, 1) => 5,
+        (-1, -1) => 6,
+        _ => 7,
+    };
+    Some((dir, x.unsigned_abs().max(y.unsigned_abs())))
+}
+
+pub(crate) fn write_gdelta(buf: &mut Vec<u8>, p: OasisPoint) {
+    match octangular(p) {
+        Some((dir, mag)) => write_uint(buf, (mag << 4) | (dir << 1)),
+        None => {
+            write_uint(
+                buf,
+                (p.x.unsigned_abs() << 2) | (u64::from(p.x < 0) << 1) | 1,
+            );
+            write_sint(buf, p.y);
+        }
+    }
+}
+
+/// Writes a point list of cumulative vertex displacements.
+fn write_point_list(buf: &mut Vec<u8>, points: &[OasisPoint]) {
+    let mut prev = OasisPoint::default();
+    let deltas = points
+        .iter()
+        .map(|&p| {
+            let d = p - prev;
+            prev = p;
+            d
+        })
+        .collect::<Vec<_>>();
+    let octs = deltas.iter().map(|&d| octangular(d)).collect::<Vec<_>>();
+    if octs.iter().all(|o| matches!(o, Some((dir, _)) if *dir < 4)) {
+        write_uint(buf, 2);
+        write_uint(buf, deltas.len() as u64);
+        for (dir, mag) in octs.into_iter().flatten() {
+            write_uint(buf, (mag << 2) | dir);
+        }
+    } else if octs.iter().all(Option::is_some) {
+        write_uint(buf, 3);
+        write_uint(buf, deltas.len() as u64);
+        for (dir, mag) in octs.into_iter().flatten() {
+            write_uint(buf, (mag << 3) | dir);
+        }
+    } else {
+        write_uint(buf, 4);
+        write_uint(buf, deltas.len() as u64);
+        for d in deltas {
+            write_gdelta(buf, d);
+        }
+    }
+}
+
+fn write_repetition(buf: &mut Vec<u8>, repetition: &OasisRepetitionSpec) -> OasisResult<()> {
+    let repetition = match repetition {
+        OasisRepetitionSpec::Reuse => {
+            write_uint(buf, 0);
+            return Ok(());
+        }
+        OasisRepetitionSpec::Explicit(repetition) => repetition,
+    };
+    if repetition.len() < 2 {
+        return Err(OasisError::InvalidArgument(arcstr::literal!(
+            "repetitions must have at least two elements"
+        )));
+    }
+    match *repetition {
+        OasisRepetition::Grid {
+            cols,
+            rows,
+            col_step,
+            row_step,
+        } => {
+            let col_axis = col_step.y == 0 && col_step.x >= 0;
+            let row_axis = row_step.x == 0 && row_step.y >= 0;
+            match (cols, rows) {
+                (_, 1) if col_axis => {
+                    write_uint(buf, 2);
+                    write_uint(buf, cols - 2);
+                    write_uint(buf, col_step.x as u64);
+                }
+                (1, _) if row_axis => {
+                    write_uint(buf, 3);
+                    write_uint(buf, rows - 2);
+                    write_uint(buf, row_step.y as u64);
+                }
+                (_, 1) => {
+                    write_uint(buf, 9);
+                    write_uint(buf, cols - 2);
+                    write_gdelta(buf, col_step);
+                }
+                (1, _) => {
+                    write_uint(buf, 9);
+                    write_uint(buf, rows - 2);
+                    write_gdelta(buf, row_step);
+                }
+                _ if col_axis && row_axis => {
+                    write_uint(buf, 1);
+                    write_uint(buf, cols - 2);
+                    write_uint(buf, rows - 2);
+                    write_uint(buf, col_step.x as u64);
+                    write_uint(buf, row_step.y as u64);
+                }
+                _ => {
+                    write_uint(buf, 8);
+                    write_uint(buf, cols - 2);
+                    write_uint(buf, rows - 2);
+                    write_gdelta(buf, col_step);
+                    write_gdelta(buf, row_step);
+                }
+            }
+        }
+        OasisRepetition::Arbitrary(ref offsets) => {
+            if offsets[0] != OasisPoint::default() {
+                return Err(OasisError::InvalidArgument(arcstr::literal!(
+                    "arbitrary repetitions must start at the origin"
+                )));
+            }
+            write_uint(buf, 10);
+            write_uint(buf, offsets.len() as u64 - 2);
+            for pair in offsets.windows(2) {
+                write_gdelta(buf, pair[1] - pair[0]);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_prop_value(buf: &mut Vec<u8>, value: &OasisPropValue) {
+    match value {
+        OasisPropValue::Real(v) => write_real(buf, *v),
+        OasisPropValue::Unsigned(v) => {
+            write_uint(buf, 8);
+            write_uint(buf, *v);
+        }
+        OasisPropValue::Signed(v) => {
+            write_uint(buf, 9);
+            write_sint(buf, *v);
+        }
+        OasisPropValue::AString(s) => {
+            write_uint(buf, 10);
+            write_bstring(buf, s.as_bytes());
+        }
+        OasisPropValue::BString(s) => {
+            write_uint(buf, 11);
+            write_bstring(buf, s);
+        }
+        OasisPropValue::NString(s) => {
+            write_uint(buf, 12);
+            write_bstring(buf, s.as_bytes());
+        }
+        OasisPropValue::AStringRef(r) => {
+            write_uint(buf, 13);
+            write_uint(buf, *r);
+        }
+        OasisPropValue::BStringRef(r) => {
+            write_uint(buf, 14);
+            write_uint(buf, *r);
+        }
+        OasisPropValue::NStringRef(r) => {
+            write_uint(buf, 15);
+            write_uint(buf, *r);
+        }
+    }
+}
+
+/// Accumulates the fields of a record following its info byte.
+struct Fields {
+    info: u8,
+    buf: Vec<u8>,
+}
+
+impl Fields {
+    fn new() -> Self {
+        Self {
+            info: 0,
+            buf: Vec::new(),
+        }
+    }
+
+    fn uint(&mut self, bit: u8, value: Option<u64>) {
+        if let Some(value) = value {
+            self.info |= bit;
+            write_uint(&mut self.buf, value);
+        }
+    }
+
+    fn sint(&mut self, bit: u8, value: Option<i64>) {
+        if let Some(value) = value {
+            self.info |= bit;
+            write_sint(&mut self.buf, value);
+        }
+    }
+
+    fn name(&mut self, bit: u8, ref_bit: u8, value: &Option<OasisNameRef>) {
+        match value {
+            Some(OasisNameRef::Name(name)) => {
+                self.info |= bit;
+                write_bstring(&mut self.buf, name.as_bytes());
+            }
+            Some(OasisNameRef::Ref(refnum)) => {
+                self.info |= bit | ref_bit;
+                write_uint(&mut self.buf, *refnum);
+            }
+            None => {}
+        }
+    }
+
+    fn points(&mut self, bit: u8, value: &Option<Vec<OasisPoint>>) {
+        if let Some(points) = value {
+            self.info |= bit;
+            write_point_list(&mut self.buf, points);
+        }
+    }
+
+    /// Writes the trailing x, y, and repetition fields shared by most element records.
+    fn position(
+        &mut self,
+        x: Option<i64>,
+        y: Option<i64>,
+        repetition: &Option<OasisRepetitionSpec>,
+        bits: (u8, u8, u8),
+    ) -> OasisResult<()> {
+        self.sint(bits.0, x);
+        self.sint(bits.1, y);
+        if let Some(repetition) = repetition {
+            self.info |= bits.2;
+            write_repetition(&mut self.buf, repetition)?;
+        }
+        Ok(())
+    }
+
+    fn finish(self, buf: &mut Vec<u8>, id: u64) {
+        write_uint(buf, id);
+        buf.push(self.info);
+        buf.extend_from_slice(&self.buf);
+    }
+}
+
+fn write_named(buf: &mut Vec<u8>, ids: (u64, u64), name: &[u8], refnum: Option<u64>) {
+    write_uint(buf, if refnum.is_some() { ids.1 } else { ids.0 });
+    write_bstring(buf, name);
+    if let Some(refnum) = refnum {
+        write_uint(buf, refnum);
+    }
+}
+
+/// Encodes any record other than [`OasisRecord::End`].
+fn encode_record(buf: &mut Vec<u8>, record: &OasisRecord) -> OasisResult<()> {
+    const XYR: (u8, u8, u8) = (0x10, 0x08, 0x04);
+    match record {
+        OasisRecord::Pad => write_uint(buf, 0),
+        OasisRecord::Start {
+            version,
+            unit,
+            offsets,
+        } => {
+            write_uint(buf, 1);
+            write_bstring(buf, version.as_bytes());
+            write_real(buf, *unit);
+            write_uint(buf, u64::from(offsets.is_none()));
+            if let Some(offsets) = offsets {
+                write_table_offsets(buf, offsets);
+            }
+        }
+        OasisRecord::End { .. } => unreachable!("END records are written by the OasisWriter"),
+        OasisRecord::CellName { name, refnum } => {
+            write_named(buf, (3, 4), name.as_bytes(), *refnum)
+        }
+        OasisRecord::TextString { string, refnum } => {
+            write_named(buf, (5, 6), string.as_bytes(), *refnum)
+        }
+        OasisRecord::PropName { name, refnum } => {
+            write_named(buf, (7, 8), name.as_bytes(), *refnum)
+        }
+        OasisRecord::PropString { string, refnum } => write_named(buf, (9, 10), string, *refnum),
+        OasisRecord::LayerName {
+            name,
+            layers,
+            datatypes,
+            text,
+        } => {
+            write_uint(buf, if *text { 12 } else { 11 });
+            write_bstring(buf, name.as_bytes());
+            write_interval(buf, layers);
+            write_interval(buf, datatypes);
+        }
+        OasisRecord::Cell(OasisNameRef::Ref(refnum)) => {
+            write_uint(buf, 13);
+            write_uint(buf, *refnum);
+        }
+        OasisRecord::Cell(OasisNameRef::Name(name)) => {
+            write_uint(buf, 14);
+            write_bstring(buf, name.as_bytes());
+        }
+        OasisRecord::XyAbsolute => write_uint(buf, 15),
+        OasisRecord::XyRelative => write_uint(buf, 16),
+        OasisRecord::Placement {
+            cell,
+            x,
+            y,
+            mag,
+            angle,
+            flip,
+            repetition,
+        } => {
+            let mut f = Fields::new();
+            f.name(0x80, 0x40, cell);
+            let quarter_turns = angle / 90.;
+            let id = if mag.is_none()
+                && quarter_turns.fract() == 0.
+                && (0. ..4.).contains(&quarter_turns)
+            {
+                f.info |= (quarter_turns as u8) << 1;
+                17
+            } else {
+                if let Some(mag) = mag {
+                    f.info |= 0x04;
+                    write_real(&mut f.buf, *mag);
+                }
+                if *angle != 0. {
+                    f.info |= 0x02;
+                    write_real(&mut f.buf, *angle);
+                }
+                18
+            };
+            if *flip {
+                f.info |= 0x01;
+            }
+            f.position(*x, *y, repetition, (0x20, 0x10, 0x08))?;
+            f.finish(buf, id);
+        }
+        OasisRecord::Text {
+            string,
+            layer,
+            texttype,
+            x,
+            y,
+            repetition,
+        } => {
+            let mut f = Fields::new();
+            f.name(0x40, 0x20, string);
+            f.uint(0x01, *layer);
+            f.uint(0x02, *texttype);
+            f.position(*x, *y, repetition, XYR)?;
+            f.finish(buf, 19);
+        }
+        OasisRecord::Rectangle {
+            layer,
+            datatype,
+            width,
+            height,
+            square,
+            x,
+            y,
+            repetition,
+        } => {
+            let mut f = Fields::new();
+            f.uint(0x01, *layer);
+            f.uint(0x02, *datatype);
+            f.uint(0x40, *width);
+            if *square {
+                f.info |= 0x80;
+            } else {
+                f.uint(0x20, *height);
+            }
+            f.position(*x, *y, repetition, XYR)?;
+            f.finish(buf, 20);
+        }
+        OasisRecord::Polygon {
+            layer,
+            datatype,
+            points,
+            x,
+            y,
+            repetition,
+        } => {
+            let mut f = Fields::new();
+            f.uint(0x01, *layer);
+            f.uint(0x02, *datatype);
+            f.points(0x20, points);
+            f.position(*x, *y, repetition, XYR)?;
+            f.finish(buf, 21);
+        }
+        OasisRecord::Path {
+            layer,
+            datatype,
+            half_width,
+            extensions,
+            points,
+            x,
+            y,
+            repetition,
+        } => {
+            let mut f = Fields::new();
+            f.uint(0x01, *layer);
+            f.uint(0x02, *datatype);
+            f.uint(0x40, *half_width);
+            if let Some((start, end)) = extensions {
+                f.info |= 0x80;
+                let bits = |ext: &Option<OasisPathExtension>| match ext {
+                    None => 0,
+                    Some(OasisPathExtension::Flush) => 1,
+                    Some(OasisPathExtension::HalfWidth) => 2,
+                    Some(OasisPathExtension::Explicit(_)) => 3,
+                };
+                write_uint(&mut f.buf, (bits(start) << 2) | bits(end));
+                for ext in [start, end].into_iter().flatten() {
+                    if let OasisPathExtension::Explicit(d) = ext {
+                        write_sint(&mut f.buf, *d);
+                    }
+                }
+            }
+            f.points(0x20, points);
+            f.position(*x, *y, repetition, XYR)?;
+            f.finish(buf, 22);
+        }
+        OasisRecord::Trapezoid {
+            layer,
+            datatype,
+            vertical,
+            width,
+            height,
+            delta_a,
+            delta_b,
+            x,
+            y,
+            repetition,
+        } => {
+            let mut f = Fields::new();
+            f.uint(0x01, *layer);
+            f.uint(0x02, *datatype);
+            f.uint(0x40, *width);
+            f.uint(0x20, *height);
+            if *vertical {
+                f.info |= 0x80;
+            }
+            let id = match (*delta_a, *delta_b) {
+                (a, 0) => {
+                    write_sint(&mut f.buf, a);
+                    24
+                }
+                (0, b) => {
+                    write_sint(&mut f.buf, b);
+                    25
+                }
+                (a, b) => {
+                    write_sint(&mut f.buf, a);
+                    write_sint(&mut f.buf, b);
+                    23
+                }
+            };
+            f.position(*x, *y, repetition, XYR)?;
+            f.finish(buf, id);
+        }
+        OasisRecord::CTrapezoid {
+            layer,
+            datatype,
+            ctype,
+            width,
+            height,
+            x,
+            y,
+            repetition,
+        } => {
+            let mut f = Fields::new();
+            f.uint(0x01, *layer);
+            f.uint(0x02, *datatype);
+            f.uint(0x80, *ctype);
+            f.uint(0x40, *width);
+            f.uint(0x20, *height);
+            f.position(*x, *y, repetition, XYR)?;
+            f.finish(buf, 26);
+        }
+        OasisRecord::Circle {
+            layer,
+            datatype,
+            radius,
+            x,
+            y,
+            repetition,
+        } => {
+            let mut f = Fields::new();
+            f.uint(0x01, *layer);
+            f.uint(0x02, *datatype);
+            f.uint(0x20, *radius);
+            f.position(*x, *y, repetition, XYR)?;
+            f.finish(buf, 27);
+        }
+        OasisRecord::Property {
+            name,
+            values,
+            standard,
+        } => {
+            let mut f = Fields::new();
+            f.name(0x04, 0x02, name);
+            if *standard {
+                f.info |= 0x01;
+            }
+            match values {
+                None => f.info |= 0x08,
+                Some(values) => {
+                    if values.len() < 15 {
+                        f.info |= (values.len() as u8) << 4;
+                    } else {
+                        f.info |= 0xf0;
+                        write_uint(&mut f.buf, values.len() as u64);
+                    }
+                    for value in values {
+                        write_prop_value(&mut f.buf, value);
+                    }
+                }
+            }
+            f.finish(buf, 28);
+        }
+        OasisRecord::PropertyRepeat => write_uint(buf, 29),
+        OasisRecord::XName {
+            attribute,
+            string,
+            refnum,
+        } => {
+            write_uint(buf, if refnum.is_some() { 31 } else { 30 });
+            write_uint(buf, *attribute);
+            write_bstring(buf, string);
+            if let Some(refnum) = refnum {
+                write_uint(buf, *refnum);
+            }
+        }
+        OasisRecord::XElement { attribute, data } => {
+            write_uint(buf, 32);
+            write_uint(buf, *attribute);
+            write_bstring(buf, data);
+        }
+        OasisRecord::XGeometry {
+            attribute,
+            layer,
+            datatype,
+            data,
+            x,
+            y,
+            repetition,
+        } => {
+            write_uint(buf, 33);
+            let mut f = Fields::new();
+            write_uint(&mut f.buf, *attribute);
+            f.uint(0x01, *layer);
+            f.uint(0x02, *datatype);
+            write_bstring(&mut f.buf, data);
+            f.position(*x, *y, repetition, XYR)?;
+            buf.push(f.info);
+            buf.extend_from_slice(&f.buf);
+        }
+    }
+    Ok(())
+}
+
+/// Returns `value` if it differs from the modal variable, updating the modal variable.
+fn changed<T: PartialEq + Clone>(modal: &mut Option<T>, value: T) -> Option<T> {
+    if modal.as_ref() == Some(&value) {
+        None
+    } else {
+        *modal = Some(value.clone());
+        Some(value)
+    }
+}
+
+/// Returns a coordinate if it differs from the modal coordinate, updating the modal coordinate.
+fn changed_coord(modal: &mut i64, value: i64) -> Option<i64> {
+    if *modal == value {
+        None
+    } else {
+        *modal = value;
+        Some(value)
+    }
+}
+
+/// Returns the relative position and displacements of a list of absolute vertices.
+fn split_points(points: &[OasisPoint], kind: &str) -> OasisResult<(OasisPoint, Vec<OasisPoint>)> {
+    let Some(&origin) = points.first() else {
+        return Err(OasisError::InvalidArgument(arcstr::format!(
+            "{kind} has no vertices"
+        )));
+    };
+    Ok((origin, points[1..].iter().map(|&p| p - origin).collect()))
+}
+
+/// A library-level OASIS encoder.
+///
+/// Writes name tables and omits record fields that match the current modal variables.
+pub(crate) struct OasisEncoder<W: Write> {
+    writer: OasisWriter<W>,
+    modal: Modal,
+    cellnames: HashMap<ArcStr, u64>,
+    textstrings: HashMap<ArcStr, u64>,
+}
+
+impl<W: Write> OasisEncoder<W> {
+    pub(crate) fn new(writer: OasisWriter<W>) -> Self {
+        Self {
+            writer,
+            modal: Modal::default(),
+            cellnames: HashMap::new(),
+            textstrings: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn encode_lib(mut self, lib: &OasisLibrary) -> OasisResult<()> {
+        self.writer.write_record(&OasisRecord::Start {
+            version: arcstr::literal!(VERSION),
+            unit: lib.unit,
+            offsets: None,
+        })?;
+
+        // Collect the name tables, in order of first use.
+        let mut cellnames = Vec::new();
+        let mut textstrings = Vec::new();
+        for cell in lib.cells.iter() {
+            cellnames.push(cell.name.clone());
+            for elem in cell.elements.iter() {
+                match elem {
+                    OasisElement::Placement(p) => cellnames.push(p.cell.clone()),
+                    OasisElement::Text(t) => textstrings.push(t.string.clone()),
+                    _ => {}
+                }
+            }
+        }
+        let offsets = OasisTableOffsets {
+            cellname: self.encode_table(cellnames, true)?,
+            textstring: self.encode_table(textstrings, false)?,
+            ..Default::default()
+        };
+
+        for cell in lib.cells.iter() {
+            self.encode_cell(cell)?;
+        }
+        self.writer.write_record(&OasisRecord::End {
+            offsets: Some(offsets),
+            validation: OasisValidation::Crc32,
+        })
+    }
+
+    /// Writes a name table, returning its table offset.
+    fn encode_table(&mut self, names: Vec<ArcStr>, cells: bool) -> OasisResult<(bool, u64)> {
+        let pos = self.writer.pos();
+        let table = if cells {
+            &mut self.cellnames
+        } else {
+            &mut self.textstrings
+        };
+        let mut records = Vec::new();
+        for name in names {
+            if !table.contains_key(&name) {
+                let refnum = table.len() as u64;
+                table.insert(name.clone(), refnum);
+                records.push(if cells {
+                    OasisRecord::CellName {
+                        name,
+                        refnum: Some(refnum),
+                    }
+                } else {
+                    OasisRecord::TextString {
+                        string: name,
+                        refnum: Some(refnum),
+                    }
+                });
+            }
+        }
+        if records.is_empty() {
+            return Ok((false, 0));
+        }
+        for record in records.iter() {
+            self.writer.write_record(record)?;
+        }
+        Ok((true, pos))
+    }
+
+    fn encode_cell(&mut self, cell: &OasisCell) -> OasisResult<()> {
+        self.modal = Modal::default();
+        self.writer.begin_cblock()?;
+        self.writer
+            .write_record(&OasisRecord::Cell(OasisNameRef::Ref(
+                self.cellnames[&cell.name],
+            )))?;
+        for elem in cell.elements.iter() {
+            let record = self.encode_element(elem)?;
+            self.writer.write_record(&record)?;
+        }
+        self.writer.end_cblock()
+    }
+
+    fn encode_repetition(
+        &mut self,
+        repetition: &Option<OasisRepetition>,
+    ) -> Option<OasisRepetitionSpec> {
+        let repetition = repetition.as_ref()?;
+        Some(
+            match changed(&mut self.modal.repetition, repetition.clone()) {
+                Some(repetition) => OasisRepetitionSpec::Explicit(repetition),
+                None => OasisRepetitionSpec::Reuse,
+            },
+        )
+    }
+
+    fn encode_element(&mut self, elem: &OasisElement) -> OasisResult<OasisRecord> {
+        Ok(match elem {
+            OasisElement::Placement(p) => {
+                let cell = OasisNameRef::Ref(self.cellnames[&p.cell]);
+                let m = &mut self.modal;
+                OasisRecord::Placement {
+                    cell: changed(&mut m.placement_cell, cell),
+                    x: changed_coord(&mut m.placement_x, p.x),
+                    y: changed_coord(&mut m.placement_y, p.y),
+                    mag: Some(p.mag).filter(|&mag| mag != 1.),
+                    angle: p.angle,
+                    flip: p.flip,
+                    repetition: self.encode_repetition(&p.repetition),
+                }
+            }
+            OasisElement::Text(t) => {
+                let string = OasisNameRef::Ref(self.textstrings[&t.string]);
+                let m = &mut self.modal;
+                OasisRecord::Text {
+                    string: changed(&mut m.text_string, string),
+                    layer: changed(&mut m.textlayer, t.layer),
+                    texttype: changed(&mut m.texttype, t.texttype),
+                    x: changed_coord(&mut m.text_x, t.x),
+                    y: changed_coord(&mut m.text_y, t.y),
+                    repetition: self.encode_repetition(&t.repetition),
+                }
+            }
+            OasisElement::Rectangle(r) => {
+                let m = &mut self.modal;
+                let square = r.width == r.height;
+                let width = changed(&mut m.geometry_w, r.width);
+                let height = if square {
+                    m.geometry_h = Some(r.height);
+                    None
+                } else {
+                    changed(&mut m.geometry_h, r.height)
+                };
+                OasisRecord::Rectangle {
+                    layer: changed(&mut m.layer, r.layer),
+                    datatype: changed(&mut m.datatype, r.datatype),
+                    width: if square { Some(r.width) } else { width },
+                    height,
+                    square,
+                    x: changed_coord(&mut m.geometry_x, r.x),
+                    y: changed_coord(&mut m.geometry_y, r.y),
+                    repetition: self.encode_repetition(&r.repetition),
+                }
+            }
+            OasisElement::Polygon(p) => {
+                let (origin, points) = split_points(&p.points, "polygon")?;
+                let m = &mut self.modal;
+                OasisRecord::Polygon {
+                    layer: changed(&mut m.layer, p.layer),
+                    datatype: changed(&mut m.datatype, p.datatype),
+                    points: changed(&mut m.polygon_points, points),
+                    x: changed_coord(&mut m.geometry_x, origin.x),
+                    y: changed_coord(&mut m.geometry_y, origin.y),
+                    repetition: self.encode_repetition(&p.repetition),
+                }
+            }
+            OasisElement::Path(p) => {
+                let (origin, points) = split_points(&p.points, "path")?;
+                let m = &mut self.modal;
+                let start = changed(&mut m.path_start_extension, p.start_extension);
+                let end = changed(&mut m.path_end_extension, p.end_extension);
+                OasisRecord::Path {
+                    layer: changed(&mut m.layer, p.layer),
+                    datatype: changed(&mut m.datatype, p.datatype),
+                    half_width: changed(&mut m.path_half_width, p.half_width),
+                    extensions: if start.is_some() || end.is_some() {
+                        Some((start, end))
+                    } else {
+                        None
+                    },
+                    points: changed(&mut m.path_points, points),
+                    x: changed_coord(&mut m.geometry_x, origin.x),
+                    y: changed_coord(&mut m.geometry_y, origin.y),
+                    repetition: self.encode_repetition(&p.repetition),
+                }
+            }
+        })
+    }
+}
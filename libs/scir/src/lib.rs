@@ -1369,6 +1369,12 @@ impl Port {
     pub fn signal(&self) -> SignalId {
         self.signal
     }
+
+    /// The direction of this port.
+    #[inline]
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
 }
 
 impl From<Decimal> for Expr {
@@ -97,6 +97,16 @@ impl SpiceLikeNetlister for NetlisterImpl {
                 self.write_expr(out, value)?;
                 name
             }
+            NetlistPrimitiveDeviceKind::Cap2 { pos, neg, value } => {
+                let name = arcstr::format!("C{}", name);
+                write!(out, "{}", name)?;
+                for port in [pos, neg] {
+                    write!(out, " {}", port)?;
+                }
+                write!(out, " ")?;
+                self.write_expr(out, value)?;
+                name
+            }
             NetlistPrimitiveDeviceKind::RawInstance { ports, cell } => {
                 let name = arcstr::format!("X{}", name);
                 write!(out, "{}", name)?;
@@ -1,6 +1,9 @@
 //! The set of PDK layers.
 #![allow(missing_docs)]
-use substrate::pdk::layers::{LayerFamily, Layers};
+use substrate::layout::lef::{LefLayer, LefRules};
+use substrate::layout::svg::{Color, FillPattern, LayerStyle, LayerStyles};
+use substrate::layout::via::{Enclosure, ViaRules};
+use substrate::pdk::layers::{Layer, LayerFamily, LayerId, Layers};
 
 #[derive(Layers)]
 pub struct Sky130Layers {
@@ -141,6 +144,129 @@ pub struct Sky130Layers {
     pub outline: Outline,
 }
 
+impl Sky130Layers {
+    /// Returns the rules for exporting routing and via layers to LEF.
+    pub fn lef_rules(&self) -> LefRules {
+        LefRules::new()
+            .with_layer(LefLayer::new(self.li1.drawing.id(), "li1"))
+            .with_layer(LefLayer::new(self.mcon.drawing.id(), "mcon"))
+            .with_layer(LefLayer::new(self.met1.drawing.id(), "met1"))
+            .with_layer(LefLayer::new(self.via.drawing.id(), "via"))
+            .with_layer(LefLayer::new(self.met2.drawing.id(), "met2"))
+            .with_layer(LefLayer::new(self.via2.drawing.id(), "via2"))
+            .with_layer(LefLayer::new(self.met3.drawing.id(), "met3"))
+            .with_layer(LefLayer::new(self.via3.drawing.id(), "via3"))
+            .with_layer(LefLayer::new(self.met4.drawing.id(), "met4"))
+            .with_layer(LefLayer::new(self.via4.drawing.id(), "via4"))
+            .with_layer(LefLayer::new(self.met5.drawing.id(), "met5"))
+            .with_boundary(self.pr_boundary.id())
+    }
+
+    /// Returns the styles of layers in layouts rendered to SVG.
+    pub fn layer_styles(&self) -> LayerStyles {
+        use FillPattern::*;
+        let style =
+            |layer: LayerId, r, g, b, fill| LayerStyle::new(layer, Color::rgb(r, g, b), fill);
+        LayerStyles::new()
+            .with_layer(style(self.nwell.drawing.id(), 0x26, 0x8c, 0x6b, Dots))
+            .with_layer(style(self.diff.drawing.id(), 0x00, 0xcc, 0x66, Hatch))
+            .with_layer(style(self.tap.drawing.id(), 0x99, 0x66, 0x00, Hatch))
+            .with_layer(style(self.poly.drawing.id(), 0xff, 0x00, 0x00, BackHatch))
+            .with_layer(style(
+                self.licon1.drawing.id(),
+                0x80,
+                0x00,
+                0x80,
+                CrossHatch,
+            ))
+            .with_layer(style(self.li1.drawing.id(), 0xff, 0xa0, 0xff, Hatch))
+            .with_layer(style(self.mcon.drawing.id(), 0xc0, 0x00, 0xc0, CrossHatch))
+            .with_layer(style(self.met1.drawing.id(), 0x00, 0x80, 0xff, BackHatch))
+            .with_layer(style(self.via.drawing.id(), 0x00, 0x00, 0xff, CrossHatch))
+            .with_layer(style(self.met2.drawing.id(), 0xff, 0x66, 0x00, Hatch))
+            .with_layer(style(self.via2.drawing.id(), 0xc8, 0x64, 0x00, CrossHatch))
+            .with_layer(style(self.met3.drawing.id(), 0x00, 0xc8, 0xc8, BackHatch))
+            .with_layer(style(self.via3.drawing.id(), 0x00, 0x8c, 0x8c, CrossHatch))
+            .with_layer(style(self.met4.drawing.id(), 0x8c, 0x00, 0xc8, Hatch))
+            .with_layer(style(self.via4.drawing.id(), 0x5a, 0x00, 0x82, CrossHatch))
+            .with_layer(style(self.met5.drawing.id(), 0xc8, 0xc8, 0x00, BackHatch))
+            .with_layer(style(self.pr_boundary.id(), 0x80, 0x80, 0x80, Hollow))
+    }
+
+    /// Returns the rules for contacts and vias between adjacent conducting layers.
+    ///
+    /// Dimensions are in nanometers.
+    pub fn via_rules(&self) -> Vec<ViaRules> {
+        let li1 = self.li1.drawing.id();
+        let licon = |bot, bot_enclosure| ViaRules {
+            cut: self.licon1.drawing.id(),
+            bot,
+            top: li1,
+            size: 170,
+            spacing: 170,
+            bot_enclosure,
+            top_enclosure: Enclosure::new(0, 80),
+            grid: 5,
+        };
+
+        vec![
+            licon(self.diff.drawing.id(), Enclosure::new(40, 60)),
+            licon(self.tap.drawing.id(), Enclosure::new(0, 120)),
+            licon(self.poly.drawing.id(), Enclosure::new(50, 80)),
+            ViaRules {
+                cut: self.mcon.drawing.id(),
+                bot: li1,
+                top: self.met1.drawing.id(),
+                size: 170,
+                spacing: 190,
+                bot_enclosure: Enclosure::uniform(0),
+                top_enclosure: Enclosure::new(30, 60),
+                grid: 5,
+            },
+            ViaRules {
+                cut: self.via.drawing.id(),
+                bot: self.met1.drawing.id(),
+                top: self.met2.drawing.id(),
+                size: 150,
+                spacing: 170,
+                bot_enclosure: Enclosure::new(55, 85),
+                top_enclosure: Enclosure::new(55, 85),
+                grid: 5,
+            },
+            ViaRules {
+                cut: self.via2.drawing.id(),
+                bot: self.met2.drawing.id(),
+                top: self.met3.drawing.id(),
+                size: 200,
+                spacing: 200,
+                bot_enclosure: Enclosure::new(40, 85),
+                top_enclosure: Enclosure::uniform(65),
+                grid: 5,
+            },
+            ViaRules {
+                cut: self.via3.drawing.id(),
+                bot: self.met3.drawing.id(),
+                top: self.met4.drawing.id(),
+                size: 200,
+                spacing: 200,
+                bot_enclosure: Enclosure::new(60, 90),
+                top_enclosure: Enclosure::uniform(65),
+                grid: 5,
+            },
+            ViaRules {
+                cut: self.via4.drawing.id(),
+                bot: self.met4.drawing.id(),
+                top: self.met5.drawing.id(),
+                size: 800,
+                spacing: 800,
+                bot_enclosure: Enclosure::uniform(190),
+                top_enclosure: Enclosure::uniform(310),
+                grid: 5,
+            },
+        ]
+    }
+}
+
 #[derive(LayerFamily, Clone, Copy)]
 pub struct Pwell {
     #[layer(gds = "64/44", primary)]
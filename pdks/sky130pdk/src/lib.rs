@@ -8,6 +8,9 @@ use std::path::PathBuf;
 use crate::layers::Sky130Layers;
 use corner::*;
 use rust_decimal_macros::dec;
+use substrate::layout::lef::LefRules;
+use substrate::layout::svg::LayerStyles;
+use substrate::layout::via::ViaRules;
 use substrate::pdk::Pdk;
 
 pub mod corner;
@@ -69,6 +72,18 @@ impl Pdk for Sky130OpenPdk {
             arcstr::literal!("sky130_fd_pr__pfet_20v0"),
         ]
     }
+
+    fn via_rules(&self, layers: &Self::Layers) -> Vec<ViaRules> {
+        layers.via_rules()
+    }
+
+    fn lef_rules(&self, layers: &Self::Layers) -> LefRules {
+        layers.lef_rules()
+    }
+
+    fn layer_styles(&self, layers: &Self::Layers) -> LayerStyles {
+        layers.layer_styles()
+    }
 }
 
 /// The commercial Sky 130 PDK.
@@ -112,4 +127,16 @@ impl Pdk for Sky130CommercialPdk {
             arcstr::literal!("pvhv"),
         ]
     }
+
+    fn via_rules(&self, layers: &Self::Layers) -> Vec<ViaRules> {
+        layers.via_rules()
+    }
+
+    fn lef_rules(&self, layers: &Self::Layers) -> LefRules {
+        layers.lef_rules()
+    }
+
+    fn layer_styles(&self, layers: &Self::Layers) -> LayerStyles {
+        layers.layer_styles()
+    }
 }
@@ -6,10 +6,12 @@ use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
+use ::diagnostics::IssueSet;
 use arcstr::ArcStr;
 use config::Config;
 use examples::get_snippets;
 use indexmap::IndexMap;
+use rust_decimal_macros::dec;
 use scir::TopKind;
 use tracing::{span, Level};
 
@@ -17,17 +19,26 @@ use crate::block::Block;
 use crate::cache::Cache;
 use crate::diagnostics::SourceInfo;
 use crate::error::Result;
-use crate::execute::{Executor, LocalExecutor};
+use crate::execute::{
+    ConcurrencyLimits, Executor, JobSet, LimitedExecutor, LocalExecutor, Task, WorkerPool,
+};
 use crate::io::{
     Flatten, Flipped, HasNameTree, LayoutBundleBuilder, LayoutType, NodeContext, NodePriority,
     Port, SchematicType,
 };
-use crate::layout::element::RawCell;
-use crate::layout::error::{GdsExportError, LayoutError};
+use crate::layout::def::DefImporter;
+use crate::layout::drc::DrcViolation;
+use crate::layout::element::{RawCell, Shape};
+use crate::layout::error::LayoutError;
 use crate::layout::gds::{GdsExporter, GdsImporter, ImportedGds};
+use crate::layout::lef::{LefExporter, LefImporter};
+use crate::layout::parasitics::RcModel;
+use crate::layout::svg::{SvgOptions, SvgRenderer};
+use crate::layout::via::{Via, ViaRules};
 use crate::layout::CellBuilder as LayoutCellBuilder;
 use crate::layout::{Cell as LayoutCell, CellHandle as LayoutCellHandle};
 use crate::layout::{LayoutContext, LayoutImplemented};
+use crate::pdk::layer_map::LayerMap;
 use crate::pdk::layers::GdsLayerSpec;
 use crate::pdk::layers::LayerContext;
 use crate::pdk::layers::LayerId;
@@ -58,6 +69,7 @@ pub struct Context<PDK: Pdk> {
     inner: Arc<RwLock<ContextInner>>,
     simulators: Arc<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
     executor: Arc<dyn Executor>,
+    pool: WorkerPool,
     /// A cache for storing the results of expensive computations.
     pub cache: Cache,
 }
@@ -67,6 +79,8 @@ pub struct ContextBuilder<PDK: Pdk> {
     pdk: Option<PDK>,
     simulators: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
     executor: Arc<dyn Executor>,
+    limits: Option<ConcurrencyLimits>,
+    pool: Option<WorkerPool>,
     cache: Option<Cache>,
 }
 
@@ -83,6 +97,8 @@ impl<PDK: Pdk> ContextBuilder<PDK> {
             pdk: None,
             simulators: Default::default(),
             executor: Arc::new(LocalExecutor),
+            limits: None,
+            pool: None,
             cache: None,
         }
     }
@@ -100,6 +116,22 @@ impl<PDK: Pdk> ContextBuilder<PDK> {
         self
     }
 
+    /// Sets limits on the jobs that may run concurrently.
+    ///
+    /// The limits are shared by all simulations run using the built context.
+    pub fn concurrency_limits(mut self, limits: ConcurrencyLimits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /// Sets the pool of threads on which background jobs and simulations run.
+    ///
+    /// Defaults to the [global](WorkerPool::global) pool.
+    pub fn worker_pool(mut self, pool: WorkerPool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
     /// Installs the given simulator.
     ///
     /// Only one simulator of any given type can exist.
@@ -127,12 +159,18 @@ impl<PDK: Pdk> ContextBuilder<PDK> {
 
         let cfg = Config::default().expect("requires valid Substrate configuration");
 
+        let executor: Arc<dyn Executor> = match self.limits {
+            Some(limits) => Arc::new(LimitedExecutor::new(self.executor, limits)),
+            None => self.executor,
+        };
+
         Context {
             pdk: Arc::new(self.pdk.unwrap()),
             layers,
             inner: Arc::new(RwLock::new(ContextInner::new(layer_ctx))),
             simulators: Arc::new(self.simulators),
-            executor: self.executor,
+            executor,
+            pool: self.pool.unwrap_or_else(WorkerPool::global),
             cache: self.cache.unwrap_or_else(|| {
                 Cache::new(
                     cfg.cache
@@ -152,6 +190,7 @@ impl<PDK: Pdk> Clone for Context<PDK> {
             inner: self.inner.clone(),
             simulators: self.simulators.clone(),
             executor: self.executor.clone(),
+            pool: self.pool.clone(),
             cache: self.cache.clone(),
         }
     }
@@ -230,11 +269,106 @@ impl<PDK: Pdk> Context<PDK> {
 
         let inner = self.inner.read().unwrap();
         GdsExporter::new(cell.raw.clone(), &inner.layers)
+            .export_to_file(path)
+            .map_err(LayoutError::from)?;
+        Ok(())
+    }
+
+    /// Writes a layout to a GDS file, using the GDS layers given by a layer map.
+    ///
+    /// See [`GdsExporter::with_layer_map`].
+    pub fn write_layout_with_layer_map<T: LayoutImplemented<PDK>>(
+        &self,
+        block: T,
+        path: impl AsRef<Path>,
+        map: &LayerMap,
+    ) -> Result<()> {
+        let handle = self.generate_layout(block);
+        let cell = handle.try_cell()?;
+
+        let inner = self.inner.read().unwrap();
+        GdsExporter::new(cell.raw.clone(), &inner.layers)
+            .with_layer_map(map)
+            .export_to_file(path)
+            .map_err(LayoutError::from)?;
+        Ok(())
+    }
+
+    /// Writes a layout to an OASIS file.
+    pub fn write_layout_oasis<T: LayoutImplemented<PDK>>(
+        &self,
+        block: T,
+        path: impl AsRef<Path>,
+    ) -> Result<()> {
+        let handle = self.generate_layout(block);
+        let cell = handle.try_cell()?;
+
+        let inner = self.inner.read().unwrap();
+        let lib = GdsExporter::new(cell.raw.clone(), &inner.layers)
             .export()
-            .map_err(LayoutError::from)?
-            .save(path)
-            .map_err(GdsExportError::from)
             .map_err(LayoutError::from)?;
+        oasis::OasisLibrary::from_gds(&lib)?.save(path)?;
+        Ok(())
+    }
+
+    /// Writes a LEF abstract of the layout of `block` using the PDK's LEF rules.
+    ///
+    /// See [`LefExporter`].
+    pub fn write_lef<T: LayoutImplemented<PDK>>(
+        &self,
+        block: T,
+        path: impl AsRef<Path>,
+    ) -> Result<()> {
+        let handle = self.generate_layout(block);
+        let cell = handle.try_cell()?;
+        let io = cell.block().io();
+        let directions = HashMap::from_iter(io.flat_names(None).into_iter().zip(io.flatten_vec()));
+        let rules = self.pdk.lef_rules(&self.layers);
+
+        let inner = self.inner.read().unwrap();
+        let lef = LefExporter::new(
+            cell.raw.clone(),
+            directions,
+            &inner.layers,
+            &rules,
+            PDK::LAYOUT_DB_UNITS.unwrap_or(dec!(1e-9)),
+        )
+        .export()?;
+        let path = path.as_ref();
+        if let Some(prefix) = path.parent() {
+            std::fs::create_dir_all(prefix).map_err(Arc::new)?;
+        }
+        std::fs::write(path, lef).map_err(Arc::new)?;
+        Ok(())
+    }
+
+    /// Renders the layout of `block` to an SVG file using the PDK's layer styles.
+    ///
+    /// See [`SvgRenderer`].
+    pub fn write_svg<T: LayoutImplemented<PDK>>(
+        &self,
+        block: T,
+        path: impl AsRef<Path>,
+        options: &SvgOptions,
+    ) -> Result<()> {
+        let handle = self.generate_layout(block);
+        let cell = handle.try_cell()?;
+        let styles = self.pdk.layer_styles(&self.layers);
+
+        let inner = self.inner.read().unwrap();
+        let svg = SvgRenderer::new(
+            cell.raw.clone(),
+            &inner.layers,
+            &styles,
+            options,
+            PDK::LAYOUT_DB_UNITS.unwrap_or(dec!(1e-9)),
+        )
+        .render();
+        let path = path.as_ref();
+        if let Some(prefix) = path.parent() {
+            std::fs::create_dir_all(prefix).map_err(Arc::new)?;
+        }
+        std::fs::write(path, svg).map_err(Arc::new)?;
         Ok(())
     }
 
@@ -251,6 +385,45 @@ impl<PDK: Pdk> Context<PDK> {
         Ok(imported)
     }
 
+    /// Reads a layout from a GDS file, using the layer names given by a layer map.
+    ///
+    /// See [`GdsImporter::with_layer_map`].
+    pub fn read_gds_with_layer_map(
+        &self,
+        path: impl AsRef<Path>,
+        map: &LayerMap,
+    ) -> Result<ImportedGds> {
+        let lib = gds::GdsLibrary::load(path)?;
+        let mut inner = self.inner.write().unwrap();
+        let ContextInner {
+            ref mut layers,
+            ref mut layout,
+            ..
+        } = *inner;
+        let imported = GdsImporter::new(&lib, layout, layers, PDK::LAYOUT_DB_UNITS)
+            .with_layer_map(map)
+            .import()?;
+        Ok(imported)
+    }
+
+    /// Reads a layout from an OASIS file.
+    pub fn read_oasis(&self, path: impl AsRef<Path>) -> Result<ImportedGds> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let lib = oasis::OasisLibrary::load(path)?.to_gds(name)?;
+        let mut inner = self.inner.write().unwrap();
+        let ContextInner {
+            ref mut layers,
+            ref mut layout,
+            ..
+        } = *inner;
+        let imported = GdsImporter::new(&lib, layout, layers, PDK::LAYOUT_DB_UNITS).import()?;
+        Ok(imported)
+    }
+
     /// Reads the layout of a single cell from a GDS file.
     pub fn read_gds_cell(
         &self,
@@ -269,6 +442,65 @@ impl<PDK: Pdk> Context<PDK> {
         Ok(imported)
     }
 
+    /// Reads the layout of a macro from a set of LEF files.
+    ///
+    /// The files are combined, so the macro may use vias defined in a technology LEF.
+    /// See [`LefImporter`].
+    pub fn read_lef_cell(
+        &self,
+        paths: impl IntoIterator<Item = impl AsRef<Path>>,
+        cell: impl Into<ArcStr>,
+    ) -> Result<Arc<RawCell>> {
+        let lef = load_lefs(paths)?;
+        let rules = self.pdk.lef_rules(&self.layers);
+        let mut inner = self.inner.write().unwrap();
+        let ContextInner {
+            ref layers,
+            ref mut layout,
+            ..
+        } = *inner;
+        LefImporter::new(
+            &lef,
+            layout,
+            layers,
+            &rules,
+            PDK::LAYOUT_DB_UNITS.unwrap_or(dec!(1e-9)),
+        )
+        .import_macro(&cell.into())
+    }
+
+    /// Reads the layout of a placed and routed design from a DEF file.
+    ///
+    /// The macros, vias, and routing layers referenced by the design are read from
+    /// the given LEF files. Returns an error if the design is not named `cell`.
+    /// See [`DefImporter`].
+    pub fn read_def_cell(
+        &self,
+        path: impl AsRef<Path>,
+        lef_paths: impl IntoIterator<Item = impl AsRef<Path>>,
+        cell: impl Into<ArcStr>,
+    ) -> Result<Arc<RawCell>> {
+        let cell = cell.into();
+        let def = lefdef::DefDesign::load(path)?;
+        if def.name != cell {
+            return Err(LayoutError::LefDefImport(arcstr::format!(
+                "design not found in DEF file: {cell}"
+            ))
+            .into());
+        }
+        let lef = load_lefs(lef_paths)?;
+        let rules = self.pdk.lef_rules(&self.layers);
+        let db_units = PDK::LAYOUT_DB_UNITS.unwrap_or(dec!(1e-9));
+        let mut inner = self.inner.write().unwrap();
+        let ContextInner {
+            ref layers,
+            ref mut layout,
+            ..
+        } = *inner;
+        let lef = LefImporter::new(&lef, layout, layers, &rules, db_units);
+        DefImporter::new(&def, lef, db_units)?.import()
+    }
+
     /// Generates a schematic for `block` in the background.
     ///
     /// Returns a handle to the cell being generated.
@@ -362,6 +594,17 @@ impl<PDK: Pdk> Context<PDK> {
         cell.raw.to_scir_lib(TopKind::Testbench)
     }
 
+    /// Installs the layers of a layer map, returning the layer ID of each entry.
+    ///
+    /// Entries named after an installed layer refer to that layer. Otherwise, an entry
+    /// whose GDS layer is installed becomes an alias of that layer, so that it can be
+    /// looked up by name with [`Context::get_layer_by_name`] and used in other layer
+    /// maps. The remaining entries define new layers.
+    pub fn install_layer_map(&self, map: &LayerMap) -> Vec<LayerId> {
+        let mut inner = self.inner.write().unwrap();
+        inner.layers.install_layer_map(map)
+    }
+
     /// Installs a new layer set in the context.
     ///
     /// Allows for accessing GDS layers or other extra layers that are not present in the PDK.
@@ -379,33 +622,195 @@ impl<PDK: Pdk> Context<PDK> {
         inner.layers.get_gds_layer(spec)
     }
 
+    /// Gets a layer by its name.
+    ///
+    /// If multiple layers share the name, one of them is returned arbitrarily.
+    pub fn get_layer_by_name(&self, name: &str) -> Option<LayerId> {
+        let inner = self.inner.read().unwrap();
+        inner.layers.get_layer_by_name(name)
+    }
+
+    /// Checks the layout of `block` against the PDK's design rules.
+    pub fn drc<T: LayoutImplemented<PDK>>(&self, block: T) -> Result<IssueSet<DrcViolation>> {
+        let handle = self.generate_layout(block);
+        let cell = handle.try_cell()?;
+        Ok(self.pdk.drc_rules(&self.layers).check(&cell.raw))
+    }
+
+    /// Extracts a netlist from the layout of `block` using the PDK's connectivity rules.
+    pub fn extract_scir<T: LayoutImplemented<PDK>>(&self, block: T) -> Result<scir::Library> {
+        let handle = self.generate_layout(block);
+        let cell = handle.try_cell()?;
+        self.pdk
+            .connectivity(&self.layers)
+            .extract(&cell.raw)
+            .to_scir()
+    }
+
+    /// Extracts a netlist with estimated parasitics from the layout of `block`
+    /// using the PDK's connectivity rules and parasitic parameters.
+    ///
+    /// See [`Parasitics::annotate`](crate::layout::parasitics::Parasitics::annotate).
+    pub fn extract_parasitics<T: LayoutImplemented<PDK>>(
+        &self,
+        block: T,
+        model: RcModel,
+        ground: impl Into<ArcStr>,
+    ) -> Result<scir::Library> {
+        let handle = self.generate_layout(block);
+        let cell = handle.try_cell()?;
+        cell.extract_parasitics(
+            &self.pdk.connectivity(&self.layers),
+            &self.pdk.parasitics(&self.layers),
+            model,
+            ground,
+        )
+    }
+
+    /// Returns the PDK's rules for vias between the given layers, if any.
+    pub fn via_rules(&self, bot: LayerId, top: LayerId) -> Option<ViaRules> {
+        self.pdk
+            .via_rules(&self.layers)
+            .into_iter()
+            .find(|rules| rules.bot == bot && rules.top == top)
+    }
+
+    /// Creates a via connecting two overlapping rectangles using the PDK's via rules.
+    ///
+    /// Returns an error if either shape is not a rectangle, or if the PDK has no rules
+    /// for vias between the layers of the two shapes.
+    pub fn via(&self, bot: &Shape, top: &Shape) -> Result<Via> {
+        let (geometry::shape::Shape::Rect(bot_rect), geometry::shape::Shape::Rect(top_rect)) =
+            (bot.shape(), top.shape())
+        else {
+            return Err(LayoutError::Via(arcstr::literal!("via shapes must be rectangles")).into());
+        };
+        let rules = self.via_rules(bot.layer(), top.layer()).ok_or_else(|| {
+            LayoutError::Via(arcstr::literal!("no via rules between the given layers"))
+        })?;
+        Ok(Via::new(rules, *bot_rect, *top_rect))
+    }
+
+    /// Creates an empty set of background jobs that run on this context's executor.
+    ///
+    /// Jobs in the set are subject to the context's concurrency limits.
+    pub fn jobs(&self) -> JobSet {
+        JobSet::with_pool(self.executor.clone(), self.pool.clone())
+    }
+
     /// Simulate the given testbench.
     pub fn simulate<S, T>(&self, block: T, work_dir: impl Into<PathBuf>) -> Result<T::Output>
     where
         S: Simulator,
         T: Testbench<PDK, S>,
     {
-        let simulator = self.get_simulator::<S>();
         let block = Arc::new(block);
         let cell = self.generate_testbench_schematic(block.clone());
         // TODO: Handle errors.
         let cell = cell.cell();
         let lib = self.export_testbench_scir_for_cell(cell)?;
+        Ok(self.run_testbench(block, cell, lib, work_dir.into()))
+    }
+
+    /// Simulates the given testbench in the background, returning a handle to its output.
+    ///
+    /// Allows several testbenches to be simulated at once. Simulations run on the context's
+    /// worker pool, and are subject to the context's concurrency limits.
+    pub fn submit_simulation<S, T>(
+        &self,
+        block: T,
+        work_dir: impl Into<PathBuf>,
+    ) -> Task<Result<T::Output>>
+    where
+        S: Simulator,
+        T: Testbench<PDK, S>,
+        T::Output: Send,
+    {
+        let ctx = self.clone();
+        let work_dir = work_dir.into();
+        self.pool
+            .spawn(move || ctx.simulate::<S, T>(block, work_dir))
+    }
+
+    /// Simulates the given testbench using a netlist extracted from the layout of `dut`,
+    /// with estimated parasitics, in place of the schematic of `dut`.
+    ///
+    /// The netlist is extracted using the PDK's connectivity rules and parasitic parameters,
+    /// as in [`Context::extract_parasitics`]. It must expose the same ports as the
+    /// schematic of `dut`, which must include the net named `ground`.
+    /// Every instance of `dut` in the testbench uses the extracted netlist,
+    /// so nodes inside `dut` cannot be saved.
+    ///
+    /// Returns an error if `dut` is not instantiated in the testbench, or if it is flattened.
+    pub fn simulate_extracted<S, T, D>(
+        &self,
+        block: T,
+        dut: D,
+        model: RcModel,
+        ground: impl Into<ArcStr>,
+        work_dir: impl Into<PathBuf>,
+    ) -> Result<T::Output>
+    where
+        S: Simulator,
+        T: Testbench<PDK, S>,
+        D: Schematic<PDK> + LayoutImplemented<PDK> + Clone,
+    {
+        let block = Arc::new(block);
+        let cell = self.generate_testbench_schematic(block.clone());
+        let cell = cell.try_cell()?;
+        let mut lib = self.export_testbench_scir_for_cell(cell)?;
+
+        let schematic = self.generate_schematic(dut.clone());
+        let id = lib
+            .scir_cell_id(&schematic.try_cell()?.raw)
+            .ok_or_else(|| {
+                LayoutError::Extraction(arcstr::literal!(
+                    "device under test is not a cell of the testbench"
+                ))
+            })?;
+        let layout = self.generate_layout(dut);
+        let extracted = layout
+            .try_cell()?
+            .extract(&self.pdk.connectivity(&self.layers));
+        let annotated = self.pdk.parasitics(&self.layers).annotate_as(
+            &extracted,
+            model,
+            ground,
+            lib.scir.cell(id),
+        )?;
+        lib.replace_cell(id, annotated)?;
+
+        Ok(self.run_testbench(block, cell, lib, work_dir.into()))
+    }
+
+    fn run_testbench<S, T>(
+        &self,
+        block: Arc<T>,
+        cell: &SchematicCell<T>,
+        lib: RawLib,
+        work_dir: PathBuf,
+    ) -> T::Output
+    where
+        S: Simulator,
+        T: Testbench<PDK, S>,
+    {
+        let simulator = self.get_simulator::<S>();
         let ctx = SimulationContext {
             lib: Arc::new(lib),
-            work_dir: work_dir.into(),
+            work_dir,
             executor: self.executor.clone(),
             cache: self.cache.clone(),
         };
         let controller = SimController {
             pdk: self.pdk.clone(),
-            tb: (*cell).clone(),
+            tb: cell.clone(),
             simulator,
             ctx,
+            pool: self.pool.clone(),
         };
 
         // TODO caching
-        Ok(block.run(controller))
+        block.run(controller)
     }
 
     fn get_simulator<S: Simulator>(&self) -> Arc<S> {
@@ -425,6 +830,15 @@ impl ContextInner {
     }
 }
 
+/// Loads and combines a set of LEF files.
+fn load_lefs(paths: impl IntoIterator<Item = impl AsRef<Path>>) -> Result<lefdef::LefLibrary> {
+    let mut lef = lefdef::LefLibrary::new();
+    for path in paths {
+        lef.merge(lefdef::LefLibrary::load(path)?);
+    }
+    Ok(lef)
+}
+
 fn prepare_cell_builder<PDK: Pdk, T: Block>(
     id: crate::schematic::CellId,
     context: Context<PDK>,
@@ -1,10 +1,12 @@
 //! Error types and error handling utilities.
 
-use std::process::Command;
 use std::sync::Arc;
 
 use gds::GdsError;
+use lefdef::LefDefError;
+use oasis::OasisError;
 
+use crate::execute::ExecutionFailure;
 use crate::layout::error::{GdsImportError, LayoutError};
 
 /// A result type returning Substrate errors.
@@ -28,15 +30,24 @@ pub enum Error {
     /// An error thrown when a thread spawned during generation panics.
     #[error("a thread panicked")]
     Panic,
-    /// Executing a command failed.
-    #[error("error executing command: {0:?}")]
-    CommandFailed(Arc<Command>),
+    /// A job submitted to an [`Executor`](crate::execute::Executor) did not complete successfully.
+    #[error("execution failed: {0}")]
+    ExecutionFailed(Box<ExecutionFailure>),
+    /// A job was cancelled before it completed.
+    #[error("job was cancelled")]
+    Cancelled,
     /// GDS error.
     #[error("gds error: {0}")]
     Gds(#[from] GdsError),
     /// Error importing GDS.
     #[error("error importing GDS: {0}")]
     GdsImport(#[from] GdsImportError),
+    /// OASIS error.
+    #[error("oasis error: {0}")]
+    Oasis(#[from] OasisError),
+    /// LEF or DEF parsing error.
+    #[error("LEF/DEF error: {0}")]
+    LefDef(#[from] LefDefError),
     /// An arbitrary error for external use.
     #[error(transparent)]
     Boxed(#[from] Arc<dyn std::error::Error + Send + Sync>),
@@ -46,6 +57,10 @@ pub enum Error {
     /// Schematic to SCIR conversion produced errors.
     #[error("error converting to SCIR: {0}")]
     ScirConversion(Box<scir::Issues>),
+    /// A [`SaveSpec`](crate::simulation::data::SaveSpec) referred to a path that does not
+    /// exist in the simulated library.
+    #[error("save specification refers to an invalid path")]
+    InvalidSaveSpec,
 }
 
 impl From<LayoutError> for Error {
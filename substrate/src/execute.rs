@@ -1,12 +1,27 @@
 //! Executor (e.g. LSF, Slurm) API.
 
 use std::any::Any;
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
-use std::sync::Arc;
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::io::{Read, Seek, SeekFrom};
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
 
 use arcstr::ArcStr;
 use derive_builder::Builder;
+use indexmap::IndexMap;
+use tracing::Level;
+
+/// The number of lines of a failed job's log file to include in an [`ExecutionFailure`].
+const LOG_TAIL_LINES: usize = 20;
+
+/// The interval at which jobs with a timeout are polled for completion.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 /// Job submission options.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -17,6 +32,22 @@ pub struct ExecOpts {
     pub machines: usize,
     /// Where to place logs.
     pub logs: LogOutput,
+    /// Maximum wall-clock time a single attempt may run for.
+    ///
+    /// Attempts that exceed this limit are killed along with all of their child processes.
+    pub timeout: Option<Duration>,
+    /// Maximum memory a job may use, in megabytes.
+    pub memory: Option<u64>,
+    /// Number of times to retry a failed job.
+    pub retries: usize,
+    /// Delay before the first retry.
+    ///
+    /// The delay doubles after each subsequent failure.
+    pub backoff: Duration,
+    /// Number of license tokens of each kind required by the job.
+    ///
+    /// Only enforced by executors wrapped in a [`LimitedExecutor`].
+    pub licenses: IndexMap<ArcStr, usize>,
 }
 
 impl Default for ExecOpts {
@@ -26,10 +57,55 @@ impl Default for ExecOpts {
             cpus: None,
             machines: 1,
             logs: LogOutput::Stdio,
+            timeout: None,
+            memory: None,
+            retries: 0,
+            backoff: Duration::from_secs(1),
+            licenses: IndexMap::new(),
         }
     }
 }
 
+/// Information about a job that did not complete successfully.
+#[derive(Clone, Debug)]
+pub struct ExecutionFailure {
+    /// A description of the command that was executed.
+    pub command: String,
+    /// The exit status of the last attempt.
+    ///
+    /// [`None`] if the last attempt was killed after exceeding its timeout.
+    pub status: Option<ExitStatus>,
+    /// The number of attempts made.
+    pub attempts: usize,
+    /// The last lines of the log file written by the last attempt.
+    ///
+    /// [`None`] if logs were not written to a file.
+    pub log_tail: Option<String>,
+}
+
+impl ExecutionFailure {
+    /// Returns `true` if the last attempt was killed after exceeding its timeout.
+    #[inline]
+    pub fn timed_out(&self) -> bool {
+        self.status.is_none()
+    }
+}
+
+impl Display for ExecutionFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "command {} ", self.command)?;
+        match self.status {
+            Some(status) => write!(f, "failed with {status}")?,
+            None => write!(f, "timed out")?,
+        }
+        write!(f, " after {} attempt(s)", self.attempts)?;
+        if let Some(ref tail) = self.log_tail {
+            write!(f, "; log tail:\n{tail}")?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Default)]
 /// Where to place logs generated by a job.
 pub enum LogOutput {
@@ -40,10 +116,78 @@ pub enum LogOutput {
     File(PathBuf),
 }
 
+impl LogOutput {
+    /// Returns the path of the log file, if logs are saved to a file.
+    fn file(&self) -> Option<&Path> {
+        match self {
+            Self::File(path) => Some(path),
+            Self::Stdio => None,
+        }
+    }
+}
+
+/// A token used to cancel running jobs.
+///
+/// Clones of a token share the same cancellation state.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Creates a new token that has not been cancelled.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels all jobs associated with this token.
+    #[inline]
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if this token has been cancelled.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
 /// A job executor.
 pub trait Executor: Any + Send + Sync {
     /// Execute the given command with the given options, waiting until the command completes.
     fn execute(&self, command: Command, opts: ExecOpts) -> Result<(), crate::error::Error>;
+
+    /// Execute the given command with the given options, waiting until the command completes
+    /// or `token` is cancelled.
+    ///
+    /// Returns [`Error::Cancelled`](crate::error::Error::Cancelled) if the command was
+    /// killed due to cancellation.
+    ///
+    /// The default implementation ignores `token` and calls [`Executor::execute`].
+    fn execute_cancellable(
+        &self,
+        command: Command,
+        opts: ExecOpts,
+        token: &CancelToken,
+    ) -> Result<(), crate::error::Error> {
+        let _ = token;
+        self.execute(command, opts)
+    }
+}
+
+impl<E: Executor + ?Sized> Executor for Arc<E> {
+    fn execute(&self, command: Command, opts: ExecOpts) -> Result<(), crate::error::Error> {
+        (**self).execute(command, opts)
+    }
+
+    fn execute_cancellable(
+        &self,
+        command: Command,
+        opts: ExecOpts,
+        token: &CancelToken,
+    ) -> Result<(), crate::error::Error> {
+        (**self).execute_cancellable(command, opts, token)
+    }
 }
 
 /// Executes commands locally.
@@ -51,22 +195,208 @@ pub trait Executor: Any + Send + Sync {
 pub struct LocalExecutor;
 
 impl Executor for LocalExecutor {
-    fn execute(&self, mut command: Command, opts: ExecOpts) -> Result<(), crate::error::Error> {
-        if let LogOutput::File(ref path) = opts.logs {
+    fn execute(&self, command: Command, opts: ExecOpts) -> Result<(), crate::error::Error> {
+        self.run(command, opts, None)
+    }
+
+    fn execute_cancellable(
+        &self,
+        command: Command,
+        opts: ExecOpts,
+        token: &CancelToken,
+    ) -> Result<(), crate::error::Error> {
+        self.run(command, opts, Some(token))
+    }
+}
+
+impl LocalExecutor {
+    fn run(
+        &self,
+        mut command: Command,
+        opts: ExecOpts,
+        token: Option<&CancelToken>,
+    ) -> Result<(), crate::error::Error> {
+        #[cfg(unix)]
+        if let Some(memory) = opts.memory {
+            use std::os::unix::process::CommandExt;
+            let bytes = memory.saturating_mul(1 << 20) as libc::rlim_t;
+            let limit = libc::rlimit {
+                rlim_cur: bytes,
+                rlim_max: bytes,
+            };
+            // SAFETY: `setrlimit` is async-signal-safe and only affects the child process.
+            unsafe {
+                command.pre_exec(move || {
+                    if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+        }
+
+        run_with_retries(&mut command, opts.logs.file(), &opts, token)
+    }
+}
+
+/// Cancels the job of a submission process that was killed.
+///
+/// Called with the killed process, whose standard output is piped.
+type CancelJob<'a> = &'a dyn Fn(&mut Child) -> std::io::Result<()>;
+
+/// Runs the given command until it succeeds or the retry limit in `opts` is reached.
+///
+/// If `logs` is provided, the standard output and standard error of each attempt
+/// are written to the given file. If `token` is provided, the running attempt is killed
+/// and no further attempts are made once the token is cancelled.
+fn run_with_retries(
+    command: &mut Command,
+    logs: Option<&Path>,
+    opts: &ExecOpts,
+    token: Option<&CancelToken>,
+) -> Result<(), crate::error::Error> {
+    run_job_with_retries(command, logs, opts, token, None)
+}
+
+/// Runs the given submission command like [`run_with_retries`],
+/// calling `cancel_job` after an attempt is killed due to a timeout or cancellation.
+///
+/// If `cancel_job` is provided, the standard output of each attempt is piped
+/// rather than written to `logs`.
+fn run_job_with_retries(
+    command: &mut Command,
+    logs: Option<&Path>,
+    opts: &ExecOpts,
+    token: Option<&CancelToken>,
+    cancel_job: Option<CancelJob<'_>>,
+) -> Result<(), crate::error::Error> {
+    // Placing the job in its own process group allows it to be killed
+    // along with all of its children when it times out or is cancelled.
+    #[cfg(unix)]
+    if opts.timeout.is_some() || token.is_some() {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    let mut backoff = opts.backoff;
+    let mut attempts = 0;
+    loop {
+        if token.map(|token| token.is_cancelled()).unwrap_or(false) {
+            return Err(crate::error::Error::Cancelled);
+        }
+
+        attempts += 1;
+        if let Some(path) = logs {
             let fout = std::fs::File::create(path).map_err(Arc::new)?;
             let ferr = fout.try_clone().map_err(Arc::new)?;
             command.stdout(Stdio::from(fout)).stderr(Stdio::from(ferr));
         }
+        if cancel_job.is_some() {
+            command.stdout(Stdio::piped());
+        }
 
-        let status = command.status().map_err(Arc::new)?;
-        if !status.success() {
-            return Err(crate::error::Error::CommandFailed(Arc::new(command)));
+        let mut child = command.spawn().map_err(Arc::new)?;
+        let outcome = wait_for_child(&mut child, opts.timeout, token).map_err(Arc::new)?;
+        if let (WaitOutcome::TimedOut | WaitOutcome::Cancelled, Some(cancel_job)) =
+            (&outcome, cancel_job)
+        {
+            cancel_job(&mut child).map_err(Arc::new)?;
         }
+        let status = match outcome {
+            WaitOutcome::Exited(status) if status.success() => return Ok(()),
+            WaitOutcome::Exited(status) => Some(status),
+            WaitOutcome::TimedOut => None,
+            WaitOutcome::Cancelled => return Err(crate::error::Error::Cancelled),
+        };
 
-        Ok(())
+        let failure = ExecutionFailure {
+            command: format!("{command:?}"),
+            status,
+            attempts,
+            log_tail: logs.and_then(|path| log_tail(path, LOG_TAIL_LINES).ok()),
+        };
+        if attempts > opts.retries {
+            return Err(crate::error::Error::ExecutionFailed(Box::new(failure)));
+        }
+        tracing::event!(Level::WARN, "{failure}; retrying in {backoff:?}");
+        std::thread::sleep(backoff);
+        backoff *= 2;
+    }
+}
+
+/// The outcome of waiting for a child process.
+enum WaitOutcome {
+    /// The child exited on its own.
+    Exited(ExitStatus),
+    /// The child was killed after exceeding its timeout.
+    TimedOut,
+    /// The child was killed because its job was cancelled.
+    Cancelled,
+}
+
+/// Waits for the given child process to exit.
+///
+/// The child is killed if it exceeds `timeout` or if `token` is cancelled.
+fn wait_for_child(
+    child: &mut Child,
+    timeout: Option<Duration>,
+    token: Option<&CancelToken>,
+) -> std::io::Result<WaitOutcome> {
+    if timeout.is_none() && token.is_none() {
+        return child.wait().map(WaitOutcome::Exited);
+    }
+
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(WaitOutcome::Exited(status));
+        }
+        let outcome = if token.map(|token| token.is_cancelled()).unwrap_or(false) {
+            Some(WaitOutcome::Cancelled)
+        } else if deadline.map(|d| Instant::now() >= d).unwrap_or(false) {
+            Some(WaitOutcome::TimedOut)
+        } else {
+            None
+        };
+        if let Some(outcome) = outcome {
+            kill_process_group(child)?;
+            child.wait()?;
+            return Ok(outcome);
+        }
+        let remaining = deadline
+            .map(|d| d.saturating_duration_since(Instant::now()))
+            .unwrap_or(POLL_INTERVAL);
+        std::thread::sleep(POLL_INTERVAL.min(remaining));
     }
 }
 
+/// Kills the given child process and all other processes in its process group.
+fn kill_process_group(child: &mut Child) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        // SAFETY: `killpg` has no memory safety requirements.
+        if unsafe { libc::killpg(child.id() as libc::pid_t, libc::SIGKILL) } == 0 {
+            return Ok(());
+        }
+    }
+    child.kill()
+}
+
+/// Reads the last `lines` lines of the file at the given path.
+fn log_tail(path: &Path, lines: usize) -> std::io::Result<String> {
+    // Only read the end of the file, since logs may be very large.
+    const MAX_TAIL_BYTES: u64 = 1 << 16;
+
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(MAX_TAIL_BYTES)))?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+    let contents = String::from_utf8_lossy(&buf);
+    let tail = contents.lines().collect::<Vec<_>>();
+    Ok(tail[tail.len().saturating_sub(lines)..].join("\n"))
+}
+
 /// An executor for submitting jobs to an LSF cluster.
 #[derive(Clone, Debug, Eq, PartialEq, Builder)]
 pub struct LsfExecutor {
@@ -106,6 +436,18 @@ impl LsfExecutor {
         if let Some(cpus) = opts.cpus {
             submit.arg("-n").arg(cpus.to_string());
         }
+        if let Some(timeout) = opts.timeout {
+            // LSF run limits are specified in minutes.
+            let minutes = (timeout.as_secs() + 59) / 60;
+            submit.arg("-W").arg(minutes.max(1).to_string());
+        }
+        if let Some(memory) = opts.memory {
+            submit.arg("-M").arg(format!("{memory}MB"));
+        }
+        if let Some(path) = opts.logs.file() {
+            // LSF appends to the output file after the output of `bsub` itself.
+            submit.arg("-o").arg(path).arg("-e").arg(path);
+        }
         submit.arg(command.get_program());
         for arg in command.get_args() {
             submit.arg(arg);
@@ -127,13 +469,769 @@ impl LsfExecutor {
 
 impl Executor for LsfExecutor {
     fn execute(&self, command: Command, opts: ExecOpts) -> Result<(), crate::error::Error> {
-        let mut submit = self.command(&command, opts);
+        let mut submit = self.command(&command, opts.clone());
+        run_with_retries(&mut submit, opts.logs.file(), &opts, None)
+    }
+
+    fn execute_cancellable(
+        &self,
+        command: Command,
+        opts: ExecOpts,
+        token: &CancelToken,
+    ) -> Result<(), crate::error::Error> {
+        let mut submit = self.command(&command, opts.clone());
+        run_with_retries(&mut submit, opts.logs.file(), &opts, Some(token))
+    }
+}
+
+/// The Slurm command used to submit jobs.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum SlurmSubmit {
+    /// Run jobs using `srun`, which blocks until the job completes.
+    #[default]
+    Srun,
+    /// Submit jobs using `sbatch --wait`, which blocks until the job completes.
+    ///
+    /// The command is passed to `sbatch` using `--wrap`.
+    /// Jobs that time out or are cancelled are cancelled on the cluster using `scancel`.
+    Sbatch,
+}
+
+/// An executor for submitting jobs to a Slurm cluster.
+#[derive(Clone, Debug, Eq, PartialEq, Builder)]
+pub struct SlurmExecutor {
+    /// The command to use to submit jobs.
+    ///
+    /// Defaults to `srun` or `sbatch` depending on `submit`.
+    #[builder(setter(into, strip_option), default)]
+    command: Option<ArcStr>,
+    /// The Slurm command used to submit jobs.
+    #[builder(default)]
+    submit: SlurmSubmit,
+    /// The partition to which jobs should be submitted.
+    #[builder(setter(into, strip_option), default)]
+    partition: Option<ArcStr>,
+    /// The account to which jobs should be charged.
+    #[builder(setter(into, strip_option), default)]
+    account: Option<ArcStr>,
+    /// Extra arguments to pass to the submission command.
+    #[builder(setter(each(name = "arg", into)), default)]
+    extra_args: Vec<ArcStr>,
+    /// The command to use to cancel jobs submitted with `sbatch`.
+    ///
+    /// Defaults to `scancel`.
+    #[builder(setter(into, strip_option), default)]
+    scancel: Option<ArcStr>,
+}
+
+impl Default for SlurmExecutor {
+    fn default() -> Self {
+        Self {
+            command: None,
+            submit: SlurmSubmit::Srun,
+            partition: None,
+            account: None,
+            extra_args: Vec::new(),
+            scancel: None,
+        }
+    }
+}
+
+impl SlurmExecutor {
+    /// A builder for constructing a [`SlurmExecutor`].
+    #[inline]
+    pub fn builder() -> SlurmExecutorBuilder {
+        SlurmExecutorBuilder::default()
+    }
+
+    /// Gets the Slurm submission command.
+    pub fn command(&self, command: &Command, opts: ExecOpts) -> Command {
+        let program = match (&self.command, self.submit) {
+            (Some(program), _) => program.as_str(),
+            (None, SlurmSubmit::Srun) => "srun",
+            (None, SlurmSubmit::Sbatch) => "sbatch",
+        };
+        let mut submit = Command::new(program);
+
+        if self.submit == SlurmSubmit::Sbatch {
+            // --parsable prints the job ID, which is needed to cancel the job.
+            submit.arg("--wait").arg("--parsable");
+        }
+        if let Some(ref partition) = self.partition {
+            submit.arg(format!("--partition={partition}"));
+        }
+        if let Some(ref account) = self.account {
+            submit.arg(format!("--account={account}"));
+        }
+        if opts.machines > 1 {
+            submit.arg(format!("--nodes={}", opts.machines));
+        }
+        if let Some(cpus) = opts.cpus {
+            submit.arg(format!("--cpus-per-task={cpus}"));
+        }
+        if let Some(memory) = opts.memory {
+            submit.arg(format!("--mem={memory}M"));
+        }
+        if let Some(timeout) = opts.timeout {
+            // Slurm time limits are specified in minutes.
+            let minutes = (timeout.as_secs() + 59) / 60;
+            submit.arg(format!("--time={}", minutes.max(1)));
+        }
+        if let Some(path) = opts.logs.file() {
+            // Append to the log file so that the output of the submission command is kept.
+            submit.arg(format!("--output={}", path.display()));
+            submit.arg("--open-mode=append");
+        }
+        submit.args(self.extra_args.iter().map(|arg| arg.as_str()));
 
-        let status = submit.status().map_err(Arc::new)?;
+        match self.submit {
+            SlurmSubmit::Srun => {
+                submit.arg(command.get_program());
+                submit.args(command.get_args());
+            }
+            SlurmSubmit::Sbatch => {
+                let wrapped = std::iter::once(command.get_program())
+                    .chain(command.get_args())
+                    .map(|arg| shell_quote(&arg.to_string_lossy()))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                submit.arg(format!("--wrap={wrapped}"));
+            }
+        }
+        if let Some(dir) = command.get_current_dir() {
+            submit.current_dir(dir);
+        }
+
+        for (key, val) in command.get_envs() {
+            match val {
+                None => submit.env_remove(key),
+                Some(val) => submit.env(key, val),
+            };
+        }
+
+        submit
+    }
+}
+
+impl SlurmExecutor {
+    fn run(
+        &self,
+        command: Command,
+        opts: ExecOpts,
+        token: Option<&CancelToken>,
+    ) -> Result<(), crate::error::Error> {
+        let mut submit = self.command(&command, opts.clone());
+        let cancel_job = |child: &mut Child| self.cancel(child);
+        let cancel_job: Option<CancelJob<'_>> = match self.submit {
+            SlurmSubmit::Srun => None,
+            SlurmSubmit::Sbatch => Some(&cancel_job),
+        };
+        run_job_with_retries(&mut submit, opts.logs.file(), &opts, token, cancel_job)
+    }
+
+    /// Cancels the job submitted by the given killed `sbatch --parsable` process.
+    fn cancel(&self, child: &mut Child) -> std::io::Result<()> {
+        let mut out = String::new();
+        if let Some(mut stdout) = child.stdout.take() {
+            stdout.read_to_string(&mut out)?;
+        }
+        // The job ID is optionally followed by a semicolon and the cluster name.
+        let Some(id) = out
+            .lines()
+            .next()
+            .and_then(|line| line.split(';').next())
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+        else {
+            // The job was never submitted.
+            return Ok(());
+        };
+        let scancel = self.scancel.as_deref().unwrap_or("scancel");
+        let status = Command::new(scancel).arg(id).status()?;
         if !status.success() {
-            return Err(crate::error::Error::CommandFailed(Arc::new(submit)));
+            tracing::event!(Level::WARN, "failed to cancel Slurm job {id}: {status}");
+        }
+        Ok(())
+    }
+}
+
+impl Executor for SlurmExecutor {
+    fn execute(&self, command: Command, opts: ExecOpts) -> Result<(), crate::error::Error> {
+        self.run(command, opts, None)
+    }
+
+    fn execute_cancellable(
+        &self,
+        command: Command,
+        opts: ExecOpts,
+        token: &CancelToken,
+    ) -> Result<(), crate::error::Error> {
+        self.run(command, opts, Some(token))
+    }
+}
+
+/// A command submitted to a [`RecordingExecutor`].
+#[derive(Clone, Debug)]
+pub struct RecordedCommand {
+    /// The program that would have been run.
+    pub program: OsString,
+    /// The arguments passed to the program.
+    pub args: Vec<OsString>,
+    /// The working directory of the command.
+    ///
+    /// [`None`] if the command inherits the working directory of the current process.
+    pub current_dir: Option<PathBuf>,
+    /// Environment variables explicitly set ([`Some`]) or removed ([`None`]) for the command.
+    pub envs: Vec<(OsString, Option<OsString>)>,
+    /// The options the command was submitted with.
+    pub opts: ExecOpts,
+    /// The files in the command's working directory at the time of submission,
+    /// keyed by their path relative to the working directory.
+    ///
+    /// Empty if the command has no explicit working directory.
+    pub files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+/// An executor that records submitted commands instead of running them.
+///
+/// Optionally copies the contents of a replay directory into the working directory
+/// of each command, allowing pre-recorded simulator outputs to be parsed as if
+/// the command had run.
+///
+/// Clones of a recording executor share the same list of recorded commands.
+#[derive(Clone, Debug, Default)]
+pub struct RecordingExecutor {
+    replay: Option<PathBuf>,
+    commands: Arc<Mutex<Vec<RecordedCommand>>>,
+}
+
+impl RecordingExecutor {
+    /// Creates a new executor that only records commands.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new executor that records commands and copies the contents of `dir`
+    /// into the working directory of each command.
+    pub fn with_replay(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            replay: Some(dir.into()),
+            ..Default::default()
         }
+    }
+
+    /// Returns the commands submitted so far, in submission order.
+    pub fn commands(&self) -> Vec<RecordedCommand> {
+        self.commands.lock().unwrap().clone()
+    }
 
+    /// Clears the list of recorded commands.
+    pub fn clear(&self) {
+        self.commands.lock().unwrap().clear();
+    }
+}
+
+impl Executor for RecordingExecutor {
+    fn execute(&self, command: Command, opts: ExecOpts) -> Result<(), crate::error::Error> {
+        let current_dir = command.get_current_dir().map(|dir| dir.to_path_buf());
+        let mut files = BTreeMap::new();
+        if let Some(ref dir) = current_dir {
+            read_files(dir, dir, &mut files).map_err(Arc::new)?;
+        }
+
+        self.commands.lock().unwrap().push(RecordedCommand {
+            program: command.get_program().to_os_string(),
+            args: command.get_args().map(|arg| arg.to_os_string()).collect(),
+            current_dir: current_dir.clone(),
+            envs: command
+                .get_envs()
+                .map(|(k, v)| (k.to_os_string(), v.map(|v| v.to_os_string())))
+                .collect(),
+            opts,
+            files,
+        });
+
+        if let Some(ref replay) = self.replay {
+            let dst = current_dir.unwrap_or_else(|| PathBuf::from("."));
+            copy_dir(replay, &dst).map_err(Arc::new)?;
+        }
         Ok(())
     }
 }
+
+/// Reads the contents of all files in `dir` recursively, keyed by their path relative to `root`.
+fn read_files(
+    root: &Path,
+    dir: &Path,
+    files: &mut BTreeMap<PathBuf, Vec<u8>>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            read_files(root, &path, files)?;
+        } else {
+            let contents = std::fs::read(&path)?;
+            files.insert(path.strip_prefix(root).unwrap().to_path_buf(), contents);
+        }
+    }
+    Ok(())
+}
+
+/// Recursively copies the contents of `src` into `dst`, overwriting existing files.
+fn copy_dir(src: &Path, dst: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let path = entry.path();
+        let target = dst.join(entry.file_name());
+        if path.is_dir() {
+            copy_dir(&path, &target)?;
+        } else {
+            std::fs::copy(&path, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Quotes the given argument for use in a POSIX shell command.
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c))
+    {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Work queued on a [`WorkerPool`].
+trait Pending: Send + Sync {
+    /// Runs the work, unless it has already been started.
+    fn run(&self);
+}
+
+/// The shared state of a [`Task`].
+struct TaskState<T> {
+    work: Mutex<Option<Box<dyn FnOnce() -> T + Send>>>,
+    result: Mutex<Option<std::thread::Result<T>>>,
+    done: Condvar,
+}
+
+impl<T: Send> Pending for TaskState<T> {
+    fn run(&self) {
+        let work = self.work.lock().unwrap().take();
+        if let Some(work) = work {
+            let result = std::panic::catch_unwind(AssertUnwindSafe(work));
+            *self.result.lock().unwrap() = Some(result);
+            self.done.notify_all();
+        }
+    }
+}
+
+impl<T: Send> TaskState<T> {
+    /// Blocks until the work completes, returning a guard holding its result.
+    ///
+    /// If no worker has started the work yet, it runs on the current thread.
+    fn wait(&self) -> MutexGuard<'_, Option<std::thread::Result<T>>> {
+        self.run();
+        let mut result = self.result.lock().unwrap();
+        while result.is_none() {
+            result = self.done.wait(result).unwrap();
+        }
+        result
+    }
+}
+
+/// The queue and thread count of a [`WorkerPool`].
+#[derive(Default)]
+struct PoolState {
+    queue: VecDeque<Arc<dyn Pending>>,
+    running: usize,
+}
+
+struct PoolShared {
+    workers: usize,
+    state: Mutex<PoolState>,
+}
+
+impl PoolShared {
+    fn work(&self) {
+        loop {
+            let next = {
+                let mut state = self.state.lock().unwrap();
+                match state.queue.pop_front() {
+                    Some(next) => next,
+                    None => {
+                        state.running -= 1;
+                        return;
+                    }
+                }
+            };
+            next.run();
+        }
+    }
+}
+
+/// A bounded pool of threads on which background work runs.
+///
+/// Threads are started as work is submitted, up to the pool's limit, and exit once the
+/// queue is empty. Waiting for work that no thread has started yet runs it on the
+/// waiting thread, so work running on a pool may wait for other work on the same pool.
+///
+/// Clones of a pool share the same threads and queue.
+#[derive(Clone)]
+pub struct WorkerPool {
+    shared: Arc<PoolShared>,
+}
+
+impl WorkerPool {
+    /// Creates a pool that runs at most `workers` threads at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is zero.
+    pub fn new(workers: usize) -> Self {
+        assert!(workers > 0, "a worker pool must have at least one worker");
+        Self {
+            shared: Arc::new(PoolShared {
+                workers,
+                state: Default::default(),
+            }),
+        }
+    }
+
+    /// Returns the pool used by [`Job::submit`] and [`JobSet::new`].
+    ///
+    /// The pool runs as many threads as the machine has available cores.
+    pub fn global() -> Self {
+        static GLOBAL: OnceLock<WorkerPool> = OnceLock::new();
+        GLOBAL.get_or_init(WorkerPool::default).clone()
+    }
+
+    /// Returns the maximum number of threads this pool runs at once.
+    #[inline]
+    pub fn workers(&self) -> usize {
+        self.shared.workers
+    }
+
+    /// Runs `work` in the background, returning a handle to its output.
+    pub fn spawn<T: Send + 'static>(&self, work: impl FnOnce() -> T + Send + 'static) -> Task<T> {
+        let state = Arc::new(TaskState {
+            work: Mutex::new(Some(Box::new(work))),
+            result: Mutex::new(None),
+            done: Condvar::new(),
+        });
+        let mut pool = self.shared.state.lock().unwrap();
+        pool.queue.push_back(state.clone());
+        if pool.running < self.shared.workers {
+            pool.running += 1;
+            let shared = self.shared.clone();
+            std::thread::spawn(move || shared.work());
+        }
+        Task { state }
+    }
+}
+
+impl Default for WorkerPool {
+    fn default() -> Self {
+        Self::new(
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+        )
+    }
+}
+
+/// A handle to work running in the background on a [`WorkerPool`].
+pub struct Task<T> {
+    state: Arc<TaskState<T>>,
+}
+
+impl<T: Send> Task<T> {
+    /// Returns `true` if the work has completed.
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.state.result.lock().unwrap().is_some()
+    }
+
+    /// Blocks until the work completes, returning its output.
+    ///
+    /// # Panics
+    ///
+    /// Resumes the panic of the work if it panicked.
+    pub fn wait(self) -> T {
+        let result = self.state.wait().take().unwrap();
+        result.unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+    }
+}
+
+/// Converts the result of a job's task, treating a panic as an error.
+fn job_result(
+    result: &std::thread::Result<Result<(), crate::error::Error>>,
+) -> Result<(), crate::error::Error> {
+    match result {
+        Ok(result) => result.clone(),
+        Err(_) => Err(crate::error::Error::Panic),
+    }
+}
+
+/// A handle to a job submitted to an [`Executor`] in the background.
+///
+/// Clones of a handle refer to the same job.
+#[derive(Clone)]
+pub struct Job {
+    state: Arc<TaskState<Result<(), crate::error::Error>>>,
+    token: CancelToken,
+}
+
+impl Job {
+    /// Submits the given command to `executor` without waiting for it to complete.
+    ///
+    /// The job runs on the [global](WorkerPool::global) worker pool.
+    pub fn submit(executor: Arc<dyn Executor>, command: Command, opts: ExecOpts) -> Self {
+        Self::submit_to(&WorkerPool::global(), executor, command, opts)
+    }
+
+    /// Submits the given command to `executor` without waiting for it to complete,
+    /// waiting for it on a thread of `pool`.
+    pub fn submit_to(
+        pool: &WorkerPool,
+        executor: Arc<dyn Executor>,
+        command: Command,
+        opts: ExecOpts,
+    ) -> Self {
+        let token = CancelToken::new();
+        let task = {
+            let token = token.clone();
+            pool.spawn(move || {
+                if token.is_cancelled() {
+                    return Err(crate::error::Error::Cancelled);
+                }
+                executor.execute_cancellable(command, opts, &token)
+            })
+        };
+        Self {
+            state: task.state,
+            token,
+        }
+    }
+
+    /// Returns the result of the job if it has completed.
+    pub fn poll(&self) -> Option<Result<(), crate::error::Error>> {
+        self.state.result.lock().unwrap().as_ref().map(job_result)
+    }
+
+    /// Returns `true` if the job has completed.
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.state.result.lock().unwrap().is_some()
+    }
+
+    /// Blocks until the job completes, returning its result.
+    ///
+    /// If the job is still queued, it runs on the current thread.
+    pub fn wait(&self) -> Result<(), crate::error::Error> {
+        job_result(self.state.wait().as_ref().unwrap())
+    }
+
+    /// Requests that the job be cancelled.
+    ///
+    /// Queued jobs never start once cancelled. Running jobs are only stopped by executors
+    /// that implement [`Executor::execute_cancellable`]. Use [`Job::wait`] to wait for
+    /// the job to stop.
+    #[inline]
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+}
+
+/// A set of jobs submitted to a common executor.
+///
+/// All outstanding jobs are cancelled when the set is dropped,
+/// and dropping the set blocks until they have stopped.
+pub struct JobSet {
+    executor: Arc<dyn Executor>,
+    pool: WorkerPool,
+    jobs: Mutex<Vec<Job>>,
+}
+
+impl JobSet {
+    /// Creates an empty job set that submits jobs to the given executor.
+    ///
+    /// Jobs run on the [global](WorkerPool::global) worker pool.
+    pub fn new(executor: Arc<dyn Executor>) -> Self {
+        Self::with_pool(executor, WorkerPool::global())
+    }
+
+    /// Creates an empty job set that submits jobs to the given executor,
+    /// running them on the given worker pool.
+    pub fn with_pool(executor: Arc<dyn Executor>, pool: WorkerPool) -> Self {
+        Self {
+            executor,
+            pool,
+            jobs: Default::default(),
+        }
+    }
+
+    /// Submits the given command without waiting for it to complete.
+    pub fn submit(&self, command: Command, opts: ExecOpts) -> Job {
+        let job = Job::submit_to(&self.pool, self.executor.clone(), command, opts);
+        self.jobs.lock().unwrap().push(job.clone());
+        job
+    }
+
+    /// Cancels all outstanding jobs in the set.
+    pub fn cancel_all(&self) {
+        for job in self.jobs.lock().unwrap().iter() {
+            if !job.is_done() {
+                job.cancel();
+            }
+        }
+    }
+
+    /// Blocks until all jobs in the set complete, returning their results in submission order.
+    pub fn wait_all(&self) -> Vec<Result<(), crate::error::Error>> {
+        let jobs = self.jobs.lock().unwrap().clone();
+        jobs.iter().map(Job::wait).collect()
+    }
+}
+
+impl Drop for JobSet {
+    fn drop(&mut self) {
+        self.cancel_all();
+        for job in self.jobs.get_mut().unwrap().iter() {
+            let _ = job.wait();
+        }
+    }
+}
+
+/// Limits on the jobs that may run concurrently on a [`LimitedExecutor`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConcurrencyLimits {
+    /// Maximum number of jobs that may run at once.
+    ///
+    /// [`None`] if the number of jobs is unlimited.
+    pub max_jobs: Option<usize>,
+    /// Number of available license tokens of each kind.
+    ///
+    /// Licenses not listed here are unlimited.
+    pub licenses: IndexMap<ArcStr, usize>,
+}
+
+/// Resources currently held by running jobs.
+#[derive(Debug, Default)]
+struct Usage {
+    jobs: usize,
+    licenses: IndexMap<ArcStr, usize>,
+}
+
+/// An executor that limits the number of jobs and license tokens in use at once.
+///
+/// Jobs that would exceed the limits block until enough resources are released.
+/// A job requesting more tokens of a license than are available runs once
+/// no other job holds that license.
+pub struct LimitedExecutor<E> {
+    inner: E,
+    limits: ConcurrencyLimits,
+    usage: Mutex<Usage>,
+    released: Condvar,
+}
+
+impl<E: Executor> LimitedExecutor<E> {
+    /// Wraps `inner` so that jobs submitted to it respect the given limits.
+    pub fn new(inner: E, limits: ConcurrencyLimits) -> Self {
+        Self {
+            inner,
+            limits,
+            usage: Default::default(),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Returns the limits enforced by this executor.
+    #[inline]
+    pub fn limits(&self) -> &ConcurrencyLimits {
+        &self.limits
+    }
+
+    fn fits(&self, usage: &Usage, opts: &ExecOpts) -> bool {
+        if let Some(max_jobs) = self.limits.max_jobs {
+            if usage.jobs >= max_jobs {
+                return false;
+            }
+        }
+        opts.licenses.iter().all(|(name, &requested)| {
+            let Some(&available) = self.limits.licenses.get(name) else {
+                return true;
+            };
+            let used = usage.licenses.get(name).copied().unwrap_or_default();
+            used == 0 || used + requested <= available
+        })
+    }
+
+    /// Blocks until the job described by `opts` fits within the limits.
+    fn acquire(
+        &self,
+        opts: &ExecOpts,
+        token: Option<&CancelToken>,
+    ) -> Result<Permit<'_, E>, crate::error::Error> {
+        let mut usage = self.usage.lock().unwrap();
+        loop {
+            if token.map(|token| token.is_cancelled()).unwrap_or(false) {
+                return Err(crate::error::Error::Cancelled);
+            }
+            if self.fits(&usage, opts) {
+                usage.jobs += 1;
+                for (name, &requested) in opts.licenses.iter() {
+                    *usage.licenses.entry(name.clone()).or_default() += requested;
+                }
+                return Ok(Permit {
+                    executor: self,
+                    licenses: opts.licenses.clone(),
+                });
+            }
+            usage = self.released.wait_timeout(usage, POLL_INTERVAL).unwrap().0;
+        }
+    }
+}
+
+/// Resources held by a job running on a [`LimitedExecutor`].
+///
+/// Releases the resources when dropped.
+struct Permit<'a, E> {
+    executor: &'a LimitedExecutor<E>,
+    licenses: IndexMap<ArcStr, usize>,
+}
+
+impl<E> Drop for Permit<'_, E> {
+    fn drop(&mut self) {
+        let mut usage = self.executor.usage.lock().unwrap();
+        usage.jobs -= 1;
+        for (name, requested) in self.licenses.iter() {
+            if let Some(used) = usage.licenses.get_mut(name) {
+                *used -= requested;
+            }
+        }
+        self.executor.released.notify_all();
+    }
+}
+
+impl<E: Executor> Executor for LimitedExecutor<E> {
+    fn execute(&self, command: Command, opts: ExecOpts) -> Result<(), crate::error::Error> {
+        let _permit = self.acquire(&opts, None)?;
+        self.inner.execute(command, opts)
+    }
+
+    fn execute_cancellable(
+        &self,
+        command: Command,
+        opts: ExecOpts,
+        token: &CancelToken,
+    ) -> Result<(), crate::error::Error> {
+        let _permit = self.acquire(&opts, Some(token))?;
+        self.inner.execute_cancellable(command, opts, token)
+    }
+}
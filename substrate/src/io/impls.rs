@@ -1146,6 +1146,12 @@ impl NameBuf {
     pub fn pop(&mut self) -> Option<NameFragment> {
         self.fragments.pop()
     }
+
+    /// The fragments of this name buffer, in order.
+    #[inline]
+    pub(crate) fn fragments(&self) -> &[NameFragment] {
+        &self.fragments
+    }
 }
 
 impl Port {
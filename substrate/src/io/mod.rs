@@ -314,7 +314,7 @@ impl AsRef<NestedNode> for Terminal {
 
 /// A path to an instance's terminal.
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
-pub struct TerminalPath(NodePath);
+pub struct TerminalPath(pub(crate) NodePath);
 
 impl Deref for TerminalPath {
     type Target = NodePath;
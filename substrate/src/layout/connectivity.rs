@@ -0,0 +1,616 @@
+//! Connectivity extraction.
+//!
+//! Merges touching shapes on conducting layers, and shapes connected by vias,
+//! into nets, and recognizes devices from layer recipes. The result can be
+//! exported to SCIR for comparison against a schematic.
+
+use std::collections::HashMap;
+
+use arcstr::ArcStr;
+use geometry::bbox::Bbox;
+use geometry::boolean::Region;
+use geometry::contains::Contains;
+use geometry::point::Point;
+use geometry::polygon::Polygon;
+use geometry::rect::Rect;
+use indexmap::IndexMap;
+use scir::{Direction, LibraryBuilder, PrimitiveDevice, PrimitiveDeviceKind, TopKind};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::pdk::layers::{layer_resolver, HasPin, LayerId};
+
+use super::element::RawCell;
+use super::flatten::FlatLayout;
+use super::{Cell, ExportsLayoutData};
+
+/// A conducting layer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Conductor<L = LayerId> {
+    /// The drawing layer.
+    pub layer: L,
+    /// Pin layers whose shapes also conduct on this layer.
+    #[serde(default)]
+    pub pins: Vec<L>,
+    /// Layers whose text annotations name nets on this layer.
+    #[serde(default)]
+    pub labels: Vec<L>,
+}
+
+/// A cut layer connecting two conducting layers where it overlaps both.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Via<L = LayerId> {
+    /// The cut layer.
+    pub cut: L,
+    /// The drawing layer of the bottom conductor.
+    pub bot: L,
+    /// The drawing layer of the top conductor.
+    pub top: L,
+}
+
+/// A terminal of a [`DeviceRecipe`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Terminal<L = LayerId> {
+    /// The name of the terminal.
+    pub name: ArcStr,
+    /// The drawing layer of the conductor the terminal connects to.
+    pub layer: L,
+}
+
+/// A recipe for recognizing devices in a layout.
+///
+/// Each connected region covered by all `body` layers is a device. The body is removed from the
+/// conductors listed in `splits`, so that, for example, a transistor gate separates its
+/// source and drain.
+///
+/// Terminals connect to the conductors that touch the body. If several terminals are on the same
+/// layer, they are assigned to the touching nets in order of position, from left to right and
+/// then bottom to top.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceRecipe<L = LayerId> {
+    /// The name of the subcircuit instantiated for each recognized device.
+    pub cell: ArcStr,
+    /// The layers that together form the body of the device.
+    pub body: Vec<L>,
+    /// The conductors separated by the device body.
+    #[serde(default)]
+    pub splits: Vec<L>,
+    /// The terminals of the device, in subcircuit port order.
+    pub terminals: Vec<Terminal<L>>,
+}
+
+/// The connectivity rules of a process.
+///
+/// Like [`RuleDeck`](super::drc::RuleDeck)s, connectivity rules are generic over the
+/// way layers are identified.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Connectivity<L = LayerId> {
+    /// The conducting layers.
+    #[serde(default)]
+    pub conductors: Vec<Conductor<L>>,
+    /// The vias between conducting layers.
+    #[serde(default)]
+    pub vias: Vec<Via<L>>,
+    /// Recipes for recognizing devices.
+    #[serde(default)]
+    pub devices: Vec<DeviceRecipe<L>>,
+}
+
+impl<L> Default for Connectivity<L> {
+    fn default() -> Self {
+        Self {
+            conductors: Vec::new(),
+            vias: Vec::new(),
+            devices: Vec::new(),
+        }
+    }
+}
+
+impl<L> Connectivity<L> {
+    /// Creates an empty set of connectivity rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a conducting layer.
+    pub fn with_conductor(mut self, conductor: Conductor<L>) -> Self {
+        self.conductors.push(conductor);
+        self
+    }
+
+    /// Adds a via.
+    pub fn with_via(mut self, via: Via<L>) -> Self {
+        self.vias.push(via);
+        self
+    }
+
+    /// Adds a device recipe.
+    pub fn with_device(mut self, device: DeviceRecipe<L>) -> Self {
+        self.devices.push(device);
+        self
+    }
+}
+
+impl<L: AsRef<str>> Connectivity<L> {
+    /// Resolves layer names to layer IDs using the given lookup function.
+    ///
+    /// Returns an error if a layer name cannot be resolved.
+    pub fn resolve(&self, lookup: impl FnMut(&str) -> Option<LayerId>) -> Result<Connectivity> {
+        let mut layer = layer_resolver(lookup);
+
+        let mut conductors = Vec::with_capacity(self.conductors.len());
+        for c in self.conductors.iter() {
+            conductors.push(Conductor {
+                layer: layer(&c.layer)?,
+                pins: c.pins.iter().map(&mut layer).collect::<Result<_, _>>()?,
+                labels: c.labels.iter().map(&mut layer).collect::<Result<_, _>>()?,
+            });
+        }
+
+        let mut vias = Vec::with_capacity(self.vias.len());
+        for v in self.vias.iter() {
+            vias.push(Via {
+                cut: layer(&v.cut)?,
+                bot: layer(&v.bot)?,
+                top: layer(&v.top)?,
+            });
+        }
+
+        let mut devices = Vec::with_capacity(self.devices.len());
+        for d in self.devices.iter() {
+            let mut terminals = Vec::with_capacity(d.terminals.len());
+            for t in d.terminals.iter() {
+                terminals.push(Terminal {
+                    name: t.name.clone(),
+                    layer: layer(&t.layer)?,
+                });
+            }
+            devices.push(DeviceRecipe {
+                cell: d.cell.clone(),
+                body: d.body.iter().map(&mut layer).collect::<Result<_, _>>()?,
+                splits: d.splits.iter().map(&mut layer).collect::<Result<_, _>>()?,
+                terminals,
+            });
+        }
+
+        Ok(Connectivity {
+            conductors,
+            vias,
+            devices,
+        })
+    }
+}
+
+impl Connectivity {
+    /// Extracts the nets and devices of the given cell and all cells it instantiates.
+    pub fn extract(&self, cell: &RawCell) -> Extracted {
+        let mut extracted = self.extract_flat(&cell.flatten());
+        extracted.name = cell.name.clone();
+        extracted
+    }
+
+    /// Extracts the nets and devices of a flattened layout.
+    ///
+    /// Port shapes of all cells in the hierarchy conduct. Ports of the top cell name
+    /// and expose their nets. Other nets are named by text labels where available.
+    pub fn extract_flat(&self, layout: &FlatLayout) -> Extracted {
+        let mut regions = layout.regions();
+        for port in layout.ports.iter() {
+            for shape in std::iter::once(&port.geometry.primary)
+                .chain(port.geometry.unnamed_shapes.iter())
+                .chain(port.geometry.named_shapes.values())
+            {
+                let region = regions.entry(shape.layer().drawing()).or_default();
+                *region = region.union(&Region::from(&shape.shape().to_polygon()));
+            }
+        }
+        let empty = Region::new();
+        let region = |layer: &LayerId| regions.get(layer).unwrap_or(&empty);
+
+        // Device bodies.
+        let bodies = self
+            .devices
+            .iter()
+            .map(|recipe| {
+                let mut body: Option<Region> = None;
+                for layer in recipe.body.iter() {
+                    body = Some(match body {
+                        Some(body) => body.intersection(region(layer)),
+                        None => region(layer).clone(),
+                    });
+                }
+                body.unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+
+        // Conducting components.
+        let mut nodes: Vec<Node> = Vec::new();
+        let mut conductor_of = HashMap::new();
+        for (i, conductor) in self.conductors.iter().enumerate() {
+            conductor_of.insert(conductor.layer, i);
+            let mut r = conductor
+                .pins
+                .iter()
+                .fold(region(&conductor.layer).clone(), |r, pin| {
+                    r.union(region(pin))
+                });
+            for (recipe, body) in self.devices.iter().zip(bodies.iter()) {
+                if recipe.splits.contains(&conductor.layer) {
+                    r = r.difference(body);
+                }
+            }
+            for component in r.components() {
+                if let Some(bbox) = component.bbox() {
+                    nodes.push(Node {
+                        conductor: i,
+                        bbox,
+                        region: component,
+                    });
+                }
+            }
+        }
+
+        let mut nets = UnionFind::new(nodes.len());
+        let mut cuts = Vec::new();
+        for via in self.vias.iter() {
+            let (Some(&bot), Some(&top)) = (conductor_of.get(&via.bot), conductor_of.get(&via.top))
+            else {
+                continue;
+            };
+            for cut in region(&via.cut).components() {
+                let connected = nodes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, n)| (n.conductor == bot || n.conductor == top) && n.overlaps(&cut))
+                    .map(|(i, _)| i)
+                    .collect::<Vec<_>>();
+                for pair in connected.windows(2) {
+                    nets.union(pair[0], pair[1]);
+                }
+                if let Some(bbox) = cut.bbox() {
+                    cuts.push(Cut {
+                        layer: via.cut,
+                        bbox,
+                        pieces: connected,
+                    });
+                }
+            }
+        }
+
+        // Assign net indices.
+        let mut net_of_root = IndexMap::new();
+        let node_net = (0..nodes.len())
+            .map(|i| {
+                let root = nets.find(i);
+                let n = net_of_root.len();
+                *net_of_root.entry(root).or_insert(n)
+            })
+            .collect::<Vec<_>>();
+        let mut net_names: Vec<Option<ArcStr>> = vec![None; net_of_root.len()];
+        let mut ports = Vec::new();
+        let mut port_contacts = Vec::new();
+
+        let node_at = |layer: LayerId, shape: &Region| {
+            let conductor = *conductor_of.get(&layer)?;
+            nodes
+                .iter()
+                .position(|n| n.conductor == conductor && n.overlaps(shape))
+        };
+
+        // Name nets using ports of the top cell, then text labels.
+        for port in layout.top_ports() {
+            let shape = &port.geometry.primary;
+            let region = Region::from(&shape.shape().to_polygon());
+            if let Some(node) = node_at(shape.layer().drawing(), &region) {
+                let net = node_net[node];
+                if net_names[net].is_none() {
+                    net_names[net] = Some(port.name.to_string().into());
+                    ports.push(net);
+                    port_contacts.push(Contact {
+                        piece: node,
+                        point: clamp(nodes[node].bbox, shape.shape().bbox().unwrap().center()),
+                    });
+                }
+            }
+        }
+        for text in layout.texts.iter() {
+            let point = text.trans.offset_point();
+            let Some(net) = nodes
+                .iter()
+                .position(|n| {
+                    let conductor = &self.conductors[n.conductor];
+                    (conductor.layer == text.layer() || conductor.labels.contains(&text.layer()))
+                        && n.contains(point)
+                })
+                .map(|i| node_net[i])
+            else {
+                continue;
+            };
+            if net_names[net].is_none() {
+                net_names[net] = Some(text.text().clone());
+            }
+        }
+
+        // Recognize devices.
+        let mut devices = Vec::new();
+        for (recipe, body) in self.devices.iter().zip(bodies.iter()) {
+            for component in body.components() {
+                let touching = grow(&component);
+                let bbox = component.bbox().unwrap();
+                let mut terminals = Vec::with_capacity(recipe.terminals.len());
+                let mut contacts = Vec::with_capacity(recipe.terminals.len());
+                let mut used: HashMap<LayerId, usize> = HashMap::new();
+                for terminal in recipe.terminals.iter() {
+                    let mut candidates = conductor_of
+                        .get(&terminal.layer)
+                        .map(|&c| {
+                            nodes
+                                .iter()
+                                .enumerate()
+                                .filter(|(_, n)| n.conductor == c && n.overlaps(&touching))
+                                .map(|(i, n)| (n.bbox.left(), n.bbox.bot(), node_net[i], i))
+                                .collect::<Vec<_>>()
+                        })
+                        .unwrap_or_default();
+                    candidates.sort();
+                    candidates.dedup_by_key(|c| c.2);
+                    let k = used.entry(terminal.layer).or_default();
+                    let net = match candidates.get(*k) {
+                        Some(&(_, _, net, node)) => {
+                            contacts.push(Some(Contact {
+                                piece: node,
+                                point: clamp(nodes[node].bbox, bbox.center()),
+                            }));
+                            net
+                        }
+                        None => {
+                            // Unconnected terminals get their own floating net.
+                            net_names.push(None);
+                            contacts.push(None);
+                            net_names.len() - 1
+                        }
+                    };
+                    *k += 1;
+                    terminals.push(net);
+                }
+                devices.push(Device {
+                    cell: recipe.cell.clone(),
+                    bbox,
+                    terminals,
+                    contacts,
+                });
+            }
+        }
+
+        // Make net names unique.
+        let mut taken = HashMap::new();
+        let mut names = Vec::with_capacity(net_names.len());
+        for name in net_names.iter() {
+            if let Some(name) = name {
+                let count = taken.entry(name.clone()).or_insert(0usize);
+                *count += 1;
+                if *count == 1 {
+                    names.push(Some(name.clone()));
+                    continue;
+                }
+            }
+            names.push(None);
+        }
+        let mut next = 0;
+        let names = names
+            .into_iter()
+            .map(|name| {
+                name.unwrap_or_else(|| loop {
+                    let candidate = arcstr::format!("net{next}");
+                    next += 1;
+                    if !taken.contains_key(&candidate) {
+                        break candidate;
+                    }
+                })
+            })
+            .collect();
+
+        let pieces = nodes
+            .into_iter()
+            .zip(node_net)
+            .map(|(node, net)| Piece {
+                net,
+                layer: self.conductors[node.conductor].layer,
+                bbox: node.bbox,
+                region: node.region,
+            })
+            .collect();
+
+        Extracted {
+            name: arcstr::literal!("extracted"),
+            nets: names,
+            ports,
+            devices,
+            pieces,
+            cuts,
+            port_contacts,
+        }
+    }
+}
+
+/// A device recognized during connectivity extraction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    /// The name of the subcircuit instantiated by the device.
+    pub cell: ArcStr,
+    /// The bounding box of the device body.
+    pub bbox: Rect,
+    /// The nets connected to each terminal of the device, in subcircuit port order.
+    pub terminals: Vec<usize>,
+    /// Where each terminal connects to its net, if it connects to any conductor.
+    pub(crate) contacts: Vec<Option<Contact>>,
+}
+
+/// A connected component of a conductor, as retained for parasitic estimation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Piece {
+    pub(crate) net: usize,
+    pub(crate) layer: LayerId,
+    pub(crate) bbox: Rect,
+    pub(crate) region: Region,
+}
+
+/// A connected component of a cut layer and the pieces it connects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Cut {
+    pub(crate) layer: LayerId,
+    pub(crate) bbox: Rect,
+    pub(crate) pieces: Vec<usize>,
+}
+
+/// A point at which a port or device terminal connects to a piece.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Contact {
+    pub(crate) piece: usize,
+    pub(crate) point: Point,
+}
+
+/// The result of connectivity extraction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extracted {
+    pub(crate) name: ArcStr,
+    nets: Vec<ArcStr>,
+    ports: Vec<usize>,
+    devices: Vec<Device>,
+    pub(crate) pieces: Vec<Piece>,
+    pub(crate) cuts: Vec<Cut>,
+    pub(crate) port_contacts: Vec<Contact>,
+}
+
+impl Extracted {
+    /// Returns the names of the extracted nets, indexed by net.
+    pub fn nets(&self) -> &[ArcStr] {
+        &self.nets
+    }
+
+    /// Returns the nets exposed as ports of the extracted cell.
+    pub fn ports(&self) -> &[usize] {
+        &self.ports
+    }
+
+    /// Returns the recognized devices.
+    pub fn devices(&self) -> &[Device] {
+        &self.devices
+    }
+
+    /// Returns the index of the net with the given name.
+    pub fn net_named(&self, name: &str) -> Option<usize> {
+        self.nets.iter().position(|n| n == name)
+    }
+
+    /// Exports the extracted netlist to a SCIR library.
+    ///
+    /// The library contains a single cell named after the extracted layout cell,
+    /// with one node per net and one raw instance per device.
+    pub fn to_scir(&self) -> Result<scir::Library> {
+        let mut cell = scir::Cell::new_whitebox(self.name.clone());
+        let nodes = self
+            .nets
+            .iter()
+            .map(|name| cell.add_node(name.clone()))
+            .collect::<Vec<_>>();
+        for &port in self.ports.iter() {
+            cell.expose_port(nodes[port], Direction::InOut);
+        }
+        for (i, device) in self.devices.iter().enumerate() {
+            cell.add_primitive(PrimitiveDevice::new(
+                arcstr::format!("x{i}"),
+                PrimitiveDeviceKind::RawInstance {
+                    ports: device.terminals.iter().map(|&net| nodes[net]).collect(),
+                    cell: device.cell.clone(),
+                },
+            ));
+        }
+
+        let mut lib = LibraryBuilder::new(self.name.clone());
+        let id = lib.add_cell(cell);
+        lib.set_top(id, TopKind::Cell);
+        Ok(lib.build()?)
+    }
+}
+
+impl<T: ExportsLayoutData> Cell<T> {
+    /// Extracts the nets and devices of this cell and all cells it instantiates.
+    pub fn extract(&self, connectivity: &Connectivity) -> Extracted {
+        connectivity.extract(&self.raw)
+    }
+}
+
+/// A connected component of a conducting layer.
+struct Node {
+    conductor: usize,
+    bbox: Rect,
+    region: Region,
+}
+
+impl Node {
+    /// Returns `true` if this node overlaps `other` with positive area.
+    fn overlaps(&self, other: &Region) -> bool {
+        other
+            .bbox()
+            .map(|b| b.intersection(self.bbox).is_some())
+            .unwrap_or(false)
+            && !self.region.intersection(other).is_empty()
+    }
+}
+
+impl Node {
+    /// Returns `true` if `point` lies in this node or on its boundary.
+    fn contains(&self, point: Point) -> bool {
+        !self.bbox.contains(&point).is_none()
+            && self
+                .region
+                .trapezoids()
+                .iter()
+                .any(|t| !Polygon::from_verts(t.vertices()).contains(&point).is_none())
+    }
+}
+
+/// Returns the point of `rect` nearest to `point`.
+fn clamp(rect: Rect, point: Point) -> Point {
+    Point::new(
+        point.x.clamp(rect.left(), rect.right()),
+        point.y.clamp(rect.bot(), rect.top()),
+    )
+}
+
+/// Expands a region by one unit in every direction.
+fn grow(region: &Region) -> Region {
+    Region::from_rects(
+        region
+            .trapezoids()
+            .iter()
+            .filter_map(|t| Polygon::from_verts(t.vertices()).bbox())
+            .map(|r| r.expand_all(1)),
+    )
+}
+
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parent[a] = b;
+    }
+}
@@ -0,0 +1,274 @@
+//! DEF import.
+//!
+//! Converts placed and routed designs to layout cells, using a [`LefImporter`]
+//! for the macros, vias, and routing layers they reference.
+
+use std::sync::Arc;
+
+use arcstr::ArcStr;
+use geometry::bbox::Bbox;
+use geometry::point::Point;
+use geometry::polygon::Polygon;
+use geometry::prelude::{NamedOrientation, Orientation};
+use geometry::rect::Rect;
+use geometry::shape::Shape;
+use geometry::transform::{Transform, Transformation, Translate};
+use lefdef::{DefDesign, DefOrient, DefPlacement, DefPoint, DefRouteItem, DefShapeKind};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::error::Result;
+use crate::io::{LayoutBundleBuilder, LayoutType, Signal};
+
+use super::element::{RawCell, RawInstance, Shape as ElementShape};
+use super::error::LayoutError;
+use super::lef::{pin_name, wire_rects, LefImporter};
+
+/// An importer for DEF designs.
+///
+/// Each placed component becomes an instance of the corresponding LEF macro, and
+/// I/O pins become ports of the imported cell. Routed wires and vias of regular and
+/// special nets are drawn as shapes. If the [`LefRules`](super::lef::LefRules) specify a
+/// boundary layer, the die area is drawn on it.
+pub struct DefImporter<'a> {
+    def: &'a DefDesign,
+    lef: LefImporter<'a>,
+    scale: Decimal,
+}
+
+impl<'a> DefImporter<'a> {
+    /// Creates a new DEF importer.
+    ///
+    /// `db_units` is the size of a layout database unit in meters, and must match
+    /// the units used to create `lef`.
+    ///
+    /// Returns an error if the DEF distance units or `db_units` are not positive.
+    pub fn new(def: &'a DefDesign, lef: LefImporter<'a>, db_units: Decimal) -> Result<Self> {
+        if def.units == 0 || db_units <= Decimal::ZERO {
+            return Err(LayoutError::LefDefImport(arcstr::format!(
+                "invalid units: {} DEF units per micron, {db_units} m per database unit",
+                def.units
+            ))
+            .into());
+        }
+        let scale = dec!(1e-6)
+            .checked_div(Decimal::from(def.units) * db_units)
+            .ok_or_else(|| LayoutError::LefDefImport(arcstr::literal!("DEF units out of range")))?;
+        Ok(Self { def, lef, scale })
+    }
+
+    /// Imports the design.
+    pub fn import(mut self) -> Result<Arc<RawCell>> {
+        let mut cell = RawCell::new(self.lef.layouts().get_id(), self.def.name.clone());
+
+        if let Some(boundary) = self.lef.boundary() {
+            match self.def.die_area.as_slice() {
+                [] => {}
+                [p0, p1] => cell.add_element(ElementShape::new(boundary, self.rect(*p0, *p1)?)),
+                points => cell.add_element(ElementShape::new(
+                    boundary,
+                    Polygon::from_verts(
+                        points
+                            .iter()
+                            .map(|p| self.point(*p))
+                            .collect::<Result<_>>()?,
+                    ),
+                )),
+            }
+        }
+
+        for component in self.def.components.iter() {
+            let Some(placement) = component.placement else {
+                tracing::warn!("skipping unplaced component `{}`", component.name);
+                continue;
+            };
+            let macro_cell = self.lef.import_macro(&component.macro_name)?;
+            let bounds = self
+                .lef
+                .macro_bounds(&component.macro_name)
+                .or_else(|| macro_cell.bbox())
+                .unwrap_or_default();
+            let orientation = orientation(placement.orient);
+            let oriented = bounds
+                .transform(Transformation::from_offset_and_orientation(
+                    Point::zero(),
+                    orientation,
+                ))
+                .bbox()
+                .unwrap();
+            let at = self.point(placement.at)?;
+            let offset = Point::new(at.x - oriented.left(), at.y - oriented.bot());
+            cell.add_element(RawInstance::new(
+                macro_cell,
+                Transformation::from_offset_and_orientation(offset, orientation),
+            ));
+        }
+
+        for pin in self.def.pins.iter() {
+            let mut port = Signal.builder();
+            let mut has_geometry = false;
+            for p in pin.ports.iter() {
+                let trans = self.placement(p.placement)?;
+                for shape in p.shapes.iter() {
+                    let Some(layer) = self.lef.layer(&shape.layer) else {
+                        continue;
+                    };
+                    let shape = self.def_shape(&shape.kind)?.transform(trans);
+                    port.push(self.lef.io_shape(layer, shape.clone()));
+                    cell.add_element(ElementShape::new(layer, shape));
+                    has_geometry = true;
+                }
+            }
+            if !has_geometry {
+                tracing::warn!("ignoring pin without geometry: `{}`", pin.name);
+                continue;
+            }
+            cell.add_port(pin_name(&pin.name), port.build()?);
+        }
+
+        for net in self.def.nets.iter().chain(self.def.special_nets.iter()) {
+            for route in net.routes.iter() {
+                let mut layer = route.layer.clone();
+                let mut prev: Option<Point> = None;
+                for item in route.items.iter() {
+                    match item {
+                        DefRouteItem::Point(p) => {
+                            let p = self.point(*p)?;
+                            if let Some(q) = prev {
+                                let width = match route.width {
+                                    Some(width) => self.dbu(width)?,
+                                    None => self.lef.layer_width(&layer).ok_or_else(|| {
+                                        LayoutError::LefDefImport(arcstr::format!(
+                                            "no default wire width for layer `{layer}`"
+                                        ))
+                                    })?,
+                                };
+                                if let Some(id) = self.lef.layer(&layer) {
+                                    for rect in wire_rects(&[q, p], width)? {
+                                        cell.add_element(ElementShape::new(id, rect));
+                                    }
+                                }
+                            }
+                            prev = Some(p);
+                        }
+                        DefRouteItem::Via(name) => {
+                            let at = prev.ok_or_else(|| {
+                                LayoutError::LefDefImport(arcstr::format!(
+                                    "via `{name}` does not follow a point"
+                                ))
+                            })?;
+                            let shapes = self.via_shapes(name)?;
+                            if let Some((next, _)) = shapes
+                                .iter()
+                                .find(|(l, _)| *l != layer && !self.lef.is_cut(l))
+                            {
+                                layer = next.clone();
+                            }
+                            for (l, shape) in shapes {
+                                if let Some(id) = self.lef.layer(&l) {
+                                    cell.add_element(ElementShape::new(
+                                        id,
+                                        shape.transform(Transformation::from_offset(at)),
+                                    ));
+                                }
+                            }
+                        }
+                        DefRouteItem::Rect(p0, p1) => {
+                            let at = prev.ok_or_else(|| {
+                                LayoutError::LefDefImport(arcstr::literal!(
+                                    "route rectangle does not follow a point"
+                                ))
+                            })?;
+                            if let Some(id) = self.lef.layer(&layer) {
+                                cell.add_element(ElementShape::new(
+                                    id,
+                                    self.rect(*p0, *p1)?.translate(at),
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(Arc::new(cell))
+    }
+
+    /// Returns the shapes of a DEF or LEF via, relative to its origin.
+    fn via_shapes(&self, name: &str) -> Result<Vec<(ArcStr, Shape)>> {
+        match self.def.via(name) {
+            Some(via) => via
+                .shapes
+                .iter()
+                .map(|shape| Ok((shape.layer.clone(), self.def_shape(&shape.kind)?)))
+                .collect(),
+            None => self.lef.via_shapes(name),
+        }
+    }
+
+    fn def_shape(&self, kind: &DefShapeKind) -> Result<Shape> {
+        Ok(match kind {
+            DefShapeKind::Rect(p0, p1) => Shape::Rect(self.rect(*p0, *p1)?),
+            DefShapeKind::Polygon(points) => Shape::Polygon(Polygon::from_verts(
+                points
+                    .iter()
+                    .map(|p| self.point(*p))
+                    .collect::<Result<_>>()?,
+            )),
+        })
+    }
+
+    /// Returns the transformation of a placed pin, if any.
+    fn placement(&self, placement: Option<DefPlacement>) -> Result<Transformation> {
+        Ok(match placement {
+            Some(p) => Transformation::from_offset_and_orientation(
+                self.point(p.at)?,
+                orientation(p.orient),
+            ),
+            None => Transformation::identity(),
+        })
+    }
+
+    /// Converts a DEF distance to layout database units.
+    ///
+    /// Returns an error if the result does not fit in an [`i64`].
+    fn dbu(&self, value: i64) -> Result<i64> {
+        Decimal::from(value)
+            .checked_mul(self.scale)
+            .and_then(|value| value.round().to_i64())
+            .ok_or_else(|| {
+                LayoutError::LefDefImport(arcstr::format!("DEF distance out of range: {value}"))
+                    .into()
+            })
+    }
+
+    fn point(&self, p: DefPoint) -> Result<Point> {
+        Ok(Point::new(self.dbu(p.x)?, self.dbu(p.y)?))
+    }
+
+    /// Returns the rectangle with corners `p0` and `p1`, in layout database units.
+    fn rect(&self, p0: DefPoint, p1: DefPoint) -> Result<Rect> {
+        let (p0, p1) = (self.point(p0)?, self.point(p1)?);
+        Ok(Rect::from_sides(
+            p0.x.min(p1.x),
+            p0.y.min(p1.y),
+            p0.x.max(p1.x),
+            p0.y.max(p1.y),
+        ))
+    }
+}
+
+fn orientation(orient: DefOrient) -> Orientation {
+    match orient {
+        DefOrient::N => NamedOrientation::R0,
+        DefOrient::S => NamedOrientation::R180,
+        DefOrient::W => NamedOrientation::R90,
+        DefOrient::E => NamedOrientation::R270,
+        DefOrient::FN => NamedOrientation::ReflectHoriz,
+        DefOrient::FS => NamedOrientation::ReflectVert,
+        DefOrient::FW => NamedOrientation::FlipYx,
+        DefOrient::FE => NamedOrientation::FlipMinusYx,
+    }
+    .into()
+}
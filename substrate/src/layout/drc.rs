@@ -0,0 +1,656 @@
+//! Design rule checking.
+//!
+//! Rules are checked against the flattened, merged geometry of a cell.
+//! Width, spacing and notch rules are measured using squares of the minimum
+//! dimension, so gaps between diagonally offset corners are measured along
+//! each axis rather than along the diagonal.
+
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use arcstr::ArcStr;
+use diagnostics::{Diagnostic, IssueSet, Severity};
+use geometry::bbox::Bbox;
+use geometry::boolean::Region;
+use geometry::corner::Corner;
+use geometry::point::Point;
+use geometry::polygon::{Polygon, Winding};
+use geometry::rect::Rect;
+use geometry::shape::Shape;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::pdk::layers::{layer_resolver, LayerId};
+
+use super::element::RawCell;
+use super::error::{GdsExportError, LayoutError};
+use super::flatten::{region_shapes, FlatLayout};
+use super::{Cell, ExportsLayoutData};
+
+/// A design rule.
+///
+/// Rules are generic over the way layers are identified. PDKs implemented in Rust
+/// use [`LayerId`]s directly, while rules loaded from a
+/// [`PdkSpec`](crate::pdk::data::PdkSpec) refer to layers by name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Rule<L = LayerId> {
+    /// Shapes on `layer` must be at least `min` wide.
+    Width {
+        /// The checked layer.
+        layer: L,
+        /// The minimum width.
+        min: i64,
+    },
+    /// Distinct shapes on `layer` must be separated by at least `min`.
+    Spacing {
+        /// The checked layer.
+        layer: L,
+        /// The minimum spacing.
+        min: i64,
+    },
+    /// Gaps between two parts of the same shape on `layer` must be at least `min` wide.
+    Notch {
+        /// The checked layer.
+        layer: L,
+        /// The minimum notch width.
+        min: i64,
+    },
+    /// Shapes on `outer` must extend past shapes on `inner` by at least `min` on all sides.
+    Enclosure {
+        /// The enclosed layer.
+        inner: L,
+        /// The enclosing layer.
+        outer: L,
+        /// The minimum enclosure.
+        min: i64,
+    },
+    /// Where `layer` crosses an edge of `over`, it must continue past that edge by at least `min`.
+    Extension {
+        /// The extending layer.
+        layer: L,
+        /// The layer that must be extended past.
+        over: L,
+        /// The minimum extension.
+        min: i64,
+    },
+    /// Each connected shape on `layer` must have an area of at least `min`.
+    Area {
+        /// The checked layer.
+        layer: L,
+        /// The minimum area.
+        min: i64,
+    },
+    /// The fraction of each `window` by `window` square covered by `layer`
+    /// must be between `min` and `max`.
+    ///
+    /// Windows tile the bounding box of the checked cell and are clipped to it.
+    Density {
+        /// The checked layer.
+        layer: L,
+        /// The side length of each window.
+        window: i64,
+        /// The minimum density.
+        #[serde(default)]
+        min: f64,
+        /// The maximum density.
+        #[serde(default = "max_density")]
+        max: f64,
+    },
+}
+
+fn max_density() -> f64 {
+    1.
+}
+
+/// The kind of a [`Rule`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RuleKind {
+    /// A [`Rule::Width`] rule.
+    Width,
+    /// A [`Rule::Spacing`] rule.
+    Spacing,
+    /// A [`Rule::Notch`] rule.
+    Notch,
+    /// A [`Rule::Enclosure`] rule.
+    Enclosure,
+    /// A [`Rule::Extension`] rule.
+    Extension,
+    /// A [`Rule::Area`] rule.
+    Area,
+    /// A [`Rule::Density`] rule.
+    Density,
+}
+
+impl Display for RuleKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Width => "width",
+            Self::Spacing => "spacing",
+            Self::Notch => "notch",
+            Self::Enclosure => "enclosure",
+            Self::Extension => "extension",
+            Self::Area => "area",
+            Self::Density => "density",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl<L> Rule<L> {
+    /// Returns the kind of this rule.
+    pub fn kind(&self) -> RuleKind {
+        match self {
+            Self::Width { .. } => RuleKind::Width,
+            Self::Spacing { .. } => RuleKind::Spacing,
+            Self::Notch { .. } => RuleKind::Notch,
+            Self::Enclosure { .. } => RuleKind::Enclosure,
+            Self::Extension { .. } => RuleKind::Extension,
+            Self::Area { .. } => RuleKind::Area,
+            Self::Density { .. } => RuleKind::Density,
+        }
+    }
+
+    /// Returns the layer on which violations of this rule are reported.
+    pub fn layer(&self) -> &L {
+        match self {
+            Self::Width { layer, .. }
+            | Self::Spacing { layer, .. }
+            | Self::Notch { layer, .. }
+            | Self::Extension { layer, .. }
+            | Self::Area { layer, .. }
+            | Self::Density { layer, .. } => layer,
+            Self::Enclosure { inner, .. } => inner,
+        }
+    }
+
+    /// Converts the layers referenced by this rule using the given function.
+    pub fn try_map_layers<M, E>(self, mut f: impl FnMut(L) -> Result<M, E>) -> Result<Rule<M>, E> {
+        Ok(match self {
+            Self::Width { layer, min } => Rule::Width {
+                layer: f(layer)?,
+                min,
+            },
+            Self::Spacing { layer, min } => Rule::Spacing {
+                layer: f(layer)?,
+                min,
+            },
+            Self::Notch { layer, min } => Rule::Notch {
+                layer: f(layer)?,
+                min,
+            },
+            Self::Enclosure { inner, outer, min } => Rule::Enclosure {
+                inner: f(inner)?,
+                outer: f(outer)?,
+                min,
+            },
+            Self::Extension { layer, over, min } => Rule::Extension {
+                layer: f(layer)?,
+                over: f(over)?,
+                min,
+            },
+            Self::Area { layer, min } => Rule::Area {
+                layer: f(layer)?,
+                min,
+            },
+            Self::Density {
+                layer,
+                window,
+                min,
+                max,
+            } => Rule::Density {
+                layer: f(layer)?,
+                window,
+                min,
+                max,
+            },
+        })
+    }
+}
+
+/// A design rule with a name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamedRule<L = LayerId> {
+    /// The name of the rule, as reported in violations.
+    pub name: ArcStr,
+    /// The rule.
+    #[serde(flatten)]
+    pub rule: Rule<L>,
+}
+
+/// A set of design rules.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RuleDeck<L = LayerId> {
+    rules: Vec<NamedRule<L>>,
+}
+
+impl<L> Default for RuleDeck<L> {
+    fn default() -> Self {
+        Self { rules: Vec::new() }
+    }
+}
+
+impl<L> RuleDeck<L> {
+    /// Creates an empty rule deck.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule to the deck.
+    pub fn add(&mut self, name: impl Into<ArcStr>, rule: Rule<L>) {
+        self.rules.push(NamedRule {
+            name: name.into(),
+            rule,
+        });
+    }
+
+    /// Adds a rule to the deck, returning the modified deck.
+    pub fn with_rule(mut self, name: impl Into<ArcStr>, rule: Rule<L>) -> Self {
+        self.add(name, rule);
+        self
+    }
+
+    /// Returns the rules in the deck.
+    pub fn rules(&self) -> &[NamedRule<L>] {
+        &self.rules
+    }
+
+    /// Appends the rules of `other` to this deck.
+    pub fn extend(&mut self, other: RuleDeck<L>) {
+        self.rules.extend(other.rules);
+    }
+}
+
+impl<L: AsRef<str>> RuleDeck<L> {
+    /// Resolves layer names to layer IDs using the given lookup function.
+    ///
+    /// Returns an error if a layer name cannot be resolved.
+    pub fn resolve(self, lookup: impl FnMut(&str) -> Option<LayerId>) -> Result<RuleDeck> {
+        let mut layer = layer_resolver(lookup);
+        let rules = self
+            .rules
+            .into_iter()
+            .map(|NamedRule { name, rule }| {
+                Ok(NamedRule {
+                    name,
+                    rule: rule.try_map_layers(|name| layer(&name))?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(RuleDeck { rules })
+    }
+}
+
+impl RuleDeck {
+    /// Checks the given cell and all cells it instantiates against the rules in this deck.
+    pub fn check(&self, cell: &RawCell) -> IssueSet<DrcViolation> {
+        self.check_flat(&cell.flatten())
+    }
+
+    /// Checks a flattened layout against the rules in this deck.
+    pub fn check_flat(&self, layout: &FlatLayout) -> IssueSet<DrcViolation> {
+        let regions = layout.regions();
+        let empty = Region::new();
+        let region = |layer: &LayerId| regions.get(layer).unwrap_or(&empty);
+        let bbox = regions
+            .values()
+            .fold(None, |acc: Option<Rect>, r| match (acc, r.bbox()) {
+                (Some(a), Some(b)) => Some(a.union(b)),
+                (a, b) => a.or(b),
+            });
+
+        let mut issues = IssueSet::new();
+        for NamedRule { name, rule } in self.rules.iter() {
+            let markers = match *rule {
+                Rule::Width { layer, min } => polygons(&thin(region(&layer), min)),
+                Rule::Spacing { layer, min } => gaps(region(&layer), min, true),
+                Rule::Notch { layer, min } => gaps(region(&layer), min, false),
+                Rule::Enclosure { inner, outer, min } => {
+                    polygons(&grow(region(&inner), min, min, min, min).difference(region(&outer)))
+                }
+                Rule::Extension { layer, over, min } => {
+                    extension(region(&layer), region(&over), min)
+                }
+                Rule::Area { layer, min } => components(region(&layer))
+                    .into_iter()
+                    .filter(|c| Region::from(c).intersection(region(&layer)).area() < min)
+                    .collect(),
+                Rule::Density {
+                    layer,
+                    window,
+                    min,
+                    max,
+                } => bbox
+                    .map(|bbox| density(region(&layer), bbox, window, min, max))
+                    .unwrap_or_default(),
+            };
+            for marker in markers {
+                issues.add(DrcViolation {
+                    rule: name.clone(),
+                    kind: rule.kind(),
+                    layer: *rule.layer(),
+                    marker: simplify(marker),
+                });
+            }
+        }
+        issues
+    }
+}
+
+impl<T: ExportsLayoutData> Cell<T> {
+    /// Checks this cell and all cells it instantiates against the given rules.
+    pub fn drc(&self, rules: &RuleDeck) -> IssueSet<DrcViolation> {
+        rules.check(&self.raw)
+    }
+}
+
+/// A violation of a design rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrcViolation {
+    rule: ArcStr,
+    kind: RuleKind,
+    layer: LayerId,
+    marker: Shape,
+}
+
+impl DrcViolation {
+    /// The name of the violated rule.
+    pub fn rule(&self) -> &ArcStr {
+        &self.rule
+    }
+
+    /// The kind of the violated rule.
+    pub fn kind(&self) -> RuleKind {
+        self.kind
+    }
+
+    /// The layer on which the violation occurred.
+    pub fn layer(&self) -> LayerId {
+        self.layer
+    }
+
+    /// The area in which the violation occurred.
+    pub fn marker(&self) -> &Shape {
+        &self.marker
+    }
+}
+
+impl Display for DrcViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} violation of rule {} on layer {:?}",
+            self.kind, self.rule, self.layer
+        )?;
+        if let Some(bbox) = self.marker.bbox() {
+            write!(
+                f,
+                " at ({}, {}) to ({}, {})",
+                bbox.left(),
+                bbox.bot(),
+                bbox.right(),
+                bbox.top()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Diagnostic for DrcViolation {
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+}
+
+/// Writes the markers of the given violations to a GDS file.
+///
+/// Markers for each rule are drawn on their own GDS layer, numbered from 1 in order
+/// of first appearance, and are labeled with the name of the rule.
+pub fn write_markers(issues: &IssueSet<DrcViolation>, path: impl AsRef<Path>) -> Result<()> {
+    let mut layers = IndexMap::new();
+    let mut cell = gds::GdsStruct::new("DRC_MARKERS");
+    for issue in issues.iter() {
+        let next = layers.len() + 1;
+        let layer = i16::try_from(*layers.entry(issue.rule.clone()).or_insert(next))
+            .map_err(GdsExportError::from)
+            .map_err(LayoutError::from)?;
+        let poly = issue.marker.to_polygon();
+        let xy = poly
+            .points()
+            .iter()
+            .chain(poly.points().first())
+            .map(gds_point)
+            .collect::<Result<Vec<_>>>()?;
+        cell.elems.push(
+            gds::GdsBoundary {
+                layer,
+                datatype: 0,
+                xy,
+                ..Default::default()
+            }
+            .into(),
+        );
+        if let Some(bbox) = poly.bbox() {
+            cell.elems.push(
+                gds::GdsTextElem {
+                    string: issue.rule.clone(),
+                    layer,
+                    texttype: 0,
+                    xy: gds_point(&bbox.center())?,
+                    ..Default::default()
+                }
+                .into(),
+            );
+        }
+    }
+    let mut lib = gds::GdsLibrary::new("DRC");
+    lib.structs.push(cell);
+    lib.save(path)
+        .map_err(GdsExportError::from)
+        .map_err(LayoutError::from)?;
+    Ok(())
+}
+
+fn gds_point(p: &Point) -> Result<gds::GdsPoint> {
+    let convert = |v: i64| {
+        i32::try_from(v)
+            .map_err(GdsExportError::from)
+            .map_err(LayoutError::from)
+    };
+    Ok(gds::GdsPoint::new(convert(p.x)?, convert(p.y)?))
+}
+
+/// Minkowski sum of `region` with the box spanning `[-left, right]` by `[-bot, top]`.
+fn grow(region: &Region, left: i64, bot: i64, right: i64, top: i64) -> Region {
+    let offsets = [(-left, -bot), (right, -bot), (right, top), (-left, top)];
+    let polys = region
+        .trapezoids()
+        .iter()
+        .map(|t| {
+            let pts = t
+                .vertices()
+                .into_iter()
+                .flat_map(|p| offsets.map(|(dx, dy)| Point::new(p.x + dx, p.y + dy)))
+                .collect();
+            convex_hull(pts)
+        })
+        .collect::<Vec<_>>();
+    Region::from_polygons(&polys)
+}
+
+/// The parts of `region` that cannot be covered by a `min` by `min` square lying within `region`.
+///
+/// Shapes are closed, so a shape exactly `min` wide would erode to a zero-area line under a
+/// `min` by `min` square. The opening is therefore done with a square one unit smaller,
+/// which still leaves every shape narrower than `min` with nothing after erosion.
+fn thin(region: &Region, min: i64) -> Region {
+    let Some(bbox) = region.bbox() else {
+        return Region::new();
+    };
+    let size = min - 1;
+    if size <= 0 {
+        return Region::new();
+    }
+    let universe = Region::from(bbox.expand_all(min + 1));
+    let outside = universe.difference(region);
+    let eroded = universe.difference(&grow(&outside, size, size, 0, 0));
+    let opened = grow(&eroded, 0, 0, size, size);
+    region.difference(&opened)
+}
+
+/// Gaps narrower than `min` between distinct components of `region` (if `spacing` is true)
+/// or between parts of the same component (otherwise).
+fn gaps(region: &Region, min: i64, spacing: bool) -> Vec<Polygon> {
+    let Some(bbox) = region.bbox() else {
+        return Vec::new();
+    };
+    let outside = Region::from(bbox.expand_all(2 * min + 1)).difference(region);
+    let components = components(region)
+        .into_iter()
+        .map(Region::from)
+        .collect::<Vec<_>>();
+    polygons(&thin(&outside, min))
+        .into_iter()
+        .filter(|gap| {
+            let touching = grow(&Region::from(gap), 1, 1, 1, 1);
+            let n = components
+                .iter()
+                .filter(|c| !c.intersection(&touching).is_empty())
+                .count();
+            if spacing {
+                n > 1
+            } else {
+                n == 1
+            }
+        })
+        .collect()
+}
+
+fn extension(layer: &Region, over: &Region, min: i64) -> Vec<Polygon> {
+    let overlap = layer.intersection(over);
+    let pieces = overlap.rects().unwrap_or_else(|| {
+        overlap
+            .trapezoids()
+            .iter()
+            .filter_map(|t| Polygon::from_verts(t.vertices()).bbox())
+            .collect()
+    });
+    let mut missing = Region::new();
+    for p in pieces {
+        for strip in [
+            Rect::from_sides(p.left() - min, p.bot(), p.left(), p.top()),
+            Rect::from_sides(p.right(), p.bot(), p.right() + min, p.top()),
+            Rect::from_sides(p.left(), p.bot() - min, p.right(), p.bot()),
+            Rect::from_sides(p.left(), p.top(), p.right(), p.top() + min),
+        ] {
+            if min <= 0 || strip.width() == 0 || strip.height() == 0 {
+                continue;
+            }
+            // Only the parts of the strip beyond the edge of `over` must be covered.
+            let exposed = Region::from(strip).difference(over);
+            missing = missing.union(&exposed.difference(layer));
+        }
+    }
+    polygons(&missing)
+}
+
+fn density(region: &Region, bbox: Rect, window: i64, min: f64, max: f64) -> Vec<Polygon> {
+    let mut out = Vec::new();
+    if window <= 0 {
+        return out;
+    }
+    let mut y = bbox.bot();
+    while y < bbox.top() {
+        let mut x = bbox.left();
+        while x < bbox.right() {
+            let w = Rect::from_sides(
+                x,
+                y,
+                (x + window).min(bbox.right()),
+                (y + window).min(bbox.top()),
+            );
+            let covered = region.intersection(&Region::from(w)).area() as f64;
+            let d = covered / w.area() as f64;
+            if d < min || d > max {
+                out.push(Shape::Rect(w).to_polygon());
+            }
+            x += window;
+        }
+        y += window;
+    }
+    out
+}
+
+/// The outer boundaries of the connected components of `region`.
+fn components(region: &Region) -> Vec<Polygon> {
+    region
+        .boundaries()
+        .into_iter()
+        .filter(|b| b.winding() == Some(Winding::CounterClockwise))
+        .collect()
+}
+
+/// Splits a marker region into polygons.
+///
+/// Regions with holes are split into rectangles or trapezoids.
+fn polygons(region: &Region) -> Vec<Polygon> {
+    let boundaries = region.boundaries();
+    if boundaries
+        .iter()
+        .all(|b| b.winding() == Some(Winding::CounterClockwise))
+    {
+        boundaries
+    } else {
+        region_shapes(region)
+            .iter()
+            .map(Shape::to_polygon)
+            .collect()
+    }
+}
+
+/// Converts rectangular markers to [`Rect`]s.
+fn simplify(marker: Polygon) -> Shape {
+    let pts = marker.points();
+    if pts.len() == 4 {
+        if let Some(bbox) = marker.bbox() {
+            if pts.iter().all(|p| {
+                [
+                    Corner::LowerLeft,
+                    Corner::LowerRight,
+                    Corner::UpperLeft,
+                    Corner::UpperRight,
+                ]
+                .iter()
+                .any(|c| bbox.corner(*c) == *p)
+            }) {
+                return Shape::Rect(bbox);
+            }
+        }
+    }
+    Shape::Polygon(marker)
+}
+
+/// Returns the convex hull of the given points, in counterclockwise order.
+fn convex_hull(mut pts: Vec<Point>) -> Polygon {
+    pts.sort_by_key(|p| (p.x, p.y));
+    pts.dedup();
+    let cross = |o: Point, a: Point, b: Point| {
+        (a.x - o.x) as i128 * (b.y - o.y) as i128 - (a.y - o.y) as i128 * (b.x - o.x) as i128
+    };
+    let mut hull: Vec<Point> = Vec::with_capacity(2 * pts.len());
+    for pass in [pts.clone(), pts.into_iter().rev().collect()] {
+        let start = hull.len();
+        for p in pass {
+            while hull.len() >= start + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        hull.pop();
+    }
+    Polygon::from_verts(hull)
+}
@@ -9,7 +9,8 @@ use geometry::{
     prelude::{Bbox, Point},
     rect::Rect,
     transform::{
-        HasTransformedView, Transform, TransformMut, Transformation, Transformed, TranslateMut,
+        HasTransformedView, Transform, TransformMut, Transformation, Transformed, Translate,
+        TranslateMut,
     },
     union::BoundingUnion,
 };
@@ -22,7 +23,7 @@ use crate::{
     pdk::{layers::LayerId, Pdk},
 };
 
-use super::{Draw, DrawReceiver, ExportsLayoutData, Instance};
+use super::{Draw, DrawReceiver, ExportsLayoutData, Instance, InstanceArray};
 
 /// A context-wide unique identifier for a cell.
 #[derive(
@@ -174,6 +175,28 @@ impl<'a> TransformedRawCell<'a> {
     }
 }
 
+/// A property attached to a layout element.
+///
+/// Corresponds to a GDS property, which consists of an attribute number and a string value.
+/// Properties are commonly used by downstream tools for annotations such as net names.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Property {
+    /// The attribute number.
+    pub attr: i16,
+    /// The value of the property.
+    pub value: ArcStr,
+}
+
+impl Property {
+    /// Creates a new property.
+    pub fn new(attr: i16, value: impl Into<ArcStr>) -> Self {
+        Self {
+            attr,
+            value: value.into(),
+        }
+    }
+}
+
 /// A raw layout instance.
 ///
 /// Consists of a pointer to an underlying cell and its instantiated transformation.
@@ -182,6 +205,7 @@ impl<'a> TransformedRawCell<'a> {
 pub struct RawInstance {
     pub(crate) cell: Arc<RawCell>,
     pub(crate) trans: Transformation,
+    pub(crate) properties: Vec<Property>,
 }
 
 impl RawInstance {
@@ -190,14 +214,26 @@ impl RawInstance {
         Self {
             cell: cell.into(),
             trans,
+            properties: Vec::new(),
         }
     }
 
+    /// Adds a property to the instance.
+    pub fn with_property(mut self, property: Property) -> Self {
+        self.properties.push(property);
+        self
+    }
+
     /// Returns a reference to the child cell.
     #[inline]
     pub fn cell(&self) -> Transformed<'_, RawCell> {
         self.cell.transformed_view(self.trans)
     }
+
+    /// Returns the properties of the instance.
+    pub fn properties(&self) -> &[Property] {
+        &self.properties
+    }
 }
 
 impl Bbox for RawInstance {
@@ -210,10 +246,7 @@ impl<T: ExportsLayoutData> TryFrom<Instance<T>> for RawInstance {
     type Error = Error;
 
     fn try_from(value: Instance<T>) -> Result<Self> {
-        Ok(Self {
-            cell: value.try_cell()?.raw,
-            trans: value.trans,
-        })
+        Ok(Self::new(value.try_cell()?.raw, value.trans))
     }
 }
 
@@ -244,12 +277,199 @@ impl HasTransformedView for RawInstance {
     }
 }
 
+/// A raw two-dimensional array of layout instances.
+///
+/// Consists of a pointer to an underlying cell, the transformation of the instance
+/// at row 0 and column 0, and the pitch vectors between adjacent columns and rows.
+/// Pitch vectors are given in the coordinate system of the parent cell.
+///
+/// The instance at row `i` and column `j` is translated by `i * row_pitch + j * col_pitch`
+/// relative to the instance at row 0 and column 0.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct RawInstanceArray {
+    pub(crate) cell: Arc<RawCell>,
+    pub(crate) trans: Transformation,
+    pub(crate) rows: usize,
+    pub(crate) cols: usize,
+    pub(crate) row_pitch: Point,
+    pub(crate) col_pitch: Point,
+    pub(crate) properties: Vec<Property>,
+}
+
+impl RawInstanceArray {
+    /// Create a new raw instance array of the given cell.
+    pub fn new(
+        cell: impl Into<Arc<RawCell>>,
+        trans: Transformation,
+        rows: usize,
+        cols: usize,
+        row_pitch: Point,
+        col_pitch: Point,
+    ) -> Self {
+        Self {
+            cell: cell.into(),
+            trans,
+            rows,
+            cols,
+            row_pitch,
+            col_pitch,
+            properties: Vec::new(),
+        }
+    }
+
+    /// Adds a property to the array.
+    pub fn with_property(mut self, property: Property) -> Self {
+        self.properties.push(property);
+        self
+    }
+
+    /// Returns the properties of the array.
+    pub fn properties(&self) -> &[Property] {
+        &self.properties
+    }
+
+    /// Returns a reference to the child cell, transformed by the transformation
+    /// of the instance at row 0 and column 0.
+    #[inline]
+    pub fn cell(&self) -> Transformed<'_, RawCell> {
+        self.cell.transformed_view(self.trans)
+    }
+
+    /// The number of rows in the array.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The number of columns in the array.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// The displacement between adjacent rows.
+    pub fn row_pitch(&self) -> Point {
+        self.row_pitch
+    }
+
+    /// The displacement between adjacent columns.
+    pub fn col_pitch(&self) -> Point {
+        self.col_pitch
+    }
+
+    /// The total number of instances in the array.
+    pub fn len(&self) -> usize {
+        self.rows * self.cols
+    }
+
+    /// Returns `true` if the array contains no instances.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn offset(&self, row: usize, col: usize) -> Point {
+        let (row, col) = (row as i64, col as i64);
+        Point::new(
+            row * self.row_pitch.x + col * self.col_pitch.x,
+            row * self.row_pitch.y + col * self.col_pitch.y,
+        )
+    }
+
+    /// Returns the instance at the given row and column, if it exists.
+    pub fn instance(&self, row: usize, col: usize) -> Option<RawInstance> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        Some(RawInstance::new(
+            self.cell.clone(),
+            Transformation::cascade(
+                Transformation::from_offset(self.offset(row, col)),
+                self.trans,
+            ),
+        ))
+    }
+
+    /// Returns an iterator over the instances of the array in row-major order.
+    pub fn instances(&self) -> impl Iterator<Item = RawInstance> + '_ {
+        (0..self.rows)
+            .flat_map(move |row| (0..self.cols).map(move |col| (row, col)))
+            .map(|(row, col)| self.instance(row, col).unwrap())
+    }
+}
+
+impl Bbox for RawInstanceArray {
+    fn bbox(&self) -> Option<Rect> {
+        if self.is_empty() {
+            return None;
+        }
+        let rect = self.cell.bbox()?.transform(self.trans);
+        // The instances lie on a parallelogram lattice, so the instances at its
+        // corners bound the entire array.
+        [
+            (0, 0),
+            (0, self.cols - 1),
+            (self.rows - 1, 0),
+            (self.rows - 1, self.cols - 1),
+        ]
+        .into_iter()
+        .map(|(row, col)| rect.translate(self.offset(row, col)))
+        .reduce(|a, b| a.union(b))
+    }
+}
+
+impl<T: ExportsLayoutData> TryFrom<InstanceArray<T>> for RawInstanceArray {
+    type Error = Error;
+
+    fn try_from(value: InstanceArray<T>) -> Result<Self> {
+        Ok(Self {
+            cell: value.inst.try_cell()?.raw,
+            trans: value.inst.trans,
+            rows: value.rows,
+            cols: value.cols,
+            row_pitch: value.row_pitch,
+            col_pitch: value.col_pitch,
+            properties: Vec::new(),
+        })
+    }
+}
+
+impl<PDK: Pdk> Draw<PDK> for RawInstanceArray {
+    fn draw(self, recv: &mut DrawReceiver<PDK>) -> Result<()> {
+        recv.draw_element(self);
+        Ok(())
+    }
+}
+
+impl TranslateMut for RawInstanceArray {
+    fn translate_mut(&mut self, p: Point) {
+        self.transform_mut(Transformation::from_offset(p));
+    }
+}
+
+impl TransformMut for RawInstanceArray {
+    fn transform_mut(&mut self, trans: Transformation) {
+        self.trans = Transformation::cascade(trans, self.trans);
+        // Pitch vectors are displacements, so only the orientation applies.
+        let orientation =
+            Transformation::from_offset_and_orientation(Point::zero(), trans.orientation());
+        self.row_pitch.transform_mut(orientation);
+        self.col_pitch.transform_mut(orientation);
+    }
+}
+
+impl HasTransformedView for RawInstanceArray {
+    type TransformedView<'a> = RawInstanceArray;
+
+    fn transformed_view(&self, trans: Transformation) -> Self::TransformedView<'_> {
+        self.clone().transform(trans)
+    }
+}
+
 /// A primitive layout shape consisting of a layer and a geometric shape.
 #[derive(Debug, Clone, Eq, PartialEq)]
 #[allow(dead_code)]
 pub struct Shape {
     layer: LayerId,
     shape: geometry::shape::Shape,
+    pub(crate) properties: Vec<Property>,
 }
 
 impl Shape {
@@ -258,9 +478,21 @@ impl Shape {
         Self {
             layer: *layer.as_ref(),
             shape: shape.into(),
+            properties: Vec::new(),
         }
     }
 
+    /// Adds a property to the shape.
+    pub fn with_property(mut self, property: Property) -> Self {
+        self.properties.push(property);
+        self
+    }
+
+    /// Returns the properties of the shape.
+    pub fn properties(&self) -> &[Property] {
+        &self.properties
+    }
+
     /// Returns the layer that this shape is on.
     pub fn layer(&self) -> LayerId {
         self.layer
@@ -293,6 +525,7 @@ impl HasTransformedView for Shape {
         Shape {
             layer: self.layer,
             shape: self.shape.transformed_view(trans),
+            properties: self.properties.clone(),
         }
     }
 }
@@ -316,13 +549,62 @@ impl<PDK: Pdk> Draw<PDK> for Shape {
     }
 }
 
+/// The horizontal justification of a text annotation relative to its location.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HorizontalJustification {
+    /// The text begins at its location.
+    #[default]
+    Left,
+    /// The text is centered on its location.
+    Center,
+    /// The text ends at its location.
+    Right,
+}
+
+/// The vertical justification of a text annotation relative to its location.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VerticalJustification {
+    /// The top of the text is at its location.
+    #[default]
+    Top,
+    /// The text is centered on its location.
+    Middle,
+    /// The bottom of the text is at its location.
+    Bottom,
+}
+
+/// The justification of a text annotation relative to its location.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextJustification {
+    /// The horizontal justification.
+    pub horizontal: HorizontalJustification,
+    /// The vertical justification.
+    pub vertical: VerticalJustification,
+}
+
+impl TextJustification {
+    /// Creates a new text justification.
+    pub fn new(horizontal: HorizontalJustification, vertical: VerticalJustification) -> Self {
+        Self {
+            horizontal,
+            vertical,
+        }
+    }
+}
+
 /// A primitive text annotation consisting of a layer, string, and location.
+///
+/// Text annotations may also specify how they are presented by layout viewers.
 #[derive(Default, Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub struct Text {
     layer: LayerId,
     text: ArcStr,
     pub(crate) trans: Transformation,
+    pub(crate) justification: TextJustification,
+    pub(crate) font: u8,
+    pub(crate) mag: Option<f64>,
+    pub(crate) properties: Vec<Property>,
 }
 
 impl Text {
@@ -332,9 +614,67 @@ impl Text {
             layer: *layer.as_ref(),
             text: text.into(),
             trans,
+            justification: TextJustification::default(),
+            font: 0,
+            mag: None,
+            properties: Vec::new(),
         }
     }
 
+    /// Sets the justification of the text relative to its location.
+    pub fn with_justification(mut self, justification: TextJustification) -> Self {
+        self.justification = justification;
+        self
+    }
+
+    /// Sets the font number of the text.
+    ///
+    /// GDS supports fonts 0 through 3, whose appearance is defined by layout viewers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `font` is greater than 3.
+    pub fn with_font(mut self, font: u8) -> Self {
+        assert!(font <= 3, "GDS text fonts range from 0 to 3, got {font}");
+        self.font = font;
+        self
+    }
+
+    /// Sets the magnification of the text.
+    ///
+    /// GDS stores the size of text only as a magnification, which viewers such as KLayout
+    /// display as the height of the text in user units.
+    pub fn with_mag(mut self, mag: f64) -> Self {
+        self.mag = Some(mag);
+        self
+    }
+
+    /// Adds a property to the text annotation.
+    pub fn with_property(mut self, property: Property) -> Self {
+        self.properties.push(property);
+        self
+    }
+
+    /// Gets the justification of the text relative to its location.
+    pub fn justification(&self) -> TextJustification {
+        self.justification
+    }
+
+    /// Gets the font number of the text.
+    pub fn font(&self) -> u8 {
+        self.font
+    }
+
+    /// Gets the magnification of the text, if specified.
+    pub fn mag(&self) -> Option<f64> {
+        self.mag
+    }
+
+    /// Gets the properties of the text annotation.
+    pub fn properties(&self) -> &[Property] {
+        &self.properties
+    }
+
     /// Gets the layer that this annotation is on.
     pub fn layer(&self) -> LayerId {
         self.layer
@@ -378,6 +718,8 @@ impl<PDK: Pdk> Draw<PDK> for Text {
 pub enum Element {
     /// A raw layout instance.
     Instance(RawInstance),
+    /// A raw array of layout instances.
+    InstanceArray(RawInstanceArray),
     /// A primitive layout shape.
     Shape(Shape),
     /// A primitive text annotation.
@@ -389,6 +731,8 @@ pub enum Element {
 pub enum ElementRef<'a> {
     /// A raw layout instance.
     Instance(&'a RawInstance),
+    /// A raw array of layout instances.
+    InstanceArray(&'a RawInstanceArray),
     /// A primitive layout shape.
     Shape(&'a Shape),
     /// A primitive text annotation.
@@ -403,6 +747,7 @@ impl Element {
     pub fn as_ref(&self) -> ElementRef<'_> {
         match self {
             Self::Instance(x) => ElementRef::Instance(x),
+            Self::InstanceArray(x) => ElementRef::InstanceArray(x),
             Self::Shape(x) => ElementRef::Shape(x),
             Self::Text(x) => ElementRef::Text(x),
         }
@@ -417,6 +762,15 @@ impl Element {
         }
     }
 
+    /// If this is an `InstanceArray` variant, returns the contained instance array.
+    /// Otherwise, returns [`None`].
+    pub fn instance_array(self) -> Option<RawInstanceArray> {
+        match self {
+            Self::InstanceArray(x) => Some(x),
+            _ => None,
+        }
+    }
+
     /// If this is a `Shape` variant, returns the contained shape.
     /// Otherwise, returns [`None`].
     pub fn shape(self) -> Option<Shape> {
@@ -446,6 +800,15 @@ impl<'a> ElementRef<'a> {
         }
     }
 
+    /// If this is an `InstanceArray` variant, returns the contained instance array.
+    /// Otherwise, returns [`None`].
+    pub fn instance_array(self) -> Option<&'a RawInstanceArray> {
+        match self {
+            Self::InstanceArray(x) => Some(x),
+            _ => None,
+        }
+    }
+
     /// If this is a `Shape` variant, returns the contained shape.
     /// Otherwise, returns [`None`].
     pub fn shape(self) -> Option<&'a Shape> {
@@ -469,6 +832,7 @@ impl Bbox for Element {
     fn bbox(&self) -> Option<geometry::rect::Rect> {
         match self {
             Element::Instance(inst) => inst.bbox(),
+            Element::InstanceArray(array) => array.bbox(),
             Element::Shape(shape) => shape.bbox(),
             Element::Text(_) => None,
         }
@@ -481,6 +845,12 @@ impl From<RawInstance> for Element {
     }
 }
 
+impl From<RawInstanceArray> for Element {
+    fn from(value: RawInstanceArray) -> Self {
+        Self::InstanceArray(value)
+    }
+}
+
 impl From<Shape> for Element {
     fn from(value: Shape) -> Self {
         Self::Shape(value)
@@ -505,6 +875,7 @@ impl TranslateMut for Element {
     fn translate_mut(&mut self, p: Point) {
         match self {
             Element::Instance(inst) => inst.translate_mut(p),
+            Element::InstanceArray(array) => array.translate_mut(p),
             Element::Shape(shape) => shape.translate_mut(p),
             Element::Text(text) => text.translate_mut(p),
         }
@@ -515,6 +886,7 @@ impl TransformMut for Element {
     fn transform_mut(&mut self, trans: Transformation) {
         match self {
             Element::Instance(inst) => inst.transform_mut(trans),
+            Element::InstanceArray(array) => array.transform_mut(trans),
             Element::Shape(shape) => shape.transform_mut(trans),
             Element::Text(text) => text.transform_mut(trans),
         }
@@ -532,6 +904,7 @@ impl Bbox for ElementRef<'_> {
     fn bbox(&self) -> Option<geometry::rect::Rect> {
         match self {
             ElementRef::Instance(inst) => inst.bbox(),
+            ElementRef::InstanceArray(array) => array.bbox(),
             ElementRef::Shape(shape) => shape.bbox(),
             ElementRef::Text(_) => None,
         }
@@ -544,6 +917,12 @@ impl<'a> From<&'a RawInstance> for ElementRef<'a> {
     }
 }
 
+impl<'a> From<&'a RawInstanceArray> for ElementRef<'a> {
+    fn from(value: &'a RawInstanceArray) -> Self {
+        Self::InstanceArray(value)
+    }
+}
+
 impl<'a> From<&'a Shape> for ElementRef<'a> {
     fn from(value: &'a Shape) -> Self {
         Self::Shape(value)
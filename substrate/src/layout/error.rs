@@ -15,6 +15,30 @@ pub enum LayoutError {
     /// An error with defining the IO of a Substrate layout cell.
     #[error("error specifying layout IO")]
     IoDefinition,
+    /// A layer name that does not correspond to any installed layer.
+    #[error("unknown layer: {0}")]
+    UnknownLayer(ArcStr),
+    /// A net that could not be routed.
+    #[error("routing failed: {0}")]
+    Routing(ArcStr),
+    /// A layout whose netlist could not be extracted.
+    #[error("extraction failed: {0}")]
+    Extraction(ArcStr),
+    /// A via that could not be generated.
+    #[error("invalid via: {0}")]
+    Via(ArcStr),
+    /// A cell that could not be exported as a LEF abstract.
+    #[error("error exporting LEF: {0}")]
+    LefExport(ArcStr),
+    /// An error converting LEF or DEF data to Substrate cells.
+    #[error("error importing LEF/DEF: {0}")]
+    LefDefImport(ArcStr),
+    /// A malformed layer map or layer properties file.
+    #[error("error parsing layer map: {0}")]
+    LayerMap(ArcStr),
+    /// A color that is not of the form `#rrggbb`.
+    #[error("invalid color: {0}")]
+    InvalidColor(ArcStr),
 }
 
 impl From<GdsExportError> for LayoutError {
@@ -35,6 +59,9 @@ pub enum GdsExportError {
     /// An error in writing a GDS file.
     #[error("error writing GDS file: {0:?}")]
     Write(gds::GdsError),
+    /// A layer with no entry in the layer map used for export.
+    #[error("layer `{0}` has no entry in the layer map")]
+    UnmappedLayer(ArcStr),
 }
 
 impl From<std::num::TryFromIntError> for GdsExportError {
@@ -0,0 +1,159 @@
+//! Flattening of layout hierarchies.
+
+use geometry::boolean::Region;
+use geometry::polygon::Winding;
+use geometry::shape::Shape;
+use geometry::transform::{HasTransformedView, Transform, Transformation};
+use indexmap::IndexMap;
+
+use crate::io::{NameBuf, PortGeometry};
+use crate::pdk::layers::LayerId;
+
+use super::element::{Element, RawCell, Text};
+use super::{Cell, ExportsLayoutData};
+
+/// A port of a cell in a flattened layout hierarchy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlatPort {
+    /// The path to the cell containing the port.
+    ///
+    /// Each entry is the index of an instance among the instances of its parent cell.
+    /// Ports of the top cell have an empty path.
+    pub path: Vec<usize>,
+    /// The name of the port.
+    pub name: NameBuf,
+    /// The geometry of the port, in the coordinate system of the top cell.
+    pub geometry: PortGeometry,
+}
+
+/// The contents of a layout cell with its instance hierarchy flattened.
+///
+/// All geometry is given in the coordinate system of the flattened cell.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FlatLayout {
+    /// Shapes, grouped by layer.
+    pub shapes: IndexMap<LayerId, Vec<Shape>>,
+    /// Text annotations.
+    pub texts: Vec<Text>,
+    /// Ports of the flattened cell and of all cells it instantiates.
+    pub ports: Vec<FlatPort>,
+}
+
+impl FlatLayout {
+    /// Returns the shapes on the given layer.
+    pub fn shapes_on(&self, layer: LayerId) -> &[Shape] {
+        self.shapes
+            .get(&layer)
+            .map(|v| v.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Returns the ports of the flattened cell itself.
+    pub fn top_ports(&self) -> impl Iterator<Item = &FlatPort> {
+        self.ports.iter().filter(|port| port.path.is_empty())
+    }
+
+    /// Returns the region covered by the shapes on each layer.
+    pub fn regions(&self) -> IndexMap<LayerId, Region> {
+        self.shapes
+            .iter()
+            .map(|(layer, shapes)| {
+                let polygons = shapes.iter().map(Shape::to_polygon).collect::<Vec<_>>();
+                (*layer, Region::from_polygons(&polygons))
+            })
+            .collect()
+    }
+
+    /// Merges overlapping and touching shapes on each layer.
+    ///
+    /// Manhattan layers are decomposed into rectangles. Other layers are converted to
+    /// their boundary polygons, or to trapezoids if they contain holes.
+    pub fn merge(&mut self) {
+        self.shapes = self
+            .regions()
+            .into_iter()
+            .map(|(layer, region)| (layer, region_shapes(&region)))
+            .collect();
+    }
+
+    /// Returns a copy of this layout with overlapping shapes on each layer merged.
+    ///
+    /// See [`FlatLayout::merge`].
+    pub fn merged(mut self) -> Self {
+        self.merge();
+        self
+    }
+
+    fn add(&mut self, cell: &RawCell, trans: Transformation, path: &mut Vec<usize>) {
+        for (name, port) in cell.ports() {
+            self.ports.push(FlatPort {
+                path: path.clone(),
+                name: name.clone(),
+                geometry: port.transformed_view(trans).into(),
+            });
+        }
+
+        let mut instances = 0;
+        for elem in cell.elements() {
+            match elem {
+                Element::Instance(inst) => {
+                    path.push(instances);
+                    self.add(&inst.cell, Transformation::cascade(trans, inst.trans), path);
+                    path.pop();
+                    instances += 1;
+                }
+                Element::InstanceArray(array) => {
+                    for inst in array.instances() {
+                        path.push(instances);
+                        self.add(&inst.cell, Transformation::cascade(trans, inst.trans), path);
+                        path.pop();
+                        instances += 1;
+                    }
+                }
+                Element::Shape(shape) => {
+                    self.shapes
+                        .entry(shape.layer())
+                        .or_default()
+                        .push(shape.shape().clone().transform(trans));
+                }
+                Element::Text(text) => self.texts.push(text.clone().transform(trans)),
+            }
+        }
+    }
+}
+
+/// Converts a region to a set of shapes covering the same area.
+pub(crate) fn region_shapes(region: &Region) -> Vec<Shape> {
+    if let Some(rects) = region.rects() {
+        return rects.into_iter().map(Shape::Rect).collect();
+    }
+    let boundaries = region.boundaries();
+    if boundaries
+        .iter()
+        .all(|b| b.winding() == Some(Winding::CounterClockwise))
+    {
+        boundaries.into_iter().map(Shape::Polygon).collect()
+    } else {
+        region
+            .trapezoids()
+            .iter()
+            .map(|t| Shape::Polygon(geometry::polygon::Polygon::from_verts(t.vertices())))
+            .collect()
+    }
+}
+
+impl RawCell {
+    /// Flattens the instance hierarchy of this cell.
+    pub fn flatten(&self) -> FlatLayout {
+        let mut flat = FlatLayout::default();
+        flat.add(self, Transformation::identity(), &mut Vec::new());
+        flat
+    }
+}
+
+impl<T: ExportsLayoutData> Cell<T> {
+    /// Flattens the instance hierarchy of this cell.
+    pub fn flatten(&self) -> FlatLayout {
+        self.raw.flatten()
+    }
+}
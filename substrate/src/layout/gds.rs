@@ -2,12 +2,12 @@
 //!
 //! Converts between Substrate's layout data-model and [`gds`] structures.
 
-use std::collections::HashSet;
+use std::io::Write;
 use std::{collections::HashMap, sync::Arc};
 
 use arcstr::ArcStr;
-use gds::HasLayer;
-use geometry::prelude::Polygon;
+pub use gds::GdsDepOrder;
+use geometry::prelude::{Path, PathEnd, Polygon};
 use geometry::transform::Transformation;
 use geometry::{
     prelude::{Corner, Orientation, Point},
@@ -22,6 +22,7 @@ use uniquify::Names;
 
 use crate::io::{LayoutBundleBuilder, LayoutType};
 use crate::layout::error::GdsExportError;
+use crate::pdk::layer_map::LayerMap;
 use crate::pdk::layers::LayerInfo;
 use crate::{
     io::{IoShape, NameBuf, PortGeometry},
@@ -31,7 +32,10 @@ use crate::{
 use super::error::{GdsImportError, GdsImportResult};
 use super::LayoutContext;
 use super::{
-    element::{CellId, Element, RawCell, RawInstance, Shape, Text},
+    element::{
+        CellId, Element, HorizontalJustification, Property, RawCell, RawInstance, RawInstanceArray,
+        Shape, Text, TextJustification, VerticalJustification,
+    },
     error::GdsExportResult,
 };
 
@@ -42,12 +46,17 @@ new_key_type! {
 
 /// An exporter for GDS files.
 ///
-/// Takes a [`RawCell`] and converts it to a [`gds::GdsLibrary`].
+/// Takes a [`RawCell`] and converts it to a [`gds::GdsLibrary`], or writes it directly to a
+/// GDS file.
 pub struct GdsExporter<'a> {
     cell: Arc<RawCell>,
     layers: &'a LayerContext,
+    layer_map: Option<HashMap<LayerId, GdsLayerSpec>>,
+    drop_unmapped_layers: bool,
     cell_db: Names<CellId>,
     gds: gds::GdsLibrary,
+    /// If present, exported cells are written here rather than added to `gds`.
+    stream: Option<gds::GdsStreamWriter<'a>>,
 }
 
 impl<'a> GdsExporter<'a> {
@@ -59,17 +68,66 @@ impl<'a> GdsExporter<'a> {
         Self {
             cell,
             layers,
+            layer_map: None,
+            drop_unmapped_layers: false,
             cell_db: Default::default(),
             gds: gds::GdsLibrary::new("TOP"),
+            stream: None,
         }
     }
 
+    /// Writes layers to the GDS layers given by a layer map rather than their own GDS layers.
+    ///
+    /// Layers are matched to entries of the map by name. Exporting an element on a layer
+    /// without an entry is an error, unless [`GdsExporter::with_unmapped_layers_dropped`]
+    /// is set.
+    pub fn with_layer_map(mut self, map: &LayerMap) -> Self {
+        self.layer_map = Some(self.layers.layer_map_export(map));
+        self
+    }
+
+    /// Skips elements on layers without an entry in the layer map, rather than failing.
+    ///
+    /// Has no effect unless a layer map is set using [`GdsExporter::with_layer_map`].
+    pub fn with_unmapped_layers_dropped(mut self) -> Self {
+        self.drop_unmapped_layers = true;
+        self
+    }
+
     /// Exports the contents of `self` as a [`gds::GdsLibrary`].
     pub fn export(mut self) -> GdsExportResult<gds::GdsLibrary> {
         self.cell.clone().export(&mut self)?;
         Ok(self.gds)
     }
 
+    /// Exports the contents of `self` to `dest` in GDS format.
+    ///
+    /// Each cell is written as soon as it is converted, in dependency order, rather than
+    /// collecting the whole library first. Child cells are converted while their parents are
+    /// partially converted, so memory use is proportional to the depth of the hierarchy:
+    /// at most one partially converted cell per level is held at a time.
+    pub fn export_to(mut self, dest: impl Write + 'a) -> GdsExportResult<()> {
+        self.stream = Some(gds::GdsStreamWriter::new(dest, &self.gds)?);
+        self.finish_stream()
+    }
+
+    /// Exports the contents of `self` to a GDS file at `path`.
+    ///
+    /// See [`GdsExporter::export_to`].
+    pub fn export_to_file(mut self, path: impl AsRef<std::path::Path>) -> GdsExportResult<()> {
+        self.stream = Some(gds::GdsStreamWriter::open(path, &self.gds)?);
+        self.finish_stream()
+    }
+
+    fn finish_stream(mut self) -> GdsExportResult<()> {
+        self.cell.clone().export(&mut self)?;
+        self.stream
+            .take()
+            .expect("stream should be present")
+            .finish()?;
+        Ok(())
+    }
+
     fn get_name(&self, cell: &RawCell) -> Option<ArcStr> {
         self.cell_db.name(&cell.id)
     }
@@ -78,8 +136,20 @@ impl<'a> GdsExporter<'a> {
         self.cell_db.assign_name(cell.id, &cell.name)
     }
 
-    fn get_layer(&self, id: LayerId) -> Option<GdsLayerSpec> {
-        self.layers.get_gds_layer_from_id(id)
+    fn get_layer(&self, id: LayerId) -> GdsExportResult<Option<GdsLayerSpec>> {
+        let Some(ref layer_map) = self.layer_map else {
+            return Ok(self.layers.get_gds_layer_from_id(id));
+        };
+        match layer_map.get(&id) {
+            Some(spec) => Ok(Some(*spec)),
+            None if self.drop_unmapped_layers => Ok(None),
+            None => Err(GdsExportError::UnmappedLayer(
+                self.layers
+                    .get_layer_info(id)
+                    .map(|info| info.name.clone())
+                    .unwrap_or_else(|| arcstr::format!("{id:?}")),
+            )),
+        }
     }
 }
 
@@ -103,7 +173,8 @@ trait ExportGds {
 }
 
 impl ExportGds for RawCell {
-    type Output = gds::GdsStruct;
+    /// The name of the exported cell.
+    type Output = ArcStr;
 
     fn export(&self, exporter: &mut GdsExporter<'_>) -> GdsExportResult<Self::Output> {
         let name = exporter.assign_name(self);
@@ -112,19 +183,20 @@ impl ExportGds for RawCell {
         let span = span!(Level::INFO, "cell", name = name_str);
         let _guard = span.enter();
 
-        let mut cell = gds::GdsStruct::new(name);
+        let mut cell = gds::GdsStruct::new(name.clone());
 
         cell.elems.extend(self.port_map().export(exporter)?);
 
         for element in self.elements.iter() {
-            if let Some(elem) = element.export(exporter)? {
-                cell.elems.push(elem);
-            }
+            cell.elems.extend(element.export(exporter)?);
         }
 
-        exporter.gds.structs.push(cell.clone());
+        match exporter.stream {
+            Some(ref mut stream) => stream.write_struct(&cell)?,
+            None => exporter.gds.structs.push(cell),
+        }
 
-        Ok(cell)
+        Ok(name)
     }
 }
 
@@ -183,6 +255,7 @@ impl PlaceLabels for geometry::shape::Shape {
         match self {
             geometry::shape::Shape::Rect(ref r) => r.label_loc(),
             geometry::shape::Shape::Polygon(ref p) => p.label_loc(),
+            geometry::shape::Shape::Path(ref p) => p.label_loc(),
         }
     }
 }
@@ -199,6 +272,12 @@ impl PlaceLabels for Polygon {
     }
 }
 
+impl PlaceLabels for Path {
+    fn label_loc(&self) -> Point {
+        self.points()[0]
+    }
+}
+
 impl ExportGds for (&NameBuf, &IoShape) {
     type Output = Vec<gds::GdsElement>;
 
@@ -224,16 +303,25 @@ impl ExportGds for (&NameBuf, &IoShape) {
 }
 
 impl ExportGds for Element {
-    type Output = Option<gds::GdsElement>;
+    type Output = Vec<gds::GdsElement>;
 
     fn export(&self, exporter: &mut GdsExporter<'_>) -> GdsExportResult<Self::Output> {
         let span = span!(Level::INFO, "element", element = ?self);
         let _guard = span.enter();
 
         Ok(match self {
-            Element::Instance(instance) => Some(instance.export(exporter)?.into()),
-            Element::Shape(shape) => shape.export(exporter)?,
-            Element::Text(text) => text.export(exporter)?.map(|text| text.into()),
+            Element::Instance(instance) => vec![instance.export(exporter)?.into()],
+            Element::InstanceArray(array) => array
+                .export(exporter)?
+                .into_iter()
+                .map(|aref| aref.into())
+                .collect(),
+            Element::Shape(shape) => shape.export(exporter)?.into_iter().collect(),
+            Element::Text(text) => text
+                .export(exporter)?
+                .into_iter()
+                .map(|text| text.into())
+                .collect(),
         })
     }
 }
@@ -248,18 +336,81 @@ impl ExportGds for RawInstance {
         let cell_name = if let Some(name) = exporter.get_name(&self.cell) {
             name
         } else {
-            self.cell.export(exporter)?.name
+            self.cell.export(exporter)?
         };
 
         Ok(gds::GdsStructRef {
             name: cell_name,
             xy: self.trans.offset_point().export(exporter)?,
             strans: Some(self.trans.orientation().export(exporter)?),
+            properties: self.properties().export(exporter)?,
             ..Default::default()
         })
     }
 }
 
+/// The largest number of rows or columns in a GDS array reference.
+const MAX_ARRAY_DIM: usize = i16::MAX as usize;
+
+impl ExportGds for RawInstanceArray {
+    /// The array references covering the array.
+    ///
+    /// Arrays with more than 32767 rows or columns are split into several array references.
+    /// Empty arrays produce no array references.
+    type Output = Vec<gds::GdsArrayRef>;
+
+    fn export(&self, exporter: &mut GdsExporter<'_>) -> GdsExportResult<Self::Output> {
+        let span = span!(Level::INFO, "instance array", array = ?self);
+        let _guard = span.enter();
+
+        let cell_name = if let Some(name) = exporter.get_name(&self.cell) {
+            name
+        } else {
+            self.cell.export(exporter)?
+        };
+
+        let strans = self.trans.orientation().export(exporter)?;
+        let properties = self.properties().export(exporter)?;
+        let offset = |p: Point, n: usize, pitch: Point| -> GdsExportResult<Point> {
+            let n = i64::try_from(n)?;
+            Ok(Point::new(p.x + n * pitch.x, p.y + n * pitch.y))
+        };
+
+        let mut arefs = Vec::new();
+        for row in (0..self.rows).step_by(MAX_ARRAY_DIM) {
+            for col in (0..self.cols).step_by(MAX_ARRAY_DIM) {
+                let rows = (self.rows - row).min(MAX_ARRAY_DIM);
+                let cols = (self.cols - col).min(MAX_ARRAY_DIM);
+
+                // GDS arrays are specified by the origin and the points one full
+                // array extent away from it in the column and row directions.
+                let p0 = offset(
+                    offset(self.trans.offset_point(), row, self.row_pitch)?,
+                    col,
+                    self.col_pitch,
+                )?;
+                let p1 = offset(p0, cols, self.col_pitch)?;
+                let p2 = offset(p0, rows, self.row_pitch)?;
+
+                arefs.push(gds::GdsArrayRef {
+                    name: cell_name.clone(),
+                    xy: [
+                        p0.export(exporter)?,
+                        p1.export(exporter)?,
+                        p2.export(exporter)?,
+                    ],
+                    cols: cols.try_into()?,
+                    rows: rows.try_into()?,
+                    strans: Some(strans.clone()),
+                    properties: properties.clone(),
+                    ..Default::default()
+                });
+            }
+        }
+        Ok(arefs)
+    }
+}
+
 impl ExportGds for Shape {
     type Output = Option<gds::GdsElement>;
 
@@ -273,6 +424,7 @@ impl ExportGds for Shape {
                     layer: layer.layer,
                     datatype: layer.xtype,
                     xy: r.export(exporter)?,
+                    properties: self.properties().export(exporter)?,
                     ..Default::default()
                 }
                 .into(),
@@ -280,9 +432,17 @@ impl ExportGds for Shape {
                     layer: layer.layer,
                     datatype: layer.xtype,
                     xy: p.export(exporter)?,
+                    properties: self.properties().export(exporter)?,
                     ..Default::default()
                 }
                 .into(),
+                geometry::shape::Shape::Path(p) => gds::GdsPath {
+                    layer: layer.layer,
+                    datatype: layer.xtype,
+                    properties: self.properties().export(exporter)?,
+                    ..p.export(exporter)?
+                }
+                .into(),
             })
         } else {
             None
@@ -298,12 +458,23 @@ impl ExportGds for Text {
         let _guard = span.enter();
 
         Ok(if let Some(layer) = self.layer().export(exporter)? {
+            let justification = self.justification();
             Some(gds::GdsTextElem {
                 string: self.text().clone(),
                 layer: layer.layer,
                 texttype: layer.xtype,
                 xy: self.trans.offset_point().export(exporter)?,
-                strans: Some(self.trans.orientation().export(exporter)?),
+                presentation: if justification == TextJustification::default() && self.font == 0 {
+                    None
+                } else {
+                    let (vertical, horizontal) = justification.export(exporter)?;
+                    Some(gds::GdsPresentation::new(self.font, vertical, horizontal))
+                },
+                strans: Some(gds::GdsStrans {
+                    mag: self.mag(),
+                    ..self.trans.orientation().export(exporter)?
+                }),
+                properties: self.properties().export(exporter)?,
                 ..Default::default()
             })
         } else {
@@ -312,6 +483,39 @@ impl ExportGds for Text {
     }
 }
 
+impl ExportGds for TextJustification {
+    /// The vertical and horizontal justification flags of a GDS presentation.
+    type Output = (u8, u8);
+
+    fn export(&self, _exporter: &mut GdsExporter<'_>) -> GdsExportResult<Self::Output> {
+        let vertical = match self.vertical {
+            VerticalJustification::Top => 0,
+            VerticalJustification::Middle => 1,
+            VerticalJustification::Bottom => 2,
+        };
+        let horizontal = match self.horizontal {
+            HorizontalJustification::Left => 0,
+            HorizontalJustification::Center => 1,
+            HorizontalJustification::Right => 2,
+        };
+        Ok((vertical, horizontal))
+    }
+}
+
+impl ExportGds for [Property] {
+    type Output = Vec<gds::GdsProperty>;
+
+    fn export(&self, _exporter: &mut GdsExporter<'_>) -> GdsExportResult<Self::Output> {
+        Ok(self
+            .iter()
+            .map(|property| gds::GdsProperty {
+                attr: property.attr,
+                value: property.value.clone(),
+            })
+            .collect())
+    }
+}
+
 impl ExportGds for Rect {
     type Output = Vec<gds::GdsPoint>;
 
@@ -346,6 +550,35 @@ impl ExportGds for Polygon {
     }
 }
 
+impl ExportGds for Path {
+    type Output = gds::GdsPath;
+
+    fn export(&self, exporter: &mut GdsExporter<'_>) -> GdsExportResult<Self::Output> {
+        let span = span!(Level::INFO, "path", path = ?self);
+        let _guard = span.enter();
+
+        let (path_type, begin_extn, end_extn) = match self.end() {
+            PathEnd::Flush => (0, None, None),
+            PathEnd::Round => (1, None, None),
+            PathEnd::HalfWidth => (2, None, None),
+            PathEnd::Custom { begin, end } => (4, Some(begin.try_into()?), Some(end.try_into()?)),
+        };
+
+        Ok(gds::GdsPath {
+            xy: self
+                .points()
+                .iter()
+                .map(|p| p.export(exporter))
+                .collect::<Result<Vec<gds::GdsPoint>, GdsExportError>>()?,
+            width: Some(self.width().try_into()?),
+            path_type: Some(path_type),
+            begin_extn,
+            end_extn,
+            ..Default::default()
+        })
+    }
+}
+
 impl ExportGds for Orientation {
     type Output = gds::GdsStrans;
 
@@ -395,7 +628,7 @@ impl ExportGds for LayerId {
         let span = span!(Level::INFO, "layer ID", layer_id = ?self);
         let _guard = span.enter();
 
-        let spec = exporter.get_layer(*self).map(|spec| spec.into());
+        let spec = exporter.get_layer(*self)?.map(|spec| spec.into());
 
         if spec.is_none() {
             tracing::event!(
@@ -416,6 +649,7 @@ pub struct GdsImporter<'a> {
     layouts: &'a mut LayoutContext,
     layers: &'a mut LayerContext,
     units: Option<Decimal>,
+    layer_map: Option<HashMap<GdsLayerSpec, LayerId>>,
 }
 
 /// An imported GDS file, after conversion to Substrate [`RawCell`]s.
@@ -439,9 +673,22 @@ impl<'a> GdsImporter<'a> {
             layouts,
             layers,
             units,
+            layer_map: None,
         }
     }
 
+    /// Reads GDS layers according to the given layer map rather than the GDS layers of
+    /// installed layers.
+    ///
+    /// Each GDS layer in the map is imported to the installed layer with the name of its
+    /// entry, or to a new layer with that name if no such layer exists. GDS layers that
+    /// are not in the map are imported to layers named `gds_<layer>_<datatype>`, which are
+    /// created if they do not already exist.
+    pub fn with_layer_map(mut self, map: &LayerMap) -> Self {
+        self.layer_map = Some(self.layers.layer_map_import(map));
+        self
+    }
+
     /// Imports a [`gds::GdsLibrary`].
     pub fn import(mut self) -> GdsImportResult<ImportedGds> {
         self.run_preimport_checks()?;
@@ -549,11 +796,7 @@ impl<'a> GdsImporter<'a> {
                 GdsPath(ref x) => Some(self.import_path(x)?),
                 GdsBox(ref x) => Some(self.import_box(x)?),
                 GdsArrayRef(ref x) => {
-                    let elems = self.import_instance_array(x)?;
-                    cell.elements.reserve(elems.len());
-                    for elem in elems {
-                        cell.add_element(elem);
-                    }
+                    cell.add_element(self.import_instance_array(x)?);
                     None
                 }
                 GdsStructRef(ref x) => {
@@ -592,10 +835,7 @@ impl<'a> GdsImporter<'a> {
             let text_elem = self.import_text_elem(textelem)?;
 
             let net_name = ArcStr::from(textelem.string.to_lowercase());
-            let text_layer = self
-                .layers
-                .get_gds_layer(textelem.layerspec().try_into()?)
-                .unwrap();
+            let text_layer = text_elem.layer();
             let loc = self.import_point(&textelem.xy)?;
 
             let family = self.layers.layer_family_for_layer_id(text_layer);
@@ -688,7 +928,8 @@ impl<'a> GdsImporter<'a> {
         // Grab (or create) its [Layer]
         let layer = self.import_element_layer(x)?;
         // Create the Element, and insert it in our slotmap
-        let shape = Shape::new(layer, inner);
+        let mut shape = Shape::new(layer, inner);
+        shape.properties = import_properties(&x.properties);
         Ok(shape)
     }
     /// Imports a [gds::GdsBox] into a [Shape]
@@ -708,26 +949,45 @@ impl<'a> GdsImporter<'a> {
         // Grab (or create) its [Layer]
         let layer = self.import_element_layer(gds_box)?;
         // Create the Element, and insert it in our slotmap
-        let shape = Shape::new(layer, inner);
+        let mut shape = Shape::new(layer, inner);
+        shape.properties = import_properties(&gds_box.properties);
         Ok(shape)
     }
-    /// Import a [gds::GdsPath] into an [Element]
+    /// Imports a [gds::GdsPath] into a [Shape]
     fn import_path(&mut self, x: &gds::GdsPath) -> GdsImportResult<Shape> {
-        let span = span!(Level::INFO, "path");
+        let span = span!(Level::INFO, "path", value=?x);
         let _guard = span.enter();
 
-        let _pts = self.import_point_vec(&x.xy)?;
-        let _width = if let Some(w) = x.width {
-            w as usize
-        } else {
+        let pts = self.import_point_vec(&x.xy)?;
+        if pts.is_empty() {
             return Err(GdsImportError::Unsupported(arcstr::literal!(
-                "GDS path width must be specified"
+                "GDS paths must contain at least one point"
             )));
+        }
+        // Negative widths indicate that the width is not affected by magnification,
+        // which has no effect since magnified instances are not supported.
+        let width = x.width.unwrap_or_default().unsigned_abs() as i64;
+        let end = match x.path_type.unwrap_or_default() {
+            0 => PathEnd::Flush,
+            1 => PathEnd::Round,
+            2 => PathEnd::HalfWidth,
+            4 => PathEnd::Custom {
+                begin: x.begin_extn.unwrap_or_default().into(),
+                end: x.end_extn.unwrap_or_default().into(),
+            },
+            path_type => {
+                return Err(GdsImportError::Unsupported(arcstr::format!(
+                    "unsupported GDS path type {path_type}"
+                )));
+            }
         };
+        let inner = geometry::shape::Shape::Path(Path::new(pts, width, end));
 
-        Err(GdsImportError::Unsupported(arcstr::literal!(
-            "GDS paths not yet supported"
-        )))
+        // Grab (or create) its [Layer]
+        let layer = self.import_element_layer(x)?;
+        let mut shape = Shape::new(layer, inner);
+        shape.properties = import_properties(&x.properties);
+        Ok(shape)
     }
     /// Import a [gds::GdsTextElem] cell/struct-instance into an [TextElement].
     fn import_text_elem(&mut self, sref: &gds::GdsTextElem) -> GdsImportResult<Text> {
@@ -735,10 +995,32 @@ impl<'a> GdsImporter<'a> {
         let span = span!(Level::INFO, "text element", text = %string);
         let _guard = span.enter();
 
-        // Convert its location
+        // Convert its location and orientation.
+        // Text magnification is kept separately, since transformations cannot be magnified.
         let loc = self.import_point(&sref.xy)?;
+        let orientation = sref
+            .strans
+            .as_ref()
+            .map(|strans| {
+                Orientation::from_reflect_and_angle(
+                    strans.reflected,
+                    strans.angle.unwrap_or_default(),
+                )
+            })
+            .unwrap_or_default();
         let layer = self.import_element_layer(sref)?;
-        Ok(Text::new(layer, string, Transformation::from_offset(loc)))
+        let mut text = Text::new(
+            layer,
+            string,
+            Transformation::from_offset_and_orientation(loc, orientation),
+        );
+        if let Some(ref presentation) = sref.presentation {
+            text.justification = import_justification(presentation);
+            text.font = presentation.font();
+        }
+        text.mag = sref.strans.as_ref().and_then(|strans| strans.mag);
+        text.properties = import_properties(&sref.properties);
+        Ok(text)
     }
     /// Import a [gds::GdsStructRef] cell/struct-instance into an [Instance]
     fn import_instance(&mut self, sref: &gds::GdsStructRef) -> GdsImportResult<RawInstance> {
@@ -753,7 +1035,7 @@ impl<'a> GdsImporter<'a> {
             .clone();
         // Convert its location
         let loc = self.import_point(&sref.xy)?;
-        Ok(RawInstance::new(
+        let mut instance = RawInstance::new(
             cell,
             Transformation::from_offset_and_orientation(
                 loc,
@@ -763,28 +1045,21 @@ impl<'a> GdsImporter<'a> {
                     .map_or(Ok(None), |v| v.map(Some))?
                     .unwrap_or_default(),
             ),
-        ))
+        );
+        instance.properties = import_properties(&sref.properties);
+        Ok(instance)
     }
-    /// Imports a (two-dimensional) [`gds::GdsArrayRef`] into [`Instance`]s.
-    ///
-    /// Returns the newly-created [`Instance`]s as a vector.
-    /// Instance names are of the form `{array.name}[{col}][{row}]`.
+    /// Imports a (two-dimensional) [`gds::GdsArrayRef`] into a [`RawInstanceArray`].
     ///
     /// GDSII arrays are described by three spatial points:
-    /// The origin, extent in "rows", and extent in "columns".
-    /// In principle these need not be the same as "x" and "y" spacing,
-    /// i.e. there might be "diamond-shaped" array specifications.
-    ///
-    /// Here, arrays are supported if they are "specified rectangular",
-    /// i.e. that (a) the first two points align in `y`, and (b) the second two points align in `x`.
-    ///
-    /// Further support for such "non-rectangular-specified" arrays may (or may not) become a future addition,
-    /// based on observed GDSII usage.
+    /// the origin, the extent in columns, and the extent in rows.
+    /// The extents are given in the coordinate system of the parent cell,
+    /// and need not be aligned to the x and y axes.
     fn import_instance_array(
         &mut self,
         aref: &gds::GdsArrayRef,
-    ) -> GdsImportResult<Vec<RawInstance>> {
-        let span = span!(Level::INFO, "instance array");
+    ) -> GdsImportResult<RawInstanceArray> {
+        let span = span!(Level::INFO, "instance array", name = %aref.name);
         let _guard = span.enter();
 
         // Look up the cell, which must be imported by now
@@ -794,48 +1069,47 @@ impl<'a> GdsImporter<'a> {
             .ok_or_else(|| GdsImportError::CellNotFound(aref.name.clone()))?;
         let cell = Arc::clone(cell);
 
+        if aref.rows <= 0 || aref.cols <= 0 {
+            return Err(GdsImportError::Unsupported(arcstr::literal!(
+                "GDS arrays must have a positive number of rows and columns"
+            )));
+        }
+
         // Convert its three (x,y) coordinates
         let p0 = self.import_point(&aref.xy[0])?;
         let p1 = self.import_point(&aref.xy[1])?;
         let p2 = self.import_point(&aref.xy[2])?;
-        // Check for (thus far) unsupported non-rectangular arrays
-        if p0.y != p1.y || p0.x != p2.x {
-            return Err(GdsImportError::Unsupported(arcstr::literal!(
-                "unsupported non-rectangular GDS array"
-            )));
-        }
-        // Sort out the inter-element spacing
-        let mut xstep = (p1.x - p0.x) / i64::from(aref.cols);
-        let mut ystep = (p2.y - p0.y) / i64::from(aref.rows);
-
-        // Incorporate the reflection/ rotation settings
-        let mut orientation = Orientation::default();
-        if let Some(strans) = &aref.strans {
-            orientation = self.import_orientation(strans)?;
-        }
-        // The angle-setting rotates the *entire* array lattice together.
-        // Update the (x,y) steps via a rotation-matrix multiplication:
-        // x = x * cos(a) - y * sin(a)
-        // y = x * sin(a) + y * cos(a)
-        let prev_xy = (i32::try_from(xstep)?, i32::try_from(ystep)?);
-        let prev_xy = (f64::from(prev_xy.0), f64::from(prev_xy.1));
-        let a = orientation.angle().to_radians(); // Rust `sin` and `cos` take radians, convert first
-        xstep = (prev_xy.0 * a.cos() - prev_xy.1 * a.sin()) as i64;
-        ystep = (prev_xy.0 * a.sin() + prev_xy.1 * a.cos()) as i64;
-
-        // Create the Instances
-        let mut insts = Vec::with_capacity((aref.rows * aref.cols) as usize);
-        for ix in 0..i64::from(aref.cols) {
-            let x = p0.x + ix * xstep;
-            for iy in 0..i64::from(aref.rows) {
-                let y = p0.y + iy * ystep;
-                insts.push(RawInstance::new(
-                    cell.clone(),
-                    Transformation::from_offset_and_orientation(Point::new(x, y), orientation),
-                ));
+        let (rows, cols) = (i64::from(aref.rows), i64::from(aref.cols));
+        let pitch = |p: Point, n: i64| {
+            let (dx, dy) = (p.x - p0.x, p.y - p0.y);
+            if dx % n != 0 || dy % n != 0 {
+                return Err(GdsImportError::Unsupported(arcstr::format!(
+                    "GDS array of `{}` with extent ({dx}, {dy}) that is not divisible by {n}",
+                    aref.name
+                )));
             }
-        }
-        Ok(insts)
+            Ok(Point::new(dx / n, dy / n))
+        };
+        let col_pitch = pitch(p1, cols)?;
+        let row_pitch = pitch(p2, rows)?;
+
+        let orientation = aref
+            .strans
+            .as_ref()
+            .map(|value| self.import_orientation(value))
+            .transpose()?
+            .unwrap_or_default();
+
+        let mut array = RawInstanceArray::new(
+            cell,
+            Transformation::from_offset_and_orientation(p0, orientation),
+            aref.rows as usize,
+            aref.cols as usize,
+            row_pitch,
+            col_pitch,
+        );
+        array.properties = import_properties(&aref.properties);
+        Ok(array)
     }
     /// Imports a [`Point`].
     fn import_point(&self, pt: &gds::GdsPoint) -> GdsImportResult<Point> {
@@ -877,6 +1151,18 @@ impl<'a> GdsImporter<'a> {
         let span = span!(Level::INFO, "layer", spec=?spec);
         let _guard = span.enter();
         let spec = spec.try_into()?;
+        if let Some(ref mut layer_map) = self.layer_map {
+            if let Some(id) = layer_map.get(&spec) {
+                return Ok(*id);
+            }
+            let name = arcstr::format!("gds_{}_{}", spec.0, spec.1);
+            let id = match self.layers.get_layer_by_name(&name) {
+                Some(id) => id,
+                None => self.layers.new_anonymous_layer(name, spec),
+            };
+            layer_map.insert(spec, id);
+            return Ok(id);
+        }
         let layers = &mut self.layers;
         Ok(if let Some(layer_spec) = layers.get_gds_layer(spec) {
             layer_spec
@@ -890,62 +1176,27 @@ impl<'a> GdsImporter<'a> {
     }
 }
 
-/// A helper for retrieving GDS dependencies in reverse topological order.
+/// Converts GDS properties to Substrate [`Property`]s.
+fn import_properties(properties: &[gds::GdsProperty]) -> Vec<Property> {
+    properties
+        .iter()
+        .map(|property| Property::new(property.attr, property.value.clone()))
+        .collect()
+}
+
+/// Converts GDS text presentation flags to a [`TextJustification`].
 ///
-/// Creates a vector of references Gds structs, ordered by their instance dependencies.
-/// Each item in the ordered return value is guaranteed *not* to instantiate any item which comes later.
-#[derive(Debug)]
-pub struct GdsDepOrder<'a> {
-    strukts: HashMap<ArcStr, &'a gds::GdsStruct>,
-    stack: Vec<&'a gds::GdsStruct>,
-    seen: HashSet<ArcStr>,
-}
-impl<'a> GdsDepOrder<'a> {
-    /// Creates a new [`GdsDepOrder`] for a [`gds::GdsLibrary`].
-    fn new(gdslib: &'a gds::GdsLibrary) -> Self {
-        // First create a map from names to structs
-        let mut strukts = HashMap::new();
-        for s in &gdslib.structs {
-            strukts.insert(s.name.clone(), s);
-        }
-        Self {
-            strukts,
-            stack: Vec::new(),
-            seen: HashSet::new(),
-        }
-    }
-    /// Returns a reverse topological sort of all structs in `gdslib`.
-    fn total_order(mut self) -> Vec<&'a gds::GdsStruct> {
-        let strukts = self
-            .strukts
-            .values()
-            .copied()
-            .collect::<Vec<&gds::GdsStruct>>();
-        for s in strukts {
-            self.push(s)
-        }
-        self.stack
-    }
-    /// Returns all dependencies of a given cell in reverse topological order.
-    fn cell_order(mut self, cell: impl Into<ArcStr>) -> Vec<&'a gds::GdsStruct> {
-        if let Some(strukt) = self.strukts.get(&cell.into()) {
-            self.push(strukt);
-        }
-        self.stack
-    }
-    /// Adds all of `strukt`'s dependencies, and then `strukt` itself, to the stack.
-    fn push(&mut self, strukt: &'a gds::GdsStruct) {
-        if !self.seen.contains(&strukt.name) {
-            for elem in &strukt.elems {
-                use gds::GdsElement::*;
-                match elem {
-                    GdsStructRef(ref x) => self.push(self.strukts.get(&x.name).unwrap()),
-                    GdsArrayRef(ref x) => self.push(self.strukts.get(&x.name).unwrap()),
-                    _ => (),
-                };
-            }
-            self.seen.insert(strukt.name.clone());
-            self.stack.push(strukt);
-        }
-    }
+/// Reserved justification values are treated as the default.
+fn import_justification(presentation: &gds::GdsPresentation) -> TextJustification {
+    let horizontal = match presentation.horizontal() {
+        1 => HorizontalJustification::Center,
+        2 => HorizontalJustification::Right,
+        _ => HorizontalJustification::Left,
+    };
+    let vertical = match presentation.vertical() {
+        1 => VerticalJustification::Middle,
+        2 => VerticalJustification::Bottom,
+        _ => VerticalJustification::Top,
+    };
+    TextJustification::new(horizontal, vertical)
 }
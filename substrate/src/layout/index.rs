@@ -0,0 +1,331 @@
+//! Spatial indexing of layout hierarchies.
+
+use std::sync::Arc;
+
+use geometry::bbox::Bbox;
+use geometry::contains::{Containment, Contains};
+use geometry::corner::Corner;
+use geometry::point::Point;
+use geometry::polygon::Polygon;
+use geometry::rect::Rect;
+use geometry::shape::Shape;
+use geometry::transform::{Transform, Transformation};
+use indexmap::IndexMap;
+use rstar::primitives::{GeomWithData, Rectangle};
+use rstar::{RTree, AABB};
+
+use crate::pdk::layers::LayerId;
+
+use super::element::{Element, RawCell, RawInstance};
+use super::{Cell, ExportsLayoutData};
+
+type Entry = GeomWithData<Rectangle<[i64; 2]>, usize>;
+
+/// A shape stored in a [`SpatialIndex`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedShape {
+    /// The path to the cell containing the shape.
+    ///
+    /// Each entry is the index of an instance among the instances of its parent cell.
+    /// Shapes drawn directly in the indexed cell have an empty path.
+    pub path: Vec<usize>,
+    /// The layer of the shape.
+    pub layer: LayerId,
+    /// The shape, in the coordinate system of the indexed cell.
+    pub shape: Shape,
+}
+
+/// An instance stored in a [`SpatialIndex`].
+#[derive(Debug, Clone)]
+pub struct IndexedInstance {
+    /// The path to the instance.
+    ///
+    /// The last entry is the index of the instance among the instances of its parent cell.
+    pub path: Vec<usize>,
+    /// The instantiated cell.
+    pub cell: Arc<RawCell>,
+    /// The transformation from the coordinate system of the instantiated cell
+    /// to that of the indexed cell.
+    pub trans: Transformation,
+    /// The bounding box of the instance, in the coordinate system of the indexed cell.
+    pub bbox: Rect,
+}
+
+/// A spatial index over the shapes and instances of a layout hierarchy.
+///
+/// All geometry is reported in the coordinate system of the indexed cell.
+///
+/// Shapes are indexed by their bounding boxes; queries then check candidates
+/// against their exact geometry. Paths are converted to polygons when the
+/// index is built.
+#[derive(Debug, Clone)]
+pub struct SpatialIndex {
+    shapes: Vec<IndexedShape>,
+    instances: Vec<IndexedInstance>,
+    layers: IndexMap<LayerId, RTree<Entry>>,
+    instance_tree: RTree<Entry>,
+}
+
+impl SpatialIndex {
+    /// Builds a spatial index over the given cell and all cells it instantiates.
+    pub fn new(cell: &RawCell) -> Self {
+        let mut shapes = Vec::new();
+        let mut instances = Vec::new();
+        collect(
+            cell,
+            Transformation::identity(),
+            &mut Vec::new(),
+            &mut shapes,
+            &mut instances,
+        );
+
+        let mut layer_entries: IndexMap<LayerId, Vec<Entry>> = IndexMap::new();
+        for (i, shape) in shapes.iter().enumerate() {
+            if let Some(bbox) = shape.shape.bbox() {
+                layer_entries
+                    .entry(shape.layer)
+                    .or_default()
+                    .push(entry(bbox, i));
+            }
+        }
+        let layers = layer_entries
+            .into_iter()
+            .map(|(layer, entries)| (layer, RTree::bulk_load(entries)))
+            .collect();
+        let instance_tree = RTree::bulk_load(
+            instances
+                .iter()
+                .enumerate()
+                .map(|(i, inst)| entry(inst.bbox, i))
+                .collect(),
+        );
+
+        Self {
+            shapes,
+            instances,
+            layers,
+            instance_tree,
+        }
+    }
+
+    /// Returns all indexed shapes.
+    pub fn shapes(&self) -> &[IndexedShape] {
+        &self.shapes
+    }
+
+    /// Returns all indexed instances, at every level of the hierarchy.
+    pub fn instances(&self) -> &[IndexedInstance] {
+        &self.instances
+    }
+
+    /// Returns the shapes on the given layer that intersect `rect`.
+    ///
+    /// Shapes that only touch the boundary of `rect` are included.
+    pub fn shapes_intersecting(
+        &self,
+        layer: LayerId,
+        rect: Rect,
+    ) -> impl Iterator<Item = &IndexedShape> {
+        self.layers
+            .get(&layer)
+            .into_iter()
+            .flat_map(move |tree| tree.locate_in_envelope_intersecting(&envelope(rect)))
+            .map(|entry| &self.shapes[entry.data])
+            .filter(move |shape| intersects(&shape.shape, rect))
+    }
+
+    /// Returns the shape on the given layer nearest to `point`, if the layer has any shapes.
+    ///
+    /// Shapes containing `point` are at distance zero. Ties are broken arbitrarily.
+    pub fn nearest_shape(&self, layer: LayerId, point: Point) -> Option<&IndexedShape> {
+        let tree = self.layers.get(&layer)?;
+        let mut best: Option<(f64, usize)> = None;
+        // Bounding box distances are lower bounds on exact distances,
+        // so the search can stop once they exceed the best exact distance.
+        for (entry, bbox_dist2) in tree.nearest_neighbor_iter_with_distance_2(&[point.x, point.y]) {
+            if let Some((best_dist2, _)) = best {
+                if bbox_dist2 as f64 > best_dist2 {
+                    break;
+                }
+            }
+            let dist2 = distance_2(&self.shapes[entry.data].shape, point);
+            if best.map_or(true, |(best_dist2, _)| dist2 < best_dist2) {
+                best = Some((dist2, entry.data));
+            }
+        }
+        best.map(|(_, i)| &self.shapes[i])
+    }
+
+    /// Returns the instances, at any level of the hierarchy, whose bounding boxes intersect `rect`.
+    pub fn instances_intersecting(&self, rect: Rect) -> impl Iterator<Item = &IndexedInstance> {
+        self.instance_tree
+            .locate_in_envelope_intersecting(&envelope(rect))
+            .map(|entry| &self.instances[entry.data])
+    }
+}
+
+fn collect(
+    cell: &RawCell,
+    trans: Transformation,
+    path: &mut Vec<usize>,
+    shapes: &mut Vec<IndexedShape>,
+    instances: &mut Vec<IndexedInstance>,
+) {
+    let mut n = 0;
+    for elem in cell.elements() {
+        match elem {
+            Element::Instance(inst) => {
+                collect_instance(inst, trans, n, path, shapes, instances);
+                n += 1;
+            }
+            Element::InstanceArray(array) => {
+                for inst in array.instances() {
+                    collect_instance(&inst, trans, n, path, shapes, instances);
+                    n += 1;
+                }
+            }
+            Element::Shape(shape) => {
+                let geom = match shape.shape() {
+                    Shape::Path(p) => Shape::Polygon(p.to_polygon()),
+                    other => other.clone(),
+                };
+                shapes.push(IndexedShape {
+                    path: path.clone(),
+                    layer: shape.layer(),
+                    shape: geom.transform(trans),
+                });
+            }
+            Element::Text(_) => {}
+        }
+    }
+}
+
+fn collect_instance(
+    inst: &RawInstance,
+    trans: Transformation,
+    n: usize,
+    path: &mut Vec<usize>,
+    shapes: &mut Vec<IndexedShape>,
+    instances: &mut Vec<IndexedInstance>,
+) {
+    let inst_trans = Transformation::cascade(trans, inst.trans);
+    path.push(n);
+    if let Some(bbox) = inst.cell.bbox() {
+        instances.push(IndexedInstance {
+            path: path.clone(),
+            cell: inst.cell.clone(),
+            trans: inst_trans,
+            bbox: bbox.transform(inst_trans),
+        });
+    }
+    collect(&inst.cell, inst_trans, path, shapes, instances);
+    path.pop();
+}
+
+fn entry(bbox: Rect, i: usize) -> Entry {
+    GeomWithData::new(
+        Rectangle::from_corners([bbox.left(), bbox.bot()], [bbox.right(), bbox.top()]),
+        i,
+    )
+}
+
+fn envelope(rect: Rect) -> AABB<[i64; 2]> {
+    AABB::from_corners([rect.left(), rect.bot()], [rect.right(), rect.top()])
+}
+
+/// Returns `true` if `shape` and `rect` share at least one point.
+fn intersects(shape: &Shape, rect: Rect) -> bool {
+    let poly = match shape {
+        Shape::Rect(r) => return r.intersection(rect).is_some(),
+        Shape::Polygon(p) => p.clone(),
+        Shape::Path(p) => p.to_polygon(),
+    };
+    if poly.points().iter().any(|p| rect.contains(p).is_full()) {
+        return true;
+    }
+    if [
+        Corner::LowerLeft,
+        Corner::LowerRight,
+        Corner::UpperLeft,
+        Corner::UpperRight,
+    ]
+    .into_iter()
+    .any(|c| poly.contains(&rect.corner(c)) != Containment::None)
+    {
+        return true;
+    }
+    let crosses = poly
+        .edges()
+        .any(|(a, b)| segment_intersects_rect(a, b, rect));
+    crosses
+}
+
+/// Clips the segment from `a` to `b` against `rect` (Liang-Barsky).
+fn segment_intersects_rect(a: Point, b: Point, rect: Rect) -> bool {
+    let (dx, dy) = ((b.x - a.x) as f64, (b.y - a.y) as f64);
+    let (mut t0, mut t1) = (0f64, 1f64);
+    for (p, q) in [
+        (-dx, (a.x - rect.left()) as f64),
+        (dx, (rect.right() - a.x) as f64),
+        (-dy, (a.y - rect.bot()) as f64),
+        (dy, (rect.top() - a.y) as f64),
+    ] {
+        if p == 0. {
+            if q < 0. {
+                return false;
+            }
+        } else {
+            let t = q / p;
+            if p < 0. {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+        }
+    }
+    t0 <= t1
+}
+
+/// Returns the squared distance from `point` to the nearest point of `shape`.
+fn distance_2(shape: &Shape, point: Point) -> f64 {
+    let poly: Polygon = match shape {
+        Shape::Rect(r) => {
+            let dx = (r.left() - point.x).max(point.x - r.right()).max(0) as f64;
+            let dy = (r.bot() - point.y).max(point.y - r.top()).max(0) as f64;
+            return dx * dx + dy * dy;
+        }
+        Shape::Polygon(p) => p.clone(),
+        Shape::Path(p) => p.to_polygon(),
+    };
+    if poly.contains(&point) != Containment::None {
+        return 0.;
+    }
+    poly.edges()
+        .map(|(a, b)| {
+            let (px, py) = ((point.x - a.x) as f64, (point.y - a.y) as f64);
+            let (dx, dy) = ((b.x - a.x) as f64, (b.y - a.y) as f64);
+            let len2 = dx * dx + dy * dy;
+            let t = if len2 == 0. {
+                0.
+            } else {
+                ((px * dx + py * dy) / len2).clamp(0., 1.)
+            };
+            let (ex, ey) = (px - t * dx, py - t * dy);
+            ex * ex + ey * ey
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+impl RawCell {
+    /// Builds a [`SpatialIndex`] over this cell and all cells it instantiates.
+    pub fn spatial_index(&self) -> SpatialIndex {
+        SpatialIndex::new(self)
+    }
+}
+
+impl<T: ExportsLayoutData> Cell<T> {
+    /// Builds a [`SpatialIndex`] over this cell and all cells it instantiates.
+    pub fn spatial_index(&self) -> SpatialIndex {
+        self.raw.spatial_index()
+    }
+}
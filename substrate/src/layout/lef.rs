@@ -0,0 +1,700 @@
+//! LEF abstract export and import.
+//!
+//! Produces a LEF `MACRO` describing the outline, pins, and obstructions of a
+//! layout cell, for use in place-and-route tools, and converts LEF macros back
+//! to layout cells.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+use arcstr::ArcStr;
+use geometry::bbox::Bbox;
+use geometry::boolean::Region;
+use geometry::point::Point;
+use geometry::polygon::Polygon;
+use geometry::rect::Rect;
+use geometry::shape::Shape;
+use geometry::transform::{Transform, Transformation};
+use indexmap::IndexMap;
+use lefdef::LefPoint;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::io::{
+    Direction, IoShape, LayoutBundleBuilder, LayoutType, NameBuf, NameFragment, Signal,
+};
+use crate::pdk::layers::{layer_resolver, HasPin, LayerContext, LayerId};
+
+use super::element::{Element, RawCell, Shape as ElementShape};
+use super::error::LayoutError;
+use super::flatten::region_shapes;
+use super::LayoutContext;
+
+/// A PDK layer exported to LEF.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LefLayer<L = LayerId> {
+    /// The layer.
+    ///
+    /// Shapes on any layer in the same layer family are exported on this layer.
+    pub layer: L,
+    /// The name of the layer in LEF files.
+    ///
+    /// Defaults to the name of the PDK layer.
+    #[serde(default)]
+    pub name: Option<ArcStr>,
+}
+
+impl<L> LefLayer<L> {
+    /// Creates a LEF layer with the given name.
+    pub fn new(layer: L, name: impl Into<ArcStr>) -> Self {
+        Self {
+            layer,
+            name: Some(name.into()),
+        }
+    }
+
+    /// Creates a LEF layer named after the PDK layer.
+    pub fn unnamed(layer: L) -> Self {
+        Self { layer, name: None }
+    }
+}
+
+/// The rules for exporting layouts to LEF.
+///
+/// Like [`Connectivity`](super::connectivity::Connectivity) rules, LEF rules are generic
+/// over the way layers are identified.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LefRules<L = LayerId> {
+    /// The layers included in pins and obstructions.
+    ///
+    /// If empty, all layers are exported under their PDK names.
+    #[serde(default)]
+    pub layers: Vec<LefLayer<L>>,
+    /// The layer whose shapes give the size of exported macros.
+    ///
+    /// If not set, or if a cell has no shapes on this layer, the size of the
+    /// macro is the bounding box of the cell.
+    #[serde(default)]
+    pub boundary: Option<L>,
+}
+
+impl<L> Default for LefRules<L> {
+    fn default() -> Self {
+        Self {
+            layers: Vec::new(),
+            boundary: None,
+        }
+    }
+}
+
+impl<L> LefRules<L> {
+    /// Creates an empty set of LEF rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an exported layer.
+    pub fn with_layer(mut self, layer: LefLayer<L>) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// Sets the boundary layer.
+    pub fn with_boundary(mut self, boundary: L) -> Self {
+        self.boundary = Some(boundary);
+        self
+    }
+}
+
+impl<L: AsRef<str>> LefRules<L> {
+    /// Resolves layer names to layer IDs using the given lookup function.
+    ///
+    /// Returns an error if a layer name cannot be resolved.
+    pub fn resolve(&self, lookup: impl FnMut(&str) -> Option<LayerId>) -> Result<LefRules> {
+        let mut layer = layer_resolver(lookup);
+
+        let mut layers = Vec::with_capacity(self.layers.len());
+        for l in self.layers.iter() {
+            layers.push(LefLayer {
+                layer: layer(&l.layer)?,
+                name: l.name.clone(),
+            });
+        }
+
+        Ok(LefRules {
+            layers,
+            boundary: self.boundary.as_ref().map(&mut layer).transpose()?,
+        })
+    }
+}
+
+/// An exporter for LEF abstracts.
+///
+/// Takes a [`RawCell`] and the directions of its ports, and writes a LEF library
+/// containing a single macro.
+///
+/// Ports without shapes on any exported layer are omitted with a warning.
+/// Obstructions exclude the area covered by pins on the same layer.
+pub struct LefExporter<'a> {
+    cell: Arc<RawCell>,
+    directions: HashMap<NameBuf, Direction>,
+    layers: &'a LayerContext,
+    rules: &'a LefRules,
+    db_units: Decimal,
+}
+
+impl<'a> LefExporter<'a> {
+    /// Creates a new LEF exporter.
+    ///
+    /// `directions` gives the direction of each port of `cell`, keyed by port name.
+    /// `db_units` is the size of a layout database unit in meters.
+    pub fn new(
+        cell: Arc<RawCell>,
+        directions: HashMap<NameBuf, Direction>,
+        layers: &'a LayerContext,
+        rules: &'a LefRules,
+        db_units: Decimal,
+    ) -> Self {
+        Self {
+            cell,
+            directions,
+            layers,
+            rules,
+            db_units,
+        }
+    }
+
+    /// Exports the cell as the text of a LEF file.
+    ///
+    /// Returns an error if `directions` is missing a port of the cell or names a port
+    /// the cell does not have.
+    pub fn export(self) -> Result<String> {
+        let flat = self.cell.flatten();
+
+        let mut blockages = Vec::new();
+        collect_blockages(&self.cell, Transformation::identity(), &mut blockages);
+
+        if let Some(name) = self
+            .directions
+            .keys()
+            .find(|name| !self.cell.ports().any(|(port, _)| port == *name))
+        {
+            return Err(LayoutError::LefExport(arcstr::format!(
+                "direction given for nonexistent port `{name}`"
+            ))
+            .into());
+        }
+
+        let mut pins = Vec::new();
+        let mut pin_regions: IndexMap<ArcStr, Vec<Shape>> = IndexMap::new();
+        for (name, port) in self.cell.ports() {
+            let direction = *self.directions.get(name).ok_or_else(|| {
+                LayoutError::LefExport(arcstr::format!("no direction given for port `{name}`"))
+            })?;
+            let mut shapes: IndexMap<ArcStr, Vec<Shape>> = IndexMap::new();
+            let mut named = port.named_shapes.iter().collect::<Vec<_>>();
+            named.sort_by(|a, b| a.0.cmp(b.0));
+            for shape in std::iter::once(&port.primary)
+                .chain(port.unnamed_shapes.iter())
+                .chain(named.into_iter().map(|(_, shape)| shape))
+            {
+                if let Some(layer) = self.lef_layer(shape.layer().drawing()) {
+                    shapes
+                        .entry(layer.clone())
+                        .or_default()
+                        .push(shape.shape().clone());
+                    pin_regions
+                        .entry(layer)
+                        .or_default()
+                        .push(shape.shape().clone());
+                }
+            }
+            if shapes.is_empty() {
+                tracing::warn!("omitting port without shapes on exported layers: `{name}`");
+                continue;
+            }
+            pins.push((lef_pin_name(name), direction, shapes));
+        }
+
+        let bounds = self
+            .rules
+            .boundary
+            .and_then(|layer| Rect::union_all_option(flat.shapes_on(layer).iter().map(Bbox::bbox)))
+            .or_else(|| {
+                Rect::union_all_option(
+                    flat.shapes
+                        .values()
+                        .flatten()
+                        .map(Bbox::bbox)
+                        .chain(pin_regions.values().flatten().map(Bbox::bbox)),
+                )
+            })
+            .unwrap_or_default();
+
+        let mut obstructions: IndexMap<ArcStr, Vec<Shape>> = IndexMap::new();
+        for (layer, shapes) in flat.shapes.iter() {
+            if Some(*layer) == self.rules.boundary {
+                continue;
+            }
+            if let Some(name) = self.lef_layer(*layer) {
+                obstructions
+                    .entry(name)
+                    .or_default()
+                    .extend(shapes.iter().cloned());
+            }
+        }
+        for (layer, shape) in blockages {
+            if let Some(name) = self.lef_layer(layer) {
+                obstructions.entry(name).or_default().push(shape);
+            }
+        }
+        let mut obs = IndexMap::new();
+        for (layer, shapes) in obstructions {
+            let mut region = polygon_region(&shapes);
+            if let Some(pins) = pin_regions.get(&layer) {
+                region = region.difference(&polygon_region(pins));
+            }
+            obs.insert(layer, region);
+        }
+
+        let name = &self.cell.name;
+        let mut out = String::new();
+        writeln!(out, "VERSION 5.8 ;").unwrap();
+        writeln!(out, "BUSBITCHARS \"[]\" ;").unwrap();
+        writeln!(out, "DIVIDERCHAR \"/\" ;").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "MACRO {name}").unwrap();
+        writeln!(out, "  CLASS BLOCK ;").unwrap();
+        writeln!(
+            out,
+            "  ORIGIN {} {} ;",
+            self.microns(-bounds.left()),
+            self.microns(-bounds.bot())
+        )
+        .unwrap();
+        writeln!(out, "  FOREIGN {name} ;").unwrap();
+        writeln!(
+            out,
+            "  SIZE {} BY {} ;",
+            self.microns(bounds.width()),
+            self.microns(bounds.height())
+        )
+        .unwrap();
+
+        for (pin, direction, shapes) in pins.iter() {
+            let direction = match direction {
+                Direction::Input => "INPUT",
+                Direction::Output => "OUTPUT",
+                Direction::InOut => "INOUT",
+            };
+            writeln!(out, "  PIN {pin}").unwrap();
+            writeln!(out, "    DIRECTION {direction} ;").unwrap();
+            writeln!(out, "    USE SIGNAL ;").unwrap();
+            writeln!(out, "    PORT").unwrap();
+            for (layer, shapes) in shapes {
+                writeln!(out, "      LAYER {layer} ;").unwrap();
+                for shape in shapes {
+                    self.write_shape(&mut out, shape, "        ");
+                }
+            }
+            writeln!(out, "    END").unwrap();
+            writeln!(out, "  END {pin}").unwrap();
+        }
+
+        if obs.values().any(|region| !region.is_empty()) {
+            writeln!(out, "  OBS").unwrap();
+            for (layer, region) in obs.iter() {
+                if region.is_empty() {
+                    continue;
+                }
+                writeln!(out, "    LAYER {layer} ;").unwrap();
+                for shape in region_shapes(region) {
+                    self.write_shape(&mut out, &shape, "      ");
+                }
+            }
+            writeln!(out, "  END").unwrap();
+        }
+
+        writeln!(out, "END {name}").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "END LIBRARY").unwrap();
+        Ok(out)
+    }
+
+    /// Returns the LEF name of the layer on which shapes on `layer` are exported, if any.
+    fn lef_layer(&self, layer: LayerId) -> Option<ArcStr> {
+        let primary = self
+            .layers
+            .layer_family_for_layer_id(layer)
+            .map(|family| family.primary)
+            .unwrap_or(layer);
+        if self.rules.layers.is_empty() {
+            return self
+                .layers
+                .get_layer_info(primary)
+                .map(|info| info.name.clone());
+        }
+        let lef = self
+            .rules
+            .layers
+            .iter()
+            .find(|l| l.layer == layer || l.layer == primary)?;
+        lef.name.clone().or_else(|| {
+            self.layers
+                .get_layer_info(lef.layer)
+                .map(|info| info.name.clone())
+        })
+    }
+
+    fn microns(&self, value: i64) -> Decimal {
+        (Decimal::from(value) * self.db_units * dec!(1e6)).normalize()
+    }
+
+    fn write_point(&self, out: &mut String, p: Point) {
+        write!(out, " {} {}", self.microns(p.x), self.microns(p.y)).unwrap();
+    }
+
+    fn write_shape(&self, out: &mut String, shape: &Shape, indent: &str) {
+        match shape {
+            Shape::Rect(rect) => {
+                write!(out, "{indent}RECT").unwrap();
+                self.write_point(out, Point::new(rect.left(), rect.bot()));
+                self.write_point(out, Point::new(rect.right(), rect.top()));
+            }
+            shape => {
+                write!(out, "{indent}POLYGON").unwrap();
+                for p in shape.to_polygon().points() {
+                    self.write_point(out, *p);
+                }
+            }
+        }
+        writeln!(out, " ;").unwrap();
+    }
+}
+
+fn polygon_region(shapes: &[Shape]) -> Region {
+    let polygons = shapes.iter().map(Shape::to_polygon).collect::<Vec<_>>();
+    Region::from_polygons(&polygons)
+}
+
+/// Collects the blockages of `cell` and all cells it instantiates.
+fn collect_blockages(cell: &RawCell, trans: Transformation, out: &mut Vec<(LayerId, Shape)>) {
+    for blockage in cell.blockages.iter() {
+        out.push((blockage.layer(), blockage.shape().clone().transform(trans)));
+    }
+    for elem in cell.elements() {
+        match elem {
+            Element::Instance(inst) => {
+                collect_blockages(&inst.cell, Transformation::cascade(trans, inst.trans), out);
+            }
+            Element::InstanceArray(array) => {
+                for inst in array.instances() {
+                    collect_blockages(&inst.cell, Transformation::cascade(trans, inst.trans), out);
+                }
+            }
+            Element::Shape(_) | Element::Text(_) => {}
+        }
+    }
+}
+
+/// An importer for LEF macros.
+///
+/// Converts the macros of a [`lefdef::LefLibrary`] to [`RawCell`]s. Pin shapes become
+/// port geometry and are also drawn on their layers, while obstructions become blockages.
+/// If the [`LefRules`] specify a boundary layer, the outline of each macro is drawn on it.
+///
+/// LEF layer names are resolved using the names in the [`LefRules`], falling back to
+/// PDK layer names. Shapes on unknown layers are skipped.
+pub struct LefImporter<'a> {
+    lef: &'a lefdef::LefLibrary,
+    layouts: &'a mut LayoutContext,
+    layers: &'a LayerContext,
+    rules: &'a LefRules,
+    db_units: Decimal,
+    cells: HashMap<ArcStr, Arc<RawCell>>,
+}
+
+impl<'a> LefImporter<'a> {
+    /// Creates a new LEF importer.
+    ///
+    /// `db_units` is the size of a layout database unit in meters.
+    pub fn new(
+        lef: &'a lefdef::LefLibrary,
+        layouts: &'a mut LayoutContext,
+        layers: &'a LayerContext,
+        rules: &'a LefRules,
+        db_units: Decimal,
+    ) -> Self {
+        Self {
+            lef,
+            layouts,
+            layers,
+            rules,
+            db_units,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Imports the macro with the given name.
+    ///
+    /// Each macro is imported at most once; subsequent calls return the same cell.
+    pub fn import_macro(&mut self, name: &str) -> Result<Arc<RawCell>> {
+        if let Some(cell) = self.cells.get(name) {
+            return Ok(cell.clone());
+        }
+        let m = self
+            .lef
+            .macro_named(name)
+            .ok_or_else(|| LayoutError::LefDefImport(arcstr::format!("macro not found: {name}")))?;
+        let origin = m.origin;
+        let point = |p: lefdef::LefPoint| LefPoint::new(p.x + origin.x, p.y + origin.y);
+
+        let mut cell = RawCell::new(self.layouts.get_id(), m.name.clone());
+        if let (Some(boundary), Some(bounds)) = (self.rules.boundary, self.macro_bounds(name)) {
+            cell.add_element(ElementShape::new(boundary, bounds));
+        }
+
+        for pin in m.pins.iter() {
+            let mut port = Signal.builder();
+            let mut has_geometry = false;
+            for geometry in pin.ports.iter() {
+                for shape in geometry.shapes.iter() {
+                    let Some(layer) = self.layer(&shape.layer) else {
+                        continue;
+                    };
+                    for s in self.lef_shapes(&shape.kind, point)? {
+                        port.push(self.io_shape(layer, s.clone()));
+                        cell.add_element(ElementShape::new(layer, s));
+                        has_geometry = true;
+                    }
+                }
+                for via in geometry.vias.iter() {
+                    cell.add_elements(self.lef_via(&via.name, point(via.at))?);
+                }
+            }
+            if !has_geometry {
+                tracing::warn!("ignoring pin without geometry: `{}`", pin.name);
+                continue;
+            }
+            cell.add_port(pin_name(&pin.name), port.build()?);
+        }
+
+        for shape in m.obs.shapes.iter() {
+            let Some(layer) = self.layer(&shape.layer) else {
+                continue;
+            };
+            for s in self.lef_shapes(&shape.kind, point)? {
+                cell.add_blockage(ElementShape::new(layer, s));
+            }
+        }
+        for via in m.obs.vias.iter() {
+            cell.add_blockages(self.lef_via(&via.name, point(via.at))?);
+        }
+
+        let cell = Arc::new(cell);
+        self.cells.insert(m.name.clone(), cell.clone());
+        Ok(cell)
+    }
+
+    pub(crate) fn layouts(&mut self) -> &mut LayoutContext {
+        self.layouts
+    }
+
+    pub(crate) fn boundary(&self) -> Option<LayerId> {
+        self.rules.boundary
+    }
+
+    /// Returns the outline of the macro with the given name, if it has a size.
+    pub(crate) fn macro_bounds(&self, name: &str) -> Option<Rect> {
+        let (w, h) = self.lef.macro_named(name)?.size?;
+        Some(Rect::from_sides(0, 0, self.microns(w), self.microns(h)))
+    }
+
+    /// Returns the layer corresponding to the LEF layer with the given name.
+    pub(crate) fn layer(&self, name: &str) -> Option<LayerId> {
+        let layer = self
+            .rules
+            .layers
+            .iter()
+            .find(|l| match &l.name {
+                Some(n) => n == name,
+                None => self
+                    .layers
+                    .get_layer_info(l.layer)
+                    .is_some_and(|info| info.name == name),
+            })
+            .map(|l| l.layer)
+            .or_else(|| self.layers.get_layer_by_name(name));
+        if layer.is_none() {
+            tracing::warn!("skipping shapes on unknown LEF layer `{}`", name);
+        }
+        layer
+    }
+
+    /// Returns the default wire width of the LEF layer with the given name.
+    pub(crate) fn layer_width(&self, name: &str) -> Option<i64> {
+        Some(self.microns(self.lef.layer(name)?.width?))
+    }
+
+    /// Returns true if the LEF layer with the given name is a cut layer.
+    pub(crate) fn is_cut(&self, name: &str) -> bool {
+        self.lef
+            .layer(name)
+            .and_then(|layer| layer.kind.as_ref())
+            .is_some_and(|kind| kind == "CUT")
+    }
+
+    /// Returns the shapes of a LEF via as pairs of LEF layer name and shape, relative to
+    /// the via origin.
+    pub(crate) fn via_shapes(&self, name: &str) -> Result<Vec<(ArcStr, Shape)>> {
+        let via = self
+            .lef
+            .via(name)
+            .ok_or_else(|| LayoutError::LefDefImport(arcstr::format!("via not found: {name}")))?;
+        let mut shapes = Vec::new();
+        for shape in via.shapes.iter() {
+            for s in self.lef_shapes(&shape.kind, |p| p)? {
+                shapes.push((shape.layer.clone(), s));
+            }
+        }
+        Ok(shapes)
+    }
+
+    /// Creates a port shape on `layer` using the pin and label layers of its layer family.
+    pub(crate) fn io_shape(&self, layer: LayerId, shape: Shape) -> IoShape {
+        match self.layers.layer_family_for_layer_id(layer) {
+            Some(family) => IoShape::new(
+                family.primary,
+                family.pin.unwrap_or(layer),
+                family.label.unwrap_or(layer),
+                shape,
+            ),
+            None => IoShape::new(layer, layer, layer, shape),
+        }
+    }
+
+    fn lef_via(&self, name: &str, at: LefPoint) -> Result<Vec<ElementShape>> {
+        let offset = Point::new(self.microns(at.x), self.microns(at.y));
+        Ok(self
+            .via_shapes(name)?
+            .into_iter()
+            .filter_map(|(layer, shape)| {
+                Some(ElementShape::new(
+                    self.layer(&layer)?,
+                    shape.transform(Transformation::from_offset(offset)),
+                ))
+            })
+            .collect())
+    }
+
+    fn lef_shapes(
+        &self,
+        kind: &lefdef::LefShapeKind,
+        point: impl Fn(LefPoint) -> LefPoint,
+    ) -> Result<Vec<Shape>> {
+        let point = |p: &LefPoint| {
+            let p = point(*p);
+            Point::new(self.microns(p.x), self.microns(p.y))
+        };
+        Ok(match kind {
+            lefdef::LefShapeKind::Rect(p0, p1) => {
+                let (p0, p1) = (point(p0), point(p1));
+                vec![Shape::Rect(Rect::from_sides(
+                    p0.x.min(p1.x),
+                    p0.y.min(p1.y),
+                    p0.x.max(p1.x),
+                    p0.y.max(p1.y),
+                ))]
+            }
+            lefdef::LefShapeKind::Polygon(points) => vec![Shape::Polygon(Polygon::from_verts(
+                points.iter().map(point).collect(),
+            ))],
+            lefdef::LefShapeKind::Path { width, points } => wire_rects(
+                &points.iter().map(point).collect::<Vec<_>>(),
+                self.microns(*width),
+            )?
+            .into_iter()
+            .map(Shape::Rect)
+            .collect(),
+        })
+    }
+
+    fn microns(&self, value: f64) -> i64 {
+        let scale = (dec!(1e-6) / self.db_units).to_f64().unwrap();
+        (value * scale).round() as i64
+    }
+}
+
+/// Returns the rectangles of a wire of the given width along `points`.
+///
+/// Segments have square ends that extend half the width past their endpoints.
+/// A single point produces a square.
+pub(crate) fn wire_rects(points: &[Point], width: i64) -> Result<Vec<Rect>> {
+    let lo = width / 2;
+    let hi = width - lo;
+    let rect = |a: Point, b: Point| {
+        Rect::from_sides(
+            a.x.min(b.x) - lo,
+            a.y.min(b.y) - lo,
+            a.x.max(b.x) + hi,
+            a.y.max(b.y) + hi,
+        )
+    };
+    match points {
+        [] => Ok(Vec::new()),
+        [p] => Ok(vec![rect(*p, *p)]),
+        points => points
+            .windows(2)
+            .map(|w| {
+                if w[0].x != w[1].x && w[0].y != w[1].y {
+                    return Err(LayoutError::LefDefImport(arcstr::literal!(
+                        "non-Manhattan wires are not supported"
+                    ))
+                    .into());
+                }
+                Ok(rect(w[0], w[1]))
+            })
+            .collect(),
+    }
+}
+
+/// Converts a port name to a LEF pin name.
+///
+/// Indexed fragments are written as bus bits using the `[]` bus bit characters,
+/// so that `data[3]` is converted back to the same port name by [`pin_name`].
+fn lef_pin_name(name: &NameBuf) -> String {
+    let mut out = String::new();
+    for fragment in name.fragments() {
+        match fragment {
+            NameFragment::Idx(idx) => write!(out, "[{idx}]").unwrap(),
+            NameFragment::Str(s) => {
+                if !out.is_empty() {
+                    out.push('_');
+                }
+                out.push_str(s);
+            }
+        }
+    }
+    out
+}
+
+/// Converts a LEF or DEF pin name to a port name.
+///
+/// Bus bits such as `data[3]` are converted to indexed names.
+pub(crate) fn pin_name(name: &str) -> NameBuf {
+    if let Some((base, idx)) = name
+        .strip_suffix(']')
+        .and_then(|name| name.rsplit_once('['))
+    {
+        if let Ok(idx) = idx.parse::<usize>() {
+            let mut buf = pin_name(base);
+            buf.push(idx);
+            return buf;
+        }
+    }
+    NameBuf::from(name)
+}
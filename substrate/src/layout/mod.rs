@@ -17,7 +17,8 @@ use examples::get_snippets;
 use geometry::{
     prelude::{Bbox, Point},
     transform::{
-        HasTransformedView, Transform, TransformMut, Transformation, Transformed, TranslateMut,
+        HasTransformedView, Transform, TransformMut, Transformation, Transformed, Translate,
+        TranslateMut,
     },
     union::BoundingUnion,
 };
@@ -29,12 +30,22 @@ use crate::sealed;
 use crate::{block::Block, error::Error};
 use crate::{context::Context, error::Result};
 
-use self::element::{CellId, Element, RawCell, RawInstance, Shape};
+use self::element::{CellId, Element, RawCell, RawInstance, RawInstanceArray, Shape};
 
+pub mod connectivity;
+pub mod def;
+pub mod drc;
 pub mod element;
 pub mod error;
+pub mod flatten;
 pub mod gds;
+pub mod index;
+pub mod lef;
+pub mod parasitics;
+pub mod route;
+pub mod svg;
 pub mod tiling;
+pub mod via;
 
 /// Data exported from a generated layout.
 ///
@@ -394,6 +405,134 @@ impl<PDK: Pdk, I: LayoutImplemented<PDK>> Draw<PDK> for Instance<I> {
     }
 }
 
+/// A two-dimensional array of instances of a layout cell.
+///
+/// Drawn as a single arrayed element rather than as individual instances,
+/// and exported to GDS as an array reference.
+///
+/// The instance at row `i` and column `j` is translated by `i * row_pitch + j * col_pitch`
+/// relative to the instance at row 0 and column 0.
+pub struct InstanceArray<T: ExportsLayoutData> {
+    inst: Instance<T>,
+    rows: usize,
+    cols: usize,
+    row_pitch: Point,
+    col_pitch: Point,
+}
+
+impl<T: ExportsLayoutData> Clone for InstanceArray<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inst: self.inst.clone(),
+            ..*self
+        }
+    }
+}
+
+impl<T: ExportsLayoutData> InstanceArray<T> {
+    /// Creates an array of `rows` by `cols` copies of `inst`.
+    ///
+    /// `inst` becomes the instance at row 0 and column 0.
+    pub fn new(
+        inst: Instance<T>,
+        rows: usize,
+        cols: usize,
+        row_pitch: Point,
+        col_pitch: Point,
+    ) -> Self {
+        Self {
+            inst,
+            rows,
+            cols,
+            row_pitch,
+            col_pitch,
+        }
+    }
+
+    /// The number of rows in the array.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The number of columns in the array.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// The displacement between adjacent rows.
+    pub fn row_pitch(&self) -> Point {
+        self.row_pitch
+    }
+
+    /// The displacement between adjacent columns.
+    pub fn col_pitch(&self) -> Point {
+        self.col_pitch
+    }
+
+    /// Returns the instance at the given row and column, if it exists.
+    pub fn get(&self, row: usize, col: usize) -> Option<Instance<T>> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        let (i, j) = (row as i64, col as i64);
+        Some(self.inst.clone().translate(Point::new(
+            i * self.row_pitch.x + j * self.col_pitch.x,
+            i * self.row_pitch.y + j * self.col_pitch.y,
+        )))
+    }
+}
+
+impl<T: ExportsLayoutData> Bbox for InstanceArray<T> {
+    fn bbox(&self) -> Option<geometry::rect::Rect> {
+        if self.rows == 0 || self.cols == 0 {
+            return None;
+        }
+        // The instances lie on a parallelogram lattice, so the instances at its
+        // corners bound the entire array.
+        [
+            (0, 0),
+            (0, self.cols - 1),
+            (self.rows - 1, 0),
+            (self.rows - 1, self.cols - 1),
+        ]
+        .into_iter()
+        .filter_map(|(row, col)| self.get(row, col))
+        .collect::<Vec<_>>()
+        .bbox()
+    }
+}
+
+impl<T: ExportsLayoutData> TranslateMut for InstanceArray<T> {
+    fn translate_mut(&mut self, p: Point) {
+        self.inst.translate_mut(p)
+    }
+}
+
+impl<T: ExportsLayoutData> TransformMut for InstanceArray<T> {
+    fn transform_mut(&mut self, trans: Transformation) {
+        self.inst.transform_mut(trans);
+        let orientation =
+            Transformation::from_offset_and_orientation(Point::zero(), trans.orientation());
+        self.row_pitch.transform_mut(orientation);
+        self.col_pitch.transform_mut(orientation);
+    }
+}
+
+impl<T: ExportsLayoutData> HasTransformedView for InstanceArray<T> {
+    type TransformedView<'a> = InstanceArray<T>;
+
+    fn transformed_view(&self, trans: Transformation) -> Self::TransformedView<'_> {
+        self.clone().transform(trans)
+    }
+}
+
+impl<PDK: Pdk, I: LayoutImplemented<PDK>> Draw<PDK> for InstanceArray<I> {
+    fn draw(self, recv: &mut DrawReceiver<PDK>) -> Result<()> {
+        recv.draw_instance_array(self);
+        Ok(())
+    }
+}
+
 /// A layout cell builder.
 ///
 /// Constructed once for each invocation of [`Layout::layout`].
@@ -484,16 +623,29 @@ impl<PDK: Pdk, T> Bbox for CellBuilder<PDK, T> {
 /// A receiver for drawing layout objects.
 ///
 /// Implements the primitive functions that layout objects need to implement [`Draw`].
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct DrawReceiver<PDK> {
     phantom: PhantomData<PDK>,
     containers: Vec<Container<PDK>>,
-    instances: Vec<Arc<OnceCell<Option<RawInstance>>>>,
+    instances: Vec<Arc<OnceCell<Option<Element>>>>,
     elements: Vec<Element>,
     blockages: Vec<Shape>,
     trans: Transformation,
 }
 
+impl<PDK> Clone for DrawReceiver<PDK> {
+    fn clone(&self) -> Self {
+        Self {
+            phantom: PhantomData,
+            containers: self.containers.clone(),
+            instances: self.instances.clone(),
+            elements: self.elements.clone(),
+            blockages: self.blockages.clone(),
+            trans: self.trans,
+        }
+    }
+}
+
 impl<PDK> DrawReceiver<PDK> {
     pub(crate) fn new() -> Self {
         Self {
@@ -507,7 +659,7 @@ impl<PDK> DrawReceiver<PDK> {
     }
 
     /// Blocks on instances and returns pointers to them.
-    fn get_instances(&self) -> Vec<&RawInstance> {
+    fn get_instances(&self) -> Vec<&Element> {
         self.instances
             .iter()
             .map(|instance| instance.wait().as_ref().unwrap())
@@ -520,7 +672,7 @@ impl<PDK> DrawReceiver<PDK> {
             .into_iter()
             .map(|instance| instance.wait().clone().unwrap())
         {
-            elements.push(instance.transform(self.trans).into());
+            elements.push(instance.transform(self.trans));
         }
 
         elements.extend(
@@ -562,9 +714,33 @@ impl<PDK: Pdk> DrawReceiver<PDK> {
 
         let cell = inst.cell.clone();
         thread::spawn(move || {
-            instance.set(cell.try_cell().ok().map(|cell| RawInstance {
-                cell: cell.raw.clone(),
-                trans: inst.trans,
+            instance.set(
+                cell.try_cell()
+                    .ok()
+                    .map(|cell| RawInstance::new(cell.raw.clone(), inst.trans).into()),
+            )
+        });
+    }
+
+    pub(crate) fn draw_instance_array<I: LayoutImplemented<PDK>>(
+        &mut self,
+        array: InstanceArray<I>,
+    ) {
+        let instance = Arc::new(OnceCell::new());
+        self.instances.push(instance.clone());
+
+        thread::spawn(move || {
+            instance.set(array.inst.cell.try_cell().ok().map(|cell| {
+                RawInstanceArray {
+                    cell: cell.raw.clone(),
+                    trans: array.inst.trans,
+                    rows: array.rows,
+                    cols: array.cols,
+                    row_pitch: array.row_pitch,
+                    col_pitch: array.col_pitch,
+                    properties: Vec::new(),
+                }
+                .into()
             }))
         });
     }
@@ -642,12 +818,21 @@ impl<PDK: Pdk, T: Draw<PDK> + ?Sized> Draw<PDK> for Box<T> {
 }
 
 /// TODO: Temporarily private until we decide whether it is worth exposing.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub(crate) struct Container<PDK> {
     recvs: Vec<DrawReceiver<PDK>>,
     trans: Transformation,
 }
 
+impl<PDK> Clone for Container<PDK> {
+    fn clone(&self) -> Self {
+        Self {
+            recvs: self.recvs.clone(),
+            trans: self.trans,
+        }
+    }
+}
+
 impl<PDK> Default for Container<PDK> {
     fn default() -> Self {
         Self {
@@ -0,0 +1,545 @@
+//! Parasitic resistance and capacitance estimation.
+//!
+//! Estimates RC networks for the nets found by [connectivity extraction](super::connectivity)
+//! and back-annotates them into a SCIR netlist, which can then be simulated in place
+//! of a block's schematic using [`Context::simulate_extracted`](crate::context::Context::simulate_extracted).
+//!
+//! Resistance is estimated from the number of squares in each connected piece of a
+//! conductor, measured along the longer side of its bounding box. Capacitance is
+//! estimated from the area and perimeter of each piece, and is always to ground;
+//! coupling capacitance between nets is not modeled.
+
+use std::collections::{HashMap, HashSet};
+
+use arcstr::ArcStr;
+use geometry::point::Point;
+use indexmap::IndexMap;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use scir::{Direction, Expr, LibraryBuilder, PrimitiveDevice, PrimitiveDeviceKind, TopKind};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::pdk::layers::{layer_resolver, LayerId};
+
+use super::connectivity::{Connectivity, Extracted};
+use super::error::LayoutError;
+use super::{Cell, ExportsLayoutData};
+
+/// The parasitics of a conducting layer.
+///
+/// Lengths and areas are measured in layout database units.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerParasitics<L = LayerId> {
+    /// The drawing layer of the conductor.
+    pub layer: L,
+    /// The sheet resistance, in ohms per square.
+    #[serde(default)]
+    pub sheet_resistance: f64,
+    /// The capacitance to ground per unit area, in farads.
+    #[serde(default)]
+    pub area_capacitance: f64,
+    /// The capacitance to ground per unit length of perimeter, in farads.
+    #[serde(default)]
+    pub fringe_capacitance: f64,
+}
+
+/// The parasitics of a cut layer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViaParasitics<L = LayerId> {
+    /// The cut layer.
+    pub cut: L,
+    /// The resistance of each connected cut shape, in ohms.
+    pub resistance: f64,
+}
+
+/// The parasitic parameters of a process.
+///
+/// Like [`Connectivity`] rules, parasitic parameters are generic over the
+/// way layers are identified. Layers without parameters are treated as ideal conductors.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Parasitics<L = LayerId> {
+    /// Parameters of conducting layers.
+    #[serde(default)]
+    pub layers: Vec<LayerParasitics<L>>,
+    /// Parameters of cut layers.
+    #[serde(default)]
+    pub vias: Vec<ViaParasitics<L>>,
+}
+
+impl<L> Default for Parasitics<L> {
+    fn default() -> Self {
+        Self {
+            layers: Vec::new(),
+            vias: Vec::new(),
+        }
+    }
+}
+
+impl<L> Parasitics<L> {
+    /// Creates an empty set of parasitic parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the parameters of a conducting layer.
+    pub fn with_layer(mut self, layer: LayerParasitics<L>) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// Adds the parameters of a cut layer.
+    pub fn with_via(mut self, via: ViaParasitics<L>) -> Self {
+        self.vias.push(via);
+        self
+    }
+}
+
+impl<L: AsRef<str>> Parasitics<L> {
+    /// Resolves layer names to layer IDs using the given lookup function.
+    ///
+    /// Returns an error if a layer name cannot be resolved.
+    pub fn resolve(&self, lookup: impl FnMut(&str) -> Option<LayerId>) -> Result<Parasitics> {
+        let mut layer = layer_resolver(lookup);
+
+        let mut layers = Vec::with_capacity(self.layers.len());
+        for l in self.layers.iter() {
+            layers.push(LayerParasitics {
+                layer: layer(&l.layer)?,
+                sheet_resistance: l.sheet_resistance,
+                area_capacitance: l.area_capacitance,
+                fringe_capacitance: l.fringe_capacitance,
+            });
+        }
+
+        let mut vias = Vec::with_capacity(self.vias.len());
+        for v in self.vias.iter() {
+            vias.push(ViaParasitics {
+                cut: layer(&v.cut)?,
+                resistance: v.resistance,
+            });
+        }
+
+        Ok(Parasitics { layers, vias })
+    }
+}
+
+/// The way each connected piece of a conductor is modeled.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RcModel {
+    /// A single pi section per piece.
+    ///
+    /// The resistance of the piece connects its two ends, each of which
+    /// carries half of its capacitance.
+    #[default]
+    Lumped,
+    /// A chain of pi sections per piece, each no longer than `segment_length`.
+    Distributed {
+        /// The maximum length of a section, in layout database units.
+        segment_length: i64,
+    },
+}
+
+impl RcModel {
+    fn sections(&self, length: f64) -> usize {
+        match *self {
+            RcModel::Lumped => 1,
+            RcModel::Distributed { segment_length } => {
+                ((length / segment_length.max(1) as f64).ceil() as usize).max(1)
+            }
+        }
+    }
+}
+
+/// The nodes modeling a connected piece of a conductor.
+struct Section {
+    first: usize,
+    sections: usize,
+    horizontal: bool,
+    start: f64,
+    length: f64,
+}
+
+impl Section {
+    /// Returns the node nearest to `point`.
+    fn node_at(&self, point: Point) -> usize {
+        if self.sections == 0 {
+            return self.first;
+        }
+        let coord = if self.horizontal { point.x } else { point.y } as f64;
+        let t = ((coord - self.start) / self.length).clamp(0., 1.);
+        self.first + (t * self.sections as f64).round() as usize
+    }
+}
+
+/// An RC network under construction.
+#[derive(Default)]
+struct Network {
+    /// The net modeled by each node.
+    nets: Vec<usize>,
+    parent: Vec<usize>,
+    resistors: Vec<(usize, usize, f64)>,
+    caps: Vec<f64>,
+}
+
+impl Network {
+    fn add_node(&mut self, net: usize) -> usize {
+        let id = self.nets.len();
+        self.nets.push(net);
+        self.parent.push(id);
+        self.caps.push(0.);
+        id
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    /// Connects two nodes through a resistor, shorting them if `r` is zero.
+    fn connect(&mut self, a: usize, b: usize, r: f64) {
+        if r > 0. {
+            self.resistors.push((a, b, r));
+        } else {
+            let (a, b) = (self.find(a), self.find(b));
+            self.parent[a] = b;
+        }
+    }
+}
+
+impl Parasitics {
+    /// Back-annotates estimated parasitics into the netlist of an extracted layout.
+    ///
+    /// Returns a SCIR library like that of [`Extracted::to_scir`], with each net replaced
+    /// by an RC network. Nets keep their names at the node nearest to their port, or
+    /// at an arbitrary node if they are not exposed; other nodes are named after their net.
+    ///
+    /// Capacitors connect to the net named `ground`. If it is not already a port, it is
+    /// exposed as one, so that the resulting cell can be connected to the ground of a testbench.
+    pub fn annotate(
+        &self,
+        extracted: &Extracted,
+        model: RcModel,
+        ground: impl Into<ArcStr>,
+    ) -> Result<scir::Library> {
+        let cell = self.annotate_cell(
+            extracted,
+            model,
+            ground.into(),
+            extracted.name.clone(),
+            None,
+        )?;
+        let mut lib = LibraryBuilder::new(extracted.name.clone());
+        let id = lib.add_cell(cell);
+        lib.set_top(id, TopKind::Cell);
+        Ok(lib.build()?)
+    }
+
+    /// Back-annotates estimated parasitics into a replacement for `cell`,
+    /// typically the SCIR cell exported from the schematic of the extracted layout.
+    ///
+    /// Like [`Parasitics::annotate`], but the resulting cell keeps the name, port order,
+    /// and port directions of `cell`. Returns an error if `ground` is not a port of `cell`,
+    /// or if any other port of `cell` is not a port of the extracted layout or vice versa.
+    pub fn annotate_as(
+        &self,
+        extracted: &Extracted,
+        model: RcModel,
+        ground: impl Into<ArcStr>,
+        cell: &scir::Cell,
+    ) -> Result<scir::Cell> {
+        let ports = cell
+            .ports()
+            .map(|port| (cell.signal(port.signal()).name.clone(), port.direction()))
+            .collect::<Vec<_>>();
+        self.annotate_cell(
+            extracted,
+            model,
+            ground.into(),
+            cell.name().clone(),
+            Some(&ports),
+        )
+    }
+
+    fn annotate_cell(
+        &self,
+        extracted: &Extracted,
+        model: RcModel,
+        ground: ArcStr,
+        name: ArcStr,
+        ports: Option<&[(ArcStr, Direction)]>,
+    ) -> Result<scir::Cell> {
+        let layers = self
+            .layers
+            .iter()
+            .map(|l| (l.layer, l))
+            .collect::<HashMap<_, _>>();
+        let vias = self
+            .vias
+            .iter()
+            .map(|v| (v.cut, v.resistance))
+            .collect::<HashMap<_, _>>();
+
+        let mut network = Network::default();
+
+        // Conductor pieces.
+        let mut sections = Vec::with_capacity(extracted.pieces.len());
+        for piece in extracted.pieces.iter() {
+            let horizontal = piece.bbox.width() >= piece.bbox.height();
+            let (start, length) = if horizontal {
+                (piece.bbox.left(), piece.bbox.width())
+            } else {
+                (piece.bbox.bot(), piece.bbox.height())
+            };
+            let length = length.max(1) as f64;
+            let area = piece.region.area() as f64;
+            let perimeter = piece
+                .region
+                .boundaries()
+                .iter()
+                .map(|b| b.perimeter())
+                .sum::<f64>();
+
+            let (resistance, capacitance) = layers
+                .get(&piece.layer)
+                .map(|p| {
+                    (
+                        p.sheet_resistance * length * length / area,
+                        p.area_capacitance * area + p.fringe_capacitance * perimeter,
+                    )
+                })
+                .unwrap_or_default();
+            let n = if resistance > 0. {
+                model.sections(length)
+            } else {
+                0
+            };
+
+            let first = network.add_node(piece.net);
+            for _ in 0..n {
+                network.add_node(piece.net);
+            }
+            if n == 0 {
+                network.caps[first] += capacitance;
+            }
+            for k in 0..n {
+                network.connect(first + k, first + k + 1, resistance / n as f64);
+                network.caps[first + k] += capacitance / n as f64 / 2.;
+                network.caps[first + k + 1] += capacitance / n as f64 / 2.;
+            }
+
+            sections.push(Section {
+                first,
+                sections: n,
+                horizontal,
+                start: start as f64,
+                length,
+            });
+        }
+
+        // Vias.
+        for cut in extracted.cuts.iter() {
+            let resistance = vias.get(&cut.layer).copied().unwrap_or_default();
+            let center = cut.bbox.center();
+            let nodes = cut
+                .pieces
+                .iter()
+                .map(|&piece| sections[piece].node_at(center))
+                .collect::<Vec<_>>();
+            for &node in nodes.iter().skip(1) {
+                network.connect(nodes[0], node, resistance);
+            }
+        }
+
+        // The node of each net that keeps its name.
+        let mut primary = vec![None; extracted.nets().len()];
+        for (&net, contact) in extracted.ports().iter().zip(extracted.port_contacts.iter()) {
+            primary[net] = Some(sections[contact.piece].node_at(contact.point));
+        }
+        for (piece, section) in extracted.pieces.iter().zip(sections.iter()) {
+            primary[piece.net].get_or_insert(section.first);
+        }
+        let primary = primary
+            .into_iter()
+            .enumerate()
+            .map(|(net, node)| node.unwrap_or_else(|| network.add_node(net)))
+            .collect::<Vec<_>>();
+
+        let ground_net = extracted.net_named(&ground);
+        let ground_node = match ground_net {
+            Some(net) => primary[net],
+            None => network.add_node(usize::MAX),
+        };
+
+        // Name each group of shorted nodes.
+        let mut taken = extracted
+            .nets()
+            .iter()
+            .cloned()
+            .chain(std::iter::once(ground.clone()))
+            .collect::<HashSet<_>>();
+        let mut names: IndexMap<usize, ArcStr> = IndexMap::new();
+        for (net, &node) in primary.iter().enumerate() {
+            let root = network.find(node);
+            names
+                .entry(root)
+                .or_insert_with(|| extracted.nets()[net].clone());
+        }
+        let root = network.find(ground_node);
+        names.entry(root).or_insert_with(|| ground.clone());
+        let mut counters = vec![0usize; extracted.nets().len()];
+        for node in 0..network.nets.len() {
+            let root = network.find(node);
+            if names.contains_key(&root) {
+                continue;
+            }
+            let net = network.nets[node];
+            let name = loop {
+                counters[net] += 1;
+                let candidate = arcstr::format!("{}_{}", extracted.nets()[net], counters[net]);
+                if taken.insert(candidate.clone()) {
+                    break candidate;
+                }
+            };
+            names.insert(root, name);
+        }
+
+        let mut cell = scir::Cell::new_whitebox(name);
+        let signals = names
+            .iter()
+            .map(|(&root, name)| (root, cell.add_node(name.clone())))
+            .collect::<HashMap<_, _>>();
+        let signal = |network: &mut Network, node: usize| signals[&network.find(node)];
+
+        match ports {
+            None => {
+                for &net in extracted.ports() {
+                    cell.expose_port(signal(&mut network, primary[net]), Direction::InOut);
+                }
+                if !ground_net.is_some_and(|net| extracted.ports().contains(&net)) {
+                    cell.expose_port(signal(&mut network, ground_node), Direction::InOut);
+                }
+            }
+            Some(ports) => {
+                if !ports.iter().any(|(name, _)| *name == ground) {
+                    return Err(LayoutError::Extraction(arcstr::format!(
+                        "ground net {ground} is not a port"
+                    ))
+                    .into());
+                }
+                let mut exposed = HashSet::new();
+                for (name, direction) in ports {
+                    let node = if *name == ground {
+                        ground_node
+                    } else {
+                        let net = extracted
+                            .net_named(name)
+                            .filter(|net| extracted.ports().contains(net))
+                            .ok_or_else(|| {
+                                LayoutError::Extraction(arcstr::format!(
+                                    "port {name} is missing from the layout"
+                                ))
+                            })?;
+                        exposed.insert(net);
+                        primary[net]
+                    };
+                    cell.expose_port(signal(&mut network, node), *direction);
+                }
+                if let Some(&net) = extracted
+                    .ports()
+                    .iter()
+                    .find(|&&net| !exposed.contains(&net) && Some(net) != ground_net)
+                {
+                    return Err(LayoutError::Extraction(arcstr::format!(
+                        "layout port {} is not a port of {}",
+                        extracted.nets()[net],
+                        extracted.name
+                    ))
+                    .into());
+                }
+            }
+        }
+
+        for (i, device) in extracted.devices().iter().enumerate() {
+            let ports = device
+                .terminals
+                .iter()
+                .zip(device.contacts.iter())
+                .map(|(&net, contact)| {
+                    let node = match contact {
+                        Some(contact) => sections[contact.piece].node_at(contact.point),
+                        None => primary[net],
+                    };
+                    signal(&mut network, node)
+                })
+                .collect();
+            cell.add_primitive(PrimitiveDevice::new(
+                arcstr::format!("x{i}"),
+                PrimitiveDeviceKind::RawInstance {
+                    ports,
+                    cell: device.cell.clone(),
+                },
+            ));
+        }
+
+        let resistors = std::mem::take(&mut network.resistors);
+        for (i, (a, b, r)) in resistors.into_iter().enumerate() {
+            cell.add_primitive(PrimitiveDevice::new(
+                arcstr::format!("r{i}"),
+                PrimitiveDeviceKind::Res2 {
+                    pos: signal(&mut network, a),
+                    neg: signal(&mut network, b),
+                    value: value(r)?,
+                },
+            ));
+        }
+
+        let mut caps: IndexMap<usize, f64> = IndexMap::new();
+        for node in 0..network.nets.len() {
+            let root = network.find(node);
+            *caps.entry(root).or_default() += network.caps[node];
+        }
+        let gnd = signal(&mut network, ground_node);
+        for (i, (root, c)) in caps
+            .into_iter()
+            .filter(|&(root, c)| c > 0. && signals[&root] != gnd)
+            .enumerate()
+        {
+            cell.add_primitive(PrimitiveDevice::new(
+                arcstr::format!("c{i}"),
+                PrimitiveDeviceKind::Cap2 {
+                    pos: signals[&root],
+                    neg: gnd,
+                    value: value(c)?,
+                },
+            ));
+        }
+
+        Ok(cell)
+    }
+}
+
+/// Converts a resistance or capacitance to a SCIR literal with 6 significant figures.
+///
+/// Returns an error if the value is not finite or cannot be represented as a decimal.
+fn value(x: f64) -> Result<Expr> {
+    let x = Decimal::from_f64(x)
+        .ok_or_else(|| LayoutError::Extraction(arcstr::format!("invalid parasitic value: {x}")))?;
+    Ok(Expr::NumericLiteral(x.round_sf(6).unwrap_or(x).normalize()))
+}
+
+impl<T: ExportsLayoutData> Cell<T> {
+    /// Extracts this cell and back-annotates estimated parasitics into its netlist.
+    pub fn extract_parasitics(
+        &self,
+        connectivity: &Connectivity,
+        parasitics: &Parasitics,
+        model: RcModel,
+        ground: impl Into<ArcStr>,
+    ) -> Result<scir::Library> {
+        parasitics.annotate(&connectivity.extract(&self.raw), model, ground)
+    }
+}
@@ -0,0 +1,728 @@
+//! Grid-based routing.
+//!
+//! The [`Router`] connects pins using a maze router on a grid of routing tracks.
+//! Each routing layer has tracks at a fixed pitch running in its preferred direction,
+//! and wires only run along tracks. Adjacent layers are connected by vias where their
+//! tracks cross.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+use geometry::bbox::Bbox;
+use geometry::dir::Dir;
+use geometry::point::Point;
+use geometry::rect::Rect;
+use geometry::transform::{Transform, Transformation};
+use geometry::union::BoundingUnion;
+use indexmap::IndexMap;
+use rstar::primitives::{GeomWithData, Rectangle};
+use rstar::{RTree, AABB};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::io::{PortGeometry, TransformedPortGeometry};
+use crate::pdk::layers::{HasPin, LayerId};
+use crate::pdk::Pdk;
+
+use super::element::{Element, RawCell, Shape};
+use super::error::LayoutError;
+use super::{CellBuilder, Draw, DrawReceiver, ExportsLayoutData, Instance};
+
+type Entry = GeomWithData<Rectangle<[i64; 2]>, usize>;
+
+/// A layer available for routing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RoutingLayer {
+    /// The layer.
+    pub layer: LayerId,
+    /// The preferred direction of wires on this layer.
+    pub dir: Dir,
+    /// The distance between adjacent tracks.
+    pub pitch: i64,
+    /// The coordinate of the center of the track at index 0.
+    pub offset: i64,
+    /// The width of wires.
+    pub width: i64,
+    /// The minimum spacing between wires and other shapes on this layer.
+    pub spacing: i64,
+}
+
+/// A via connecting two adjacent routing layers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ViaRule {
+    /// The cut layer.
+    pub cut: LayerId,
+    /// The side length of the square cut.
+    pub size: i64,
+    /// The enclosure of the cut by the routing layers above and below it.
+    pub enclosure: i64,
+    /// The minimum spacing between cuts and other shapes on the cut layer.
+    pub spacing: i64,
+}
+
+/// The configuration of a [`Router`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouterConfig {
+    /// The routing layers, from bottom to top.
+    pub layers: Vec<RoutingLayer>,
+    /// The vias between routing layers.
+    ///
+    /// The via at index `i` connects the layers at indices `i` and `i + 1`.
+    pub vias: Vec<ViaRule>,
+    /// The cost of a via, in units of wire length.
+    ///
+    /// If not specified, a via costs twice the largest pitch of the layers it connects.
+    pub via_cost: Option<i64>,
+}
+
+impl RouterConfig {
+    /// Creates an empty router configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a routing layer above the existing layers.
+    pub fn with_layer(mut self, layer: RoutingLayer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// Adds a via above the existing vias.
+    pub fn with_via(mut self, via: ViaRule) -> Self {
+        self.vias.push(via);
+        self
+    }
+
+    /// Sets the cost of a via.
+    pub fn with_via_cost(mut self, cost: i64) -> Self {
+        self.via_cost = Some(cost);
+        self
+    }
+}
+
+/// A set of shapes to be connected by a [`Router`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Pin {
+    shapes: Vec<Shape>,
+}
+
+impl Pin {
+    /// Creates a pin from the given shapes.
+    pub fn new(shapes: impl IntoIterator<Item = Shape>) -> Self {
+        Self {
+            shapes: shapes.into_iter().collect(),
+        }
+    }
+
+    /// Returns the shapes of the pin.
+    pub fn shapes(&self) -> &[Shape] {
+        &self.shapes
+    }
+}
+
+impl From<Shape> for Pin {
+    fn from(value: Shape) -> Self {
+        Self::new([value])
+    }
+}
+
+impl From<&PortGeometry> for Pin {
+    fn from(value: &PortGeometry) -> Self {
+        Self::new(
+            std::iter::once(&value.primary)
+                .chain(value.unnamed_shapes.iter())
+                .chain(value.named_shapes.values())
+                .map(|shape| Shape::new(shape.layer().drawing(), shape.shape().clone())),
+        )
+    }
+}
+
+impl From<PortGeometry> for Pin {
+    fn from(value: PortGeometry) -> Self {
+        Self::from(&value)
+    }
+}
+
+impl<'a> From<TransformedPortGeometry<'a>> for Pin {
+    fn from(value: TransformedPortGeometry<'a>) -> Self {
+        Self::from(PortGeometry::from(value))
+    }
+}
+
+/// The shapes of a routed net.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Route {
+    shapes: Vec<Shape>,
+}
+
+impl Route {
+    /// Returns the shapes making up the route.
+    pub fn shapes(&self) -> &[Shape] {
+        &self.shapes
+    }
+}
+
+impl<PDK: Pdk> Draw<PDK> for Route {
+    fn draw(self, recv: &mut DrawReceiver<PDK>) -> Result<()> {
+        for shape in self.shapes {
+            recv.draw(shape)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Obstacles {
+    rects: Vec<Rect>,
+    /// Whether each obstacle is a blockage, which may not be overlapped even by its own net.
+    blockages: Vec<bool>,
+    tree: RTree<Entry>,
+}
+
+/// A grid-based maze router.
+///
+/// Obstacles are represented by their bounding boxes. Each routed net becomes an obstacle
+/// for nets routed after it.
+#[derive(Debug, Clone)]
+pub struct Router {
+    config: RouterConfig,
+    bounds: Option<Rect>,
+    obstacles: IndexMap<LayerId, Obstacles>,
+}
+
+impl Router {
+    /// Creates a router with the given configuration and no obstacles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration does not have exactly one via between each pair of
+    /// adjacent routing layers, or if any layer has a non-positive pitch.
+    pub fn new(config: RouterConfig) -> Self {
+        assert_eq!(
+            config.vias.len() + 1,
+            config.layers.len().max(1),
+            "router requires one via between each pair of adjacent routing layers"
+        );
+        assert!(
+            config.layers.iter().all(|layer| layer.pitch > 0),
+            "routing layer pitches must be positive"
+        );
+        Self {
+            config,
+            bounds: None,
+            obstacles: IndexMap::new(),
+        }
+    }
+
+    /// Restricts routing to the given rectangle.
+    ///
+    /// By default, routes may extend a few tracks beyond the bounding box
+    /// of the obstacles and pins.
+    pub fn with_bounds(mut self, bounds: Rect) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Adds an obstacle.
+    ///
+    /// Obstacles that touch a pin on the same layer are assumed to belong
+    /// to the pin's net, and are ignored when routing that net.
+    pub fn add_obstacle(&mut self, shape: &Shape) {
+        self.add(shape, false);
+    }
+
+    /// Adds a blockage.
+    ///
+    /// Unlike other obstacles, blockages are avoided by all nets.
+    pub fn add_blockage(&mut self, shape: &Shape) {
+        self.add(shape, true);
+    }
+
+    /// Adds the shapes, ports, and blockages of a cell and all cells it instantiates as obstacles.
+    pub fn add_cell(&mut self, cell: &RawCell, trans: Transformation) {
+        for (_, port) in cell.ports() {
+            for shape in Pin::from(port).shapes {
+                self.add_obstacle(&shape.transform(trans));
+            }
+        }
+        for blockage in cell.blockages.iter() {
+            self.add_blockage(&blockage.clone().transform(trans));
+        }
+        self.add_elements(cell.elements(), trans);
+    }
+
+    /// Adds the contents of an instance as obstacles.
+    pub fn add_instance<T: ExportsLayoutData>(&mut self, inst: &Instance<T>) -> Result<()> {
+        let cell = inst.cell.try_cell()?;
+        self.add_cell(&cell.raw, inst.trans);
+        Ok(())
+    }
+
+    fn add_elements<'a>(
+        &mut self,
+        elements: impl Iterator<Item = &'a Element>,
+        trans: Transformation,
+    ) {
+        for element in elements {
+            match element {
+                Element::Instance(inst) => {
+                    self.add_cell(&inst.cell, Transformation::cascade(trans, inst.trans))
+                }
+                Element::InstanceArray(array) => {
+                    for inst in array.instances() {
+                        self.add_cell(&inst.cell, Transformation::cascade(trans, inst.trans))
+                    }
+                }
+                Element::Shape(shape) => self.add_obstacle(&shape.clone().transform(trans)),
+                Element::Text(_) => {}
+            }
+        }
+    }
+
+    fn add(&mut self, shape: &Shape, blockage: bool) {
+        let Some(rect) = shape.shape().bbox() else {
+            return;
+        };
+        let obstacles = self.obstacles.entry(shape.layer()).or_default();
+        obstacles.tree.insert(entry(rect, obstacles.rects.len()));
+        obstacles.rects.push(rect);
+        obstacles.blockages.push(blockage);
+    }
+
+    /// Routes a net connecting the given pins.
+    ///
+    /// Pins must have at least one shape on a routing layer. Pins whose shapes do not contain
+    /// any grid point are connected to the nearest grid point that can be reached by a short
+    /// stub without coming too close to obstacles. Pins of nets that have not been routed yet
+    /// should be added as obstacles, so that earlier nets avoid them.
+    ///
+    /// The route is added to the router's obstacles. Returns an error if the pins cannot be connected.
+    pub fn route(&mut self, pins: impl IntoIterator<Item = impl Into<Pin>>) -> Result<Route> {
+        let pins = pins.into_iter().map(Into::into).collect::<Vec<Pin>>();
+        let pin_shapes = pins.iter().flat_map(|pin| pin.shapes.iter());
+
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => {
+                let margin = 4 * self
+                    .config
+                    .layers
+                    .iter()
+                    .map(|l| l.pitch)
+                    .max()
+                    .unwrap_or(0);
+                let bbox = self
+                    .obstacles
+                    .values()
+                    .flat_map(|o| o.rects.iter().copied())
+                    .chain(pin_shapes.clone().filter_map(|s| s.shape().bbox()))
+                    .fold(None, |acc: Option<Rect>, r| acc.bounding_union(&Some(r)));
+                match bbox {
+                    Some(bbox) => bbox.expand_all(margin),
+                    None => return Ok(Route::default()),
+                }
+            }
+        };
+
+        // Obstacles touching the pins belong to this net.
+        let mut exempt: HashSet<(LayerId, usize)> = HashSet::new();
+        for shape in pin_shapes {
+            let (Some(obstacles), Some(rect)) =
+                (self.obstacles.get(&shape.layer()), shape.shape().bbox())
+            else {
+                continue;
+            };
+            for e in obstacles
+                .tree
+                .locate_in_envelope_intersecting(&envelope(rect))
+            {
+                if !obstacles.blockages[e.data] {
+                    exempt.insert((shape.layer(), e.data));
+                }
+            }
+        }
+
+        let grid = Grid::new(&self.config, bounds);
+        let search = Search {
+            config: &self.config,
+            obstacles: &self.obstacles,
+            exempt: &exempt,
+            grid: &grid,
+        };
+
+        let mut route = Route::default();
+        let mut targets = Vec::with_capacity(pins.len());
+        for pin in pins.iter() {
+            let (nodes, stubs) = search.access(pin)?;
+            route.shapes.extend(stubs);
+            targets.push(nodes);
+        }
+
+        if !targets.is_empty() {
+            let mut tree = targets.swap_remove(0);
+            while !targets.is_empty() {
+                let path = search.shortest_path(&tree, &targets).ok_or_else(|| {
+                    LayoutError::Routing(arcstr::literal!("no path found between pins"))
+                })?;
+                let reached = *path.last().unwrap();
+                let i = targets.iter().position(|t| t.contains(&reached)).unwrap();
+                tree.extend(targets.swap_remove(i));
+                route.shapes.extend(search.path_shapes(&path));
+                tree.extend(path);
+            }
+        }
+
+        for shape in route.shapes.iter() {
+            self.add_obstacle(shape);
+        }
+        Ok(route)
+    }
+}
+
+/// The tracks of a single routing layer within the routing bounds.
+struct LayerGrid {
+    dir: Dir,
+    /// Coordinates of the tracks, perpendicular to the preferred direction.
+    tracks: Vec<i64>,
+    /// Coordinates along each track at which the wire may stop or change layers.
+    stops: Vec<i64>,
+    base: usize,
+}
+
+impl LayerGrid {
+    fn point(&self, track: usize, stop: usize) -> Point {
+        match self.dir {
+            Dir::Horiz => Point::new(self.stops[stop], self.tracks[track]),
+            Dir::Vert => Point::new(self.tracks[track], self.stops[stop]),
+        }
+    }
+
+    fn index(&self, point: Point) -> Option<usize> {
+        let (track, stop) = match self.dir {
+            Dir::Horiz => (point.y, point.x),
+            Dir::Vert => (point.x, point.y),
+        };
+        let track = self.tracks.binary_search(&track).ok()?;
+        let stop = self.stops.binary_search(&stop).ok()?;
+        Some(self.base + track * self.stops.len() + stop)
+    }
+
+    fn len(&self) -> usize {
+        self.tracks.len() * self.stops.len()
+    }
+}
+
+struct Grid {
+    layers: Vec<LayerGrid>,
+}
+
+impl Grid {
+    fn new(config: &RouterConfig, bounds: Rect) -> Self {
+        let tracks = |layer: &RoutingLayer, dir: Dir| {
+            let (lo, hi) = match dir {
+                Dir::Horiz => (bounds.bot(), bounds.top()),
+                Dir::Vert => (bounds.left(), bounds.right()),
+            };
+            let first = (lo - layer.offset).div_euclid(layer.pitch)
+                + ((lo - layer.offset).rem_euclid(layer.pitch) != 0) as i64;
+            let last = (hi - layer.offset).div_euclid(layer.pitch);
+            (first..=last)
+                .map(|k| layer.offset + k * layer.pitch)
+                .collect::<Vec<_>>()
+        };
+
+        let mut layers = Vec::with_capacity(config.layers.len());
+        let mut base = 0;
+        for (i, layer) in config.layers.iter().enumerate() {
+            let mut stops = [i.checked_sub(1), Some(i + 1)]
+                .into_iter()
+                .flatten()
+                .filter_map(|j| config.layers.get(j))
+                .filter(|other| other.dir != layer.dir)
+                .flat_map(|other| tracks(other, other.dir))
+                .collect::<Vec<_>>();
+            if stops.is_empty() {
+                stops = tracks(layer, layer.dir.other());
+            }
+            stops.sort();
+            stops.dedup();
+            let grid = LayerGrid {
+                dir: layer.dir,
+                tracks: tracks(layer, layer.dir),
+                stops,
+                base,
+            };
+            base += grid.len();
+            layers.push(grid);
+        }
+        Self { layers }
+    }
+
+    /// Returns the layer, track, and stop of a node.
+    fn locate(&self, node: usize) -> (usize, usize, usize) {
+        let layer = self.layers.iter().rposition(|l| l.base <= node).unwrap();
+        let grid = &self.layers[layer];
+        let i = node - grid.base;
+        (layer, i / grid.stops.len(), i % grid.stops.len())
+    }
+
+    fn point(&self, node: usize) -> (usize, Point) {
+        let (layer, track, stop) = self.locate(node);
+        (layer, self.layers[layer].point(track, stop))
+    }
+}
+
+struct Search<'a> {
+    config: &'a RouterConfig,
+    obstacles: &'a IndexMap<LayerId, Obstacles>,
+    exempt: &'a HashSet<(LayerId, usize)>,
+    grid: &'a Grid,
+}
+
+impl<'a> Search<'a> {
+    /// Returns `true` if `rect` comes closer than `spacing` to an obstacle on `layer`.
+    fn blocked(&self, layer: LayerId, rect: Rect, spacing: i64) -> bool {
+        let Some(obstacles) = self.obstacles.get(&layer) else {
+            return false;
+        };
+        let rect = rect.expand_all(spacing);
+        obstacles
+            .tree
+            .locate_in_envelope_intersecting(&envelope(rect))
+            .any(|e| {
+                let o = obstacles.rects[e.data];
+                o.left() < rect.right()
+                    && rect.left() < o.right()
+                    && o.bot() < rect.top()
+                    && rect.bot() < o.top()
+                    && !self.exempt.contains(&(layer, e.data))
+            })
+    }
+
+    fn wire(&self, layer: usize, a: Point, b: Point) -> Rect {
+        let hw = self.config.layers[layer].width / 2;
+        Rect::from_sides(a.x.min(b.x), a.y.min(b.y), a.x.max(b.x), a.y.max(b.y)).expand_all(hw)
+    }
+
+    fn node_free(&self, node: usize) -> bool {
+        let (layer, p) = self.grid.point(node);
+        let l = &self.config.layers[layer];
+        !self.blocked(l.layer, self.wire(layer, p, p), l.spacing)
+    }
+
+    fn via_shapes(&self, layer: usize, p: Point) -> [Shape; 3] {
+        let via = &self.config.vias[layer];
+        let cut = Rect::from_sides(p.x, p.y, p.x, p.y).expand_all(via.size / 2);
+        let pad = |layer: usize| {
+            Shape::new(
+                self.config.layers[layer].layer,
+                cut.expand_all(via.enclosure).union(self.wire(layer, p, p)),
+            )
+        };
+        [pad(layer), Shape::new(via.cut, cut), pad(layer + 1)]
+    }
+
+    /// Returns `true` if a via from `layer` to the layer above can be placed at `p`.
+    fn via_free(&self, layer: usize, p: Point) -> bool {
+        let via = &self.config.vias[layer];
+        let [bot, cut, top] = self.via_shapes(layer, p);
+        let bot_l = &self.config.layers[layer];
+        let top_l = &self.config.layers[layer + 1];
+        !self.blocked(bot_l.layer, bot.shape().bbox().unwrap(), bot_l.spacing)
+            && !self.blocked(via.cut, cut.shape().bbox().unwrap(), via.spacing)
+            && !self.blocked(top_l.layer, top.shape().bbox().unwrap(), top_l.spacing)
+    }
+
+    fn via_cost(&self, layer: usize) -> i64 {
+        self.config.via_cost.unwrap_or_else(|| {
+            2 * self.config.layers[layer]
+                .pitch
+                .max(self.config.layers[layer + 1].pitch)
+        })
+    }
+
+    /// Returns the grid nodes that can be used to access `pin`, along with any stubs required.
+    fn access(&self, pin: &Pin) -> Result<(Vec<usize>, Vec<Shape>)> {
+        let mut nodes = Vec::new();
+        let mut nearest: Option<(i64, usize, Shape)> = None;
+        for shape in pin.shapes.iter() {
+            let Some(layer) = self
+                .config
+                .layers
+                .iter()
+                .position(|l| l.layer == shape.layer())
+            else {
+                continue;
+            };
+            let Some(rect) = shape.shape().bbox() else {
+                continue;
+            };
+            let grid = &self.grid.layers[layer];
+            for track in 0..grid.tracks.len() {
+                for stop in 0..grid.stops.len() {
+                    let node = grid.base + track * grid.stops.len() + stop;
+                    let p = grid.point(track, stop);
+                    if p.x >= rect.left()
+                        && p.x <= rect.right()
+                        && p.y >= rect.bot()
+                        && p.y <= rect.top()
+                    {
+                        nodes.push(node);
+                    } else if nodes.is_empty() {
+                        let q = Point::new(
+                            p.x.clamp(rect.left(), rect.right()),
+                            p.y.clamp(rect.bot(), rect.top()),
+                        );
+                        let dist = (p.x - q.x).abs() + (p.y - q.y).abs();
+                        if nearest.as_ref().map_or(true, |(d, _, _)| dist < *d)
+                            && self.node_free(node)
+                        {
+                            let stub = self.wire(layer, p, q);
+                            let l = &self.config.layers[layer];
+                            if !self.blocked(l.layer, stub, l.spacing) {
+                                nearest = Some((dist, node, Shape::new(shape.layer(), stub)));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if !nodes.is_empty() {
+            return Ok((nodes, Vec::new()));
+        }
+        match nearest {
+            Some((_, node, stub)) => Ok((vec![node], vec![stub])),
+            None => Err(LayoutError::Routing(arcstr::literal!(
+                "pin has no accessible shapes on a routing layer"
+            ))
+            .into()),
+        }
+    }
+
+    /// Returns the neighbors of a node and the cost of moving to each.
+    fn neighbors(&self, node: usize) -> Vec<(usize, i64)> {
+        let (layer, track, stop) = self.grid.locate(node);
+        let grid = &self.grid.layers[layer];
+        let l = &self.config.layers[layer];
+        let p = grid.point(track, stop);
+        let mut out = Vec::with_capacity(4);
+        for next in [stop.checked_sub(1), Some(stop + 1)].into_iter().flatten() {
+            if next >= grid.stops.len() {
+                continue;
+            }
+            let q = grid.point(track, next);
+            if !self.blocked(l.layer, self.wire(layer, p, q), l.spacing) {
+                out.push((
+                    grid.base + track * grid.stops.len() + next,
+                    (grid.stops[next] - grid.stops[stop]).abs(),
+                ));
+            }
+        }
+        if layer + 1 < self.grid.layers.len() {
+            if let Some(up) = self.grid.layers[layer + 1].index(p) {
+                if self.via_free(layer, p) {
+                    out.push((up, self.via_cost(layer)));
+                }
+            }
+        }
+        if layer > 0 {
+            if let Some(down) = self.grid.layers[layer - 1].index(p) {
+                if self.via_free(layer - 1, p) {
+                    out.push((down, self.via_cost(layer - 1)));
+                }
+            }
+        }
+        out
+    }
+
+    /// Finds the cheapest path from any node in `sources` to any node in `targets`.
+    fn shortest_path(&self, sources: &[usize], targets: &[Vec<usize>]) -> Option<Vec<usize>> {
+        let targets = targets.iter().flatten().copied().collect::<HashSet<_>>();
+        let mut dist: IndexMap<usize, i64> = IndexMap::new();
+        let mut prev: IndexMap<usize, usize> = IndexMap::new();
+        let mut heap = BinaryHeap::new();
+        for &s in sources {
+            dist.insert(s, 0);
+            heap.push(Reverse((0, s)));
+        }
+        while let Some(Reverse((d, node))) = heap.pop() {
+            if dist.get(&node).is_some_and(|&best| d > best) {
+                continue;
+            }
+            if targets.contains(&node) {
+                let mut path = vec![node];
+                let mut cur = node;
+                while let Some(&p) = prev.get(&cur) {
+                    path.push(p);
+                    cur = p;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for (next, cost) in self.neighbors(node) {
+                let nd = d + cost;
+                if dist.get(&next).map_or(true, |&best| nd < best)
+                    && (targets.contains(&next) || self.node_free(next))
+                {
+                    dist.insert(next, nd);
+                    prev.insert(next, node);
+                    heap.push(Reverse((nd, next)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Converts a path of grid nodes to shapes.
+    fn path_shapes(&self, path: &[usize]) -> Vec<Shape> {
+        let mut shapes = Vec::new();
+        let mut start = 0;
+        for i in 1..=path.len() {
+            let (layer, p) = self.grid.point(path[i - 1]);
+            let next = path.get(i).map(|&n| self.grid.point(n));
+            if next.map_or(true, |(l, _)| l != layer) {
+                let (_, first) = self.grid.point(path[start]);
+                if i - 1 > start {
+                    shapes.push(Shape::new(
+                        self.config.layers[layer].layer,
+                        self.wire(layer, first, p),
+                    ));
+                }
+                if let Some((l, _)) = next {
+                    shapes.extend(self.via_shapes(layer.min(l), p));
+                }
+                start = i;
+            }
+        }
+        shapes
+    }
+}
+
+fn entry(bbox: Rect, i: usize) -> Entry {
+    GeomWithData::new(
+        Rectangle::from_corners([bbox.left(), bbox.bot()], [bbox.right(), bbox.top()]),
+        i,
+    )
+}
+
+fn envelope(rect: Rect) -> AABB<[i64; 2]> {
+    AABB::from_corners([rect.left(), rect.bot()], [rect.right(), rect.top()])
+}
+
+impl<PDK: Pdk, T> CellBuilder<PDK, T> {
+    /// Creates a router that treats everything drawn in this cell so far as obstacles.
+    ///
+    /// Blocks until all instances drawn so far have been generated.
+    pub fn router(&self, config: RouterConfig) -> Router {
+        let mut elements = Vec::new();
+        let mut blockages = Vec::new();
+        self.container.clone().finish(&mut elements, &mut blockages);
+
+        let mut router = Router::new(config);
+        for blockage in blockages.iter() {
+            router.add_blockage(blockage);
+        }
+        router.add_elements(elements.iter(), Transformation::identity());
+        router
+    }
+}
@@ -0,0 +1,711 @@
+//! SVG rendering of layouts.
+//!
+//! Draws layout cells as SVG images, for previewing layouts in documentation,
+//! code review, and CI artifacts without a layout editor.
+
+use std::collections::HashSet;
+use std::fmt::{Display, Write};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use arcstr::ArcStr;
+use geometry::bbox::Bbox;
+use geometry::point::Point;
+use geometry::rect::Rect;
+use geometry::shape::Shape;
+use geometry::transform::{Transform, Transformation};
+use indexmap::IndexMap;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::pdk::layers::{layer_resolver, HasPin, LayerContext, LayerId};
+
+use super::element::{Element, RawCell, Text};
+use super::error::LayoutError;
+
+/// An RGB color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Color {
+    /// The red component.
+    pub r: u8,
+    /// The green component.
+    pub g: u8,
+    /// The blue component.
+    pub b: u8,
+}
+
+impl Color {
+    /// Creates a color from its red, green, and blue components.
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+}
+
+impl FromStr for Color {
+    type Err = LayoutError;
+
+    /// Parses a color of the form `#rrggbb`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let error = || LayoutError::InvalidColor(s.into());
+        let hex = s.strip_prefix('#').ok_or_else(error)?;
+        if hex.len() != 6 {
+            return Err(error());
+        }
+        let component = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(error)
+        };
+        Ok(Self::rgb(component(0)?, component(2)?, component(4)?))
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+/// The pattern used to fill shapes on a layer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FillPattern {
+    /// A translucent solid fill.
+    #[default]
+    Solid,
+    /// No fill; only the outlines of shapes are drawn.
+    Hollow,
+    /// Diagonal lines rising to the right.
+    Hatch,
+    /// Diagonal lines falling to the right.
+    BackHatch,
+    /// Diagonal lines in both directions.
+    CrossHatch,
+    /// A grid of dots.
+    Dots,
+}
+
+/// The style of a layer in rendered layouts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayerStyle<L = LayerId> {
+    /// The layer.
+    pub layer: L,
+    /// The color of shape outlines and fills.
+    pub color: Color,
+    /// The fill pattern.
+    #[serde(default)]
+    pub fill: FillPattern,
+}
+
+impl<L> LayerStyle<L> {
+    /// Creates a new layer style.
+    pub fn new(layer: L, color: Color, fill: FillPattern) -> Self {
+        Self { layer, color, fill }
+    }
+}
+
+/// The styles of layers in rendered layouts.
+///
+/// Layers are drawn in the order in which they are styled, from bottom to top.
+/// A layer without a style of its own is drawn without fill in the color of the
+/// primary layer of its layer family, if that layer is styled. Other layers are
+/// drawn on top of styled layers using colors from a built-in palette.
+///
+/// Like [`LefRules`](super::lef::LefRules), layer styles are generic over the way
+/// layers are identified.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayerStyles<L = LayerId> {
+    /// The styled layers.
+    #[serde(default)]
+    pub layers: Vec<LayerStyle<L>>,
+}
+
+impl<L> Default for LayerStyles<L> {
+    fn default() -> Self {
+        Self { layers: Vec::new() }
+    }
+}
+
+impl<L> LayerStyles<L> {
+    /// Creates an empty set of layer styles.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a layer style.
+    pub fn with_layer(mut self, style: LayerStyle<L>) -> Self {
+        self.layers.push(style);
+        self
+    }
+}
+
+impl<L: AsRef<str>> LayerStyles<L> {
+    /// Resolves layer names to layer IDs using the given lookup function.
+    ///
+    /// Returns an error if a layer name cannot be resolved.
+    pub fn resolve(&self, lookup: impl FnMut(&str) -> Option<LayerId>) -> Result<LayerStyles> {
+        let mut layer = layer_resolver(lookup);
+        let mut layers = Vec::with_capacity(self.layers.len());
+        for style in self.layers.iter() {
+            layers.push(LayerStyle {
+                layer: layer(&style.layer)?,
+                color: style.color,
+                fill: style.fill,
+            });
+        }
+        Ok(LayerStyles { layers })
+    }
+}
+
+/// Options for rendering layouts to SVG.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SvgOptions {
+    /// The number of levels of the instance hierarchy to draw.
+    ///
+    /// Instances below this depth are drawn as outlines labeled with the name of
+    /// their cell. If `None`, the hierarchy is fully flattened.
+    pub depth: Option<usize>,
+    /// Layers that are not drawn.
+    pub hidden_layers: HashSet<LayerId>,
+    /// Whether to label the ports of the rendered cell.
+    pub port_labels: bool,
+    /// Whether to draw text annotations.
+    pub text_labels: bool,
+    /// Whether to draw the outlines of all instances.
+    pub instance_outlines: bool,
+    /// Whether to draw a scale bar below the layout.
+    pub scale_bar: bool,
+    /// The width of the image in pixels.
+    pub width: u32,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            depth: None,
+            hidden_layers: HashSet::new(),
+            port_labels: true,
+            text_labels: true,
+            instance_outlines: true,
+            scale_bar: true,
+            width: 800,
+        }
+    }
+}
+
+impl SvgOptions {
+    /// Creates the default rendering options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the number of levels of the instance hierarchy that are drawn.
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    /// Hides the given layer.
+    pub fn with_hidden_layer(mut self, layer: impl AsRef<LayerId>) -> Self {
+        self.hidden_layers.insert(*layer.as_ref());
+        self
+    }
+
+    /// Sets whether ports are labeled.
+    pub fn with_port_labels(mut self, port_labels: bool) -> Self {
+        self.port_labels = port_labels;
+        self
+    }
+
+    /// Sets whether text annotations are drawn.
+    pub fn with_text_labels(mut self, text_labels: bool) -> Self {
+        self.text_labels = text_labels;
+        self
+    }
+
+    /// Sets whether instance outlines are drawn.
+    pub fn with_instance_outlines(mut self, instance_outlines: bool) -> Self {
+        self.instance_outlines = instance_outlines;
+        self
+    }
+
+    /// Sets whether a scale bar is drawn.
+    pub fn with_scale_bar(mut self, scale_bar: bool) -> Self {
+        self.scale_bar = scale_bar;
+        self
+    }
+
+    /// Sets the width of the image in pixels.
+    pub fn with_width(mut self, width: u32) -> Self {
+        self.width = width;
+        self
+    }
+}
+
+/// Colors assigned to layers without a style.
+const PALETTE: &[Color] = &[
+    Color::rgb(0x1f, 0x77, 0xb4),
+    Color::rgb(0xff, 0x7f, 0x0e),
+    Color::rgb(0x2c, 0xa0, 0x2c),
+    Color::rgb(0xd6, 0x27, 0x28),
+    Color::rgb(0x94, 0x67, 0xbd),
+    Color::rgb(0x8c, 0x56, 0x4b),
+    Color::rgb(0xe3, 0x77, 0xc2),
+    Color::rgb(0x7f, 0x7f, 0x7f),
+    Color::rgb(0xbc, 0xbd, 0x22),
+    Color::rgb(0x17, 0xbe, 0xcf),
+];
+
+/// The color of instance outlines, port labels, and the scale bar.
+const FOREGROUND: Color = Color::rgb(0x20, 0x20, 0x20);
+
+/// The space around the layout, in pixels.
+const MARGIN: f64 = 20.;
+
+/// The height of the area below the layout reserved for the scale bar, in pixels.
+const SCALE_BAR_HEIGHT: f64 = 30.;
+
+/// The spacing of fill patterns, in pixels.
+const PATTERN_SIZE: u32 = 8;
+
+/// The geometry drawn in a rendered layout, in the coordinate system of the top cell.
+#[derive(Default)]
+struct Scene {
+    shapes: IndexMap<LayerId, Vec<Shape>>,
+    texts: Vec<Text>,
+    /// Instance outlines, with the cell name of instances that are not expanded.
+    outlines: Vec<(Rect, Option<ArcStr>)>,
+}
+
+/// A renderer that draws layout cells as SVG images.
+pub struct SvgRenderer<'a> {
+    cell: Arc<RawCell>,
+    layers: &'a LayerContext,
+    styles: &'a LayerStyles,
+    options: &'a SvgOptions,
+    db_units: Decimal,
+}
+
+impl<'a> SvgRenderer<'a> {
+    /// Creates a new SVG renderer.
+    ///
+    /// `db_units` is the size of a layout database unit in meters, and is used
+    /// to label the scale bar.
+    pub fn new(
+        cell: Arc<RawCell>,
+        layers: &'a LayerContext,
+        styles: &'a LayerStyles,
+        options: &'a SvgOptions,
+        db_units: Decimal,
+    ) -> Self {
+        Self {
+            cell,
+            layers,
+            styles,
+            options,
+            db_units,
+        }
+    }
+
+    /// Renders the cell to a string containing an SVG document.
+    pub fn render(self) -> String {
+        let mut scene = Scene::default();
+        self.collect(&self.cell, Transformation::identity(), 0, &mut scene);
+
+        let bounds = self.cell.bbox().unwrap_or_default();
+        let view = View::new(bounds, self.options.width);
+        let height = view.height
+            + if self.options.scale_bar {
+                SCALE_BAR_HEIGHT
+            } else {
+                0.
+            };
+
+        let mut svg = String::new();
+        writeln!(
+            &mut svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+            self.options.width,
+            num(height),
+            self.options.width,
+            num(height)
+        )
+        .unwrap();
+        writeln!(&mut svg, "<title>{}</title>", escape(&self.cell.name)).unwrap();
+
+        let layers = self.layer_styles(&scene);
+
+        writeln!(&mut svg, "<defs>").unwrap();
+        for (i, (_, color, fill)) in layers.iter().enumerate() {
+            write_pattern(&mut svg, i, *color, *fill);
+        }
+        writeln!(&mut svg, "</defs>").unwrap();
+
+        for (i, (layer, color, fill)) in layers.iter().enumerate() {
+            let name = self
+                .layers
+                .get_layer_info(*layer)
+                .map(|info| info.name.clone())
+                .unwrap_or_default();
+            let fill = match fill {
+                FillPattern::Solid => format!("fill=\"{color}\" fill-opacity=\"0.4\""),
+                FillPattern::Hollow => "fill=\"none\"".to_string(),
+                _ => format!("fill=\"url(#fill{i})\""),
+            };
+            writeln!(
+                &mut svg,
+                "<g data-layer=\"{}\" {fill} stroke=\"{color}\" stroke-width=\"1\">",
+                escape(&name)
+            )
+            .unwrap();
+            for shape in scene.shapes[layer].iter() {
+                write_shape(&mut svg, &view, shape);
+            }
+            writeln!(&mut svg, "</g>").unwrap();
+        }
+
+        if !scene.outlines.is_empty() {
+            writeln!(
+                &mut svg,
+                "<g class=\"instances\" fill=\"none\" stroke=\"{FOREGROUND}\" stroke-width=\"1\" stroke-dasharray=\"4 2\">"
+            )
+            .unwrap();
+            for (rect, name) in scene.outlines.iter() {
+                write_shape(&mut svg, &view, &Shape::Rect(*rect));
+                if let Some(name) = name {
+                    let (x, y) = view.map(Point::new(rect.left(), rect.top()));
+                    writeln!(
+                        &mut svg,
+                        "<text x=\"{}\" y=\"{}\" fill=\"{FOREGROUND}\" stroke=\"none\" font-size=\"10\" font-family=\"sans-serif\">{}</text>",
+                        num(x + 2.),
+                        num(y + 12.),
+                        escape(name)
+                    )
+                    .unwrap();
+                }
+            }
+            writeln!(&mut svg, "</g>").unwrap();
+        }
+
+        let mut labels = Vec::new();
+        if self.options.text_labels {
+            for text in scene.texts.iter() {
+                labels.push((text.trans.offset_point(), text.text().to_string()));
+            }
+        }
+        if self.options.port_labels {
+            for (name, port) in self.cell.ports() {
+                if self.is_hidden(port.primary.layer().pin()) {
+                    continue;
+                }
+                if let Some(rect) = port.primary.bbox() {
+                    labels.push((rect.center(), name.to_string()));
+                }
+            }
+        }
+        if !labels.is_empty() {
+            writeln!(
+                &mut svg,
+                "<g class=\"labels\" fill=\"{FOREGROUND}\" font-size=\"12\" font-family=\"sans-serif\" text-anchor=\"middle\" dominant-baseline=\"central\">"
+            )
+            .unwrap();
+            for (at, label) in labels {
+                let (x, y) = view.map(at);
+                writeln!(
+                    &mut svg,
+                    "<text x=\"{}\" y=\"{}\">{}</text>",
+                    num(x),
+                    num(y),
+                    escape(&label)
+                )
+                .unwrap();
+            }
+            writeln!(&mut svg, "</g>").unwrap();
+        }
+
+        if self.options.scale_bar {
+            self.write_scale_bar(&mut svg, &view, bounds);
+        }
+
+        writeln!(&mut svg, "</svg>").unwrap();
+        svg
+    }
+
+    fn is_hidden(&self, layer: LayerId) -> bool {
+        self.options.hidden_layers.contains(&layer)
+    }
+
+    fn collect(&self, cell: &RawCell, trans: Transformation, level: usize, scene: &mut Scene) {
+        // Port shapes are drawn on their pin layers, as in exported GDS files.
+        for (_, port) in cell.ports() {
+            let shapes = std::iter::once(&port.primary)
+                .chain(port.unnamed_shapes.iter())
+                .chain(port.named_shapes.values());
+            for shape in shapes {
+                let layer = shape.layer().pin();
+                if !self.is_hidden(layer) {
+                    scene
+                        .shapes
+                        .entry(layer)
+                        .or_default()
+                        .push(shape.shape().clone().transform(trans));
+                }
+            }
+        }
+
+        for elem in cell.elements() {
+            match elem {
+                Element::Instance(inst) => self.collect_instance(
+                    &inst.cell,
+                    Transformation::cascade(trans, inst.trans),
+                    level,
+                    scene,
+                ),
+                Element::InstanceArray(array) => {
+                    for inst in array.instances() {
+                        self.collect_instance(
+                            &inst.cell,
+                            Transformation::cascade(trans, inst.trans),
+                            level,
+                            scene,
+                        );
+                    }
+                }
+                Element::Shape(shape) => {
+                    if !self.is_hidden(shape.layer()) {
+                        scene
+                            .shapes
+                            .entry(shape.layer())
+                            .or_default()
+                            .push(shape.shape().clone().transform(trans));
+                    }
+                }
+                Element::Text(text) => {
+                    if !self.is_hidden(text.layer()) {
+                        scene.texts.push(text.clone().transform(trans));
+                    }
+                }
+            }
+        }
+    }
+
+    fn collect_instance(
+        &self,
+        cell: &RawCell,
+        trans: Transformation,
+        level: usize,
+        scene: &mut Scene,
+    ) {
+        let expand = self.options.depth.map_or(true, |depth| level < depth);
+        if self.options.instance_outlines || !expand {
+            if let Some(rect) = cell.bbox().and_then(|bbox| bbox.transform(trans).bbox()) {
+                scene
+                    .outlines
+                    .push((rect, (!expand).then(|| cell.name.clone())));
+            }
+        }
+        if expand {
+            self.collect(cell, trans, level + 1, scene);
+        }
+    }
+
+    /// Returns the layers to draw, in order, with their colors and fill patterns.
+    fn layer_styles(&self, scene: &Scene) -> Vec<(LayerId, Color, FillPattern)> {
+        let mut layers = Vec::new();
+        for style in self.styles.layers.iter() {
+            if scene.shapes.contains_key(&style.layer) {
+                layers.push((style.layer, style.color, style.fill));
+            }
+        }
+        let mut palette = PALETTE.iter().cycle();
+        for layer in scene.shapes.keys() {
+            if self.styles.layers.iter().any(|style| style.layer == *layer) {
+                continue;
+            }
+            let family_color = self
+                .layers
+                .layer_family_for_layer_id(*layer)
+                .and_then(|family| {
+                    self.styles
+                        .layers
+                        .iter()
+                        .find(|style| style.layer == family.primary)
+                })
+                .map(|style| style.color);
+            match family_color {
+                Some(color) => layers.push((*layer, color, FillPattern::Hollow)),
+                None => layers.push((*layer, *palette.next().unwrap(), FillPattern::Hatch)),
+            }
+        }
+        layers
+    }
+
+    fn write_scale_bar(&self, svg: &mut String, view: &View, bounds: Rect) {
+        // The longest length of the form 1, 2, or 5 times a power of 10 that
+        // is at most a fifth of the width of the layout.
+        let target = (bounds.width() / 5).max(1);
+        let mut unit = 1;
+        while unit * 10 <= target {
+            unit *= 10;
+        }
+        let length = [5, 2, 1]
+            .into_iter()
+            .map(|m| m * unit)
+            .find(|length| *length <= target)
+            .unwrap();
+
+        let x0 = MARGIN;
+        let x1 = MARGIN + length as f64 * view.scale;
+        let y = view.height + SCALE_BAR_HEIGHT / 2. - 5.;
+        writeln!(
+            svg,
+            "<g class=\"scale-bar\" stroke=\"{FOREGROUND}\" stroke-width=\"2\">"
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>",
+            num(x0),
+            num(y),
+            num(x1),
+            num(y)
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" fill=\"{FOREGROUND}\" stroke=\"none\" font-size=\"12\" font-family=\"sans-serif\" dominant-baseline=\"central\">{}</text>",
+            num(x1 + 6.),
+            num(y),
+            self.physical_length(length)
+        )
+        .unwrap();
+        writeln!(svg, "</g>").unwrap();
+    }
+
+    /// Formats a length in layout database units as a physical length.
+    fn physical_length(&self, length: i64) -> String {
+        let meters = Decimal::from(length) * self.db_units;
+        let microns = meters * dec!(1e6);
+        if microns >= Decimal::ONE {
+            format!("{} µm", microns.normalize())
+        } else {
+            format!("{} nm", (meters * dec!(1e9)).normalize())
+        }
+    }
+}
+
+/// The mapping from layout coordinates to image coordinates.
+struct View {
+    bounds: Rect,
+    /// The size of a layout database unit in pixels.
+    scale: f64,
+    /// The height of the image without the scale bar.
+    height: f64,
+}
+
+impl View {
+    fn new(bounds: Rect, width: u32) -> Self {
+        let scale = (width as f64 - 2. * MARGIN).max(1.) / bounds.width().max(1) as f64;
+        Self {
+            bounds,
+            scale,
+            height: bounds.height() as f64 * scale + 2. * MARGIN,
+        }
+    }
+
+    /// Maps a layout point to image coordinates, flipping the y-axis.
+    fn map(&self, p: Point) -> (f64, f64) {
+        (
+            MARGIN + (p.x - self.bounds.left()) as f64 * self.scale,
+            MARGIN + (self.bounds.top() - p.y) as f64 * self.scale,
+        )
+    }
+}
+
+fn write_shape(svg: &mut String, view: &View, shape: &Shape) {
+    match shape {
+        Shape::Rect(rect) => {
+            let (x, y) = view.map(Point::new(rect.left(), rect.top()));
+            writeln!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+                num(x),
+                num(y),
+                num(rect.width() as f64 * view.scale),
+                num(rect.height() as f64 * view.scale)
+            )
+            .unwrap();
+        }
+        shape => {
+            let points = shape
+                .to_polygon()
+                .points()
+                .iter()
+                .map(|p| {
+                    let (x, y) = view.map(*p);
+                    format!("{},{}", num(x), num(y))
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(svg, "<polygon points=\"{points}\"/>").unwrap();
+        }
+    }
+}
+
+fn write_pattern(svg: &mut String, index: usize, color: Color, fill: FillPattern) {
+    let s = PATTERN_SIZE;
+    let lines = match fill {
+        FillPattern::Solid | FillPattern::Hollow => return,
+        FillPattern::Hatch => vec![(0, s, s, 0)],
+        FillPattern::BackHatch => vec![(0, 0, s, s)],
+        FillPattern::CrossHatch => vec![(0, s, s, 0), (0, 0, s, s)],
+        FillPattern::Dots => Vec::new(),
+    };
+    writeln!(
+        svg,
+        "<pattern id=\"fill{index}\" width=\"{s}\" height=\"{s}\" patternUnits=\"userSpaceOnUse\">"
+    )
+    .unwrap();
+    for (x1, y1, x2, y2) in lines {
+        writeln!(
+            svg,
+            "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"{color}\" stroke-width=\"1\"/>"
+        )
+        .unwrap();
+    }
+    if fill == FillPattern::Dots {
+        writeln!(
+            svg,
+            "<circle cx=\"{}\" cy=\"{}\" r=\"1\" fill=\"{color}\"/>",
+            s / 2,
+            s / 2
+        )
+        .unwrap();
+    }
+    writeln!(svg, "</pattern>").unwrap();
+}
+
+/// Formats a coordinate with at most two decimal places.
+fn num(value: f64) -> String {
+    let s = format!("{value:.2}");
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    match s {
+        "-0" => "0".to_string(),
+        s => s.to_string(),
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -5,17 +5,17 @@ use std::marker::PhantomData;
 use downcast_rs::{impl_downcast, Downcast};
 use geometry::{
     align::AlignRectMut,
-    prelude::{AlignMode, Bbox},
+    prelude::{AlignMode, Bbox, Corner, Point},
     rect::Rect,
     side::Sides,
-    transform::TranslateMut,
+    transform::{Translate, TranslateMut},
 };
 use serde::{Deserialize, Serialize};
 use slotmap::{new_key_type, SlotMap};
 
 use crate::pdk::Pdk;
 
-use super::{Draw, DrawReceiver};
+use super::{Draw, DrawReceiver, Instance, InstanceArray, LayoutImplemented};
 
 /// A tileable layout object.
 pub trait Tileable<PDK: Pdk>: Draw<PDK> + AlignRectMut + Downcast {}
@@ -166,7 +166,7 @@ impl<PDK: Pdk> ArrayTiler<PDK> {
         let mut raw_tile: RawTile<_> = tile.into();
         if let Some(key) = self.array.last() {
             let srect = raw_tile.rect;
-            ArrayTiler::align_with_prev(&mut raw_tile, &self.config, srect, self.tiles[*key].rect);
+            Self::align_with_prev(&mut raw_tile, &self.config, srect, self.tiles[*key].rect);
         }
         let key = self.tiles.insert(raw_tile);
         self.array.push(key);
@@ -195,6 +195,51 @@ impl<PDK: Pdk> ArrayTiler<PDK> {
         tiles.into_iter().map(|tile| self.push(tile))
     }
 
+    /// Pushes `num` repetitions of the given instance tile to the tiler as a single
+    /// [`InstanceArray`], returning a key to the array.
+    ///
+    /// The repetitions are placed exactly as they would be by [`ArrayTiler::push_num`],
+    /// but are drawn as one arrayed element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num` is zero.
+    pub fn push_array<T: LayoutImplemented<PDK>>(
+        &mut self,
+        tile: Tile<Instance<T>>,
+        num: usize,
+    ) -> ArrayTileKey<InstanceArray<T>> {
+        assert!(num > 0, "instance arrays must have at least one instance");
+        let mut first = tile.rect;
+        if let Some(key) = self.array.last() {
+            Self::align_with_prev(&mut first, &self.config, tile.rect, self.tiles[*key].rect);
+        }
+        let mut next = tile.rect;
+        Self::align_with_prev(&mut next, &self.config, tile.rect, tile.rect);
+        let pitch = next.corner(Corner::LowerLeft) - tile.rect.corner(Corner::LowerLeft);
+        let last = (num - 1) as i64;
+
+        let array = InstanceArray::new(
+            tile.inner
+                .translate(first.corner(Corner::LowerLeft) - tile.rect.corner(Corner::LowerLeft)),
+            1,
+            num,
+            Point::zero(),
+            pitch,
+        );
+        // Subsequent tiles are aligned to the last repetition.
+        let rect = first.translate(Point::new(last * pitch.x, last * pitch.y));
+        let key = self.tiles.insert(RawTile {
+            inner: Box::new(array),
+            rect,
+        });
+        self.array.push(key);
+        ArrayTileKey {
+            key,
+            phantom: PhantomData,
+        }
+    }
+
     /// Gets a tiled object using its [`ArrayTileKey`].
     pub fn get<T: Tileable<PDK>>(&self, key: ArrayTileKey<T>) -> Option<&T> {
         self.tiles
@@ -203,7 +248,7 @@ impl<PDK: Pdk> ArrayTiler<PDK> {
     }
 
     fn align_with_prev(
-        tile: &mut RawTile<PDK>,
+        tile: &mut impl AlignRectMut,
         config: &ArrayTilerConfig,
         srect: Rect,
         orect: Rect,
@@ -435,6 +480,36 @@ impl<PDK: Pdk> GridTiler<PDK> {
         tiles.into_iter().map(|tile| self.push(tile))
     }
 
+    /// Pushes a `rows` by `cols` array of abutting repetitions of the given instance tile
+    /// to the tiler as a single [`InstanceArray`], returning a key to the array.
+    ///
+    /// The array occupies a single grid tile, with row 0 at the top and column 0 at the left.
+    /// Row and column spans of the provided [`GridTile`] apply to the array as a whole.
+    pub fn push_array<T: LayoutImplemented<PDK>>(
+        &mut self,
+        tile: impl Into<GridTile<Instance<T>>>,
+        rows: usize,
+        cols: usize,
+    ) -> GridTileKey<InstanceArray<T>> {
+        let tile = tile.into();
+        self.push(GridTile {
+            tile: tile.tile.map(|tile| {
+                let (w, h) = (tile.rect.width(), tile.rect.height());
+                let array =
+                    InstanceArray::new(tile.inner, rows, cols, Point::new(0, -h), Point::new(w, 0));
+                let rect = Rect::from_sides(
+                    tile.rect.left(),
+                    tile.rect.top() - h * rows as i64,
+                    tile.rect.left() + w * cols as i64,
+                    tile.rect.top(),
+                );
+                Tile::new(array, rect)
+            }),
+            colspan: tile.colspan,
+            rowspan: tile.rowspan,
+        })
+    }
+
     fn last_row_mut(&mut self) -> &mut Vec<RawTileKey> {
         self.grid.last_mut().unwrap()
     }
@@ -0,0 +1,241 @@
+//! Via and via array generation.
+
+use geometry::dir::Dir;
+use geometry::rect::Rect;
+use geometry::side::Sides;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::pdk::layers::LayerId;
+use crate::pdk::Pdk;
+
+use super::element::Shape;
+use super::error::LayoutError;
+use super::{Draw, DrawReceiver};
+
+/// The enclosure of a via cut by a conducting layer.
+///
+/// Cuts must be enclosed by at least `min` on all sides, and by at least `end`
+/// on both sides in one direction.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Enclosure {
+    /// The minimum enclosure on all sides.
+    pub min: i64,
+    /// The minimum enclosure on the two ends in one direction.
+    pub end: i64,
+}
+
+impl Enclosure {
+    /// Creates an enclosure that is the same on all sides.
+    pub const fn uniform(min: i64) -> Self {
+        Self { min, end: min }
+    }
+
+    /// Creates an enclosure with a larger enclosure on two opposite sides.
+    pub const fn new(min: i64, end: i64) -> Self {
+        Self { min, end }
+    }
+
+    /// Returns the enclosure on each side if the end enclosure is in direction `dir`.
+    fn sides(&self, dir: Dir) -> Sides<i64> {
+        let end = self.end.max(self.min);
+        match dir {
+            Dir::Horiz => Sides::new(end, self.min, end, self.min),
+            Dir::Vert => Sides::new(self.min, end, self.min, end),
+        }
+    }
+}
+
+/// The design rules for vias between two conducting layers.
+///
+/// Dimensions are in layout database units.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ViaRules {
+    /// The cut layer.
+    pub cut: LayerId,
+    /// The bottom conducting layer.
+    pub bot: LayerId,
+    /// The top conducting layer.
+    pub top: LayerId,
+    /// The side length of a square cut.
+    pub size: i64,
+    /// The minimum spacing between cuts.
+    pub spacing: i64,
+    /// The enclosure of cuts by the bottom layer.
+    pub bot_enclosure: Enclosure,
+    /// The enclosure of cuts by the top layer.
+    pub top_enclosure: Enclosure,
+    /// The manufacturing grid to which cuts are snapped.
+    ///
+    /// The cut pitch is rounded up to a multiple of the grid so that every cut is on-grid.
+    pub grid: i64,
+}
+
+/// A via array connecting two overlapping rectangles on adjacent layers.
+///
+/// Places as many cuts as fit in the overlap of the two rectangles while satisfying
+/// the enclosure rules of both layers, and draws landing pads around the cut array
+/// on both layers. The cut array is centered in the overlap as nearly as the manufacturing
+/// grid allows.
+///
+/// # Examples
+///
+/// ```
+/// # use geometry::rect::Rect;
+/// # use substrate::layout::via::{Enclosure, Via, ViaRules};
+/// # use substrate::pdk::layers::LayerId;
+/// let rules = ViaRules {
+///     cut: LayerId::default(),
+///     bot: LayerId::default(),
+///     top: LayerId::default(),
+///     size: 100,
+///     spacing: 100,
+///     bot_enclosure: Enclosure::uniform(50),
+///     top_enclosure: Enclosure::new(0, 50),
+///     grid: 5,
+/// };
+/// let via = Via::new(
+///     rules,
+///     Rect::from_sides(0, 0, 1000, 200),
+///     Rect::from_sides(0, 0, 600, 200),
+/// );
+/// assert_eq!(via.cuts().unwrap().len(), 3);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Via {
+    rules: ViaRules,
+    bot: Rect,
+    top: Rect,
+}
+
+/// The arrangement of cuts chosen for a [`Via`].
+struct Placement {
+    cuts: Vec<Rect>,
+    bot_dir: Dir,
+    top_dir: Dir,
+}
+
+impl Via {
+    /// Creates a via connecting `bot` on the bottom layer to `top` on the top layer.
+    pub fn new(rules: ViaRules, bot: Rect, top: Rect) -> Self {
+        Self { rules, bot, top }
+    }
+
+    /// Returns the design rules of this via.
+    pub fn rules(&self) -> &ViaRules {
+        &self.rules
+    }
+
+    /// Returns the cuts of this via.
+    ///
+    /// Returns an error if no cut fits within the enclosures of the bottom and top rectangles.
+    pub fn cuts(&self) -> Result<Vec<Rect>> {
+        Ok(self.placement()?.cuts)
+    }
+
+    /// Returns the cuts and landing pads of this via.
+    ///
+    /// Returns an error if no cut fits within the enclosures of the bottom and top rectangles.
+    pub fn shapes(&self) -> Result<Vec<Shape>> {
+        let placement = self.placement()?;
+        let array = placement
+            .cuts
+            .iter()
+            .copied()
+            .reduce(|a, b| a.union(b))
+            .unwrap();
+        let r = &self.rules;
+        let mut shapes = vec![
+            Shape::new(
+                r.bot,
+                array.expand_sides(r.bot_enclosure.sides(placement.bot_dir)),
+            ),
+            Shape::new(
+                r.top,
+                array.expand_sides(r.top_enclosure.sides(placement.top_dir)),
+            ),
+        ];
+        shapes.extend(placement.cuts.into_iter().map(|cut| Shape::new(r.cut, cut)));
+        Ok(shapes)
+    }
+
+    fn placement(&self) -> Result<Placement> {
+        let r = &self.rules;
+        if self.bot.intersection(self.top).is_none() {
+            return Err(LayoutError::Via(arcstr::literal!("via rectangles do not overlap")).into());
+        }
+
+        let mut best: Option<Placement> = None;
+        for bot_dir in [Dir::Horiz, Dir::Vert] {
+            for top_dir in [Dir::Horiz, Dir::Vert] {
+                let region = self
+                    .bot
+                    .shrink_sides(r.bot_enclosure.sides(bot_dir))
+                    .zip(self.top.shrink_sides(r.top_enclosure.sides(top_dir)))
+                    .and_then(|(a, b)| a.intersection(b));
+                let Some(region) = region else {
+                    continue;
+                };
+                let cuts = self.array(region);
+                if cuts.len() > best.as_ref().map_or(0, |p| p.cuts.len()) {
+                    best = Some(Placement {
+                        cuts,
+                        bot_dir,
+                        top_dir,
+                    });
+                }
+            }
+        }
+
+        best.ok_or_else(|| {
+            LayoutError::Via(arcstr::literal!(
+                "no via cut fits within the enclosures of the via rectangles"
+            ))
+            .into()
+        })
+    }
+
+    /// Returns the largest array of on-grid cuts that fits in `region`,
+    /// centered within it as nearly as the grid allows.
+    fn array(&self, region: Rect) -> Vec<Rect> {
+        let r = &self.rules;
+        let grid = r.grid.max(1);
+        let pitch = (r.size + r.spacing + grid - 1).div_euclid(grid) * grid;
+        let fit = |len: i64, lo: i64| {
+            if len < r.size {
+                return Vec::new();
+            }
+            let mut n = (len - r.size) / pitch + 1;
+            while n > 0 {
+                let total = (n - 1) * pitch + r.size;
+                let center = lo + (len - total) / 2;
+                let down = center.div_euclid(grid) * grid;
+                let start = [down, down + grid]
+                    .into_iter()
+                    .find(|&start| start >= lo && start + total <= lo + len);
+                if let Some(start) = start {
+                    return (0..n).map(|i| start + i * pitch).collect::<Vec<_>>();
+                }
+                n -= 1;
+            }
+            Vec::new()
+        };
+        let xs = fit(region.width(), region.left());
+        let ys = fit(region.height(), region.bot());
+        ys.iter()
+            .flat_map(|&y| {
+                xs.iter()
+                    .map(move |&x| Rect::from_sides(x, y, x + r.size, y + r.size))
+            })
+            .collect()
+    }
+}
+
+impl<PDK: Pdk> Draw<PDK> for Via {
+    fn draw(self, recv: &mut DrawReceiver<PDK>) -> Result<()> {
+        for shape in self.shapes()? {
+            recv.draw(shape)?;
+        }
+        Ok(())
+    }
+}
@@ -3,13 +3,105 @@
 use std::collections::HashMap;
 
 use arcstr::ArcStr;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
+use crate::layout::connectivity::{Conductor, Connectivity, DeviceRecipe, Via};
+use crate::layout::drc::RuleDeck;
+use crate::layout::parasitics::Parasitics;
+
 /// Top-level specification of a PDK.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PdkSpec {
     pdk: PdkDef,
     layers: LayerFamilies,
+    #[serde(default)]
+    drc: RuleDeck<ArcStr>,
+    #[serde(default)]
+    vias: IndexMap<ArcStr, ViaDef>,
+    #[serde(default)]
+    devices: Vec<DeviceRecipe<ArcStr>>,
+    #[serde(default)]
+    parasitics: Parasitics<ArcStr>,
+}
+
+impl PdkSpec {
+    /// Returns the design rules declared by the PDK.
+    ///
+    /// Rules refer to layers by name, and can be resolved to layer IDs
+    /// using [`RuleDeck::resolve`].
+    pub fn drc_rules(&self) -> &RuleDeck<ArcStr> {
+        &self.drc
+    }
+
+    /// Returns the parasitic parameters declared by the PDK.
+    ///
+    /// Parameters refer to layers by name, and can be resolved to layer IDs
+    /// using [`Parasitics::resolve`].
+    pub fn parasitics(&self) -> &Parasitics<ArcStr> {
+        &self.parasitics
+    }
+
+    /// Returns the connectivity rules of the PDK.
+    ///
+    /// The drawing layers of routing and base layer families conduct, with their pin
+    /// and label layers attached. Each via connects the drawing layers of its
+    /// top and bottom families through the drawing layer of the cut family of the same name.
+    pub fn connectivity(&self) -> Connectivity<ArcStr> {
+        let element = |family: &LayerFamily, name: &str| {
+            family.elements.get(name).map(|layer| layer.name.clone())
+        };
+        let drawing = |family: &str| {
+            self.layers
+                .inner
+                .get(family)
+                .and_then(|family| element(family, "drawing"))
+        };
+
+        let mut families = self.layers.inner.iter().collect::<Vec<_>>();
+        families.sort_by_key(|(name, _)| *name);
+        let conductors = families
+            .into_iter()
+            .filter(|(_, family)| {
+                matches!(
+                    family.routing_kind,
+                    RoutingKind::Routing | RoutingKind::Base
+                )
+            })
+            .filter_map(|(_, family)| {
+                Some(Conductor {
+                    layer: element(family, "drawing")?,
+                    pins: element(family, "pin").into_iter().collect(),
+                    labels: element(family, "label").into_iter().collect(),
+                })
+            })
+            .collect();
+
+        let vias = self
+            .vias
+            .iter()
+            .filter(|(name, _)| {
+                self.layers
+                    .inner
+                    .get(*name)
+                    .map(|family| family.routing_kind == RoutingKind::Cut)
+                    .unwrap_or(false)
+            })
+            .filter_map(|(name, via)| {
+                Some(Via {
+                    cut: drawing(name)?,
+                    bot: drawing(&via.bot)?,
+                    top: drawing(&via.top)?,
+                })
+            })
+            .collect();
+
+        Connectivity {
+            conductors,
+            vias,
+            devices: self.devices.clone(),
+        }
+    }
 }
 
 /// PDK declaration.
@@ -18,6 +110,13 @@ pub struct PdkDef {
     name: ArcStr,
 }
 
+/// A via between two layer families.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViaDef {
+    top: ArcStr,
+    bot: ArcStr,
+}
+
 /// A collection of layer families.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -60,6 +159,7 @@ pub enum RoutingKind {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::layout::drc::Rule;
 
     #[test]
     fn deserialize_example_pdk_toml() {
@@ -81,5 +181,44 @@ mod tests {
             "met1_drawing",
         );
         assert_eq!(spec.layers.inner["via1"].routing_kind, RoutingKind::Cut,);
+
+        let connectivity = spec.connectivity();
+        assert_eq!(connectivity.conductors.len(), 2);
+        assert_eq!(connectivity.conductors[0].layer, "met1_drawing");
+        assert_eq!(connectivity.conductors[0].pins, vec!["met1_pin"]);
+        assert_eq!(
+            connectivity.vias,
+            vec![Via {
+                cut: "via1_drawing".into(),
+                bot: "met1_drawing".into(),
+                top: "met2_drawing".into(),
+            }]
+        );
+
+        let parasitics = spec.parasitics();
+        assert_eq!(parasitics.layers.len(), 2);
+        assert_eq!(parasitics.layers[1].layer, "met2_drawing");
+        assert_eq!(parasitics.layers[1].sheet_resistance, 0.07);
+        assert_eq!(parasitics.vias[0].resistance, 4.5);
+
+        let rules = spec.drc_rules().rules();
+        assert_eq!(rules.len(), 4);
+        assert_eq!(rules[0].name, "met1.w.1");
+        assert_eq!(
+            rules[0].rule,
+            Rule::Width {
+                layer: "met1_drawing".into(),
+                min: 140
+            }
+        );
+        assert_eq!(
+            rules[3].rule,
+            Rule::Density {
+                layer: "met1_drawing".into(),
+                window: 10_000,
+                min: 0.2,
+                max: 1.,
+            }
+        );
     }
 }
@@ -0,0 +1,308 @@
+//! GDS layer maps.
+//!
+//! A [`LayerMap`] assigns names to GDS layer numbers. Layer maps can be read from
+//! Cadence-style layer map files and KLayout layer properties (`.lyp`) files, and are
+//! used to define layers at runtime, to name layers of imported GDS files, and to
+//! translate between the GDS numberings of different PDK variants.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arcstr::ArcStr;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::layout::error::LayoutError;
+use crate::layout::svg::{Color, FillPattern, LayerStyle, LayerStyles};
+
+use super::layers::GdsLayerSpec;
+
+/// The display properties of a layer read from a layer properties file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayerDisplay {
+    /// The color of the layer.
+    pub color: Color,
+    /// The fill pattern of the layer.
+    pub fill: FillPattern,
+    /// Whether the layer is visible.
+    pub visible: bool,
+}
+
+/// An entry of a [`LayerMap`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayerMapEntry {
+    /// The name of the layer.
+    pub name: ArcStr,
+    /// The purpose of the layer, such as `drawing` or `pin`.
+    #[serde(default)]
+    pub purpose: Option<ArcStr>,
+    /// The GDS layer and datatype.
+    pub gds: GdsLayerSpec,
+    /// Display properties, if the entry was read from a layer properties file.
+    #[serde(default)]
+    pub display: Option<LayerDisplay>,
+}
+
+impl LayerMapEntry {
+    /// Creates a new layer map entry.
+    pub fn new(name: impl Into<ArcStr>, purpose: Option<ArcStr>, gds: GdsLayerSpec) -> Self {
+        Self {
+            name: name.into(),
+            purpose,
+            gds,
+            display: None,
+        }
+    }
+
+    /// The full name of the layer.
+    ///
+    /// Layers with a purpose are named `<name>.<purpose>`.
+    pub fn full_name(&self) -> ArcStr {
+        match self.purpose {
+            Some(ref purpose) => arcstr::format!("{}.{}", self.name, purpose),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// A mapping between layer names and GDS layer numbers.
+///
+/// Layers are identified by their [full names](LayerMapEntry::full_name), which are
+/// matched against the names of installed layers and against names added by
+/// [`Context::install_layer_map`](crate::context::Context::install_layer_map).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayerMap {
+    /// The entries of the layer map.
+    pub entries: Vec<LayerMapEntry>,
+}
+
+impl LayerMap {
+    /// Creates an empty layer map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an entry to the layer map.
+    pub fn with_entry(mut self, entry: LayerMapEntry) -> Self {
+        self.entries.push(entry);
+        self
+    }
+
+    /// Loads a layer map file or a KLayout layer properties file.
+    ///
+    /// Files with a `.lyp` extension are parsed with [`LayerMap::parse_lyp`]; all other
+    /// files are parsed with [`LayerMap::parse`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let src = std::fs::read_to_string(path).map_err(Arc::new)?;
+        if path.extension().is_some_and(|ext| ext == "lyp") {
+            Self::parse_lyp(&src)
+        } else {
+            Self::parse(&src)
+        }
+    }
+
+    /// Parses a Cadence-style layer map.
+    ///
+    /// Each line contains a layer name, a purpose, a GDS layer number, and a GDS
+    /// datatype, separated by whitespace. Text following a `#` is ignored.
+    pub fn parse(src: &str) -> Result<Self> {
+        let mut map = Self::new();
+        for (i, line) in src.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |msg: &str| LayoutError::LayerMap(arcstr::format!("line {}: {msg}", i + 1));
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let [name, purpose, layer, datatype] = fields.as_slice() else {
+                return Err(
+                    error("expected a layer name, purpose, GDS layer, and GDS datatype").into(),
+                );
+            };
+            let number = |s: &str| {
+                s.parse::<u8>()
+                    .map_err(|_| error(&format!("invalid GDS number `{s}`")))
+            };
+            map.entries.push(LayerMapEntry::new(
+                *name,
+                Some((*purpose).into()),
+                GdsLayerSpec(number(layer)?, number(datatype)?),
+            ));
+        }
+        Ok(map)
+    }
+
+    /// Parses a KLayout layer properties (`.lyp`) file.
+    ///
+    /// Entries are created for layers whose source specifies a single GDS layer
+    /// and datatype. A layer is named after its `name` property, up to the first
+    /// space, or `gds_<layer>_<datatype>` if it has no name. Names of the form
+    /// `<name>.<purpose>` are split into a name and a purpose.
+    pub fn parse_lyp(src: &str) -> Result<Self> {
+        let mut map = Self::new();
+        // Properties may be nested within the `group-members` of other properties.
+        let mut stack: Vec<LypProperties> = Vec::new();
+        let mut rest = src;
+        while let Some(start) = rest.find('<') {
+            let text = &rest[..start];
+            let end = rest[start..]
+                .find('>')
+                .ok_or_else(|| LayoutError::LayerMap(arcstr::literal!("unterminated XML tag")))?;
+            let tag = &rest[start + 1..start + end];
+            rest = &rest[start + end + 1..];
+
+            if tag.starts_with('?') || tag.starts_with('!') || tag.ends_with('/') {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                match name.trim() {
+                    "properties" | "group-members" => {
+                        if let Some(properties) = stack.pop() {
+                            map.entries.extend(properties.entry());
+                        }
+                    }
+                    name => {
+                        if let Some(properties) = stack.last_mut() {
+                            properties.set(name, &unescape(text.trim()));
+                        }
+                    }
+                }
+            } else if matches!(
+                tag.split_whitespace().next(),
+                Some("properties" | "group-members")
+            ) {
+                stack.push(LypProperties::default());
+            }
+        }
+        Ok(map)
+    }
+
+    /// Returns the entry with the given full name.
+    pub fn entry_named(&self, name: &str) -> Option<&LayerMapEntry> {
+        self.entries.iter().find(|entry| entry.full_name() == name)
+    }
+
+    /// Returns the first entry with the given GDS layer and datatype.
+    pub fn entry_for_gds(&self, gds: GdsLayerSpec) -> Option<&LayerMapEntry> {
+        self.entries.iter().find(|entry| entry.gds == gds)
+    }
+
+    /// Returns the styles of entries with display properties, identified by their full names.
+    ///
+    /// The styles can be resolved to installed layers using [`LayerStyles::resolve`].
+    pub fn styles(&self) -> LayerStyles<ArcStr> {
+        LayerStyles {
+            layers: self
+                .entries
+                .iter()
+                .filter_map(|entry| {
+                    let display = entry.display?;
+                    Some(LayerStyle::new(
+                        entry.full_name(),
+                        display.color,
+                        display.fill,
+                    ))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The properties of a layer in a `.lyp` file.
+#[derive(Default)]
+struct LypProperties {
+    name: Option<String>,
+    source: Option<String>,
+    fill_color: Option<Color>,
+    frame_color: Option<Color>,
+    dither_pattern: Option<String>,
+    visible: Option<bool>,
+}
+
+impl LypProperties {
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "name" => self.name = Some(value.to_string()),
+            "source" => self.source = Some(value.to_string()),
+            "fill-color" => self.fill_color = value.parse().ok(),
+            "frame-color" => self.frame_color = value.parse().ok(),
+            "dither-pattern" => self.dither_pattern = Some(value.to_string()),
+            "visible" => self.visible = Some(value == "true"),
+            _ => {}
+        }
+    }
+
+    /// Converts the properties to a layer map entry.
+    ///
+    /// Returns `None` if the source is not a single GDS layer, as for groups of layers.
+    fn entry(self) -> Option<LayerMapEntry> {
+        let gds = self.source.as_deref().and_then(lyp_source)?;
+        let name = self
+            .name
+            .as_deref()
+            .and_then(|name| name.split_whitespace().next())
+            .map(ArcStr::from)
+            .unwrap_or_else(|| arcstr::format!("gds_{}_{}", gds.0, gds.1));
+        let (name, purpose) = match name.split_once('.') {
+            Some((name, purpose)) => (ArcStr::from(name), Some(ArcStr::from(purpose))),
+            None => (name, None),
+        };
+        let display = self
+            .fill_color
+            .or(self.frame_color)
+            .map(|color| LayerDisplay {
+                color,
+                fill: self
+                    .dither_pattern
+                    .as_deref()
+                    .map(dither_pattern)
+                    .unwrap_or_default(),
+                visible: self.visible.unwrap_or(true),
+            });
+        Some(LayerMapEntry {
+            name,
+            purpose,
+            gds,
+            display,
+        })
+    }
+}
+
+/// Parses a KLayout layer source of the form `<layer>/<datatype>@<layout>`.
+///
+/// Returns `None` if the source contains wildcards or ranges.
+fn lyp_source(source: &str) -> Option<GdsLayerSpec> {
+    let spec = source
+        .split_whitespace()
+        .find(|token| token.contains('/'))?
+        .split('@')
+        .next()?;
+    let (layer, datatype) = spec.split_once('/')?;
+    Some(GdsLayerSpec(layer.parse().ok()?, datatype.parse().ok()?))
+}
+
+/// Converts a KLayout dither pattern to the closest [`FillPattern`].
+fn dither_pattern(pattern: &str) -> FillPattern {
+    match pattern
+        .strip_prefix('I')
+        .and_then(|i| i.parse::<u32>().ok())
+    {
+        Some(0) => FillPattern::Solid,
+        Some(1) => FillPattern::Hollow,
+        Some(2 | 3) => FillPattern::Dots,
+        Some(4..=7) => FillPattern::Hatch,
+        Some(8..=11) => FillPattern::BackHatch,
+        Some(12 | 13) => FillPattern::CrossHatch,
+        Some(_) => FillPattern::Dots,
+        None => FillPattern::Solid,
+    }
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -12,6 +12,9 @@ use serde::{Deserialize, Serialize};
 use slotmap::{new_key_type, SlotMap};
 use tracing::Level;
 
+use super::layer_map::LayerMap;
+use crate::layout::error::LayoutError;
+
 /// A context-wide unique identifier for a layer.
 #[derive(
     Default, Debug, Copy, Clone, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord,
@@ -24,6 +27,16 @@ impl LayerId {
     }
 }
 
+/// Returns a function that resolves layer names to [`LayerId`]s using `lookup`.
+///
+/// The returned function fails with [`LayoutError::UnknownLayer`] for names that `lookup`
+/// cannot resolve.
+pub(crate) fn layer_resolver<L: AsRef<str>>(
+    mut lookup: impl FnMut(&str) -> Option<LayerId>,
+) -> impl FnMut(&L) -> Result<LayerId, LayoutError> {
+    move |name| lookup(name.as_ref()).ok_or_else(|| LayoutError::UnknownLayer(name.as_ref().into()))
+}
+
 impl AsRef<LayerId> for LayerId {
     #[inline]
     fn as_ref(&self) -> &LayerId {
@@ -45,6 +58,7 @@ pub struct LayerContext {
     layers_id_to_info: HashMap<LayerId, LayerInfo>,
     layer_id_to_family_key: HashMap<LayerId, LayerFamilyKey>,
     layer_families: SlotMap<LayerFamilyKey, LayerFamilyInfo>,
+    layer_aliases: HashMap<ArcStr, LayerId>,
 }
 
 impl LayerContext {
@@ -105,6 +119,83 @@ impl LayerContext {
         self.layers_gds_to_info.get(&spec).map(|info| info.id)
     }
 
+    pub(crate) fn get_layer_by_name(&self, name: &str) -> Option<LayerId> {
+        if let Some(id) = self.layer_aliases.get(name) {
+            return Some(*id);
+        }
+        self.layers_id_to_info
+            .values()
+            .find(|info| info.name == name)
+            .map(|info| info.id)
+    }
+
+    /// Installs the layers of a layer map, returning the layer ID of each entry.
+    ///
+    /// Entries named after an installed layer refer to that layer. Otherwise, an entry
+    /// whose GDS layer is installed becomes an alias of that layer, and other entries
+    /// create new layers.
+    pub(crate) fn install_layer_map(&mut self, map: &LayerMap) -> Vec<LayerId> {
+        map.entries
+            .iter()
+            .map(|entry| {
+                let name = entry.full_name();
+                if let Some(id) = self.get_layer_by_name(&name) {
+                    id
+                } else if let Some(id) = self.get_gds_layer(entry.gds) {
+                    self.layer_aliases.insert(name, id);
+                    id
+                } else {
+                    self.new_layer_with_id(|id| LayerInfo {
+                        id,
+                        name,
+                        gds: Some(entry.gds),
+                    })
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the layers that GDS layers are imported to when using the given layer map.
+    ///
+    /// Entries are matched to installed layers by name. Entries without a matching layer
+    /// create new layers, which are assigned the GDS layer of the entry if it is not
+    /// already in use.
+    pub(crate) fn layer_map_import(&mut self, map: &LayerMap) -> HashMap<GdsLayerSpec, LayerId> {
+        let mut layers = HashMap::new();
+        for entry in map.entries.iter() {
+            let name = entry.full_name();
+            let id = match self.get_layer_by_name(&name) {
+                Some(id) => id,
+                None => self.new_anonymous_layer(name, entry.gds),
+            };
+            layers.entry(entry.gds).or_insert(id);
+        }
+        layers
+    }
+
+    /// Returns the GDS layers that layers are exported to when using the given layer map.
+    ///
+    /// Entries whose names do not match an installed layer are ignored.
+    pub(crate) fn layer_map_export(&self, map: &LayerMap) -> HashMap<LayerId, GdsLayerSpec> {
+        let mut layers = HashMap::new();
+        for entry in map.entries.iter() {
+            if let Some(id) = self.get_layer_by_name(&entry.full_name()) {
+                layers.entry(id).or_insert(entry.gds);
+            }
+        }
+        layers
+    }
+
+    /// Creates a new layer, assigning it the given GDS layer if that layer is not in use.
+    pub(crate) fn new_anonymous_layer(&mut self, name: ArcStr, gds: GdsLayerSpec) -> LayerId {
+        let gds = (!self.layers_gds_to_info.contains_key(&gds)).then_some(gds);
+        self.new_layer_with_id(|id| LayerInfo { id, name, gds })
+    }
+
+    pub(crate) fn get_layer_info(&self, id: LayerId) -> Option<&LayerInfo> {
+        self.layers_id_to_info.get(&id)
+    }
+
     pub(crate) fn get_gds_layer_from_id(&self, id: LayerId) -> Option<GdsLayerSpec> {
         self.layers_id_to_info.get(&id).unwrap().gds
     }
@@ -2,6 +2,7 @@
 
 pub mod corner;
 pub mod data;
+pub mod layer_map;
 pub mod layers;
 
 use std::any::Any;
@@ -12,6 +13,12 @@ use rust_decimal::Decimal;
 use crate::block::Block;
 use crate::error::Result;
 use crate::io::{LayoutType, SchematicType};
+use crate::layout::connectivity::Connectivity;
+use crate::layout::drc::RuleDeck;
+use crate::layout::lef::LefRules;
+use crate::layout::parasitics::Parasitics;
+use crate::layout::svg::LayerStyles;
+use crate::layout::via::ViaRules;
 use crate::layout::{CellBuilder as LayoutCellBuilder, ExportsLayoutData, Layout};
 use crate::schematic::{CellBuilder as SchematicCellBuilder, ExportsSchematicData, Schematic};
 use crate::sealed;
@@ -37,6 +44,50 @@ pub trait Pdk: Send + Sync + Any {
     fn schematic_primitives(&self) -> Vec<ArcStr> {
         Vec::new()
     }
+
+    /// The design rules of the PDK.
+    ///
+    /// The default implementation returns an empty rule deck.
+    fn drc_rules(&self, _layers: &Self::Layers) -> RuleDeck {
+        RuleDeck::new()
+    }
+
+    /// The connectivity rules of the PDK, used for extracting netlists from layouts.
+    ///
+    /// The default implementation returns an empty set of rules.
+    fn connectivity(&self, _layers: &Self::Layers) -> Connectivity {
+        Connectivity::new()
+    }
+
+    /// The parasitic parameters of the PDK, used for estimating RC networks of extracted nets.
+    ///
+    /// The default implementation returns an empty set of parameters,
+    /// treating all layers as ideal conductors.
+    fn parasitics(&self, _layers: &Self::Layers) -> Parasitics {
+        Parasitics::new()
+    }
+
+    /// The via rules of the PDK, used for generating [`Via`](crate::layout::via::Via)s.
+    ///
+    /// The default implementation returns no rules.
+    fn via_rules(&self, _layers: &Self::Layers) -> Vec<ViaRules> {
+        Vec::new()
+    }
+
+    /// The rules for exporting layouts to LEF.
+    ///
+    /// The default implementation exports all layers under their PDK names.
+    fn lef_rules(&self, _layers: &Self::Layers) -> LefRules {
+        LefRules::new()
+    }
+
+    /// The styles of layers in layouts rendered to SVG.
+    ///
+    /// The default implementation returns no styles, so that layers are drawn
+    /// in colors from a built-in palette.
+    fn layer_styles(&self, _layers: &Self::Layers) -> LayerStyles {
+        LayerStyles::new()
+    }
 }
 
 /// A PDK that has a schematic for block `B`.
@@ -3,6 +3,8 @@
 use std::collections::{HashMap, HashSet};
 
 use arcstr::ArcStr;
+use regex::Regex;
+use scir::netlist::NetlistLibConversion;
 use scir::{
     Cell, CellId as ScirCellId, CellInner, IndexOwned, Instance, InstancePathTail, LibraryBuilder,
     SignalPathTail, TopKind,
@@ -11,6 +13,7 @@ use uniquify::Names;
 
 use crate::io::{Node, NodePath, TerminalPath};
 use crate::schematic::{InstancePath, PrimitiveNode};
+use crate::simulation::data::SaveSpec;
 
 use super::{BlackboxElement, CellId, InstanceId, RawCell, RawCellContent};
 
@@ -70,6 +73,24 @@ impl ScirLibConversionBuilder {
 }
 
 impl RawLib {
+    /// Returns the ID of the SCIR cell exported for `cell`.
+    ///
+    /// Returns [`None`] if `cell` is not part of this library, or if it was flattened.
+    pub(crate) fn scir_cell_id(&self, cell: &RawCell) -> Option<ScirCellId> {
+        self.conv.id_mapping.get(&cell.id).copied()
+    }
+
+    /// Replaces the SCIR cell with the given ID, revalidating the library.
+    ///
+    /// Conversion metadata is not updated, so paths into the replaced cell
+    /// can no longer be converted.
+    pub(crate) fn replace_cell(&mut self, id: ScirCellId, cell: Cell) -> Result<(), scir::Issues> {
+        let mut lib = (*self.scir).clone();
+        lib.overwrite_cell_with_id(id, cell);
+        self.scir = lib.build()?;
+        Ok(())
+    }
+
     fn convert_instance_path_inner<'a>(
         &self,
         top: CellId,
@@ -169,6 +190,208 @@ impl RawLib {
         })
     }
 
+    /// Resolves a [`SaveSpec`] to the list of Substrate [`NodePath`]s it refers to.
+    ///
+    /// Nodes that are connected through instance ports are only listed once, using
+    /// the highest-level path to the node. Paths are sorted by their SCIR names.
+    ///
+    /// Returns [`None`] if the spec refers to an invalid path.
+    pub fn node_paths(&self, spec: &SaveSpec) -> Option<Vec<NodePath>> {
+        let (paths, pattern) = match spec {
+            SaveSpec::Nested(path) => (self.nested_node_paths(path.top, &path.path)?, None),
+            SaveSpec::Ports(path) => (
+                self.port_paths(path.top, &path.path)?
+                    .into_iter()
+                    .map(|path| path.0)
+                    .collect(),
+                None,
+            ),
+            SaveSpec::Pattern(re) => (
+                self.nested_node_paths(self.top_cell_id(), std::iter::empty())?,
+                Some(re),
+            ),
+            SaveSpec::Paths(paths) => (paths.clone(), None),
+        };
+        self.sort_by_name(paths, pattern, |path| path)
+    }
+
+    /// Resolves a [`SaveSpec`] to the list of Substrate [`TerminalPath`]s it refers to.
+    ///
+    /// [`SaveSpec::Nested`] refers to all terminals of all instances nested within
+    /// the given instance, while [`SaveSpec::Pattern`] is matched against the names of
+    /// every instance terminal in the library. Paths are sorted by their SCIR names.
+    ///
+    /// Returns [`None`] if the spec refers to an invalid path.
+    pub fn terminal_paths(&self, spec: &SaveSpec) -> Option<Vec<TerminalPath>> {
+        let (paths, pattern) = match spec {
+            SaveSpec::Nested(path) => (self.nested_terminal_paths(path.top, &path.path)?, None),
+            SaveSpec::Ports(path) => (self.port_paths(path.top, &path.path)?, None),
+            SaveSpec::Pattern(re) => (
+                self.nested_terminal_paths(self.top_cell_id(), std::iter::empty())?,
+                Some(re),
+            ),
+            SaveSpec::Paths(paths) => (paths.iter().cloned().map(TerminalPath).collect(), None),
+        };
+        self.sort_by_name(paths, pattern, |path| path.as_ref())
+    }
+
+    /// Returns the SCIR name of the signal at the given [`NodePath`].
+    ///
+    /// The name consists of the SCIR names of all instances in the path, followed by the
+    /// signal name, separated by periods. Bus indices are appended in square brackets.
+    pub fn node_path_name(&self, path: &NodePath) -> Option<String> {
+        let path = self.convert_node_path(path)?;
+        let scir::NamedSignalPath {
+            mut instances,
+            signal,
+            index,
+        } = self
+            .scir
+            .convert_signal_path(&NetlistLibConversion::new(), &path);
+        instances.push(signal);
+        let mut name = instances.join(".");
+        if let Some(index) = index {
+            name.push_str(&format!("[{}]", index));
+        }
+        Some(name)
+    }
+
+    fn top_cell_id(&self) -> CellId {
+        self.conv
+            .cells
+            .values()
+            .find(|cell| cell.top)
+            .expect("library has no top cell")
+            .id
+    }
+
+    /// Sorts `paths` by their SCIR names, keeping only those matching `pattern` if given.
+    fn sort_by_name<P>(
+        &self,
+        paths: Vec<P>,
+        pattern: Option<&Regex>,
+        node: impl Fn(&P) -> &NodePath,
+    ) -> Option<Vec<P>> {
+        let mut named = paths
+            .into_iter()
+            .map(|path| Some((self.node_path_name(node(&path))?, path)))
+            .collect::<Option<Vec<_>>>()?;
+        if let Some(re) = pattern {
+            named.retain(|(name, _)| re.is_match(name));
+        }
+        named.sort_by(|a, b| a.0.cmp(&b.0));
+        Some(named.into_iter().map(|(_, path)| path).collect())
+    }
+
+    fn nested_node_paths<'a>(
+        &self,
+        top: CellId,
+        instances: impl IntoIterator<Item = &'a InstanceId>,
+    ) -> Option<Vec<NodePath>> {
+        let mut instances = instances.into_iter().copied().collect::<Vec<_>>();
+        let (_, cell, _) = self.convert_instance_path_inner(top, &instances)?;
+        let mut seen = HashSet::new();
+        let mut paths = Vec::new();
+        self.collect_node_paths(top, cell, &mut instances, &mut seen, &mut paths);
+        Some(paths)
+    }
+
+    /// Must ensure that `instances` is returned to its original value by the end of the
+    /// function call.
+    fn collect_node_paths(
+        &self,
+        top: CellId,
+        conv: &ScirCellConversion,
+        instances: &mut Vec<InstanceId>,
+        seen: &mut HashSet<(Vec<scir::InstanceId>, scir::SliceOne)>,
+        paths: &mut Vec<NodePath>,
+    ) {
+        for &node in conv.signals.keys() {
+            let path = NodePath {
+                top,
+                instances: instances.clone(),
+                node,
+            };
+            let signal = self
+                .scir
+                .simplify_path(self.convert_node_path(&path).unwrap());
+            if let SignalPathTail::Scir { slice, .. } = signal.tail {
+                if seen.insert((signal.instances, slice)) {
+                    paths.push(path);
+                }
+            }
+        }
+        for (id, inst) in conv.instances.iter() {
+            instances.push(*id);
+            self.collect_node_paths(top, self.child_conv(inst), instances, seen, paths);
+            instances.pop().unwrap();
+        }
+    }
+
+    fn port_paths<'a>(
+        &self,
+        top: CellId,
+        instances: impl IntoIterator<Item = &'a InstanceId>,
+    ) -> Option<Vec<TerminalPath>> {
+        let instances = instances.into_iter().copied().collect::<Vec<_>>();
+        let (_, cell, _) = self.convert_instance_path_inner(top, &instances)?;
+        Some(
+            cell.ports
+                .iter()
+                .map(|&node| {
+                    TerminalPath(NodePath {
+                        top,
+                        instances: instances.clone(),
+                        node,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    fn nested_terminal_paths<'a>(
+        &self,
+        top: CellId,
+        instances: impl IntoIterator<Item = &'a InstanceId>,
+    ) -> Option<Vec<TerminalPath>> {
+        let mut instances = instances.into_iter().copied().collect::<Vec<_>>();
+        let (_, cell, _) = self.convert_instance_path_inner(top, &instances)?;
+        let mut paths = Vec::new();
+        self.collect_terminal_paths(top, cell, &mut instances, &mut paths);
+        Some(paths)
+    }
+
+    /// Must ensure that `instances` is returned to its original value by the end of the
+    /// function call.
+    fn collect_terminal_paths(
+        &self,
+        top: CellId,
+        conv: &ScirCellConversion,
+        instances: &mut Vec<InstanceId>,
+        paths: &mut Vec<TerminalPath>,
+    ) {
+        for (id, inst) in conv.instances.iter() {
+            instances.push(*id);
+            let child = self.child_conv(inst);
+            paths.extend(child.ports.iter().map(|&node| {
+                TerminalPath(NodePath {
+                    top,
+                    instances: instances.clone(),
+                    node,
+                })
+            }));
+            self.collect_terminal_paths(top, child, instances, paths);
+            instances.pop().unwrap();
+        }
+    }
+
+    fn child_conv<'a>(&'a self, inst: &'a ScirInstanceConversion) -> &'a ScirCellConversion {
+        match inst.instance.as_ref() {
+            RawCellContent::Opaque(_) => &self.conv.cells[&inst.child],
+            RawCellContent::Clear(conv) => conv,
+        }
+    }
+
     /// Must ensure that `instances` is returned to its original value by the end of the
     /// function call.
     fn find_connected_terminals_in_scir_instance(
@@ -291,6 +514,8 @@ pub(crate) struct ScirCellConversion {
     pub(crate) top: bool,
     /// Map Substrate nodes to SCIR signal IDs and indices.
     pub(crate) signals: HashMap<Node, scir::SliceOne>,
+    /// The Substrate nodes corresponding to the ports of this cell, in order.
+    pub(crate) ports: Vec<Node>,
     /// Map Substrate instance IDs to SCIR instances and their underlying Substrate cell.
     pub(crate) instances: HashMap<InstanceId, ScirInstanceConversion>,
     pub(crate) primitives: Vec<ScirPrimitiveDeviceConversion>,
@@ -303,6 +528,7 @@ impl ScirCellConversion {
             id,
             top: false,
             signals: HashMap::new(),
+            ports: Vec::new(),
             instances: HashMap::new(),
             primitives: Vec::new(),
         }
@@ -442,6 +668,7 @@ impl RawCell {
         }
 
         let mut conv = ScirCellConversion::new(ctx.id);
+        conv.ports = self.ports.iter().map(|port| port.node()).collect();
         let mut nodes = HashMap::new();
         let mut roots_added = HashSet::new();
 
@@ -679,6 +679,13 @@ impl<T: ExportsSchematicData> Cell<T> {
         self.io.nested_view(&self.path)
     }
 
+    /// Returns the path to this cell.
+    ///
+    /// The path is relative to this cell and thus contains no instances.
+    pub fn path(&self) -> &InstancePath {
+        &self.path
+    }
+
     fn nested_view(&self, parent: &InstancePath) -> NestedCellView<'_, T> {
         NestedCellView {
             block: &self.block,
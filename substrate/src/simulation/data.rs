@@ -1,9 +1,16 @@
 //! Interfaces for interacting with simulation data.
 
+use std::ops::Deref;
+
 pub use codegen::FromSaved;
+use indexmap::IndexMap;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use type_dispatch::impl_dispatch;
 
+use crate::error::{Error, Result};
 use crate::io::{NestedNode, NodePath, Terminal, TerminalPath};
+use crate::schematic::InstancePath;
 use crate::simulation::{Analysis, SimulationContext, Simulator, SupportedBy};
 
 /// A simulation artifact with node data `V` that can be indexed by key `K`.
@@ -36,6 +43,14 @@ pub trait FromSaved<S: Simulator, A: Analysis> {
     fn from_saved(output: &<A as Analysis>::Output, key: Self::Key) -> Self;
 }
 
+impl<S: Simulator, A: Analysis, T: FromSaved<S, A>> FromSaved<S, A> for Result<T> {
+    type Key = Result<T::Key>;
+
+    fn from_saved(output: &<A as Analysis>::Output, key: Self::Key) -> Self {
+        key.map(|key| T::from_saved(output, key))
+    }
+}
+
 /// A simulation output that can be saved in an analysis within a given simulator.
 ///
 /// `T` is any type that can be used as arguments for deciding what should be saved in
@@ -83,3 +98,163 @@ impl<N1, N2, S: Simulator, A: Analysis + SupportedBy<S>, T: for<'a> Save<S, A, N
         T::save(ctx, &to_save.path(), opts)
     }
 }
+
+/// A request to save a collection of signals.
+///
+/// Save specifications are resolved against the SCIR library being simulated
+/// using [`RawLib::node_paths`](crate::schematic::conv::RawLib::node_paths) for voltages
+/// and [`RawLib::terminal_paths`](crate::schematic::conv::RawLib::terminal_paths) for currents.
+#[derive(Debug, Clone)]
+pub enum SaveSpec {
+    /// All nodes within the cell at the given instance path, including nodes of
+    /// nested instances.
+    ///
+    /// When saving currents, refers to all terminals of all nested instances.
+    Nested(InstancePath),
+    /// All ports of the instance at the given path.
+    Ports(InstancePath),
+    /// All nodes whose SCIR names match the given regular expression.
+    ///
+    /// Names are formed by joining SCIR instance names and the signal name with periods
+    /// (e.g. `inst0.inst1.out[3]`).
+    ///
+    /// When saving currents, the expression is matched against the names of instance terminals.
+    Pattern(Regex),
+    /// An explicit list of node paths.
+    ///
+    /// When saving currents, each path must refer to an instance terminal.
+    Paths(Vec<NodePath>),
+}
+
+impl SaveSpec {
+    /// Creates a [`SaveSpec::Nested`] for the given instance path.
+    pub fn nested(path: &InstancePath) -> Self {
+        Self::Nested(path.clone())
+    }
+
+    /// Creates a [`SaveSpec::Ports`] for the given instance path.
+    pub fn ports(path: &InstancePath) -> Self {
+        Self::Ports(path.clone())
+    }
+
+    /// Creates a [`SaveSpec::Pattern`] from the given regular expression.
+    pub fn pattern(re: &str) -> Result<Self, regex::Error> {
+        Ok(Self::Pattern(Regex::new(re)?))
+    }
+}
+
+impl FromIterator<NodePath> for SaveSpec {
+    fn from_iter<T: IntoIterator<Item = NodePath>>(iter: T) -> Self {
+        Self::Paths(iter.into_iter().collect())
+    }
+}
+
+impl FromIterator<TerminalPath> for SaveSpec {
+    fn from_iter<T: IntoIterator<Item = TerminalPath>>(iter: T) -> Self {
+        Self::Paths(iter.into_iter().map(|path| path.0).collect())
+    }
+}
+
+/// An identifier for a collection of saved voltages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoltagesKey<K>(pub(crate) Vec<(NodePath, K)>);
+
+impl<K> VoltagesKey<K> {
+    /// Saves the voltage of each node referred to by `spec` using `save`.
+    ///
+    /// Returns [`Error::InvalidSaveSpec`] if `spec` refers to an invalid path.
+    pub fn save(
+        ctx: &SimulationContext,
+        spec: &SaveSpec,
+        mut save: impl FnMut(&NodePath) -> K,
+    ) -> Result<Self> {
+        let paths = ctx.lib.node_paths(spec).ok_or(Error::InvalidSaveSpec)?;
+        Ok(Self(
+            paths
+                .into_iter()
+                .map(|path| {
+                    let key = save(&path);
+                    (path, key)
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// A collection of saved voltages of type `V`, keyed by node path.
+///
+/// Simulators support saving a `Result<Voltages<V>>` from a [`SaveSpec`],
+/// since the spec may refer to paths that do not exist in the simulated library.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Voltages<V>(pub(crate) IndexMap<NodePath, V>);
+
+impl<V> Deref for Voltages<V> {
+    type Target = IndexMap<NodePath, V>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<S: Simulator, A: Analysis, V: FromSaved<S, A>> FromSaved<S, A> for Voltages<V> {
+    type Key = VoltagesKey<V::Key>;
+    fn from_saved(output: &<A as Analysis>::Output, key: Self::Key) -> Self {
+        Voltages(
+            key.0
+                .into_iter()
+                .map(|(path, key)| (path, V::from_saved(output, key)))
+                .collect(),
+        )
+    }
+}
+
+/// An identifier for a collection of saved currents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurrentsKey<K>(pub(crate) Vec<(TerminalPath, K)>);
+
+impl<K> CurrentsKey<K> {
+    /// Saves the current through each terminal referred to by `spec` using `save`.
+    ///
+    /// Returns [`Error::InvalidSaveSpec`] if `spec` refers to an invalid path.
+    pub fn save(
+        ctx: &SimulationContext,
+        spec: &SaveSpec,
+        mut save: impl FnMut(&TerminalPath) -> K,
+    ) -> Result<Self> {
+        let paths = ctx.lib.terminal_paths(spec).ok_or(Error::InvalidSaveSpec)?;
+        Ok(Self(
+            paths
+                .into_iter()
+                .map(|path| {
+                    let key = save(&path);
+                    (path, key)
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// A collection of saved currents of type `I`, keyed by terminal path.
+///
+/// Simulators support saving a `Result<Currents<I>>` from a [`SaveSpec`],
+/// since the spec may refer to paths that do not exist in the simulated library.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Currents<I>(pub(crate) IndexMap<TerminalPath, I>);
+
+impl<I> Deref for Currents<I> {
+    type Target = IndexMap<TerminalPath, I>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<S: Simulator, A: Analysis, I: FromSaved<S, A>> FromSaved<S, A> for Currents<I> {
+    type Key = CurrentsKey<I::Key>;
+    fn from_saved(output: &<A as Analysis>::Output, key: Self::Key) -> Self {
+        Currents(
+            key.0
+                .into_iter()
+                .map(|(path, key)| (path, I::from_saved(output, key)))
+                .collect(),
+        )
+    }
+}
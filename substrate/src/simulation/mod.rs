@@ -1,7 +1,7 @@
 //! Substrate's simulation API.
 
 use std::any::Any;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use impl_trait_for_tuples::impl_for_tuples;
@@ -10,13 +10,13 @@ use serde::Serialize;
 
 use crate::block::Block;
 use crate::cache::Cache;
-use crate::execute::Executor;
+use crate::execute::{Executor, Task, WorkerPool};
 use crate::io::{SchematicType, TestbenchIo};
 use crate::pdk::corner::InstallCorner;
 use crate::pdk::Pdk;
 use crate::schematic::conv::RawLib;
 use crate::schematic::{Cell, ExportsSchematicData, SimCellBuilder};
-use crate::simulation::data::Save;
+use crate::simulation::data::{FromSaved, Save};
 use codegen::simulator_tuples;
 
 pub mod data;
@@ -117,6 +117,7 @@ pub struct SimController<PDK: Pdk, S: Simulator, T: Testbench<PDK, S>> {
     /// The current testbench cell.
     pub tb: Cell<T>,
     pub(crate) ctx: SimulationContext,
+    pub(crate) pool: WorkerPool,
 }
 
 /// Set an initial condition.
@@ -161,6 +162,44 @@ impl<PDK: Pdk + InstallCorner<S>, S: Simulator, T: Testbench<PDK, S>> SimControl
         Ok(O::from_saved(&output, key))
     }
 
+    /// Runs the given analysis in the background, returning a handle to the desired output.
+    ///
+    /// The analysis runs in the subdirectory `name` of the testbench's working directory,
+    /// so that several analyses can be submitted before waiting for any of them.
+    /// Outputs are marked for saving before this function returns.
+    ///
+    /// See [`SimController::simulate`] for the handling of `corner`.
+    pub fn submit<A, O>(
+        &self,
+        name: impl AsRef<Path>,
+        mut options: S::Options,
+        corner: Option<&PDK::Corner>,
+        input: A,
+    ) -> Task<Result<O, S::Error>>
+    where
+        A: Analysis + SupportedBy<S> + Send + 'static,
+        O: for<'b> Save<S, A, &'b Cell<T>> + Send + 'static,
+        <O as FromSaved<S, A>>::Key: Send + 'static,
+        S::Options: Send + 'static,
+        S::Error: Send + 'static,
+    {
+        if let Some(corner) = corner {
+            self.pdk.install_corner(corner, &mut options);
+        }
+        let ctx = SimulationContext {
+            work_dir: self.ctx.work_dir.join(name),
+            lib: self.ctx.lib.clone(),
+            executor: self.ctx.executor.clone(),
+            cache: self.ctx.cache.clone(),
+        };
+        let key = O::save(&ctx, &self.tb, &mut options);
+        let simulator = self.simulator.clone();
+        self.pool.spawn(move || {
+            let output = simulator.simulate(&ctx, options, input)?;
+            Ok(O::from_saved(&output, key))
+        })
+    }
+
     /// Set an initial condition by mutating the given options.
     pub fn set_initial_condition<K, V>(&self, key: K, value: V, options: &mut S::Options)
     where
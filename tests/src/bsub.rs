@@ -1,5 +1,6 @@
 use std::io::Read;
 use std::process::Command;
+use std::time::Duration;
 
 use substrate::execute::{ExecOpts, Executor, LsfExecutor};
 
@@ -44,6 +45,8 @@ fn lsf_executor_command() {
         &cmd,
         ExecOpts {
             cpus: Some(2),
+            timeout: Some(Duration::from_secs(90)),
+            memory: Some(4096),
             ..Default::default()
         },
     );
@@ -55,6 +58,10 @@ fn lsf_executor_command() {
     assert_eq!(args[2], "myqueue");
     assert_eq!(args[3], "-n");
     assert_eq!(args[4], "2");
-    assert_eq!(args[5], "touch");
-    assert_eq!(args[6], "hello.txt");
+    assert_eq!(args[5], "-W");
+    assert_eq!(args[6], "2");
+    assert_eq!(args[7], "-M");
+    assert_eq!(args[8], "4096MB");
+    assert_eq!(args[9], "touch");
+    assert_eq!(args[10], "hello.txt");
 }
@@ -0,0 +1,190 @@
+use arcstr::ArcStr;
+use geometry::prelude::Bbox;
+use geometry::rect::Rect;
+use serde::{Deserialize, Serialize};
+use substrate::block::Block;
+use substrate::context::Context;
+use substrate::layout::drc::{write_markers, Rule, RuleDeck, RuleKind};
+use substrate::layout::element::Shape;
+use substrate::layout::{ExportsLayoutData, Layout};
+use substrate::pdk::layers::Layer;
+
+use crate::paths::get_path;
+use crate::shared::pdk::ExamplePdkA;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Block, Serialize, Deserialize, Hash)]
+#[substrate(io = "()")]
+pub struct DrcExample;
+
+impl ExportsLayoutData for DrcExample {
+    type Data = ();
+}
+
+impl Layout<ExamplePdkA> for DrcExample {
+    fn layout(
+        &self,
+        _io: &mut <<Self as substrate::block::Block>::Io as substrate::io::LayoutType>::Builder,
+        cell: &mut substrate::layout::CellBuilder<ExamplePdkA, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        let poly = cell.ctx.layers.polya;
+        let met1 = cell.ctx.layers.met1a.drawing;
+        let met2 = cell.ctx.layers.met2a;
+
+        for (layer, rect) in [
+            // Two shapes spaced too closely.
+            (poly.id(), Rect::from_sides(0, 0, 100, 100)),
+            (poly.id(), Rect::from_sides(110, 0, 210, 100)),
+            // A shape that is too narrow.
+            (poly.id(), Rect::from_sides(0, 200, 30, 300)),
+            // A U-shaped polygon with a narrow notch.
+            (poly.id(), Rect::from_sides(300, 0, 360, 200)),
+            (poly.id(), Rect::from_sides(370, 0, 430, 200)),
+            (poly.id(), Rect::from_sides(300, 0, 430, 60)),
+            // A shape that is too small.
+            (met2.id(), Rect::from_sides(200, 200, 250, 250)),
+            // Insufficient enclosure.
+            (met1.id(), Rect::from_sides(500, 500, 550, 550)),
+            (met2.id(), Rect::from_sides(495, 495, 555, 555)),
+            // Insufficient extension at the top edge.
+            (met2.id(), Rect::from_sides(1000, 0, 1200, 100)),
+            (poly.id(), Rect::from_sides(1050, -30, 1150, 110)),
+        ] {
+            cell.draw(Shape::new(layer, rect))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Block, Serialize, Deserialize, Hash)]
+#[substrate(io = "()")]
+pub struct DrcLimitExample;
+
+impl ExportsLayoutData for DrcLimitExample {
+    type Data = ();
+}
+
+impl Layout<ExamplePdkA> for DrcLimitExample {
+    fn layout(
+        &self,
+        _io: &mut <<Self as substrate::block::Block>::Io as substrate::io::LayoutType>::Builder,
+        cell: &mut substrate::layout::CellBuilder<ExamplePdkA, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        let poly = cell.ctx.layers.polya.id();
+
+        for rect in [
+            // Two minimum width shapes at exactly the minimum spacing.
+            Rect::from_sides(0, 0, 50, 200),
+            Rect::from_sides(70, 0, 120, 200),
+            // A U-shaped polygon with a notch of exactly the minimum width.
+            Rect::from_sides(300, 0, 350, 200),
+            Rect::from_sides(370, 0, 420, 200),
+            Rect::from_sides(300, 0, 420, 50),
+        ] {
+            cell.draw(Shape::new(poly, rect))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn drc_reports_rule_violations() {
+    let test_name = "drc_reports_rule_violations";
+    let ctx = Context::new(ExamplePdkA);
+    let issues = ctx.drc(DrcExample).expect("failed to run DRC");
+
+    let mut violations = issues
+        .iter()
+        .map(|issue| (issue.rule().to_string(), issue.marker().bbox().unwrap()))
+        .collect::<Vec<_>>();
+    violations.sort();
+
+    let expected = |rule: &str, rect| (rule.to_string(), rect);
+    let mut expected_violations = vec![
+        expected("poly.w.1", Rect::from_sides(0, 200, 30, 300)),
+        expected("poly.s.1", Rect::from_sides(100, 0, 110, 100)),
+        expected("poly.n.1", Rect::from_sides(360, 60, 370, 200)),
+        expected("poly.ext.met2", Rect::from_sides(1050, 110, 1150, 120)),
+        expected("met2.enc.met1", Rect::from_sides(490, 490, 495, 560)),
+        expected("met2.enc.met1", Rect::from_sides(495, 490, 555, 495)),
+        expected("met2.enc.met1", Rect::from_sides(495, 555, 555, 560)),
+        expected("met2.enc.met1", Rect::from_sides(555, 490, 560, 560)),
+        expected("met2.a.1", Rect::from_sides(200, 200, 250, 250)),
+    ];
+    expected_violations.sort();
+    assert_eq!(violations, expected_violations);
+    assert_eq!(issues.num_errors(), 9);
+
+    let path = get_path(test_name, "markers.gds");
+    write_markers(&issues, &path).expect("failed to write markers");
+    let lib = gds::GdsLibrary::load(&path).expect("failed to read markers");
+    // Each violation has a marker boundary and a label.
+    assert_eq!(lib.structs[0].elems.len(), 18);
+}
+
+#[test]
+fn drc_checks_density() {
+    let ctx = Context::new(ExamplePdkA);
+    let handle = ctx.generate_layout(DrcExample);
+    let rules = RuleDeck::new().with_rule(
+        "met2.den.1",
+        Rule::Density {
+            layer: ctx.layers.met2a.id(),
+            window: 1000,
+            min: 0.05,
+            max: 1.,
+        },
+    );
+    let issues = handle.cell().drc(&rules);
+
+    assert_eq!(issues.len(), 1);
+    let issue = issues.iter().next().unwrap();
+    assert_eq!(issue.kind(), RuleKind::Density);
+    assert_eq!(
+        issue.marker().bbox().unwrap(),
+        Rect::from_sides(0, -30, 1000, 555)
+    );
+}
+
+#[test]
+fn drc_rules_resolve_layer_names() {
+    let ctx = Context::new(ExamplePdkA);
+    let rules: RuleDeck<ArcStr> = RuleDeck::new()
+        .with_rule(
+            "poly.w.1",
+            Rule::Width {
+                layer: "poly_a".into(),
+                min: 50,
+            },
+        )
+        .with_rule(
+            "poly.s.1",
+            Rule::Spacing {
+                layer: "poly_a".into(),
+                min: 20,
+            },
+        );
+
+    let rules = rules
+        .resolve(|name| ctx.get_layer_by_name(name))
+        .expect("failed to resolve layers");
+    let handle = ctx.generate_layout(DrcExample);
+    assert_eq!(handle.cell().drc(&rules).len(), 2);
+
+    let unknown: RuleDeck<ArcStr> = RuleDeck::new().with_rule(
+        "bad",
+        Rule::Width {
+            layer: "nonexistent".into(),
+            min: 1,
+        },
+    );
+    assert!(unknown.resolve(|name| ctx.get_layer_by_name(name)).is_err());
+}
+
+#[test]
+fn drc_accepts_geometry_at_the_limit() {
+    let ctx = Context::new(ExamplePdkA);
+    let issues = ctx.drc(DrcLimitExample).expect("failed to run DRC");
+    assert!(issues.is_empty(), "unexpected violations: {issues:?}");
+}
@@ -0,0 +1,340 @@
+use std::ffi::OsStr;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use arcstr::ArcStr;
+use indexmap::IndexMap;
+use substrate::error::Error;
+use substrate::execute::{
+    ConcurrencyLimits, ExecOpts, Executor, Job, JobSet, LimitedExecutor, LocalExecutor, LogOutput,
+    LsfExecutor, RecordingExecutor, WorkerPool,
+};
+
+use crate::paths::get_path;
+
+#[test]
+fn local_executor_kills_jobs_that_time_out() {
+    let mut cmd = Command::new("bash");
+    // The background process ensures that the entire process group is killed,
+    // not just the top-level shell.
+    cmd.arg("-c").arg("sleep 30 & wait");
+
+    let start = Instant::now();
+    let err = LocalExecutor
+        .execute(
+            cmd,
+            ExecOpts {
+                timeout: Some(Duration::from_millis(200)),
+                ..Default::default()
+            },
+        )
+        .expect_err("job should time out");
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    match err {
+        Error::ExecutionFailed(failure) => {
+            assert!(failure.timed_out());
+            assert_eq!(failure.attempts, 1);
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+}
+
+#[test]
+fn local_executor_retries_failed_jobs() {
+    let counter = get_path("local_executor_retries_failed_jobs", "counter.txt");
+    let _ = std::fs::remove_file(&counter);
+    std::fs::create_dir_all(counter.parent().unwrap()).unwrap();
+
+    let mut cmd = Command::new("bash");
+    cmd.arg("-c").arg(format!(
+        "n=$(cat {counter:?} 2>/dev/null || echo 0); n=$((n+1)); echo $n > {counter:?}; [ $n -ge 3 ]"
+    ));
+
+    LocalExecutor
+        .execute(
+            cmd,
+            ExecOpts {
+                retries: 2,
+                backoff: Duration::from_millis(10),
+                ..Default::default()
+            },
+        )
+        .expect("job should succeed on the third attempt");
+
+    let attempts = std::fs::read_to_string(&counter).unwrap();
+    assert_eq!(attempts.trim(), "3");
+}
+
+#[test]
+fn local_executor_reports_exit_status_and_log_tail() {
+    let log = get_path("local_executor_reports_exit_status_and_log_tail", "job.log");
+    std::fs::create_dir_all(log.parent().unwrap()).unwrap();
+
+    let mut cmd = Command::new("bash");
+    cmd.arg("-c")
+        .arg("for i in $(seq 1 100); do echo line $i; done; exit 3");
+
+    let err = LocalExecutor
+        .execute(
+            cmd,
+            ExecOpts {
+                logs: LogOutput::File(log),
+                retries: 1,
+                backoff: Duration::from_millis(10),
+                ..Default::default()
+            },
+        )
+        .expect_err("job should fail");
+
+    match err {
+        Error::ExecutionFailed(failure) => {
+            assert!(!failure.timed_out());
+            assert_eq!(failure.status.unwrap().code(), Some(3));
+            assert_eq!(failure.attempts, 2);
+            let tail = failure.log_tail.unwrap();
+            assert_eq!(tail.lines().count(), 20);
+            assert_eq!(tail.lines().last(), Some("line 100"));
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+}
+
+#[test]
+fn lsf_executor_reports_log_tail() {
+    let stub = get_path("lsf_executor_reports_log_tail", "bsub.sh");
+    let log = stub.with_file_name("job.log");
+    std::fs::create_dir_all(stub.parent().unwrap()).unwrap();
+    // A stand-in for `bsub` that runs the job locally, appending its output to the `-o` file.
+    std::fs::write(
+        &stub,
+        r#"#!/bin/bash
+while [ $# -gt 0 ]; do
+    case "$1" in
+        -K) shift ;;
+        -o) out="$2"; shift 2 ;;
+        -*) shift 2 ;;
+        *) break ;;
+    esac
+done
+"$@" >> "$out" 2>&1
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut cmd = Command::new("bash");
+    cmd.arg("-c").arg("echo job output; exit 3");
+
+    let bsub = LsfExecutor::builder()
+        .bsub(stub.to_str().unwrap())
+        .queue("normal")
+        .build()
+        .unwrap();
+    let opts = ExecOpts {
+        logs: LogOutput::File(log.clone()),
+        ..Default::default()
+    };
+    let submit = bsub.command(&cmd, opts.clone());
+    let args = submit.get_args().collect::<Vec<_>>();
+    let (o, e) = (OsStr::new("-o"), OsStr::new("-e"));
+    assert_eq!(args[3..7], [o, log.as_os_str(), e, log.as_os_str()]);
+
+    match bsub.execute(cmd, opts).expect_err("job should fail") {
+        Error::ExecutionFailed(failure) => {
+            assert_eq!(failure.status.unwrap().code(), Some(3));
+            assert_eq!(failure.log_tail.as_deref(), Some("job output"));
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+}
+
+#[test]
+fn jobs_run_in_the_background() {
+    let mut cmd = Command::new("bash");
+    cmd.arg("-c").arg("sleep 0.5");
+
+    let job = Job::submit(Arc::new(LocalExecutor), cmd, ExecOpts::default());
+    assert!(job.poll().is_none());
+    assert!(!job.is_done());
+
+    job.wait().expect("job should succeed");
+    assert!(job.is_done());
+    assert!(matches!(job.poll(), Some(Ok(()))));
+}
+
+#[test]
+fn jobs_can_be_cancelled() {
+    let mut cmd = Command::new("bash");
+    cmd.arg("-c").arg("sleep 30 & wait");
+
+    let start = Instant::now();
+    let job = Job::submit(Arc::new(LocalExecutor), cmd, ExecOpts::default());
+    std::thread::sleep(Duration::from_millis(200));
+    job.cancel();
+
+    assert!(matches!(job.wait(), Err(Error::Cancelled)));
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn dropping_job_set_cancels_outstanding_jobs() {
+    let jobs = JobSet::new(Arc::new(LocalExecutor));
+    let handles = (0..3)
+        .map(|_| {
+            let mut cmd = Command::new("bash");
+            cmd.arg("-c").arg("sleep 30 & wait");
+            jobs.submit(cmd, ExecOpts::default())
+        })
+        .collect::<Vec<_>>();
+
+    let start = Instant::now();
+    std::thread::sleep(Duration::from_millis(200));
+    drop(jobs);
+
+    for job in handles {
+        assert!(job.is_done());
+        assert!(matches!(job.wait(), Err(Error::Cancelled)));
+    }
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
+
+/// An executor that records the maximum number of jobs it ran concurrently.
+#[derive(Default)]
+struct ConcurrencyTracker {
+    running: AtomicUsize,
+    max_running: AtomicUsize,
+}
+
+impl Executor for ConcurrencyTracker {
+    fn execute(&self, _command: Command, _opts: ExecOpts) -> Result<(), Error> {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_running.fetch_max(running, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(100));
+        self.running.fetch_sub(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+fn max_concurrency(limits: ConcurrencyLimits, opts: ExecOpts) -> usize {
+    let tracker = Arc::new(ConcurrencyTracker::default());
+    let jobs = JobSet::new(Arc::new(LimitedExecutor::new(tracker.clone(), limits)));
+    for _ in 0..6 {
+        jobs.submit(Command::new("true"), opts.clone());
+    }
+    for result in jobs.wait_all() {
+        result.expect("job should succeed");
+    }
+    tracker.max_running.load(Ordering::SeqCst)
+}
+
+#[test]
+fn limited_executor_limits_running_jobs() {
+    let max = max_concurrency(
+        ConcurrencyLimits {
+            max_jobs: Some(2),
+            ..Default::default()
+        },
+        ExecOpts::default(),
+    );
+    assert!((1..=2).contains(&max));
+}
+
+#[test]
+fn limited_executor_limits_license_usage() {
+    let license = ArcStr::from("spectre");
+    let max = max_concurrency(
+        ConcurrencyLimits {
+            licenses: IndexMap::from_iter([(license.clone(), 3)]),
+            ..Default::default()
+        },
+        ExecOpts {
+            licenses: IndexMap::from_iter([(license, 2)]),
+            ..Default::default()
+        },
+    );
+    assert_eq!(max, 1);
+}
+
+#[test]
+fn worker_pool_limits_running_jobs() {
+    let tracker = Arc::new(ConcurrencyTracker::default());
+    let jobs = JobSet::with_pool(tracker.clone(), WorkerPool::new(2));
+    let handles = (0..6)
+        .map(|_| jobs.submit(Command::new("true"), ExecOpts::default()))
+        .collect::<Vec<_>>();
+    // Polling, rather than waiting, ensures that only the pool's threads run jobs.
+    while !handles.iter().all(Job::is_done) {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(tracker.max_running.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn worker_pool_runs_nested_tasks() {
+    let pool = WorkerPool::new(1);
+    let inner = pool.clone();
+    let task = pool.spawn(move || inner.spawn(|| 1).wait() + 1);
+    assert_eq!(task.wait(), 2);
+}
+
+/// An executor that ignores cancellation, counting the jobs it completes.
+#[derive(Default)]
+struct UncancellableExecutor {
+    completed: AtomicUsize,
+}
+
+impl Executor for UncancellableExecutor {
+    fn execute(&self, _command: Command, _opts: ExecOpts) -> Result<(), Error> {
+        std::thread::sleep(Duration::from_millis(300));
+        self.completed.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+#[test]
+fn dropping_job_set_waits_for_running_jobs() {
+    let executor = Arc::new(UncancellableExecutor::default());
+    let jobs = JobSet::with_pool(executor.clone(), WorkerPool::new(2));
+    for _ in 0..2 {
+        jobs.submit(Command::new("true"), ExecOpts::default());
+    }
+    std::thread::sleep(Duration::from_millis(100));
+    drop(jobs);
+    assert_eq!(executor.completed.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn recording_executor_records_commands_without_running_them() {
+    let dir = get_path(
+        "recording_executor_records_commands_without_running_them",
+        "work",
+    );
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("input.txt"), "input").unwrap();
+    let _ = std::fs::remove_file(dir.join("output.txt"));
+
+    let mut cmd = Command::new("bash");
+    cmd.arg("-c")
+        .arg("touch output.txt")
+        .current_dir(&dir)
+        .env("FOO", "bar");
+
+    let executor = RecordingExecutor::new();
+    executor.execute(cmd, ExecOpts::default()).unwrap();
+    assert!(!dir.join("output.txt").exists());
+
+    let commands = executor.commands();
+    assert_eq!(commands.len(), 1);
+    let command = &commands[0];
+    assert_eq!(command.program, "bash");
+    assert_eq!(command.args, ["-c", "touch output.txt"]);
+    assert_eq!(command.current_dir.as_deref(), Some(dir.as_path()));
+    assert_eq!(command.envs, [("FOO".into(), Some("bar".into()))]);
+    assert_eq!(command.files.len(), 1);
+    assert_eq!(command.files[Path::new("input.txt")], b"input");
+}
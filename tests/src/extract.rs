@@ -0,0 +1,393 @@
+use std::path::Path;
+
+use cache::multi::MultiCache;
+use geometry::rect::Rect;
+use geometry::transform::Transformation;
+use ngspice::blocks::Vsource;
+use ngspice::tran::Tran;
+use ngspice::{Ngspice, Options};
+use rust_decimal_macros::dec;
+use scir::{Expr, PrimitiveDeviceKind};
+use serde::{Deserialize, Serialize};
+use spice::Netlister;
+use substrate::block::Block;
+use substrate::cache::Cache;
+use substrate::context::Context;
+use substrate::execute::RecordingExecutor;
+use substrate::io::{InOut, Io, IoShape, SchematicType, ShapePort, Signal, TestbenchIo};
+use substrate::layout::connectivity::{Conductor, Connectivity, DeviceRecipe, Terminal, Via};
+use substrate::layout::element::{Shape, Text};
+use substrate::layout::parasitics::{LayerParasitics, Parasitics, RcModel, ViaParasitics};
+use substrate::layout::{ExportsLayoutData, Layout};
+use substrate::pdk::layers::{Layer, Layers};
+use substrate::schematic::{ExportsSchematicData, Schematic, SimCellBuilder};
+use substrate::simulation::{HasSimSchematic, SimController, Testbench};
+
+use crate::paths::get_path;
+use crate::shared::pdk::ExamplePdkA;
+
+#[derive(Layers)]
+pub struct CutLayers {
+    #[layer(gds = "70/44")]
+    pub via12: Via12,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Block, Serialize, Deserialize, Hash)]
+#[substrate(io = "()")]
+pub struct ExtractExample;
+
+impl ExportsLayoutData for ExtractExample {
+    type Data = ();
+}
+
+impl Layout<ExamplePdkA> for ExtractExample {
+    fn layout(
+        &self,
+        _io: &mut <<Self as substrate::block::Block>::Io as substrate::io::LayoutType>::Builder,
+        cell: &mut substrate::layout::CellBuilder<ExamplePdkA, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        let cuts = cell.ctx.install_layers::<CutLayers>();
+        let poly = cell.ctx.layers.polya.id();
+        let met1 = cell.ctx.layers.met1a.drawing.id();
+        let met2 = cell.ctx.layers.met2a.id();
+
+        for (layer, rect) in [
+            // A transistor with its gate connected to metal 2 through a via.
+            (met1, Rect::from_sides(0, 0, 300, 100)),
+            (poly, Rect::from_sides(100, -50, 150, 150)),
+            (cuts.via12.id(), Rect::from_sides(110, 120, 140, 145)),
+            (met2, Rect::from_sides(100, 110, 400, 160)),
+            // An unconnected shape with a duplicate label.
+            (met1, Rect::from_sides(500, 0, 600, 100)),
+        ] {
+            cell.draw(Shape::new(layer, rect))?;
+        }
+
+        for (layer, text, x, y) in [
+            (cell.ctx.layers.met1a.label.id(), "d", 50., 50.),
+            (met2, "gate", 300., 130.),
+            (cell.ctx.layers.met1a.label.id(), "d", 550., 50.),
+        ] {
+            cell.draw(Text::new(layer, text, Transformation::translate(x, y)))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn connectivity(ctx: &Context<ExamplePdkA>, cuts: &CutLayers) -> Connectivity {
+    let poly = ctx.layers.polya.id();
+    let met1 = ctx.layers.met1a.drawing.id();
+    let met2 = ctx.layers.met2a.id();
+
+    Connectivity::new()
+        .with_conductor(Conductor {
+            layer: poly,
+            pins: Vec::new(),
+            labels: Vec::new(),
+        })
+        .with_conductor(Conductor {
+            layer: met1,
+            pins: vec![ctx.layers.met1a.pin.id()],
+            labels: vec![ctx.layers.met1a.label.id()],
+        })
+        .with_conductor(Conductor {
+            layer: met2,
+            pins: Vec::new(),
+            labels: Vec::new(),
+        })
+        .with_via(Via {
+            cut: cuts.via12.id(),
+            bot: poly,
+            top: met2,
+        })
+        .with_device(DeviceRecipe {
+            cell: "nfet".into(),
+            body: vec![poly, met1],
+            splits: vec![met1],
+            terminals: vec![
+                Terminal {
+                    name: "d".into(),
+                    layer: met1,
+                },
+                Terminal {
+                    name: "g".into(),
+                    layer: poly,
+                },
+                Terminal {
+                    name: "s".into(),
+                    layer: met1,
+                },
+            ],
+        })
+}
+
+#[test]
+fn extraction_finds_nets_and_devices() {
+    let ctx = Context::new(ExamplePdkA);
+    let cuts = ctx.install_layers::<CutLayers>();
+    let connectivity = connectivity(&ctx, &cuts);
+
+    let handle = ctx.generate_layout(ExtractExample);
+    let extracted = handle.cell().extract(&connectivity);
+
+    // Source, drain, gate, and the unconnected shape.
+    assert_eq!(extracted.nets().len(), 4);
+    assert_eq!(extracted.devices().len(), 1);
+
+    let device = &extracted.devices()[0];
+    assert_eq!(device.cell, "nfet");
+    assert_eq!(device.bbox, Rect::from_sides(100, 0, 150, 100));
+    let terminals = device
+        .terminals
+        .iter()
+        .map(|&net| extracted.nets()[net].as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(terminals[..2], ["d", "gate"]);
+    assert_ne!(terminals[2], "d");
+
+    let lib = extracted.to_scir().expect("failed to export SCIR");
+    let cell = lib.cell(lib.top_cell().unwrap());
+    assert_eq!(cell.name(), "extract_example");
+    assert_eq!(cell.signals().count(), 4);
+    assert_eq!(
+        cell.contents().as_ref().unwrap_clear().primitives().count(),
+        1
+    );
+}
+
+#[test]
+fn parasitic_extraction_builds_rc_networks() {
+    let ctx = Context::new(ExamplePdkA);
+    let cuts = ctx.install_layers::<CutLayers>();
+    let connectivity = connectivity(&ctx, &cuts);
+    let parasitics = Parasitics::new()
+        .with_layer(LayerParasitics {
+            layer: ctx.layers.polya.id(),
+            sheet_resistance: 10.,
+            area_capacitance: 0.,
+            fringe_capacitance: 0.,
+        })
+        .with_layer(LayerParasitics {
+            layer: ctx.layers.met1a.drawing.id(),
+            sheet_resistance: 0.1,
+            area_capacitance: 1e-18,
+            fringe_capacitance: 0.,
+        })
+        .with_layer(LayerParasitics {
+            layer: ctx.layers.met2a.id(),
+            sheet_resistance: 0.05,
+            area_capacitance: 0.,
+            fringe_capacitance: 0.,
+        })
+        .with_via(ViaParasitics {
+            cut: cuts.via12.id(),
+            resistance: 5.,
+        });
+
+    let handle = ctx.generate_layout(ExtractExample);
+    let cell = handle.cell();
+
+    let lib = cell
+        .extract_parasitics(&connectivity, &parasitics, RcModel::Lumped, "vss")
+        .expect("failed to annotate parasitics");
+    let top = lib.cell(lib.top_cell().unwrap());
+    let primitives = top
+        .contents()
+        .as_ref()
+        .unwrap_clear()
+        .primitives()
+        .map(|(_, p)| p.clone())
+        .collect::<Vec<_>>();
+    let count =
+        |f: fn(&PrimitiveDeviceKind) -> bool| primitives.iter().filter(|p| f(&p.kind)).count();
+
+    // Two nodes per piece, plus ground.
+    assert_eq!(top.signals().count(), 11);
+    assert_eq!(top.ports().count(), 1);
+    // One resistor per piece, and one for the via.
+    assert_eq!(count(|k| matches!(k, PrimitiveDeviceKind::Res2 { .. })), 6);
+    // Half of the capacitance of each metal 1 piece at each of its ends.
+    assert_eq!(count(|k| matches!(k, PrimitiveDeviceKind::Cap2 { .. })), 6);
+    assert_eq!(
+        count(|k| matches!(k, PrimitiveDeviceKind::RawInstance { .. })),
+        1
+    );
+
+    let values = primitives
+        .iter()
+        .filter_map(|p| match &p.kind {
+            PrimitiveDeviceKind::Res2 {
+                value: Expr::NumericLiteral(value),
+                ..
+            }
+            | PrimitiveDeviceKind::Cap2 {
+                value: Expr::NumericLiteral(value),
+                ..
+            } => Some(*value),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    for value in [dec!(40), dec!(0.3), dec!(0.15), dec!(5), dec!(7.5e-15)] {
+        assert!(values.contains(&value), "missing {value}");
+    }
+
+    let lib = cell
+        .extract_parasitics(
+            &connectivity,
+            &parasitics,
+            RcModel::Distributed { segment_length: 50 },
+            "vss",
+        )
+        .expect("failed to annotate parasitics");
+    let top = lib.cell(lib.top_cell().unwrap());
+    let resistors = top
+        .contents()
+        .as_ref()
+        .unwrap_clear()
+        .primitives()
+        .filter(|(_, p)| matches!(p.kind, PrimitiveDeviceKind::Res2 { .. }))
+        .count();
+    assert_eq!(resistors, 4 + 2 + 3 + 2 + 6 + 1);
+
+    let mut buf = Vec::new();
+    Netlister::new(&lib, &[], &mut buf).export().unwrap();
+    let netlist = String::from_utf8(buf).unwrap();
+    assert!(netlist.contains("gate"));
+    assert!(netlist.contains("vss"));
+}
+
+#[test]
+fn parasitic_extraction_rejects_invalid_values() {
+    let ctx = Context::new(ExamplePdkA);
+    let cuts = ctx.install_layers::<CutLayers>();
+    let connectivity = connectivity(&ctx, &cuts);
+    let parasitics = Parasitics::new().with_layer(LayerParasitics {
+        layer: ctx.layers.met2a.id(),
+        sheet_resistance: f64::INFINITY,
+        area_capacitance: 0.,
+        fringe_capacitance: 0.,
+    });
+
+    let handle = ctx.generate_layout(ExtractExample);
+    assert!(handle
+        .cell()
+        .extract_parasitics(&connectivity, &parasitics, RcModel::Lumped, "vss")
+        .is_err());
+}
+
+#[derive(Io, Clone, Default)]
+pub struct WireIo {
+    #[substrate(layout_type = "ShapePort")]
+    pub a: InOut<Signal>,
+    #[substrate(layout_type = "ShapePort")]
+    pub vss: InOut<Signal>,
+}
+
+/// A metal 1 wire, ideal in schematic.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Block, Serialize, Deserialize, Hash)]
+#[substrate(io = "WireIo")]
+pub struct Wire;
+
+impl ExportsSchematicData for Wire {
+    type Data = ();
+}
+
+impl Schematic<ExamplePdkA> for Wire {
+    fn schematic(
+        &self,
+        _io: &<<Self as Block>::Io as SchematicType>::Bundle,
+        _cell: &mut substrate::schematic::CellBuilder<ExamplePdkA, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        Ok(())
+    }
+}
+
+impl ExportsLayoutData for Wire {
+    type Data = ();
+}
+
+impl Layout<ExamplePdkA> for Wire {
+    fn layout(
+        &self,
+        io: &mut <<Self as substrate::block::Block>::Io as substrate::io::LayoutType>::Builder,
+        cell: &mut substrate::layout::CellBuilder<ExamplePdkA, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        let met1 = cell.ctx.layers.met1a;
+        cell.draw(Shape::new(met1.drawing, Rect::from_sides(0, 0, 2000, 100)))?;
+        io.a.set(IoShape::with_layers(met1, Rect::from_sides(0, 0, 100, 100)));
+        io.vss.set(IoShape::with_layers(
+            met1,
+            Rect::from_sides(0, 300, 2000, 400),
+        ));
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Block, Serialize, Deserialize, Hash)]
+#[substrate(io = "TestbenchIo")]
+pub struct WireTb;
+
+impl ExportsSchematicData for WireTb {
+    type Data = ();
+}
+
+impl HasSimSchematic<ExamplePdkA, Ngspice> for WireTb {
+    fn schematic(
+        &self,
+        io: &<<Self as Block>::Io as SchematicType>::Bundle,
+        cell: &mut SimCellBuilder<ExamplePdkA, Ngspice, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        let dut = cell.instantiate(Wire);
+        cell.connect(dut.io().vss, io.vss);
+        let vsource = cell.instantiate_tb(Vsource::dc(dec!(1.8)));
+        cell.connect(vsource.io().p, dut.io().a);
+        cell.connect(vsource.io().n, io.vss);
+        Ok(())
+    }
+}
+
+impl Testbench<ExamplePdkA, Ngspice> for WireTb {
+    type Output = ();
+
+    fn run(&self, sim: SimController<ExamplePdkA, Ngspice, Self>) -> Self::Output {
+        sim.simulate_default(
+            Options::default(),
+            None,
+            Tran {
+                step: dec!(2e-10),
+                stop: dec!(2e-9),
+                ..Default::default()
+            },
+        )
+        .expect("failed to run simulation");
+    }
+}
+
+#[test]
+fn simulate_extracted_uses_parasitic_netlist() {
+    let test_name = "simulate_extracted_uses_parasitic_netlist";
+    let sim_dir = get_path(test_name, "sim/");
+    // Replays the outputs of another testbench, since only the netlist is checked.
+    let executor = RecordingExecutor::with_replay(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/data/ngspice/resistor_tb"
+    ));
+    let ctx = Context::builder()
+        .pdk(ExamplePdkA)
+        .with_simulator(Ngspice::default())
+        .cache(Cache::new(MultiCache::builder().build()))
+        .executor(executor.clone())
+        .build();
+
+    ctx.simulate_extracted(WireTb, Wire, RcModel::Lumped, "vss", sim_dir)
+        .expect("failed to simulate extracted view");
+
+    let commands = executor.commands();
+    assert_eq!(commands.len(), 1);
+    let netlist = std::str::from_utf8(&commands[0].files[Path::new("netlist.spice")]).unwrap();
+    // The ports keep the order of the schematic, and 20 squares of metal 1 become a resistor.
+    assert!(netlist.contains(".SUBCKT wire a vss"));
+    assert!(netlist.contains("Rr0 a a_1 2\n"));
+    assert!(netlist.contains("Cc0 a vss 0.0000000000001"));
+}
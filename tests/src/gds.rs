@@ -1,8 +1,20 @@
+use geometry::prelude::{Bbox, Path, PathEnd, Point, Rect};
+use geometry::side::Sides;
+use geometry::transform::Transformation;
+use serde::{Deserialize, Serialize};
+use substrate::block::Block;
+use substrate::context::Context;
+use substrate::layout::element::{
+    HorizontalJustification, Property, RawInstanceArray, Shape, Text, TextJustification,
+    VerticalJustification,
+};
+use substrate::layout::tiling::{ArrayTiler, GridTile, GridTiler, Tile, TileAlignMode};
+use substrate::layout::{ExportsLayoutData, InstanceArray, Layout};
 use substrate::pdk::layers::GdsLayerSpec;
 use test_log::test;
 
 use crate::paths::{get_path, test_data};
-use crate::shared::pdk::sky130_open_ctx;
+use crate::shared::pdk::{sky130_open_ctx, ExamplePdkA};
 
 #[test]
 fn test_gds_import() {
@@ -181,3 +193,540 @@ fn test_gds_reexport() {
     assert_eq!(r.width(), 50);
     assert_eq!(r.height(), 25);
 }
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Block, Serialize, Deserialize, Hash)]
+#[substrate(io = "()")]
+pub struct PathExample;
+
+impl ExportsLayoutData for PathExample {
+    type Data = ();
+}
+
+impl Layout<ExamplePdkA> for PathExample {
+    fn layout(
+        &self,
+        _io: &mut <<Self as Block>::Io as substrate::io::LayoutType>::Builder,
+        cell: &mut substrate::layout::CellBuilder<ExamplePdkA, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        for (i, end) in [
+            PathEnd::Flush,
+            PathEnd::Round,
+            PathEnd::HalfWidth,
+            PathEnd::Custom { begin: 5, end: 15 },
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let y = 100 * i as i64;
+            cell.draw(Shape::new(
+                cell.ctx.layers.met2a,
+                Path::new(
+                    vec![
+                        Point::new(0, y),
+                        Point::new(200, y),
+                        Point::new(200, y + 50),
+                    ],
+                    20,
+                    end,
+                ),
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_gds_path_round_trip() {
+    let gds_path = get_path("test_gds_path_round_trip", "layout.gds");
+    let ctx = Context::new(ExamplePdkA);
+    ctx.write_layout(PathExample, &gds_path)
+        .expect("failed to write layout");
+
+    let lib = gds::GdsLibrary::load(&gds_path).expect("failed to load GDS file");
+    let gds_paths = lib.structs[0]
+        .elems
+        .iter()
+        .filter_map(|elem| match elem {
+            gds::GdsElement::GdsPath(path) => Some(path),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(gds_paths.len(), 4);
+    assert_eq!(
+        gds_paths
+            .iter()
+            .map(|path| path.path_type)
+            .collect::<Vec<_>>(),
+        [Some(0), Some(1), Some(2), Some(4)]
+    );
+    assert!(gds_paths.iter().all(|path| path.width == Some(20)));
+    assert_eq!(gds_paths[3].begin_extn, Some(5));
+    assert_eq!(gds_paths[3].end_extn, Some(15));
+
+    let ctx = Context::new(ExamplePdkA);
+    let cell_map = ctx
+        .read_gds(&gds_path)
+        .expect("failed to import GDS file")
+        .cells;
+    let cell = cell_map.get("path_example").unwrap();
+    let mut paths = cell
+        .elements()
+        .filter_map(|e| e.as_ref().shape())
+        .map(|shape| {
+            assert_eq!(shape.layer(), *ctx.layers.met2a.as_ref());
+            shape.shape().path().expect("expected a path").clone()
+        })
+        .collect::<Vec<_>>();
+    paths.sort_by_key(|path| path.points()[0].y);
+
+    let ends = paths.iter().map(|path| path.end()).collect::<Vec<_>>();
+    assert_eq!(
+        ends,
+        [
+            PathEnd::Flush,
+            PathEnd::Round,
+            PathEnd::HalfWidth,
+            PathEnd::Custom { begin: 5, end: 15 },
+        ]
+    );
+    assert_eq!(paths[3].bbox(), Some(Rect::from_sides(-5, 290, 210, 365)));
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Block, Serialize, Deserialize, Hash)]
+#[substrate(io = "()")]
+pub struct ArrayLeaf;
+
+impl ExportsLayoutData for ArrayLeaf {
+    type Data = ();
+}
+
+impl Layout<ExamplePdkA> for ArrayLeaf {
+    fn layout(
+        &self,
+        _io: &mut <<Self as Block>::Io as substrate::io::LayoutType>::Builder,
+        cell: &mut substrate::layout::CellBuilder<ExamplePdkA, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        cell.draw(Shape::new(
+            cell.ctx.layers.met2a,
+            Rect::from_sides(0, 0, 100, 50),
+        ))?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Block, Serialize, Deserialize, Hash)]
+#[substrate(io = "()")]
+pub struct ArrayExample;
+
+impl ExportsLayoutData for ArrayExample {
+    type Data = ();
+}
+
+impl Layout<ExamplePdkA> for ArrayExample {
+    fn layout(
+        &self,
+        _io: &mut <<Self as Block>::Io as substrate::io::LayoutType>::Builder,
+        cell: &mut substrate::layout::CellBuilder<ExamplePdkA, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        let leaf = cell.generate(ArrayLeaf);
+        let tile = Tile::from_bbox(leaf).with_padding(Sides::uniform(10));
+
+        let mut tiler = ArrayTiler::new(TileAlignMode::PosAdjacent, TileAlignMode::Center);
+        tiler.push_array(tile.clone(), 4);
+        cell.draw(tiler)?;
+
+        let mut grid = GridTiler::new();
+        grid.push_array(GridTile::new(tile), 2, 3);
+        cell.draw(grid.tile())?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_gds_array_round_trip() {
+    let gds_path = get_path("test_gds_array_round_trip", "layout.gds");
+    let ctx = Context::new(ExamplePdkA);
+    ctx.write_layout(ArrayExample, &gds_path)
+        .expect("failed to write layout");
+    let expected = ctx.generate_layout(ArrayExample).cell().flatten();
+
+    let lib = gds::GdsLibrary::load(&gds_path).expect("failed to load GDS file");
+    let top = lib
+        .structs
+        .iter()
+        .find(|strukt| strukt.name == "array_example")
+        .expect("top cell not found");
+    assert!(top
+        .elems
+        .iter()
+        .all(|elem| matches!(elem, gds::GdsElement::GdsArrayRef(_))));
+    let arefs = top
+        .elems
+        .iter()
+        .filter_map(|elem| match elem {
+            gds::GdsElement::GdsArrayRef(aref) => Some((
+                aref.rows,
+                aref.cols,
+                aref.xy.clone().map(|p| Point::new(p.x.into(), p.y.into())),
+            )),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        arefs,
+        [
+            (
+                1,
+                4,
+                [Point::new(0, 0), Point::new(480, 0), Point::new(0, 0)]
+            ),
+            (
+                2,
+                3,
+                [
+                    Point::new(10, -60),
+                    Point::new(370, -60),
+                    Point::new(10, -200)
+                ]
+            ),
+        ]
+    );
+
+    let ctx = Context::new(ExamplePdkA);
+    let cell_map = ctx
+        .read_gds(&gds_path)
+        .expect("failed to import GDS file")
+        .cells;
+    let cell = cell_map.get("array_example").unwrap();
+    let arrays = cell
+        .elements()
+        .filter_map(|e| e.as_ref().instance_array())
+        .collect::<Vec<_>>();
+    assert_eq!(arrays.len(), 2);
+    assert_eq!(arrays[1].rows(), 2);
+    assert_eq!(arrays[1].cols(), 3);
+    assert_eq!(arrays[1].row_pitch(), Point::new(0, -70));
+    assert_eq!(arrays[1].col_pitch(), Point::new(120, 0));
+    assert_eq!(cell.bbox(), Some(Rect::from_sides(0, -130, 460, 50)));
+
+    let met2a = *ctx.layers.met2a.as_ref();
+    let mut shapes = cell.flatten().shapes_on(met2a).to_vec();
+    let mut expected = expected.shapes_on(met2a).to_vec();
+    shapes.sort_by_key(|s| s.bbox().map(|r| (r.bot(), r.left())));
+    expected.sort_by_key(|s| s.bbox().map(|r| (r.bot(), r.left())));
+    assert_eq!(shapes.len(), 10);
+    assert_eq!(shapes, expected);
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Block, Serialize, Deserialize, Hash)]
+#[substrate(io = "()")]
+pub struct LargeArrayExample;
+
+impl ExportsLayoutData for LargeArrayExample {
+    type Data = ();
+}
+
+impl Layout<ExamplePdkA> for LargeArrayExample {
+    fn layout(
+        &self,
+        _io: &mut <<Self as Block>::Io as substrate::io::LayoutType>::Builder,
+        cell: &mut substrate::layout::CellBuilder<ExamplePdkA, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        let leaf = cell.generate(ArrayLeaf);
+        cell.draw(InstanceArray::new(
+            leaf,
+            2,
+            40_000,
+            Point::new(0, -100),
+            Point::new(200, 0),
+        ))?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_gds_export_splits_large_arrays() {
+    let gds_path = get_path("test_gds_export_splits_large_arrays", "layout.gds");
+    let ctx = Context::new(ExamplePdkA);
+    ctx.write_layout(LargeArrayExample, &gds_path)
+        .expect("failed to write layout");
+
+    // GDS arrays have at most 32767 columns.
+    let lib = gds::GdsLibrary::load(&gds_path).expect("failed to load GDS file");
+    let top = lib
+        .structs
+        .iter()
+        .find(|strukt| strukt.name == "large_array_example")
+        .expect("top cell not found");
+    let arefs = top
+        .elems
+        .iter()
+        .filter_map(|elem| match elem {
+            gds::GdsElement::GdsArrayRef(aref) => Some((aref.rows, aref.cols, aref.xy[0].x)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(arefs, [(2, 32767, 0), (2, 7233, 6_553_400)]);
+
+    let ctx = Context::new(ExamplePdkA);
+    let cell_map = ctx
+        .read_gds(&gds_path)
+        .expect("failed to import GDS file")
+        .cells;
+    let cell = cell_map.get("large_array_example").unwrap();
+    let len = cell
+        .elements()
+        .filter_map(|e| e.as_ref().instance_array().map(|a| a.len()))
+        .sum::<usize>();
+    assert_eq!(len, 80_000);
+    assert_eq!(cell.bbox(), Some(Rect::from_sides(0, -100, 7_999_900, 50)));
+}
+
+#[test]
+fn test_gds_import_rejects_uneven_arrays() {
+    let gds_path = get_path("test_gds_import_rejects_uneven_arrays", "layout.gds");
+    let mut lib = gds::GdsLibrary::new("lib");
+    lib.structs.push(gds::GdsStruct::new("leaf"));
+    let mut top = gds::GdsStruct::new("top");
+    top.elems.push(
+        gds::GdsArrayRef {
+            name: "leaf".into(),
+            xy: [
+                gds::GdsPoint::new(0, 0),
+                gds::GdsPoint::new(100, 0),
+                gds::GdsPoint::new(0, 100),
+            ],
+            cols: 3,
+            rows: 2,
+            ..Default::default()
+        }
+        .into(),
+    );
+    lib.structs.push(top);
+    lib.save(&gds_path).expect("failed to write GDS file");
+
+    // The column extent of 100 cannot be divided into 3 equal pitches.
+    let ctx = Context::new(ExamplePdkA);
+    assert!(ctx.read_gds(&gds_path).is_err());
+}
+
+#[test]
+fn test_gds_import_path_with_minimum_width() {
+    let gds_path = get_path("test_gds_import_path_with_minimum_width", "layout.gds");
+    let mut lib = gds::GdsLibrary::new("lib");
+    let mut top = gds::GdsStruct::new("top");
+    top.elems.push(
+        gds::GdsPath {
+            layer: 1,
+            datatype: 0,
+            xy: vec![gds::GdsPoint::new(0, 0), gds::GdsPoint::new(100, 0)],
+            width: Some(i32::MIN),
+            ..Default::default()
+        }
+        .into(),
+    );
+    lib.structs.push(top);
+    lib.save(&gds_path).expect("failed to write GDS file");
+
+    let ctx = Context::new(ExamplePdkA);
+    let cell_map = ctx
+        .read_gds(&gds_path)
+        .expect("failed to import GDS file")
+        .cells;
+    let cell = cell_map.get("top").unwrap();
+    let widths = cell
+        .elements()
+        .filter_map(|e| e.as_ref().shape())
+        .map(|shape| shape.shape().path().expect("expected a path").width())
+        .collect::<Vec<_>>();
+    assert_eq!(widths, [1 << 31]);
+}
+
+#[test]
+fn test_gds_export_dependency_order() {
+    let gds_path = get_path("test_gds_export_dependency_order", "layout.gds");
+    let ctx = Context::new(ExamplePdkA);
+    ctx.write_layout(ArrayExample, &gds_path)
+        .expect("failed to write layout");
+
+    // Cells are written as they are exported, so each cell must follow the cells it instantiates.
+    let lib = gds::GdsLibrary::load(&gds_path).expect("failed to load GDS file");
+    let mut written = std::collections::HashSet::new();
+    for strukt in lib.structs.iter() {
+        for elem in strukt.elems.iter() {
+            let name = match elem {
+                gds::GdsElement::GdsStructRef(sref) => &sref.name,
+                gds::GdsElement::GdsArrayRef(aref) => &aref.name,
+                _ => continue,
+            };
+            assert!(
+                written.contains(name),
+                "`{name}` written after `{}`",
+                strukt.name
+            );
+        }
+        written.insert(strukt.name.clone());
+    }
+    assert_eq!(lib.structs.last().unwrap().name, "array_example");
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Block, Serialize, Deserialize, Hash)]
+#[substrate(io = "()")]
+pub struct PropertyExample;
+
+impl ExportsLayoutData for PropertyExample {
+    type Data = ();
+}
+
+impl Layout<ExamplePdkA> for PropertyExample {
+    fn layout(
+        &self,
+        _io: &mut <<Self as Block>::Io as substrate::io::LayoutType>::Builder,
+        cell: &mut substrate::layout::CellBuilder<ExamplePdkA, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        cell.draw(
+            Shape::new(cell.ctx.layers.met1a, Rect::from_sides(0, 0, 100, 20))
+                .with_property(Property::new(1, "net=vdd")),
+        )?;
+        cell.draw(
+            Text::new(
+                cell.ctx.layers.met2a,
+                "annotation",
+                Transformation::from_offset(Point::new(50, 10)),
+            )
+            .with_justification(TextJustification::new(
+                HorizontalJustification::Center,
+                VerticalJustification::Bottom,
+            ))
+            .with_font(2)
+            .with_mag(0.5)
+            .with_property(Property::new(2, "device=m0")),
+        )?;
+        let leaf = cell.generate(ArrayLeaf);
+        cell.draw(
+            RawInstanceArray::try_from(InstanceArray::new(
+                leaf,
+                2,
+                3,
+                Point::new(0, -100),
+                Point::new(200, 0),
+            ))?
+            .with_property(Property::new(3, "array=x0")),
+        )?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_gds_property_round_trip() {
+    let gds_path = get_path("test_gds_property_round_trip", "layout.gds");
+    let ctx = Context::new(ExamplePdkA);
+    ctx.write_layout(PropertyExample, &gds_path)
+        .expect("failed to write layout");
+
+    let lib = gds::GdsLibrary::load(&gds_path).expect("failed to load GDS file");
+    let top = lib
+        .structs
+        .iter()
+        .find(|strukt| strukt.name == "property_example")
+        .expect("top cell not found");
+    let text = top
+        .elems
+        .iter()
+        .find_map(|elem| match elem {
+            gds::GdsElement::GdsTextElem(text) => Some(text),
+            _ => None,
+        })
+        .expect("text not found");
+    let presentation = text.presentation.as_ref().unwrap();
+    assert_eq!((presentation.horizontal(), presentation.vertical()), (1, 2));
+    assert_eq!(presentation.font(), 2);
+    assert_eq!(text.strans.as_ref().unwrap().mag, Some(0.5));
+    let aref = top
+        .elems
+        .iter()
+        .find_map(|elem| match elem {
+            gds::GdsElement::GdsArrayRef(aref) => Some(aref),
+            _ => None,
+        })
+        .expect("array not found");
+    assert_eq!(
+        aref.properties,
+        [gds::GdsProperty {
+            attr: 3,
+            value: "array=x0".into(),
+        }]
+    );
+
+    let ctx = Context::new(ExamplePdkA);
+    let cell_map = ctx
+        .read_gds(&gds_path)
+        .expect("failed to import GDS file")
+        .cells;
+    let cell = cell_map.get("property_example").unwrap();
+
+    let shape = cell
+        .elements()
+        .find_map(|e| e.as_ref().shape().cloned())
+        .expect("shape not found");
+    assert_eq!(shape.properties(), [Property::new(1, "net=vdd")]);
+
+    let text = cell
+        .elements()
+        .find_map(|e| e.as_ref().text().cloned())
+        .expect("text not found");
+    assert_eq!(text.text(), "annotation");
+    assert_eq!(
+        text.justification(),
+        TextJustification::new(
+            HorizontalJustification::Center,
+            VerticalJustification::Bottom
+        )
+    );
+    assert_eq!(text.font(), 2);
+    assert_eq!(text.mag(), Some(0.5));
+    assert_eq!(text.properties(), [Property::new(2, "device=m0")]);
+
+    let array = cell
+        .elements()
+        .find_map(|e| e.as_ref().instance_array().cloned())
+        .expect("array not found");
+    assert_eq!((array.rows(), array.cols()), (2, 3));
+    assert_eq!(array.properties(), [Property::new(3, "array=x0")]);
+}
+
+#[test]
+fn test_gds_import_instance_properties() {
+    let gds_path = get_path("test_gds_import_instance_properties", "layout.gds");
+    let mut lib = gds::GdsLibrary::new("lib");
+    lib.structs.push(gds::GdsStruct::new("leaf"));
+    let mut top = gds::GdsStruct::new("top");
+    top.elems.push(
+        gds::GdsStructRef {
+            name: "leaf".into(),
+            xy: gds::GdsPoint::new(10, 20),
+            properties: vec![gds::GdsProperty {
+                attr: 3,
+                value: "inst=x0".into(),
+            }],
+            ..Default::default()
+        }
+        .into(),
+    );
+    lib.structs.push(top);
+    lib.save(&gds_path).expect("failed to write GDS file");
+
+    let ctx = Context::new(ExamplePdkA);
+    let cell_map = ctx
+        .read_gds(&gds_path)
+        .expect("failed to import GDS file")
+        .cells;
+    let inst = cell_map
+        .get("top")
+        .unwrap()
+        .elements()
+        .find_map(|e| e.as_ref().instance().cloned())
+        .expect("instance not found");
+    assert_eq!(inst.properties(), [Property::new(3, "inst=x0")]);
+}
@@ -32,6 +32,33 @@ use test_log::test;
 ))]
 pub struct BufferHardMacro;
 
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, Block, Layout)]
+#[substrate(io = "BufferIo")]
+#[substrate(layout(
+    source = "[
+        crate::paths::test_data(\"lef/sky130_tech.lef\"),
+        crate::paths::test_data(\"lef/buffer.lef\"),
+    ]",
+    name = "buffer",
+    fmt = "lef",
+    pdk = "Sky130OpenPdk"
+))]
+pub struct BufferLefHardMacro;
+
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, Block, Layout)]
+#[substrate(io = "BufferIo")]
+#[substrate(layout(
+    source = "crate::paths::test_data(\"def/buffer_row.def\")",
+    lef = "[
+        crate::paths::test_data(\"lef/sky130_tech.lef\"),
+        crate::paths::test_data(\"lef/buffer.lef\"),
+    ]",
+    name = "buffer_row",
+    fmt = "def",
+    pdk = "Sky130OpenPdk"
+))]
+pub struct BufferRowDefHardMacro;
+
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, Block, Schematic)]
 #[substrate(io = "BufferIo")]
 #[substrate(schematic(
@@ -136,3 +163,120 @@ fn export_inline_hard_macro() {
     let string = String::from_utf8(buf).unwrap();
     println!("Netlist:\n{}", string);
 }
+
+#[test]
+fn import_lef_hard_macro() {
+    use substrate::context::Context;
+    use substrate::geometry::bbox::Bbox;
+    use substrate::geometry::rect::Rect;
+    use substrate::io::NameBuf;
+    use substrate::pdk::layers::{HasPin, Layer};
+
+    // The PDK root is never read, since the macros are imported from LEF and DEF.
+    let ctx = Context::new(Sky130OpenPdk::new(""));
+    let flat = ctx.generate_layout(BufferLefHardMacro).cell().flatten();
+
+    let met1 = ctx.layers.met1.drawing.id();
+    let port = |name: &str| {
+        flat.top_ports()
+            .find(|port| port.name == NameBuf::from(name))
+            .unwrap_or_else(|| panic!("port `{name}` not found"))
+    };
+    assert_eq!(flat.top_ports().count(), 4);
+    let din = &port("din").geometry.primary;
+    assert_eq!(din.layer().drawing(), met1);
+    assert_eq!(din.bbox(), Some(Rect::from_sides(100, 1000, 500, 1400)));
+    assert_eq!(
+        port("vss").geometry.primary.bbox(),
+        Some(Rect::from_sides(0, -240, 2760, 240))
+    );
+
+    // The macro boundary is drawn from its size.
+    assert_eq!(
+        flat.shapes_on(ctx.layers.pr_boundary.id()),
+        &[Rect::from_sides(0, 0, 2760, 2720).into()]
+    );
+
+    ctx.write_layout(
+        BufferLefHardMacro,
+        crate::paths::get_path("import_lef_hard_macro", "layout.gds"),
+    )
+    .unwrap();
+}
+
+#[test]
+fn import_def_hard_macro() {
+    use substrate::context::Context;
+    use substrate::geometry::bbox::Bbox;
+    use substrate::geometry::rect::Rect;
+    use substrate::io::NameBuf;
+    use substrate::pdk::layers::{HasPin, Layer};
+
+    // The PDK root is never read, since the macros are imported from LEF and DEF.
+    let ctx = Context::new(Sky130OpenPdk::new(""));
+    let flat = ctx.generate_layout(BufferRowDefHardMacro).cell().flatten();
+
+    let met1 = ctx.layers.met1.drawing.id();
+    let met2 = ctx.layers.met2.drawing.id();
+    let port = |path: &[usize], name: &str| {
+        flat.ports
+            .iter()
+            .find(|port| port.path == path && port.name == NameBuf::from(name))
+            .unwrap_or_else(|| panic!("port `{name}` not found"))
+    };
+
+    let din = &port(&[], "din").geometry.primary;
+    assert_eq!(din.layer().drawing(), met2);
+    assert_eq!(din.bbox(), Some(Rect::from_sides(230, -640, 370, -500)));
+
+    // The second buffer is flipped about the y-axis and abuts the first.
+    assert_eq!(
+        port(&[0, 0], "din").geometry.primary.bbox(),
+        Some(Rect::from_sides(100, 1000, 500, 1400))
+    );
+    assert_eq!(
+        port(&[0, 1], "din").geometry.primary.bbox(),
+        Some(Rect::from_sides(5020, 1000, 5420, 1400))
+    );
+
+    let met1_shapes = flat.shapes_on(met1);
+    assert!(met1_shapes.contains(&Rect::from_sides(2390, 1130, 5290, 1270).into()));
+    assert!(met1_shapes.contains(&Rect::from_sides(0, 2480, 5520, 2960).into()));
+    assert!(flat
+        .shapes_on(met2)
+        .contains(&Rect::from_sides(230, -570, 370, 1270).into()));
+    assert_eq!(
+        flat.shapes_on(ctx.layers.via.drawing.id()),
+        &[
+            Rect::from_sides(225, 1125, 375, 1275).into(),
+            Rect::from_sides(2985, 1125, 3135, 1275).into(),
+        ]
+    );
+
+    ctx.write_layout(
+        BufferRowDefHardMacro,
+        crate::paths::get_path("import_def_hard_macro", "layout.gds"),
+    )
+    .unwrap();
+}
+
+#[test]
+fn import_def_rejects_invalid_units() {
+    use substrate::context::Context;
+
+    let ctx = Context::new(Sky130OpenPdk::new(""));
+    let lefs = [crate::paths::test_data("lef/sky130_tech.lef")];
+    for (i, (units, die_area)) in [(0, 10), (1, i64::MAX / 2)].into_iter().enumerate() {
+        let path = crate::paths::get_path("import_def_rejects_invalid_units", &format!("{i}.def"));
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(
+            &path,
+            format!(
+                "VERSION 5.8 ;\nDESIGN bad ;\nUNITS DISTANCE MICRONS {units} ;\n\
+                 DIEAREA ( 0 0 ) ( {die_area} {die_area} ) ;\nEND DESIGN\n"
+            ),
+        )
+        .unwrap();
+        assert!(ctx.read_def_cell(&path, &lefs, "bad").is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arcstr::ArcStr;
+use geometry::rect::Rect;
+use substrate::context::Context;
+use substrate::layout::element::RawCell;
+use substrate::layout::svg::{Color, FillPattern};
+use substrate::pdk::layer_map::{LayerMap, LayerMapEntry};
+use substrate::pdk::layers::{GdsLayerSpec, Layer};
+use test_log::test;
+
+use crate::paths::{get_path, test_data};
+use crate::shared::buffer::Buffer;
+use crate::shared::pdk::{ExamplePdkA, ExamplePdkB};
+
+#[test]
+fn test_parse_layer_maps() {
+    let map = LayerMap::load(test_data("layermap/example_b.map")).unwrap();
+    assert_eq!(map.entries.len(), 5);
+    let pin = map.entry_named("met1.pin").unwrap();
+    assert_eq!(pin.name, "met1");
+    assert_eq!(pin.purpose.as_deref(), Some("pin"));
+    assert_eq!(pin.gds, GdsLayerSpec(15, 15));
+    assert_eq!(
+        map.entry_for_gds(GdsLayerSpec(16, 30)).unwrap().full_name(),
+        "met2.drawing"
+    );
+
+    assert!(LayerMap::parse("met1 drawing 68\n").is_err());
+
+    let lyp = LayerMap::load(test_data("layermap/example.lyp")).unwrap();
+    let names = lyp
+        .entries
+        .iter()
+        .map(|entry| entry.full_name())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        ["poly.drawing", "met1.drawing", "met1.pin", "gds_69_20"]
+    );
+    let poly = lyp.entry_named("poly.drawing").unwrap();
+    assert_eq!(poly.gds, GdsLayerSpec(66, 20));
+    let display = poly.display.unwrap();
+    assert_eq!(display.color, Color::rgb(0xff, 0, 0));
+    assert_eq!(display.fill, FillPattern::BackHatch);
+    let pin = lyp.entry_named("met1.pin").unwrap().display.unwrap();
+    assert_eq!(pin.fill, FillPattern::Hollow);
+    assert!(!pin.visible);
+    assert_eq!(lyp.styles().layers.len(), 4);
+}
+
+#[test]
+fn test_stream_between_layer_numberings() {
+    let map_a = LayerMap::load(test_data("layermap/example_a.map")).unwrap();
+    let mut map_b = LayerMap::load(test_data("layermap/example_b.map")).unwrap();
+    let gds_path = get_path("test_stream_between_layer_numberings", "buffer_b.gds");
+
+    // Map A matches the native numbering of the PDK, so its names become aliases of PDK layers.
+    let ctx = Context::new(ExamplePdkA);
+    let ids = ctx.install_layer_map(&map_a);
+    assert_eq!(ids[0], ctx.layers.polya.id());
+    assert_eq!(
+        ctx.get_layer_by_name("met1.pin"),
+        Some(ctx.layers.met1a.pin.id())
+    );
+    // The buffer draws on marker layers that are not in map B.
+    assert!(ctx
+        .write_layout_with_layer_map(Buffer::new(5), &gds_path, &map_b)
+        .is_err());
+    for (i, name) in ["marker_1", "marker_2"].into_iter().enumerate() {
+        map_b
+            .entries
+            .push(LayerMapEntry::new(name, None, GdsLayerSpec(200, i as u8)));
+    }
+    ctx.write_layout_with_layer_map(Buffer::new(5), &gds_path, &map_b)
+        .expect("failed to write GDS");
+
+    let lib = gds::GdsLibrary::load(&gds_path).unwrap();
+    let layers = lib
+        .structs
+        .iter()
+        .flat_map(|s| s.elems.iter())
+        .filter_map(|elem| match elem {
+            gds::GdsElement::GdsBoundary(b) => Some((b.layer, b.datatype)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert!(layers.contains(&(13, 30)));
+    assert!(!layers.iter().any(|(layer, _)| *layer == 66 || *layer == 68));
+
+    let ctx = Context::new(ExamplePdkA);
+    ctx.install_layer_map(&map_a);
+    let cells = ctx
+        .read_gds_with_layer_map(&gds_path, &map_b)
+        .unwrap()
+        .cells;
+    let buffer = cells.get("buffer_5").unwrap();
+    assert!(buffer.port_named("din").is_some());
+    let flat = buffer.flatten();
+    assert_eq!(
+        flat.shapes_on(ctx.layers.polya.id()),
+        &[
+            Rect::from_sides(0, 0, 100, 200).into(),
+            Rect::from_sides(110, 0, 210, 200).into(),
+        ]
+    );
+
+    // Without the layer map, the layers are not recognized.
+    let ctx = Context::new(ExamplePdkA);
+    let cells = ctx.read_gds(&gds_path).unwrap().cells;
+    let flat = cells.get("buffer_5").unwrap().flatten();
+    assert!(flat.shapes_on(ctx.layers.polya.id()).is_empty());
+    assert!(ctx.get_gds_layer(GdsLayerSpec(13, 30)).is_some());
+}
+
+#[test]
+fn test_name_imported_layers() {
+    let gds_path = get_path("test_name_imported_layers", "buffer.gds");
+    Context::new(ExamplePdkA)
+        .write_layout(Buffer::new(5), &gds_path)
+        .expect("failed to write GDS");
+
+    // The layers of PDK A are unknown to PDK B, and are named by the layer properties file.
+    let lyp = LayerMap::load(test_data("layermap/example.lyp")).unwrap();
+    let ctx = Context::new(ExamplePdkB);
+    let cells = ctx.read_gds_with_layer_map(&gds_path, &lyp).unwrap().cells;
+    let poly = ctx.get_layer_by_name("poly.drawing").unwrap();
+    assert_eq!(ctx.get_gds_layer(GdsLayerSpec(66, 20)), Some(poly));
+    let flat = cells.get("buffer_5").unwrap().flatten();
+    assert_eq!(flat.shapes_on(poly).len(), 2);
+    let unmapped = ctx.get_layer_by_name("gds_68_5").unwrap();
+    let uses_layer = |cells: &HashMap<ArcStr, Arc<RawCell>>, layer| {
+        cells.values().flat_map(|cell| cell.elements()).any(|e| {
+            let e = e.as_ref();
+            e.shape().map(|s| s.layer()) == Some(layer)
+                || e.text().map(|t| t.layer()) == Some(layer)
+        })
+    };
+    assert!(uses_layer(&cells, unmapped));
+
+    // Importing again reuses the layers created for unmapped GDS layers.
+    let cells = ctx.read_gds_with_layer_map(&gds_path, &lyp).unwrap().cells;
+    assert!(uses_layer(&cells, unmapped));
+
+    let styles = lyp
+        .styles()
+        .resolve(|name| ctx.get_layer_by_name(name))
+        .unwrap();
+    assert_eq!(styles.layers[0].layer, poly);
+}
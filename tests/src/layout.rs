@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use substrate::block::Block;
 use substrate::context::Context;
 use substrate::geometry::transform::{Transform, TransformMut, Translate, TranslateMut};
+use substrate::io::NameBuf;
 use substrate::layout::element::Shape;
 use substrate::layout::tiling::{GridTile, GridTiler, Tile};
 use substrate::layout::{ExportsLayoutData, Instance, Layout, LayoutData};
@@ -211,3 +212,109 @@ pub enum MyData {
     Tuple(Instance<Inverter>),
     Strukt { val: Instance<Inverter> },
 }
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Block, Serialize, Deserialize, Hash)]
+#[substrate(io = "()")]
+pub struct OverlappingInverters;
+
+impl ExportsLayoutData for OverlappingInverters {
+    type Data = ();
+}
+
+impl Layout<ExamplePdkA> for OverlappingInverters {
+    fn layout(
+        &self,
+        _io: &mut <<Self as substrate::block::Block>::Io as substrate::io::LayoutType>::Builder,
+        cell: &mut substrate::layout::CellBuilder<ExamplePdkA, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        let inv1 = cell.generate(Inverter::new(5));
+        let inv2 = inv1.clone().translate(Point::new(50, 0));
+        cell.draw(inv1)?;
+        cell.draw(inv2)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn flatten_applies_instance_transformations() {
+    let ctx = Context::new(ExamplePdkA);
+    let handle = ctx.generate_layout(Buffer::new(5));
+    let flat = handle.cell().flatten();
+
+    let poly = *ctx.layers.polya.as_ref();
+    assert_eq!(
+        flat.shapes_on(poly),
+        &[
+            Rect::from_sides(0, 0, 100, 200).into(),
+            Rect::from_sides(110, 0, 210, 200).into(),
+        ]
+    );
+
+    // The buffer and each of its two inverters have 4 ports.
+    assert_eq!(flat.ports.len(), 12);
+    assert_eq!(flat.top_ports().count(), 4);
+    let inv2_dout = flat
+        .ports
+        .iter()
+        .find(|port| port.path == [1] && port.name == NameBuf::from("dout"))
+        .expect("inverter port not found");
+    assert_eq!(
+        inv2_dout.geometry.primary.bbox(),
+        Some(Rect::from_sides(185, 75, 210, 125))
+    );
+}
+
+#[test]
+fn flatten_merges_overlapping_shapes() {
+    let ctx = Context::new(ExamplePdkA);
+    let handle = ctx.generate_layout(OverlappingInverters);
+    let flat = handle.cell().flatten();
+
+    let poly = *ctx.layers.polya.as_ref();
+    assert_eq!(flat.shapes_on(poly).len(), 2);
+
+    let merged = flat.merged();
+    assert_eq!(
+        merged.shapes_on(poly),
+        &[Rect::from_sides(0, 0, 150, 200).into()]
+    );
+}
+
+#[test]
+fn spatial_index_queries_hierarchy() {
+    let ctx = Context::new(ExamplePdkA);
+    let handle = ctx.generate_layout(BufferN::new(5, 3));
+    let index = handle.cell().spatial_index();
+    let poly = *ctx.layers.polya.as_ref();
+
+    let mut hits = index
+        .shapes_intersecting(poly, Rect::from_sides(200, 50, 230, 60))
+        .map(|shape| (shape.path.clone(), shape.shape.bbox()))
+        .collect::<Vec<_>>();
+    hits.sort();
+    assert_eq!(
+        hits,
+        vec![
+            (vec![0, 1], Some(Rect::from_sides(110, 0, 210, 200))),
+            (vec![1, 0], Some(Rect::from_sides(220, 0, 320, 200))),
+        ]
+    );
+    assert_eq!(
+        index
+            .shapes_intersecting(poly, Rect::from_sides(211, 50, 219, 60))
+            .count(),
+        0
+    );
+
+    let nearest = index
+        .nearest_shape(poly, Point::new(650, 300))
+        .expect("no nearest shape");
+    assert_eq!(nearest.path, vec![2, 1]);
+
+    let mut paths = index
+        .instances_intersecting(Rect::from_sides(250, 0, 260, 10))
+        .map(|inst| inst.path.clone())
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(paths, vec![vec![1], vec![1, 0]]);
+}
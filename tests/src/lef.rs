@@ -0,0 +1,136 @@
+use geometry::rect::Rect;
+use lefdef::{LefLibrary, LefPoint, LefShape, LefShapeKind, PinDirection};
+use serde::{Deserialize, Serialize};
+use substrate::block::Block;
+use substrate::context::Context;
+use substrate::io::IoShape;
+use substrate::layout::element::Shape;
+use substrate::layout::{ExportsLayoutData, Layout};
+use substrate::pdk::layers::Layer;
+use test_log::test;
+
+use crate::extract::WireIo;
+use crate::paths::get_path;
+use crate::shared::buffer::{BufferNxM, Inverter};
+use crate::shared::pdk::ExamplePdkA;
+
+fn rect(layer: &str, x0: f64, y0: f64, x1: f64, y1: f64) -> LefShape {
+    LefShape {
+        layer: layer.into(),
+        kind: LefShapeKind::Rect(LefPoint::new(x0, y0), LefPoint::new(x1, y1)),
+    }
+}
+
+#[test]
+fn test_lef_export_inverter() {
+    let lef_path = get_path("test_lef_export_inverter", "inverter.lef");
+    let ctx = Context::new(ExamplePdkA);
+    ctx.write_lef(Inverter::new(5), &lef_path)
+        .expect("failed to write LEF");
+
+    let lef = std::fs::read_to_string(&lef_path).unwrap();
+    assert!(lef.ends_with("END inverter_5\n\nEND LIBRARY\n"));
+
+    let lib = LefLibrary::parse(&lef).expect("failed to parse LEF");
+    let m = lib.macro_named("inverter_5").expect("macro not found");
+    assert_eq!(m.origin, LefPoint::new(0., 0.));
+    assert_eq!(m.size, Some((0.1, 0.2)));
+
+    let pins = m
+        .pins
+        .iter()
+        .map(|pin| (pin.name.as_str(), pin.direction))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        pins,
+        [
+            ("vdd", Some(PinDirection::InOut)),
+            ("vss", Some(PinDirection::InOut)),
+            ("din", Some(PinDirection::Input)),
+            ("dout", Some(PinDirection::Output)),
+        ]
+    );
+    let din = m.pins.iter().find(|pin| pin.name == "din").unwrap();
+    assert_eq!(din.ports.len(), 1);
+    assert_eq!(
+        din.ports[0].shapes,
+        [rect("met_1_drawing_a", 0., 0.075, 0.025, 0.125)]
+    );
+    assert_eq!(m.obs.shapes, [rect("poly_a", 0., 0., 0.1, 0.2)]);
+}
+
+#[test]
+fn test_lef_export_array_ports() {
+    let lef_path = get_path("test_lef_export_array_ports", "buffer.lef");
+    let ctx = Context::new(ExamplePdkA);
+    ctx.write_lef(BufferNxM::new(5, 2, 2), &lef_path)
+        .expect("failed to write LEF");
+
+    let lib = LefLibrary::load(&lef_path).expect("failed to parse LEF");
+    let m = lib.macro_named("buffer_5_2x2").expect("macro not found");
+    let mut pins = m
+        .pins
+        .iter()
+        .map(|pin| pin.name.as_str())
+        .collect::<Vec<_>>();
+    pins.sort();
+    // Array elements are written as bus bits.
+    assert_eq!(
+        pins,
+        ["din[0]", "din[1]", "dout[0]", "dout[1]", "vdd", "vss"]
+    );
+}
+
+/// A metal 1 block whose pin `a` overlaps a blockage.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Block, Serialize, Deserialize, Hash)]
+#[substrate(io = "WireIo")]
+pub struct BlockedPinExample;
+
+impl ExportsLayoutData for BlockedPinExample {
+    type Data = ();
+}
+
+impl Layout<ExamplePdkA> for BlockedPinExample {
+    fn layout(
+        &self,
+        io: &mut <<Self as substrate::block::Block>::Io as substrate::io::LayoutType>::Builder,
+        cell: &mut substrate::layout::CellBuilder<ExamplePdkA, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        let met1 = cell.ctx.layers.met1a.drawing.id();
+        cell.draw(Shape::new(met1, Rect::from_sides(0, 0, 100, 100)))?;
+        cell.draw_blockage(Shape::new(met1, Rect::from_sides(100, 0, 200, 100)));
+        io.a.set(IoShape::with_layers(
+            cell.ctx.layers.met1a,
+            Rect::from_sides(150, 0, 200, 100),
+        ));
+        io.vss.set(IoShape::with_layers(
+            cell.ctx.layers.met1a,
+            Rect::from_sides(0, 0, 50, 100),
+        ));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_lef_export_excludes_pins_from_blockages() {
+    let lef_path = get_path(
+        "test_lef_export_excludes_pins_from_blockages",
+        "blocked.lef",
+    );
+    let ctx = Context::new(ExamplePdkA);
+    ctx.write_lef(BlockedPinExample, &lef_path)
+        .expect("failed to write LEF");
+
+    let lib = LefLibrary::load(&lef_path).expect("failed to parse LEF");
+    let m = lib
+        .macro_named("blocked_pin_example")
+        .expect("macro not found");
+    assert_eq!(m.size, Some((0.2, 0.1)));
+    let a = m.pins.iter().find(|pin| pin.name == "a").unwrap();
+    assert_eq!(
+        a.ports[0].shapes,
+        [rect("met_1_drawing_a", 0.15, 0., 0.2, 0.1)]
+    );
+    // The drawn shape and blockage are merged, and neither pin is obstructed.
+    assert_eq!(m.obs.shapes, [rect("met_1_drawing_a", 0.05, 0., 0.15, 0.1)]);
+}
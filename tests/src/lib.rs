@@ -6,21 +6,42 @@ pub mod bsub;
 #[cfg(test)]
 pub mod cache;
 pub mod derive;
+#[cfg(test)]
+pub mod drc;
+#[cfg(test)]
+pub mod execute;
 pub mod external;
 #[cfg(test)]
+pub mod extract;
+#[cfg(test)]
 pub mod gds;
 pub mod hard_macro;
 #[cfg(test)]
+pub mod layer_map;
+#[cfg(test)]
 pub mod layout;
 #[cfg(test)]
+pub mod lef;
+#[cfg(test)]
 pub mod netlist;
+#[cfg(test)]
+pub mod oasis;
 pub mod paths;
 #[cfg(test)]
 pub mod pdk;
 #[cfg(test)]
+pub mod route;
+#[cfg(test)]
 pub mod schematic;
 #[cfg(test)]
 pub mod scir;
 pub mod shared;
 #[cfg(test)]
 pub mod sim;
+#[cfg(test)]
+#[cfg(unix)]
+pub mod slurm;
+#[cfg(test)]
+pub mod svg;
+#[cfg(test)]
+pub mod via;
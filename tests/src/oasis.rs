@@ -0,0 +1,75 @@
+use geometry::prelude::{Bbox, Point, Rect};
+use substrate::context::Context;
+use test_log::test;
+
+use crate::gds::{ArrayExample, PathExample};
+use crate::paths::get_path;
+use crate::shared::pdk::ExamplePdkA;
+
+#[test]
+fn test_oasis_array_round_trip() {
+    let oas_path = get_path("test_oasis_array_round_trip", "layout.oas");
+    let ctx = Context::new(ExamplePdkA);
+    ctx.write_layout_oasis(ArrayExample, &oas_path)
+        .expect("failed to write layout");
+    let expected = ctx.generate_layout(ArrayExample).cell().flatten();
+
+    let lib = oasis::OasisLibrary::load(&oas_path).expect("failed to load OASIS file");
+    assert_eq!(lib.unit, 1000.);
+    let top = lib
+        .cells
+        .iter()
+        .find(|cell| cell.name == "array_example")
+        .expect("top cell not found");
+    let repetitions = top
+        .elements
+        .iter()
+        .filter_map(|elem| match elem {
+            oasis::OasisElement::Placement(p) => p.repetition.clone(),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(repetitions.len(), 2);
+
+    let ctx = Context::new(ExamplePdkA);
+    let cell_map = ctx
+        .read_oasis(&oas_path)
+        .expect("failed to import OASIS file")
+        .cells;
+    let cell = cell_map.get("array_example").unwrap();
+    let arrays = cell
+        .elements()
+        .filter_map(|e| e.as_ref().instance_array())
+        .collect::<Vec<_>>();
+    assert_eq!(arrays.len(), 2);
+    assert_eq!(arrays[1].row_pitch(), Point::new(0, -70));
+    assert_eq!(arrays[1].col_pitch(), Point::new(120, 0));
+    assert_eq!(cell.bbox(), Some(Rect::from_sides(0, -130, 460, 50)));
+
+    let met2a = *ctx.layers.met2a.as_ref();
+    let mut shapes = cell.flatten().shapes_on(met2a).to_vec();
+    let mut expected = expected.shapes_on(met2a).to_vec();
+    shapes.sort_by_key(|s| s.bbox().map(|r| (r.bot(), r.left())));
+    expected.sort_by_key(|s| s.bbox().map(|r| (r.bot(), r.left())));
+    assert_eq!(shapes.len(), 10);
+    assert_eq!(shapes, expected);
+}
+
+#[test]
+fn test_oasis_round_path_export() {
+    let oas_path = get_path("test_oasis_round_path_export", "layout.oas");
+    let ctx = Context::new(ExamplePdkA);
+    ctx.write_layout_oasis(PathExample, &oas_path)
+        .expect("failed to write layout");
+
+    let lib = oasis::OasisLibrary::load(&oas_path).expect("failed to load OASIS file");
+    let top = lib
+        .cells
+        .iter()
+        .find(|cell| cell.name == "path_example")
+        .expect("top cell not found");
+    let count = |f: fn(&oasis::OasisElement) -> bool| top.elements.iter().filter(|e| f(e)).count();
+    // The round-ended path is written as polygons.
+    assert_eq!(count(|e| matches!(e, oasis::OasisElement::Path(_))), 3);
+    assert!(count(|e| matches!(e, oasis::OasisElement::Polygon(_))) > 0);
+}
@@ -0,0 +1,207 @@
+use geometry::bbox::Bbox;
+use geometry::dir::Dir;
+use geometry::rect::Rect;
+use geometry::transform::Transformation;
+use serde::{Deserialize, Serialize};
+use substrate::block::Block;
+use substrate::context::Context;
+use substrate::layout::connectivity::{Conductor, Connectivity, Via};
+use substrate::layout::element::{Shape, Text};
+use substrate::layout::route::{Router, RouterConfig, RoutingLayer, ViaRule};
+use substrate::layout::{ExportsLayoutData, Layout};
+use substrate::pdk::layers::Layer;
+
+use crate::extract::CutLayers;
+use crate::shared::pdk::ExamplePdkA;
+
+/// A wall on metal 1 that routes must go around.
+fn wall() -> Rect {
+    Rect::from_sides(900, -300, 1100, 1300)
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Block, Serialize, Deserialize, Hash)]
+#[substrate(io = "()")]
+pub struct RouteExample;
+
+impl ExportsLayoutData for RouteExample {
+    type Data = ();
+}
+
+impl Layout<ExamplePdkA> for RouteExample {
+    fn layout(
+        &self,
+        _io: &mut <<Self as substrate::block::Block>::Io as substrate::io::LayoutType>::Builder,
+        cell: &mut substrate::layout::CellBuilder<ExamplePdkA, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        let cuts = cell.ctx.install_layers::<CutLayers>();
+        let met1 = cell.ctx.layers.met1a.drawing.id();
+        let met2 = cell.ctx.layers.met2a.id();
+
+        let pins = [
+            ("a", 0, 0),
+            ("a", 2000, 1000),
+            ("b", 0, 600),
+            ("b", 2000, 400),
+        ]
+        .map(|(name, x, y)| {
+            (
+                name,
+                Shape::new(met1, Rect::from_sides(x - 50, y - 50, x + 50, y + 50)),
+            )
+        });
+        for (name, pin) in pins.iter() {
+            cell.draw(pin.clone())?;
+            let center = pin.shape().bbox().unwrap().center();
+            cell.draw(Text::new(
+                cell.ctx.layers.met1a.label.id(),
+                *name,
+                Transformation::translate(center.x as f64, center.y as f64),
+            ))?;
+        }
+        cell.draw_blockage(Shape::new(met1, wall()));
+
+        let config = RouterConfig::new()
+            .with_layer(RoutingLayer {
+                layer: met1,
+                dir: Dir::Horiz,
+                pitch: 200,
+                offset: 0,
+                width: 100,
+                spacing: 100,
+            })
+            .with_layer(RoutingLayer {
+                layer: met2,
+                dir: Dir::Vert,
+                pitch: 200,
+                offset: 0,
+                width: 100,
+                spacing: 100,
+            })
+            .with_via(ViaRule {
+                cut: cuts.via12.id(),
+                size: 60,
+                enclosure: 20,
+                spacing: 100,
+            });
+        let mut router = cell.router(config);
+        for name in ["a", "b"] {
+            let route = router.route(
+                pins.iter()
+                    .filter(|(n, _)| *n == name)
+                    .map(|(_, pin)| pin.clone()),
+            )?;
+            cell.draw(route)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn router_connects_pins_around_blockages() {
+    let ctx = Context::new(ExamplePdkA);
+    let cuts = ctx.install_layers::<CutLayers>();
+    let met1 = ctx.layers.met1a.drawing.id();
+    let met2 = ctx.layers.met2a.id();
+
+    let handle = ctx.generate_layout(RouteExample);
+    let cell = handle.cell();
+
+    assert_eq!(
+        cell.spatial_index()
+            .shapes_intersecting(met1, wall())
+            .count(),
+        0
+    );
+
+    let connectivity = Connectivity::new()
+        .with_conductor(Conductor {
+            layer: met1,
+            pins: Vec::new(),
+            labels: vec![ctx.layers.met1a.label.id()],
+        })
+        .with_conductor(Conductor {
+            layer: met2,
+            pins: Vec::new(),
+            labels: Vec::new(),
+        })
+        .with_via(Via {
+            cut: cuts.via12.id(),
+            bot: met1,
+            top: met2,
+        });
+    let extracted = cell.extract(&connectivity);
+    let mut nets = extracted.nets().to_vec();
+    nets.sort();
+    assert_eq!(nets, ["a", "b"]);
+}
+
+/// Returns `true` if the two shapes are on the same layer and overlap with positive area.
+fn overlaps(a: &Shape, b: &Shape) -> bool {
+    let (ra, rb) = (a.shape().bbox().unwrap(), b.shape().bbox().unwrap());
+    a.layer() == b.layer()
+        && ra.left() < rb.right()
+        && rb.left() < ra.right()
+        && ra.bot() < rb.top()
+        && rb.bot() < ra.top()
+}
+
+#[test]
+fn router_keeps_nets_with_off_grid_pins_apart() {
+    let ctx = Context::new(ExamplePdkA);
+    let cuts = ctx.install_layers::<CutLayers>();
+    let met1 = ctx.layers.met1a.drawing.id();
+    let met2 = ctx.layers.met2a.id();
+
+    let config = RouterConfig::new()
+        .with_layer(RoutingLayer {
+            layer: met1,
+            dir: Dir::Horiz,
+            pitch: 200,
+            offset: 0,
+            width: 20,
+            spacing: 20,
+        })
+        .with_layer(RoutingLayer {
+            layer: met2,
+            dir: Dir::Vert,
+            pitch: 200,
+            offset: 0,
+            width: 20,
+            spacing: 20,
+        })
+        .with_via(ViaRule {
+            cut: cuts.via12.id(),
+            size: 10,
+            enclosure: 5,
+            spacing: 20,
+        });
+
+    // Neither `a[0]` nor `b[0]` contains a grid point. The shortest stub from `a[0]`,
+    // which leads to the grid point at (0, 200), would cross `b[0]`.
+    let pin = |x0, y0, x1, y1| Shape::new(met1, Rect::from_sides(x0, y0, x1, y1));
+    let a = [pin(60, 260, 140, 300), pin(990, 590, 1010, 610)];
+    let b = [pin(30, 220, 50, 240), pin(990, -210, 1010, -190)];
+
+    let mut router = Router::new(config);
+    for pin in a.iter().chain(b.iter()) {
+        router.add_obstacle(pin);
+    }
+    let route_a = router.route(a.clone()).unwrap();
+    let route_b = router.route(b.clone()).unwrap();
+
+    for (shape, other) in route_a
+        .shapes()
+        .iter()
+        .flat_map(|s| b.iter().chain(route_b.shapes()).map(move |o| (s, o)))
+    {
+        assert!(!overlaps(shape, other), "{shape:?} overlaps {other:?}");
+    }
+    for (shape, other) in route_b
+        .shapes()
+        .iter()
+        .flat_map(|s| a.iter().map(move |o| (s, o)))
+    {
+        assert!(!overlaps(shape, other), "{shape:?} overlaps {other:?}");
+    }
+}
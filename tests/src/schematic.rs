@@ -8,8 +8,9 @@ use substrate::type_dispatch::impl_dispatch;
 use substrate::{
     block::Block,
     context::Context,
-    io::{HasNameTree, InOut, NameTree, Output, Signal},
+    io::{HasNameTree, InOut, NameTree, NodePath, Output, Signal},
     schematic::{conv::RawLib, ExportsSchematicData, Schematic},
+    simulation::data::SaveSpec,
 };
 
 use crate::shared::{
@@ -209,6 +210,58 @@ fn nested_node_naming() {
     );
 }
 
+#[test]
+fn save_specs_resolve_to_paths() {
+    let ctx = Context::new(ExamplePdkA);
+    let handle = ctx.generate_schematic(Buffer::new(5));
+    let cell = handle.cell();
+    let lib = ctx.export_scir(Buffer::new(5)).unwrap();
+
+    let names = |paths: &[NodePath]| {
+        paths
+            .iter()
+            .map(|path| lib.node_path_name(path).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    // Nodes connected through instance ports are only listed once.
+    // The transistor bulks are left unconnected within each inverter.
+    let nodes = lib.node_paths(&SaveSpec::nested(cell.path())).unwrap();
+    assert_eq!(
+        names(&nodes),
+        [
+            "din",
+            "dout",
+            "inst0.xinst0_b",
+            "inst0.xinst1_b",
+            "inst1.xinst0_b",
+            "inst1.xinst1_b",
+            "vdd",
+            "vss",
+            "x"
+        ]
+    );
+
+    let inv1 = cell.data().inv1;
+    let ports = lib.node_paths(&SaveSpec::ports(inv1.path())).unwrap();
+    assert_eq!(ports.len(), 4);
+    assert!(ports.contains(&inv1.terminals().din.path()));
+    assert!(ports.contains(&inv1.terminals().dout.path()));
+
+    let pattern = SaveSpec::pattern("^x$").unwrap();
+    let nodes = lib.node_paths(&pattern).unwrap();
+    assert_eq!(names(&nodes), ["x"]);
+
+    // Both inverters have 4 terminals, and each contains 2 transistors with 4 terminals.
+    let terminals = lib.terminal_paths(&SaveSpec::nested(cell.path())).unwrap();
+    assert_eq!(terminals.len(), 24);
+
+    let gates = SaveSpec::pattern(r"^inst\d+\.inst\d+\.g$").unwrap();
+    let terminals = lib.terminal_paths(&gates).unwrap();
+    assert_eq!(terminals.len(), 4);
+    assert!(terminals.contains(&inv1.data().pmos.terminals().g.path()));
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, Hash, Eq, PartialEq)]
 pub struct Block1;
 
@@ -7,6 +7,11 @@ use spectre::Spectre;
 use substrate::block::Block;
 use substrate::context::Context;
 use substrate::io::MosIo;
+use substrate::layout::connectivity::{Conductor, Connectivity};
+use substrate::layout::drc::{Rule, RuleDeck};
+use substrate::layout::parasitics::{LayerParasitics, Parasitics};
+use substrate::pdk::corner::InstallCorner;
+use substrate::pdk::layers::Layer;
 use substrate::pdk::Pdk;
 use substrate::schematic::{
     ExportsSchematicData, PrimitiveDevice, PrimitiveDeviceKind, PrimitiveNode, Schematic,
@@ -21,6 +26,89 @@ pub struct ExamplePdkA;
 impl Pdk for ExamplePdkA {
     type Layers = ExamplePdkALayers;
     type Corner = ExampleCorner;
+
+    fn drc_rules(&self, layers: &Self::Layers) -> RuleDeck {
+        let poly = layers.polya.id();
+        let met1 = layers.met1a.drawing.id();
+        let met2 = layers.met2a.id();
+        RuleDeck::new()
+            .with_rule(
+                "poly.w.1",
+                Rule::Width {
+                    layer: poly,
+                    min: 50,
+                },
+            )
+            .with_rule(
+                "poly.s.1",
+                Rule::Spacing {
+                    layer: poly,
+                    min: 20,
+                },
+            )
+            .with_rule(
+                "poly.n.1",
+                Rule::Notch {
+                    layer: poly,
+                    min: 20,
+                },
+            )
+            .with_rule(
+                "poly.ext.met2",
+                Rule::Extension {
+                    layer: poly,
+                    over: met2,
+                    min: 20,
+                },
+            )
+            .with_rule(
+                "met2.enc.met1",
+                Rule::Enclosure {
+                    inner: met1,
+                    outer: met2,
+                    min: 10,
+                },
+            )
+            .with_rule(
+                "met2.a.1",
+                Rule::Area {
+                    layer: met2,
+                    min: 3000,
+                },
+            )
+    }
+
+    fn connectivity(&self, layers: &Self::Layers) -> Connectivity {
+        Connectivity::new()
+            .with_conductor(Conductor {
+                layer: layers.polya.id(),
+                pins: Vec::new(),
+                labels: Vec::new(),
+            })
+            .with_conductor(Conductor {
+                layer: layers.met1a.drawing.id(),
+                pins: vec![layers.met1a.pin.id()],
+                labels: vec![layers.met1a.label.id()],
+            })
+            .with_conductor(Conductor {
+                layer: layers.met2a.id(),
+                pins: Vec::new(),
+                labels: Vec::new(),
+            })
+    }
+
+    fn parasitics(&self, layers: &Self::Layers) -> Parasitics {
+        Parasitics::new().with_layer(LayerParasitics {
+            layer: layers.met1a.drawing.id(),
+            sheet_resistance: 0.1,
+            area_capacitance: 1e-18,
+            fringe_capacitance: 0.,
+        })
+    }
+}
+
+impl InstallCorner<Ngspice> for ExamplePdkA {
+    fn install_corner(&self, _corner: &Self::Corner, _opts: &mut ngspice::Options) {}
 }
 
 pub struct ExamplePdkB;
@@ -1,14 +1,20 @@
+use std::path::Path;
+
 use approx::relative_eq;
+use cache::multi::MultiCache;
 use ngspice::blocks::Vsource;
-use ngspice::tran::{Tran, TranCurrent, TranVoltage};
+use ngspice::tran::{Tran, TranCurrent, TranCurrents, TranVoltage, TranVoltages};
 use ngspice::{Ngspice, Options};
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 use sky130pdk::Sky130OpenPdk;
 use substrate::block::Block;
+use substrate::cache::Cache;
+use substrate::context::Context;
+use substrate::execute::RecordingExecutor;
 use substrate::io::{SchematicType, Signal, TestbenchIo};
 use substrate::schematic::{Cell, ExportsSchematicData, Instance, SchematicData, SimCellBuilder};
-use substrate::simulation::data::{FromSaved, Save};
+use substrate::simulation::data::{FromSaved, Save, SaveSpec};
 use substrate::simulation::{
     HasSimSchematic, SimController, SimulationContext, Simulator, Testbench,
 };
@@ -17,93 +23,103 @@ use test_log::test;
 use crate::paths::get_path;
 use crate::shared::pdk::sky130_open_ctx;
 
-#[test]
-fn ngspice_can_save_voltages_and_currents() {
-    #[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Block)]
-    #[substrate(io = "TestbenchIo")]
-    struct ResistorTb;
-
-    #[derive(SchematicData)]
-    struct ResistorTbData {
-        #[substrate(nested)]
-        r1: Instance<ngspice::blocks::Resistor>,
-        #[substrate(nested)]
-        r2: Instance<ngspice::blocks::Resistor>,
-        #[substrate(nested)]
-        r3: Instance<ngspice::blocks::Resistor>,
-    }
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Block)]
+#[substrate(io = "TestbenchIo")]
+struct ResistorTb;
 
-    impl ExportsSchematicData for ResistorTb {
-        type Data = ResistorTbData;
-    }
+#[derive(SchematicData)]
+struct ResistorTbData {
+    #[substrate(nested)]
+    r1: Instance<ngspice::blocks::Resistor>,
+    #[substrate(nested)]
+    r2: Instance<ngspice::blocks::Resistor>,
+    #[substrate(nested)]
+    r3: Instance<ngspice::blocks::Resistor>,
+}
 
-    impl HasSimSchematic<Sky130OpenPdk, Ngspice> for ResistorTb {
-        fn schematic(
-            &self,
-            io: &<<Self as Block>::Io as SchematicType>::Bundle,
-            cell: &mut SimCellBuilder<Sky130OpenPdk, Ngspice, Self>,
-        ) -> substrate::error::Result<Self::Data> {
-            let vdd = cell.signal("vdd", Signal);
-            let r1 = cell.instantiate_tb(ngspice::blocks::Resistor(dec!(100)));
-            let r2 = cell.instantiate_tb(ngspice::blocks::Resistor(dec!(100)));
-            let r3 = cell.instantiate_tb(ngspice::blocks::Resistor(dec!(100)));
-
-            cell.connect(r1.io().p, vdd);
-            cell.connect(r1.io().n, r2.io().p);
-            cell.connect(r2.io().n, io.vss);
-            cell.connect(r1.io().n, r3.io().p);
-            cell.connect(r3.io().n, io.vss);
-
-            let vsource = cell.instantiate_tb(Vsource::dc(dec!(1.8)));
-            cell.connect(vsource.io().p, vdd);
-            cell.connect(vsource.io().n, io.vss);
-
-            Ok(ResistorTbData { r1, r2, r3 })
-        }
-    }
+impl ExportsSchematicData for ResistorTb {
+    type Data = ResistorTbData;
+}
 
-    #[derive(FromSaved, Serialize, Deserialize)]
-    struct ResistorTbOutput {
-        r1: TranCurrent,
-        r2: TranCurrent,
-        r3: TranCurrent,
-        vout: TranVoltage,
-        r3_terminal: TranCurrent,
+impl HasSimSchematic<Sky130OpenPdk, Ngspice> for ResistorTb {
+    fn schematic(
+        &self,
+        io: &<<Self as Block>::Io as SchematicType>::Bundle,
+        cell: &mut SimCellBuilder<Sky130OpenPdk, Ngspice, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        resistor_tb_schematic(io, cell)
     }
+}
+
+/// Draws three resistors driven by a 1.8V source.
+fn resistor_tb_schematic<T: Block>(
+    io: &<TestbenchIo as SchematicType>::Bundle,
+    cell: &mut SimCellBuilder<Sky130OpenPdk, Ngspice, T>,
+) -> substrate::error::Result<ResistorTbData> {
+    let vdd = cell.signal("vdd", Signal);
+    let r1 = cell.instantiate_tb(ngspice::blocks::Resistor(dec!(100)));
+    let r2 = cell.instantiate_tb(ngspice::blocks::Resistor(dec!(100)));
+    let r3 = cell.instantiate_tb(ngspice::blocks::Resistor(dec!(100)));
 
-    impl Save<Ngspice, Tran, &Cell<ResistorTb>> for ResistorTbOutput {
-        fn save(
-            ctx: &SimulationContext,
-            to_save: &Cell<ResistorTb>,
-            opts: &mut <Ngspice as Simulator>::Options,
-        ) -> Self::Key {
-            Self::Key {
-                r1: TranCurrent::save(ctx, to_save.data().r1, opts),
-                r2: TranCurrent::save(ctx, to_save.data().r2, opts),
-                r3: TranCurrent::save(ctx, to_save.data().r3, opts),
-                vout: TranVoltage::save(ctx, to_save.data().r1.terminals().n, opts),
-                r3_terminal: TranCurrent::save(ctx, to_save.data().r3.terminals().p, opts),
-            }
+    cell.connect(r1.io().p, vdd);
+    cell.connect(r1.io().n, r2.io().p);
+    cell.connect(r2.io().n, io.vss);
+    cell.connect(r1.io().n, r3.io().p);
+    cell.connect(r3.io().n, io.vss);
+
+    let vsource = cell.instantiate_tb(Vsource::dc(dec!(1.8)));
+    cell.connect(vsource.io().p, vdd);
+    cell.connect(vsource.io().n, io.vss);
+
+    Ok(ResistorTbData { r1, r2, r3 })
+}
+
+#[derive(FromSaved, Serialize, Deserialize)]
+struct ResistorTbOutput {
+    r1: TranCurrent,
+    r2: TranCurrent,
+    r3: TranCurrent,
+    vout: TranVoltage,
+    r3_terminal: TranCurrent,
+}
+
+impl<T: ExportsSchematicData<Data = ResistorTbData>> Save<Ngspice, Tran, &Cell<T>>
+    for ResistorTbOutput
+{
+    fn save(
+        ctx: &SimulationContext,
+        to_save: &Cell<T>,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::Key {
+        Self::Key {
+            r1: TranCurrent::save(ctx, to_save.data().r1, opts),
+            r2: TranCurrent::save(ctx, to_save.data().r2, opts),
+            r3: TranCurrent::save(ctx, to_save.data().r3, opts),
+            vout: TranVoltage::save(ctx, to_save.data().r1.terminals().n, opts),
+            r3_terminal: TranCurrent::save(ctx, to_save.data().r3.terminals().p, opts),
         }
     }
+}
 
-    impl Testbench<Sky130OpenPdk, Ngspice> for ResistorTb {
-        type Output = ResistorTbOutput;
+impl Testbench<Sky130OpenPdk, Ngspice> for ResistorTb {
+    type Output = ResistorTbOutput;
 
-        fn run(&self, sim: SimController<Sky130OpenPdk, Ngspice, Self>) -> Self::Output {
-            sim.simulate(
-                Options::default(),
-                None,
-                Tran {
-                    step: dec!(2e-10),
-                    stop: dec!(2e-9),
-                    ..Default::default()
-                },
-            )
-            .expect("failed to run simulation")
-        }
+    fn run(&self, sim: SimController<Sky130OpenPdk, Ngspice, Self>) -> Self::Output {
+        sim.simulate(
+            Options::default(),
+            None,
+            Tran {
+                step: dec!(2e-10),
+                stop: dec!(2e-9),
+                ..Default::default()
+            },
+        )
+        .expect("failed to run simulation")
     }
+}
 
+#[test]
+fn ngspice_can_save_voltages_and_currents() {
     let test_name = "ngspice_can_save_voltages_and_currents";
     let sim_dir = get_path(test_name, "sim/");
     let ctx = sky130_open_ctx();
@@ -128,3 +144,226 @@ fn ngspice_can_save_voltages_and_currents() {
             .all(|val| relative_eq!(val, expected)));
     }
 }
+
+#[test]
+fn ngspice_can_replay_recorded_outputs() {
+    let test_name = "ngspice_can_replay_recorded_outputs";
+    let sim_dir = get_path(test_name, "sim/");
+    let executor = RecordingExecutor::with_replay(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/data/ngspice/resistor_tb"
+    ));
+    let ctx = replay_ctx(executor.clone());
+    let ResistorTbOutput {
+        r1,
+        r2,
+        r3,
+        vout,
+        r3_terminal,
+    } = ctx.simulate(ResistorTb, &sim_dir).unwrap();
+
+    let commands = executor.commands();
+    assert_eq!(commands.len(), 1);
+    let command = &commands[0];
+    assert_eq!(command.current_dir.as_deref(), Some(sim_dir.as_path()));
+    let netlist = std::str::from_utf8(&command.files[Path::new("netlist.spice")]).unwrap();
+    assert!(netlist.contains(".save v(xinst0_n)"));
+    assert!(netlist.contains(".tran 0.0000000002 0.000000002"));
+    assert!(command.files.contains_key(Path::new("simulate.sh")));
+
+    for (actual, expected) in [
+        (&*r1, 1.8 / 150.),
+        (&*r2, 1.8 / 300.),
+        (&*r3, 1.8 / 300.),
+        (&*vout, 1.8 / 3.),
+        (&*r3_terminal, -1.8 / 300.),
+    ] {
+        assert!(actual
+            .iter()
+            .cloned()
+            .all(|val| relative_eq!(val, expected)));
+    }
+}
+
+/// A resistor testbench that runs two analyses in the background.
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Block)]
+#[substrate(io = "TestbenchIo")]
+struct ResistorBatchTb;
+
+impl ExportsSchematicData for ResistorBatchTb {
+    type Data = ResistorTbData;
+}
+
+impl HasSimSchematic<Sky130OpenPdk, Ngspice> for ResistorBatchTb {
+    fn schematic(
+        &self,
+        io: &<<Self as Block>::Io as SchematicType>::Bundle,
+        cell: &mut SimCellBuilder<Sky130OpenPdk, Ngspice, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        resistor_tb_schematic(io, cell)
+    }
+}
+
+impl Testbench<Sky130OpenPdk, Ngspice> for ResistorBatchTb {
+    type Output = Vec<ResistorTbOutput>;
+
+    fn run(&self, sim: SimController<Sky130OpenPdk, Ngspice, Self>) -> Self::Output {
+        let tasks = [dec!(2e-9), dec!(4e-9)]
+            .into_iter()
+            .enumerate()
+            .map(|(i, stop)| {
+                sim.submit(
+                    format!("tran{i}"),
+                    Options::default(),
+                    None,
+                    Tran {
+                        step: dec!(2e-10),
+                        stop,
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        tasks
+            .into_iter()
+            .map(|task| task.wait().expect("failed to run simulation"))
+            .collect()
+    }
+}
+
+/// A resistor testbench that saves signals by name.
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Block)]
+#[substrate(io = "TestbenchIo")]
+struct ResistorPatternTb;
+
+impl ExportsSchematicData for ResistorPatternTb {
+    type Data = ResistorTbData;
+}
+
+impl HasSimSchematic<Sky130OpenPdk, Ngspice> for ResistorPatternTb {
+    fn schematic(
+        &self,
+        io: &<<Self as Block>::Io as SchematicType>::Bundle,
+        cell: &mut SimCellBuilder<Sky130OpenPdk, Ngspice, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        resistor_tb_schematic(io, cell)
+    }
+}
+
+#[derive(FromSaved)]
+struct ResistorPatternOutput {
+    voltages: substrate::error::Result<TranVoltages>,
+    currents: substrate::error::Result<TranCurrents>,
+}
+
+impl Save<Ngspice, Tran, &Cell<ResistorPatternTb>> for ResistorPatternOutput {
+    fn save(
+        ctx: &SimulationContext,
+        _to_save: &Cell<ResistorPatternTb>,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::Key {
+        Self::Key {
+            voltages: substrate::error::Result::<TranVoltages>::save(
+                ctx,
+                SaveSpec::pattern(r"^vdd$").unwrap(),
+                opts,
+            ),
+            currents: substrate::error::Result::<TranCurrents>::save(
+                ctx,
+                SaveSpec::pattern(r"^inst2\.p$").unwrap(),
+                opts,
+            ),
+        }
+    }
+}
+
+impl Testbench<Sky130OpenPdk, Ngspice> for ResistorPatternTb {
+    type Output = (TranVoltages, TranCurrents);
+
+    fn run(&self, sim: SimController<Sky130OpenPdk, Ngspice, Self>) -> Self::Output {
+        let output: ResistorPatternOutput = sim
+            .simulate(
+                Options::default(),
+                None,
+                Tran {
+                    step: dec!(2e-10),
+                    stop: dec!(2e-9),
+                    ..Default::default()
+                },
+            )
+            .expect("failed to run simulation");
+        (
+            output.voltages.expect("invalid voltage save spec"),
+            output.currents.expect("invalid current save spec"),
+        )
+    }
+}
+
+fn replay_ctx(executor: RecordingExecutor) -> Context<Sky130OpenPdk> {
+    // The PDK root is never read, since the testbenches only contain ngspice primitives.
+    Context::builder()
+        .pdk(Sky130OpenPdk::new("/nonexistent"))
+        .with_simulator(Ngspice::default())
+        .cache(Cache::new(MultiCache::builder().build()))
+        .executor(executor)
+        .build()
+}
+
+#[test]
+fn ngspice_can_submit_simulations() {
+    let test_name = "ngspice_can_submit_simulations";
+    let executor = RecordingExecutor::with_replay(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/data/ngspice/resistor_tb"
+    ));
+    let ctx = replay_ctx(executor.clone());
+
+    let tasks = ["a", "b"]
+        .into_iter()
+        .map(|dir| ctx.submit_simulation(ResistorTb, get_path(test_name, dir)))
+        .collect::<Vec<_>>();
+    for task in tasks {
+        let output = task.wait().unwrap();
+        assert!(output.r1.iter().all(|&val| relative_eq!(val, 1.8 / 150.)));
+    }
+
+    let sim_dir = get_path(test_name, "batch");
+    let outputs = ctx.simulate(ResistorBatchTb, &sim_dir).unwrap();
+    assert_eq!(outputs.len(), 2);
+    for output in outputs {
+        assert!(output.vout.iter().all(|&val| relative_eq!(val, 1.8 / 3.)));
+    }
+
+    let mut dirs = executor
+        .commands()
+        .into_iter()
+        .filter_map(|command| command.current_dir)
+        .filter(|dir| dir.starts_with(&sim_dir))
+        .collect::<Vec<_>>();
+    dirs.sort();
+    assert_eq!(dirs, [sim_dir.join("tran0"), sim_dir.join("tran1")]);
+}
+
+#[test]
+fn ngspice_can_save_by_pattern() {
+    let test_name = "ngspice_can_save_by_pattern";
+    let sim_dir = get_path(test_name, "sim/");
+    let executor = RecordingExecutor::with_replay(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/data/ngspice/resistor_pattern_tb"
+    ));
+    let ctx = replay_ctx(executor.clone());
+    let (voltages, currents) = ctx.simulate(ResistorPatternTb, sim_dir).unwrap();
+
+    let commands = executor.commands();
+    let netlist = std::str::from_utf8(&commands[0].files[Path::new("netlist.spice")]).unwrap();
+    assert!(netlist.contains(".save v(vdd)\n.probe i(Xinst2:1)\n"));
+
+    assert_eq!(voltages.len(), 1);
+    let (_, vdd) = voltages.first().unwrap();
+    assert!(vdd.iter().all(|&val| relative_eq!(val, 1.8)));
+
+    assert_eq!(currents.len(), 1);
+    let (_, current) = currents.first().unwrap();
+    assert!(current.iter().all(|&val| relative_eq!(val, -1.8 / 300.)));
+}
@@ -0,0 +1,225 @@
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use substrate::error::Error;
+use substrate::execute::{ExecOpts, Executor, LogOutput, SlurmExecutor, SlurmSubmit};
+
+use crate::paths::get_path;
+
+/// A stand-in for `srun` and `sbatch` that records its arguments and runs the job locally.
+const STUB: &str = r#"#!/bin/bash
+printf '%s\n' "$@" > "$(dirname "$0")/args.txt"
+out=/dev/stdout
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --parsable) echo "42;cluster"; shift ;;
+        --output=*) out="${1#--output=}"; shift ;;
+        --wrap=*) exec bash -c "${1#--wrap=}" >> "$out" 2>&1 ;;
+        --*) shift ;;
+        *) exec "$@" >> "$out" 2>&1 ;;
+    esac
+done
+"#;
+
+/// A stand-in for `scancel` that records its arguments.
+const SCANCEL_STUB: &str = r#"#!/bin/bash
+printf '%s\n' "$@" > "$(dirname "$0")/scancel.txt"
+"#;
+
+fn write_stub(test_name: &str) -> PathBuf {
+    let stub = get_path(test_name, "stub.sh");
+
+    // Ignore errors here (it is ok if the directory does not exist).
+    let _ = std::fs::remove_dir_all(stub.parent().unwrap());
+
+    std::fs::create_dir_all(stub.parent().unwrap()).unwrap();
+    for (path, contents) in [
+        (stub.clone(), STUB),
+        (stub.with_file_name("scancel.sh"), SCANCEL_STUB),
+    ] {
+        std::fs::write(&path, contents).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    stub
+}
+
+fn recorded_args(stub: &Path) -> Vec<String> {
+    std::fs::read_to_string(stub.with_file_name("args.txt"))
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn can_submit_with_srun() {
+    let stub = write_stub("can_submit_with_srun");
+    let file = stub.with_file_name("file.txt");
+
+    let mut cmd = Command::new("bash");
+    cmd.arg("-c")
+        .arg(format!("echo \"Hello, world!\" > {file:?}"));
+
+    let srun = SlurmExecutor::builder()
+        .command(stub.to_str().unwrap())
+        .partition("debug")
+        .build()
+        .unwrap();
+    srun.execute(cmd, Default::default()).expect("srun failed");
+
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap().trim(),
+        "Hello, world!"
+    );
+    assert_eq!(recorded_args(&stub)[0], "--partition=debug");
+}
+
+#[test]
+fn can_submit_with_sbatch() {
+    let stub = write_stub("can_submit_with_sbatch");
+    let file = stub.with_file_name("file.txt");
+
+    let mut cmd = Command::new("bash");
+    cmd.arg("-c")
+        .arg(format!("echo \"Hello, world!\" > {file:?}"));
+
+    let sbatch = SlurmExecutor::builder()
+        .command(stub.to_str().unwrap())
+        .submit(SlurmSubmit::Sbatch)
+        .build()
+        .unwrap();
+    sbatch
+        .execute(cmd, Default::default())
+        .expect("sbatch failed");
+
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap().trim(),
+        "Hello, world!"
+    );
+    assert_eq!(recorded_args(&stub)[0], "--wait");
+}
+
+#[test]
+fn slurm_executor_command() {
+    let mut cmd = Command::new("touch");
+    cmd.arg("hello.txt");
+
+    let exec = SlurmExecutor::builder()
+        .partition("mypartition")
+        .account("myaccount")
+        .arg("--exclusive")
+        .build()
+        .unwrap();
+    let submit = exec.command(
+        &cmd,
+        ExecOpts {
+            cpus: Some(2),
+            memory: Some(4096),
+            timeout: Some(Duration::from_secs(90)),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(submit.get_program(), "srun");
+    let args = submit.get_args().collect::<Vec<_>>();
+    assert_eq!(
+        args,
+        [
+            "--partition=mypartition",
+            "--account=myaccount",
+            "--cpus-per-task=2",
+            "--mem=4096M",
+            "--time=2",
+            "--exclusive",
+            "touch",
+            "hello.txt"
+        ]
+    );
+}
+
+#[test]
+fn slurm_executor_wraps_sbatch_command() {
+    let mut cmd = Command::new("echo");
+    cmd.arg("it's").arg("a test");
+
+    let exec = SlurmExecutor::builder()
+        .submit(SlurmSubmit::Sbatch)
+        .build()
+        .unwrap();
+    let submit = exec.command(&cmd, Default::default());
+
+    assert_eq!(submit.get_program(), "sbatch");
+    let args = submit.get_args().collect::<Vec<_>>();
+    assert_eq!(
+        args,
+        ["--wait", "--parsable", r"--wrap=echo 'it'\''s' 'a test'"]
+    );
+}
+
+#[test]
+fn srun_reports_log_tail() {
+    let stub = write_stub("srun_reports_log_tail");
+    let log = stub.with_file_name("job.log");
+
+    let mut cmd = Command::new("bash");
+    cmd.arg("-c").arg("echo job output; exit 3");
+
+    let srun = SlurmExecutor::builder()
+        .command(stub.to_str().unwrap())
+        .build()
+        .unwrap();
+    let err = srun
+        .execute(
+            cmd,
+            ExecOpts {
+                logs: LogOutput::File(log.clone()),
+                ..Default::default()
+            },
+        )
+        .expect_err("job should fail");
+
+    match err {
+        Error::ExecutionFailed(failure) => {
+            assert_eq!(failure.status.unwrap().code(), Some(3));
+            assert_eq!(failure.log_tail.as_deref(), Some("job output"));
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+    let args = recorded_args(&stub);
+    assert!(args.contains(&format!("--output={}", log.display())));
+    assert!(args.contains(&"--open-mode=append".to_string()));
+}
+
+#[test]
+fn sbatch_cancels_jobs_that_time_out() {
+    let stub = write_stub("sbatch_cancels_jobs_that_time_out");
+    let scancel = stub.with_file_name("scancel.sh");
+
+    let mut cmd = Command::new("sleep");
+    cmd.arg("30");
+
+    let sbatch = SlurmExecutor::builder()
+        .command(stub.to_str().unwrap())
+        .scancel(scancel.to_str().unwrap())
+        .submit(SlurmSubmit::Sbatch)
+        .build()
+        .unwrap();
+    let err = sbatch
+        .execute(
+            cmd,
+            ExecOpts {
+                timeout: Some(Duration::from_millis(500)),
+                ..Default::default()
+            },
+        )
+        .expect_err("job should time out");
+
+    match err {
+        Error::ExecutionFailed(failure) => assert!(failure.timed_out()),
+        err => panic!("unexpected error: {err:?}"),
+    }
+    let cancelled = std::fs::read_to_string(stub.with_file_name("scancel.txt")).unwrap();
+    assert_eq!(cancelled.trim(), "42");
+}
@@ -0,0 +1,67 @@
+use substrate::context::Context;
+use substrate::layout::svg::SvgOptions;
+use test_log::test;
+
+use crate::paths::get_path;
+use crate::shared::buffer::Buffer;
+use crate::shared::pdk::ExamplePdkA;
+
+fn layer_group<'a>(svg: &'a str, layer: &str) -> Option<&'a str> {
+    let start = svg.find(&format!("<g data-layer=\"{layer}\""))?;
+    let end = start + svg[start..].find("</g>")?;
+    Some(&svg[start..end])
+}
+
+#[test]
+fn test_svg_render_buffer() {
+    let svg_path = get_path("test_svg_render_buffer", "buffer.svg");
+    let ctx = Context::new(ExamplePdkA);
+    ctx.write_svg(Buffer::new(5), &svg_path, &SvgOptions::new())
+        .expect("failed to write SVG");
+
+    let svg = std::fs::read_to_string(&svg_path).unwrap();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"800\""));
+    assert!(svg.contains("<title>buffer_5</title>"));
+    assert!(svg.ends_with("</svg>\n"));
+
+    // The buffer is 210 units wide, and is drawn 760 pixels wide.
+    let poly = layer_group(&svg, "poly_a").expect("poly layer not drawn");
+    assert_eq!(poly.matches("<rect").count(), 2);
+    assert!(poly.contains("<rect x=\"20\" y=\"20\" width=\"361.9\" height=\"723.81\"/>"));
+
+    // Both inverters are outlined, but not labeled since they are expanded.
+    assert!(svg.contains("<g class=\"instances\""));
+    assert!(!svg.contains(">inverter_5</text>"));
+
+    for port in ["din", "dout", "vdd", "vss"] {
+        assert!(svg.contains(&format!(">{port}</text>")));
+    }
+    assert!(svg.contains(">20 nm</text>"));
+}
+
+#[test]
+fn test_svg_render_options() {
+    let ctx = Context::new(ExamplePdkA);
+
+    let svg_path = get_path("test_svg_render_options", "depth_0.svg");
+    ctx.write_svg(Buffer::new(5), &svg_path, &SvgOptions::new().with_depth(0))
+        .expect("failed to write SVG");
+    let svg = std::fs::read_to_string(&svg_path).unwrap();
+    assert!(layer_group(&svg, "poly_a").is_none());
+    assert_eq!(svg.matches(">inverter_5</text>").count(), 2);
+
+    let svg_path = get_path("test_svg_render_options", "hidden.svg");
+    let options = SvgOptions::new()
+        .with_hidden_layer(ctx.layers.polya)
+        .with_port_labels(false)
+        .with_instance_outlines(false)
+        .with_scale_bar(false);
+    ctx.write_svg(Buffer::new(5), &svg_path, &options)
+        .expect("failed to write SVG");
+    let svg = std::fs::read_to_string(&svg_path).unwrap();
+    assert!(layer_group(&svg, "poly_a").is_none());
+    assert!(layer_group(&svg, "met_1_pin_a").is_some());
+    assert!(!svg.contains(">din</text>"));
+    assert!(!svg.contains("class=\"instances\""));
+    assert!(!svg.contains("class=\"scale-bar\""));
+}
@@ -0,0 +1,127 @@
+use geometry::bbox::Bbox;
+use geometry::rect::Rect;
+use serde::{Deserialize, Serialize};
+use sky130pdk::Sky130OpenPdk;
+use substrate::block::Block;
+use substrate::context::Context;
+use substrate::layout::element::Shape;
+use substrate::layout::via::{Enclosure, Via, ViaRules};
+use substrate::layout::{ExportsLayoutData, Layout};
+use substrate::pdk::layers::{Layer, LayerId};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Block, Serialize, Deserialize, Hash)]
+#[substrate(io = "()")]
+pub struct ViaExample;
+
+impl ExportsLayoutData for ViaExample {
+    type Data = ();
+}
+
+impl Layout<Sky130OpenPdk> for ViaExample {
+    fn layout(
+        &self,
+        _io: &mut <<Self as substrate::block::Block>::Io as substrate::io::LayoutType>::Builder,
+        cell: &mut substrate::layout::CellBuilder<Sky130OpenPdk, Self>,
+    ) -> substrate::error::Result<Self::Data> {
+        let met1 = Shape::new(
+            cell.ctx.layers.met1.drawing.id(),
+            Rect::from_sides(0, 0, 1000, 300),
+        );
+        let met2 = Shape::new(
+            cell.ctx.layers.met2.drawing.id(),
+            Rect::from_sides(0, 0, 1000, 300),
+        );
+        let via = cell.ctx.via(&met1, &met2)?;
+        cell.draw(met1)?;
+        cell.draw(met2)?;
+        cell.draw(via)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn sky130_via_arrays_fill_overlap() {
+    let ctx = Context::new(Sky130OpenPdk::new(""));
+    let handle = ctx.generate_layout(ViaExample);
+    let cuts = handle
+        .cell()
+        .flatten()
+        .shapes_on(ctx.layers.via.drawing.id())
+        .iter()
+        .filter_map(|s| s.bbox())
+        .collect::<Vec<_>>();
+
+    // The end enclosure of 85 nm on both layers leaves room for 3 cuts in a single row.
+    assert_eq!(cuts.len(), 3);
+    for cut in cuts.iter() {
+        assert_eq!((cut.width(), cut.height()), (150, 150));
+        assert!(cut.left() >= 85 && cut.right() <= 915);
+        assert!(cut.bot() >= 55 && cut.top() <= 245);
+    }
+    for pair in cuts.windows(2) {
+        assert_eq!(pair[1].left() - pair[0].right(), 170);
+    }
+}
+
+#[test]
+fn sky130_via_requires_room_for_a_cut() {
+    let ctx = Context::new(Sky130OpenPdk::new(""));
+    let li1 = Shape::new(
+        ctx.layers.li1.drawing.id(),
+        Rect::from_sides(0, 0, 170, 170),
+    );
+    let met1 = Shape::new(
+        ctx.layers.met1.drawing.id(),
+        Rect::from_sides(0, 0, 170, 170),
+    );
+    // The met1 end enclosure of 60 nm does not fit around a 170 nm cut.
+    let via = ctx.via(&li1, &met1).unwrap();
+    assert!(via.cuts().is_err());
+    assert!(via.shapes().is_err());
+
+    let met2 = Shape::new(
+        ctx.layers.met2.drawing.id(),
+        Rect::from_sides(0, 0, 170, 170),
+    );
+    assert!(ctx.via(&li1, &met2).is_err());
+}
+
+#[test]
+fn via_cuts_are_snapped_to_grid() {
+    let rules = ViaRules {
+        cut: LayerId::default(),
+        bot: LayerId::default(),
+        top: LayerId::default(),
+        size: 100,
+        spacing: 100,
+        bot_enclosure: Enclosure::uniform(0),
+        top_enclosure: Enclosure::uniform(0),
+        grid: 10,
+    };
+    // Centering two cuts in 333 units would start them at 16.
+    let rect = Rect::from_sides(0, 0, 333, 100);
+    let cuts = Via::new(rules, rect, rect).cuts().unwrap();
+    assert_eq!(
+        cuts,
+        [
+            Rect::from_sides(10, 0, 110, 100),
+            Rect::from_sides(210, 0, 310, 100)
+        ]
+    );
+
+    // Off-grid regions may fit fewer cuts once snapped.
+    let rect = Rect::from_sides(5, 0, 108, 100);
+    assert!(Via::new(rules, rect, rect).cuts().is_err());
+
+    // An off-grid pitch of 195 is rounded up to 200.
+    let rules = ViaRules {
+        spacing: 95,
+        ..rules
+    };
+    let rect = Rect::from_sides(0, 0, 500, 100);
+    let cuts = Via::new(rules, rect, rect).cuts().unwrap();
+    assert_eq!(
+        cuts.iter().map(|cut| cut.left()).collect::<Vec<_>>(),
+        [0, 200, 400]
+    );
+}
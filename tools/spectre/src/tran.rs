@@ -8,10 +8,13 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::sync::Arc;
+use substrate::error::Result;
 use substrate::io::{NodePath, TerminalPath};
 use substrate::schematic::conv::RawLib;
 use substrate::schematic::{Cell, ExportsSchematicData};
-use substrate::simulation::data::{FromSaved, HasSimData, Save};
+use substrate::simulation::data::{
+    Currents, CurrentsKey, FromSaved, HasSimData, Save, SaveSpec, Voltages, VoltagesKey,
+};
 use substrate::simulation::{Analysis, SimulationContext, Simulator, Supports};
 use substrate::type_dispatch::impl_dispatch;
 
@@ -262,6 +265,52 @@ impl<T> Save<Spectre, Tran, T> for TranCurrent {
     }
 }
 
+/// A collection of saved transient voltages, keyed by node path.
+pub type TranVoltages = Voltages<TranVoltage>;
+
+/// A collection of saved transient currents, keyed by terminal path.
+pub type TranCurrents = Currents<TranCurrent>;
+
+impl Save<Spectre, Tran, &SaveSpec> for Result<TranVoltages> {
+    fn save(
+        ctx: &SimulationContext,
+        to_save: &SaveSpec,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::Key {
+        VoltagesKey::save(ctx, to_save, |path| TranVoltage::save(ctx, path, opts))
+    }
+}
+
+impl Save<Spectre, Tran, SaveSpec> for Result<TranVoltages> {
+    fn save(
+        ctx: &SimulationContext,
+        to_save: SaveSpec,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::Key {
+        Self::save(ctx, &to_save, opts)
+    }
+}
+
+impl Save<Spectre, Tran, &SaveSpec> for Result<TranCurrents> {
+    fn save(
+        ctx: &SimulationContext,
+        to_save: &SaveSpec,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::Key {
+        CurrentsKey::save(ctx, to_save, |path| TranCurrent::save(ctx, path, opts))
+    }
+}
+
+impl Save<Spectre, Tran, SaveSpec> for Result<TranCurrents> {
+    fn save(
+        ctx: &SimulationContext,
+        to_save: SaveSpec,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::Key {
+        Self::save(ctx, &to_save, opts)
+    }
+}
+
 impl HasSimData<str, Vec<f64>> for TranOutput {
     fn get_data(&self, k: &str) -> Option<&Vec<f64>> {
         self.raw_values.get(k).map(|x| x.as_ref())